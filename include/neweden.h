@@ -0,0 +1,97 @@
+#ifndef NEWEDEN_H
+#define NEWEDEN_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * `Preference::Shortest`.
+ */
+#define NEWEDEN_SHORTEST 0
+
+/**
+ * `Preference::Highsec`.
+ */
+#define NEWEDEN_HIGHSEC 1
+
+/**
+ * `Preference::LowsecAndNullsec`.
+ */
+#define NEWEDEN_LOWSEC_AND_NULLSEC 2
+
+/**
+ * A loaded universe. Opaque to C.
+ */
+typedef struct NewedenUniverse NewedenUniverse;
+
+/**
+ * The systems of a route, from the origin to the destination.
+ */
+typedef struct NewedenPath {
+  /**
+   * `len` system ids.
+   */
+  const uint32_t *systems;
+  size_t len;
+  size_t jumps;
+} NewedenPath;
+
+/**
+ * The message of the last failed call on this thread, or null. The string is valid
+ * until the next failing call on the same thread.
+ */
+const char *neweden_last_error(void);
+
+/**
+ * Loads a universe from a JSON bundle file, see `source::json`.
+ *
+ * # Safety
+ * `path` must be null or a nul terminated string.
+ */
+NewedenUniverse *neweden_universe_load(const char *path);
+
+#if defined(NEWEDEN_SQLITE)
+/**
+ * Loads a universe from a SQLite static data export.
+ *
+ * # Safety
+ * `path` must be null or a nul terminated string.
+ */
+NewedenUniverse *neweden_universe_load_sqlite(const char *path);
+#endif
+
+/**
+ * Releases a universe. Null is ignored.
+ *
+ * # Safety
+ * `universe` must be null or returned by a `neweden_universe_load` function and
+ * not released before.
+ */
+void neweden_universe_free(NewedenUniverse *universe);
+
+/**
+ * The route from `from` to `to` with one of the `NEWEDEN_*` preferences, or null
+ * if there is none.
+ *
+ * # Safety
+ * `universe` must be null or a universe that was not released.
+ */
+NewedenPath *neweden_route(const NewedenUniverse *universe,
+                           uint32_t from,
+                           uint32_t to,
+                           uint32_t preference);
+
+/**
+ * Releases a path. Null is ignored.
+ *
+ * # Safety
+ * `path` must be null or returned by `neweden_route` and not released before.
+ */
+void neweden_path_free(NewedenPath *path);
+
+#endif /* NEWEDEN_H */
@@ -0,0 +1,292 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Routing over third party storage without converting it into a `Universe`.
+//!
+//! Implement `MapStore` for your storage, e.g. a cache in front of a redis backed
+//! dynamic map, and wrap it in a `StoreUniverse`. The wrapper implements
+//! `Navigatable` and `Galaxy`, so the path finder and the analyses work on it
+//! directly. Only systems and connections are required; range queries fall back to
+//! scanning all systems unless the store overrides `MapStore::systems_within`, for
+//! which `SpatialIndex` provides a ready made spatial index.
+//!
+//! # Example
+//! ```
+//! use std::collections::HashMap;
+//!
+//! use neweden::adapter::{MapStore, SpatialIndex, StoreUniverse};
+//! use neweden::navigation::PathBuilder;
+//! use neweden::{Connection, ConnectionSource, ConnectionType, Coordinate, Meters};
+//! use neweden::{Navigatable, StargateType, System, SystemId};
+//!
+//! struct MyMap {
+//!     systems: HashMap<SystemId, System>,
+//!     gates: Vec<(SystemId, SystemId)>,
+//!     index: SpatialIndex,
+//! }
+//!
+//! impl MapStore for MyMap {
+//!     fn system(&self, id: &SystemId) -> Option<&System> {
+//!         self.systems.get(id)
+//!     }
+//!
+//!     fn systems(&self) -> Vec<&System> {
+//!         self.systems.values().collect()
+//!     }
+//!
+//!     fn connections(&self, from: &SystemId) -> Vec<Connection> {
+//!         self.gates
+//!             .iter()
+//!             .filter(|(a, _)| a == from)
+//!             .map(|&(from, to)| Connection {
+//!                 from,
+//!                 to,
+//!                 type_: ConnectionType::Stargate(StargateType::Local),
+//!                 source: ConnectionSource::Sde,
+//!             })
+//!             .collect()
+//!     }
+//!
+//!     fn systems_within(&self, coordinate: &Coordinate, range: Meters) -> Vec<&System> {
+//!         self.index.within(coordinate, range)
+//!     }
+//! }
+//!
+//! let system = |id: u32, x: f64| {
+//!     System::new(id.into(), "", Coordinate { x, y: 0.0, z: 0.0 }, 0.5.into())
+//! };
+//! let systems = vec![system(1, 0.0), system(2, 1.0), system(3, 2.0)];
+//! let map = MyMap {
+//!     index: SpatialIndex::new(systems.clone()),
+//!     systems: systems.into_iter().map(|s| (s.id, s)).collect(),
+//!     gates: vec![(1.into(), 2.into()), (2.into(), 3.into())],
+//! };
+//!
+//! let universe = StoreUniverse::new(map);
+//! let path = PathBuilder::new(&universe)
+//!     .waypoint(universe.get_system(&1.into()).unwrap())
+//!     .waypoint(universe.get_system(&3.into()).unwrap())
+//!     .build()
+//!     .unwrap();
+//! assert_eq!(2, path.jumps());
+//! ```
+use std::borrow::Cow;
+
+use crate::types::{
+    Connection, Coordinate, Galaxy, Meters, Navigatable, SecurityClass, Stargate, System, SystemId,
+};
+
+/// The storage a `StoreUniverse` routes over.
+pub trait MapStore {
+    fn system(&self, id: &SystemId) -> Option<&System>;
+
+    /// All systems of the map.
+    fn systems(&self) -> Vec<&System>;
+
+    /// The connections leaving a system.
+    fn connections(&self, from: &SystemId) -> Vec<Connection>;
+
+    /// The systems within `range` of a coordinate. The default scans all systems,
+    /// stores with many systems should use a `SpatialIndex` instead.
+    fn systems_within(&self, coordinate: &Coordinate, range: Meters) -> Vec<&System> {
+        self.systems()
+            .into_iter()
+            .filter(|s| distance_2(&s.coordinate, coordinate) <= range.0 * range.0)
+            .collect()
+    }
+
+    /// The stargates in a system, if the store knows their positions.
+    fn stargates(&self, _system: &SystemId) -> &[Stargate] {
+        &[]
+    }
+}
+
+fn point(coordinate: &Coordinate) -> [f64; 3] {
+    [coordinate.x, coordinate.y, coordinate.z]
+}
+
+fn distance_2(a: &Coordinate, b: &Coordinate) -> f64 {
+    let (dx, dy, dz) = (a.x - b.x, a.y - b.y, a.z - b.z);
+    dx * dx + dy * dy + dz * dz
+}
+
+/// A spatial index over systems for implementing `MapStore::systems_within`. It
+/// keeps its own copy of the systems, which must be updated when systems move in
+/// or out of the store.
+#[derive(Debug, Clone)]
+pub struct SpatialIndex {
+    rtree: rstar::RTree<System>,
+}
+
+impl SpatialIndex {
+    pub fn new(systems: Vec<System>) -> Self {
+        Self {
+            rtree: rstar::RTree::bulk_load(systems),
+        }
+    }
+
+    pub fn insert(&mut self, system: System) {
+        self.rtree.insert(system);
+    }
+
+    /// Removes the system with the same id. Returns whether it was indexed.
+    pub fn remove(&mut self, system: &System) -> bool {
+        self.rtree.remove(system).is_some()
+    }
+
+    pub fn within(&self, coordinate: &Coordinate, range: Meters) -> Vec<&System> {
+        self.rtree
+            .locate_within_distance(point(coordinate), range.0 * range.0)
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.rtree.size()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rtree.size() == 0
+    }
+}
+
+/// Makes a `MapStore` usable wherever a `Navigatable` or `Galaxy` is expected.
+pub struct StoreUniverse<S> {
+    store: S,
+}
+
+impl<S: MapStore> StoreUniverse<S> {
+    pub fn new(store: S) -> Self {
+        Self { store }
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    pub fn into_inner(self) -> S {
+        self.store
+    }
+}
+
+impl<S: MapStore> Navigatable for StoreUniverse<S> {
+    fn get_system(&self, id: &SystemId) -> Option<&System> {
+        self.store.system(id)
+    }
+
+    fn get_connections(&self, from: &SystemId) -> Option<Cow<'_, [Connection]>> {
+        let connections = self.store.connections(from);
+        if connections.is_empty() {
+            None
+        } else {
+            Some(Cow::Owned(connections))
+        }
+    }
+
+    /// Like `Universe`, only returns lowsec and nullsec systems, the possible
+    /// destinations of jump drives and bridges.
+    fn get_systems_by_range(&self, from: &SystemId, range: Meters) -> Option<Vec<&System>> {
+        let system = self.store.system(from)?;
+        let systems = self
+            .store
+            .systems_within(&system.coordinate, range)
+            .into_iter()
+            .filter(|s| SecurityClass::from(s.security) != SecurityClass::Highsec)
+            .collect();
+        Some(systems)
+    }
+
+    fn get_stargates(&self, system: &SystemId) -> &[Stargate] {
+        self.store.stargates(system)
+    }
+}
+
+impl<S: MapStore> Galaxy for StoreUniverse<S> {
+    fn systems(&self) -> Vec<&System> {
+        self.store.systems()
+    }
+
+    fn connections(&self) -> Vec<(SystemId, SystemId)> {
+        self.store
+            .systems()
+            .iter()
+            .flat_map(|s| self.store.connections(&s.id))
+            .map(|c| (c.from, c.to))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+    use crate::types::{self, Lightyears};
+
+    /// A store backed by a universe, to compare the adapter against the original.
+    struct Mirror(types::Universe);
+
+    impl MapStore for Mirror {
+        fn system(&self, id: &SystemId) -> Option<&System> {
+            self.0.get_system(id)
+        }
+
+        fn systems(&self) -> Vec<&System> {
+            Galaxy::systems(&self.0)
+        }
+
+        fn connections(&self, from: &SystemId) -> Vec<Connection> {
+            self.0
+                .get_connections(from)
+                .map(Cow::into_owned)
+                .unwrap_or_default()
+        }
+    }
+
+    #[test]
+    fn test_store_universe() {
+        let universe = universe();
+        let adapter = StoreUniverse::new(Mirror(crate::testing::universe()));
+
+        let mut expected = Galaxy::connections(&universe);
+        let mut actual = Galaxy::connections(&adapter);
+        expected.sort();
+        actual.sort();
+        assert_eq!(expected, actual);
+
+        let range = Meters::from(Lightyears(2.5));
+        let ids = |systems: Option<Vec<&System>>| {
+            let mut ids = systems.unwrap().iter().map(|s| s.id).collect::<Vec<_>>();
+            ids.sort();
+            ids
+        };
+        assert_eq!(
+            ids(universe.get_systems_by_range(&DELTA, range)),
+            ids(adapter.get_systems_by_range(&DELTA, range))
+        );
+        assert_eq!(
+            vec![DELTA, ECHO],
+            ids(adapter.get_systems_by_range(&DELTA, range))
+        );
+    }
+
+    #[test]
+    fn test_spatial_index() {
+        let universe = universe();
+        let mut index =
+            SpatialIndex::new(Galaxy::systems(&universe).into_iter().cloned().collect());
+        assert_eq!(8, index.len());
+        let golf = universe.get_system(&GOLF).unwrap();
+        let mut near = index
+            .within(&golf.coordinate, Meters::from(Lightyears(2.5)))
+            .iter()
+            .map(|s| s.id)
+            .collect::<Vec<_>>();
+        near.sort();
+        assert_eq!(vec![CHARLIE, FOXTROT, GOLF, HOTEL], near);
+
+        assert!(index.remove(golf));
+        assert!(!index.remove(golf));
+        assert_eq!(7, index.len());
+    }
+}
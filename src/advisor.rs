@@ -0,0 +1,519 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! A single entry point to plan a trip, combining routing, overlays, risk and
+//! ship characteristics into one structured answer.
+//!
+//! # Example
+//! ```
+//! use neweden::advisor::{plan_trip, TripRequest};
+//! use neweden::builder::UniverseBuilder;
+//! use neweden::{Connection, ConnectionSource, ConnectionType, Coordinate, StargateType, System};
+//!
+//! let system = |id: u32, sec: f32| {
+//!     System::new(id.into(), "", Coordinate { x: 0.0, y: 0.0, z: 0.0 }, sec.into())
+//! };
+//! let gate = |from: u32, to: u32| Connection {
+//!     from: from.into(),
+//!     to: to.into(),
+//!     type_: ConnectionType::Stargate(StargateType::Local),
+//!     source: ConnectionSource::Sde,
+//! };
+//! let universe = UniverseBuilder::new()
+//!     .system(system(1, 0.9))
+//!     .system(system(2, 0.4))
+//!     .connection(gate(1, 2))
+//!     .build();
+//!
+//! let plan = plan_trip(&universe, &TripRequest::new(1.into(), 2.into())).unwrap();
+//! assert_eq!(1, plan.legs.len());
+//! assert_eq!(1, plan.risk.lowsec);
+//! ```
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::navigation::{
+    Cost, CostFunction, Path, PathBuilder, PathElement, Preference, RouteError, ShipProfile,
+    TravelTime,
+};
+use crate::types::{self, Galaxy, Navigatable};
+
+/// Cost added for a system with a risk of 1.0 when routing around risk.
+const RISK_WEIGHT: f32 = 20.0;
+/// Systems with a risk at or above this value are reported in the advisories.
+const HIGH_RISK: f32 = 0.5;
+
+/// Errors returned by `plan_trip`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum TripError {
+    #[error("unknown system {0:?}")]
+    UnknownSystem(types::SystemId),
+    #[error(transparent)]
+    Route(#[from] RouteError),
+}
+
+/// Estimates how dangerous a system currently is, from 0.0 (safe) to 1.0 (certain
+/// loss). Typically backed by kill statistics or intel channels.
+///
+/// Closures taking a system and returning the risk implement this trait.
+pub trait RiskProvider {
+    fn risk(&self, system: &types::System) -> f32;
+}
+
+impl<F> RiskProvider for F
+where
+    F: Fn(&types::System) -> f32,
+{
+    fn risk(&self, system: &types::System) -> f32 {
+        self(system)
+    }
+}
+
+/// Historical activity per system and hour of day, typically aggregated from
+/// killmails and jump statistics. The risk of passing a system during an hour (0 to
+/// 23, UTC) ranges from 0.0 (safe) to 1.0 (certain loss).
+///
+/// Closures taking a system and an hour and returning the risk implement this trait.
+pub trait ActivityModel {
+    fn risk_at(&self, system: &types::System, hour: u32) -> f32;
+}
+
+impl<F> ActivityModel for F
+where
+    F: Fn(&types::System, u32) -> f32,
+{
+    fn risk_at(&self, system: &types::System, hour: u32) -> f32 {
+        self(system, hour)
+    }
+}
+
+/// Everything `plan_trip` needs to know about a trip.
+pub struct TripRequest<'r> {
+    origin: types::SystemId,
+    destination: types::SystemId,
+    ship: ShipProfile,
+    preference: Preference,
+    risk: Option<&'r dyn RiskProvider>,
+    overlays: Vec<types::Connection>,
+}
+
+impl<'r> TripRequest<'r> {
+    pub fn new(origin: types::SystemId, destination: types::SystemId) -> Self {
+        Self {
+            origin,
+            destination,
+            ship: ShipProfile::default(),
+            preference: Preference::Shortest,
+            risk: None,
+            overlays: Vec::new(),
+        }
+    }
+
+    pub fn ship(mut self, ship: ShipProfile) -> Self {
+        self.ship = ship;
+        self
+    }
+
+    pub fn prefer(mut self, preference: Preference) -> Self {
+        self.preference = preference;
+        self
+    }
+
+    /// Route around risky systems and report them in the plan.
+    pub fn risk(mut self, risk: &'r dyn RiskProvider) -> Self {
+        self.risk = Some(risk);
+        self
+    }
+
+    /// Additional connections, such as wormholes or bridges, that may be used.
+    pub fn overlay(mut self, connection: types::Connection) -> Self {
+        self.overlays.push(connection);
+        self
+    }
+
+    pub fn overlays(mut self, connections: Vec<types::Connection>) -> Self {
+        self.overlays.extend(connections);
+        self
+    }
+}
+
+/// A single jump of a trip.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TripLeg {
+    pub from: types::SystemId,
+    pub to: types::SystemId,
+    pub via: types::ConnectionType,
+    /// Security of the system jumped into.
+    pub security: types::Security,
+}
+
+/// Summary of the systems a trip passes through, including the origin.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RiskSummary {
+    pub highsec: usize,
+    pub lowsec: usize,
+    pub nullsec: usize,
+    /// The most dangerous system according to the risk provider, if one was given.
+    pub riskiest: Option<(types::SystemId, f32)>,
+}
+
+/// Things a pilot should be told about before undocking.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Advisory {
+    /// The route leaves highsec for the first time at this system.
+    LeavesHighsec(types::SystemId),
+    EntersNullsec(types::SystemId),
+    HighRisk(types::SystemId, f32),
+    UsesWormhole {
+        from: types::SystemId,
+        to: types::SystemId,
+    },
+    UsesBridge {
+        from: types::SystemId,
+        to: types::SystemId,
+    },
+    /// Filaments land in a random system, the route may not work out.
+    UsesFilament {
+        from: types::SystemId,
+        to: types::SystemId,
+    },
+}
+
+/// The answer to a `TripRequest`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TripPlan {
+    pub legs: Vec<TripLeg>,
+    pub eta: Duration,
+    /// Isotopes needed for bridges along the route.
+    pub fuel: u32,
+    pub risk: RiskSummary,
+    pub advisories: Vec<Advisory>,
+}
+
+/// The recommended departure of `best_departure_window`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepartureWindow {
+    /// How long to wait before departing.
+    pub wait: Duration,
+    /// The hour of day of the departure, UTC.
+    pub hour: u32,
+    /// The risk of losing the ship anywhere along the route when departing then.
+    pub risk: f32,
+    /// The route risk when departing at the start of every hour of day, starting at
+    /// midnight UTC, e.g. for plotting.
+    pub curve: Vec<f32>,
+}
+
+/// Recommends when to depart on the route within the next `within`: now, or at the
+/// start of one of the following hours, whichever has the lowest aggregated route
+/// risk. Every system is weighed with the risk of the hour the ship passes it,
+/// according to the travel time of the ship. Ties go to the earlier departure.
+///
+/// # Example
+/// ```
+/// use std::time::{Duration, SystemTime};
+/// use neweden::advisor::best_departure_window;
+/// use neweden::builder::UniverseBuilder;
+/// use neweden::navigation::{PathBuilder, ShipProfile};
+/// use neweden::{Coordinate, Navigatable, System};
+///
+/// let universe = UniverseBuilder::new()
+///     .system(System::new(1.into(), "Tama", Coordinate { x: 0.0, y: 0.0, z: 0.0 }, 0.3.into()))
+///     .build();
+/// let tama = universe.get_system(&1.into()).unwrap();
+/// let route = PathBuilder::new(&universe).waypoint(tama).waypoint(tama).build().unwrap();
+///
+/// // camped during the european prime time
+/// let activity = |_: &System, hour: u32| if (17..23).contains(&hour) { 0.8 } else { 0.1 };
+/// let window = best_departure_window(
+///     &route,
+///     Duration::from_secs(24 * 3600),
+///     &activity,
+///     &ShipProfile::default(),
+///     SystemTime::now(),
+/// );
+/// assert!(!(17..23).contains(&window.hour));
+/// assert_eq!(24, window.curve.len());
+/// ```
+pub fn best_departure_window(
+    route: &Path,
+    within: Duration,
+    activity: &dyn ActivityModel,
+    ship: &ShipProfile,
+    now: SystemTime,
+) -> DepartureWindow {
+    // seconds from the departure at which every system is entered
+    let mut elapsed = 0;
+    let mut passes = Vec::new();
+    let legs = TravelTime::new(ship).legs(route);
+    for (i, system) in route.systems().enumerate() {
+        passes.push((system, elapsed));
+        elapsed += legs.get(i).map_or(0, Duration::as_secs);
+    }
+    let risk = |departure: u64| {
+        let safe = passes
+            .iter()
+            .map(|(system, elapsed)| {
+                let hour = ((departure + elapsed) / 3600 % 24) as u32;
+                1.0 - activity.risk_at(system, hour).clamp(0.0, 1.0)
+            })
+            .product::<f32>();
+        1.0 - safe
+    };
+
+    let curve = (0..24).map(|hour| risk(hour * 3600)).collect::<Vec<_>>();
+    let now = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let candidates = std::iter::once(0).chain(
+        (1..)
+            .map(|n| (now / 3600 + n) * 3600 - now)
+            .take_while(|wait| *wait <= within.as_secs()),
+    );
+    let mut best: Option<(u64, f32)> = None;
+    for wait in candidates {
+        let r = risk(now + wait);
+        if best.is_none_or(|(_, b)| r < b) {
+            best = Some((wait, r));
+        }
+    }
+    let (wait, risk) = best.unwrap_or((0, 0.0));
+    DepartureWindow {
+        wait: Duration::from_secs(wait),
+        hour: ((now + wait) / 3600 % 24) as u32,
+        risk,
+        curve,
+    }
+}
+
+/// Plans a trip: finds a route through the universe extended by the overlays, avoiding
+/// risky systems if a risk provider is given, and estimates time, fuel and risk.
+pub fn plan_trip<U: Galaxy + Navigatable>(
+    universe: &U,
+    request: &TripRequest,
+) -> Result<TripPlan, TripError> {
+    let universe = types::ExtendedUniverse::new(universe, request.overlays.clone().into());
+    let origin = universe
+        .get_system(&request.origin)
+        .ok_or(TripError::UnknownSystem(request.origin))?;
+    let destination = universe
+        .get_system(&request.destination)
+        .ok_or(TripError::UnknownSystem(request.destination))?;
+
+    let preference = request.preference;
+    let risk = request.risk;
+    let cost = move |universe: &dyn Navigatable, connection: &types::Connection| {
+        let base = preference.cost(universe, connection)?;
+        let penalty = match (risk, universe.get_system(&connection.to)) {
+            (Some(risk), Some(system)) => (risk.risk(system).clamp(0.0, 1.0) * RISK_WEIGHT) as Cost,
+            _ => 0,
+        };
+        Some(base + penalty)
+    };
+    let path = PathBuilder::new(&universe)
+        .waypoint(origin)
+        .waypoint(destination)
+        .cost_function(cost)
+        .try_build()?;
+
+    let mut plan = TripPlan {
+        legs: Vec::new(),
+        eta: path.estimated_time(&request.ship),
+        fuel: 0,
+        risk: RiskSummary::default(),
+        advisories: Vec::new(),
+    };
+    let mut previous: Option<&types::System> = None;
+    let mut via = None;
+    let mut left_highsec = false;
+    let mut in_nullsec = false;
+    for element in path.iter() {
+        let system = match element {
+            PathElement::Connection(type_, _) => {
+                via = Some(type_);
+                continue;
+            }
+            PathElement::System(system) | PathElement::Waypoint(system) => system,
+        };
+
+        match types::SecurityClass::from(&system.security) {
+            types::SecurityClass::Highsec => {
+                plan.risk.highsec += 1;
+                in_nullsec = false;
+            }
+            types::SecurityClass::Lowsec => {
+                plan.risk.lowsec += 1;
+                in_nullsec = false;
+                if !left_highsec {
+                    plan.advisories.push(Advisory::LeavesHighsec(system.id));
+                }
+                left_highsec = true;
+            }
+            types::SecurityClass::Nullsec => {
+                plan.risk.nullsec += 1;
+                if !left_highsec {
+                    plan.advisories.push(Advisory::LeavesHighsec(system.id));
+                }
+                if !in_nullsec {
+                    plan.advisories.push(Advisory::EntersNullsec(system.id));
+                }
+                left_highsec = true;
+                in_nullsec = true;
+            }
+        }
+        if let Some(risk) = request.risk {
+            let risk = risk.risk(system);
+            if risk >= HIGH_RISK {
+                plan.advisories.push(Advisory::HighRisk(system.id, risk));
+            }
+            if plan.risk.riskiest.is_none_or(|(_, r)| risk > r) {
+                plan.risk.riskiest = Some((system.id, risk));
+            }
+        }
+
+        if let (Some(from), Some(via)) = (previous, via.take()) {
+            match &via {
+                types::ConnectionType::Stargate(_) => {}
+                types::ConnectionType::Bridge(_) => {
+                    let ly = types::Lightyears::from(from.distance(system));
+                    plan.fuel += (ly.0 * f64::from(request.ship.fuel_per_lightyear)).round() as u32;
+                    plan.advisories.push(Advisory::UsesBridge {
+                        from: from.id,
+                        to: system.id,
+                    });
+                }
+                types::ConnectionType::Wormhole(_) => {
+                    plan.advisories.push(Advisory::UsesWormhole {
+                        from: from.id,
+                        to: system.id,
+                    });
+                }
+                types::ConnectionType::Filament(_) => {
+                    plan.advisories.push(Advisory::UsesFilament {
+                        from: from.id,
+                        to: system.id,
+                    });
+                }
+            }
+            plan.legs.push(TripLeg {
+                from: from.id,
+                to: system.id,
+                via,
+                security: system.security,
+            });
+        }
+        previous = Some(system);
+    }
+
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+
+    #[test]
+    fn test_plan_trip() {
+        let universe = universe();
+        let plan = plan_trip(&universe, &TripRequest::new(ALPHA, ECHO)).unwrap();
+        assert_eq!(4, plan.legs.len());
+        let path = PathBuilder::new(&universe)
+            .waypoint(universe.get_system(&ALPHA).unwrap())
+            .waypoint(universe.get_system(&ECHO).unwrap())
+            .build()
+            .unwrap();
+        assert_eq!(path.estimated_time(&ShipProfile::default()), plan.eta);
+        assert_eq!(
+            (3, 1, 1),
+            (plan.risk.highsec, plan.risk.lowsec, plan.risk.nullsec)
+        );
+        assert_eq!(
+            vec![
+                Advisory::LeavesHighsec(DELTA),
+                Advisory::EntersNullsec(ECHO)
+            ],
+            plan.advisories
+        );
+    }
+
+    #[test]
+    fn test_best_departure_window() {
+        let universe = universe();
+        let route = PathBuilder::new(&universe)
+            .waypoint(universe.get_system(&ALPHA).unwrap())
+            .waypoint(universe.get_system(&ECHO).unwrap())
+            .build()
+            .unwrap();
+        // Delta is camped until 06:00, everything else is quiet
+        let activity = |s: &types::System, hour: u32| {
+            if s.id == DELTA && hour < 6 {
+                0.5
+            } else {
+                0.0
+            }
+        };
+        let ship = ShipProfile::default();
+        let three_am = UNIX_EPOCH + Duration::from_secs(3 * 3600 + 600);
+
+        let window = best_departure_window(
+            &route,
+            Duration::from_secs(3600),
+            &activity,
+            &ship,
+            three_am,
+        );
+        assert_eq!(Duration::ZERO, window.wait);
+        assert_eq!(3, window.hour);
+        assert_eq!(0.5, window.risk);
+        assert_eq!(0.5, window.curve[5]);
+        assert_eq!(0.0, window.curve[6]);
+
+        let window = best_departure_window(
+            &route,
+            Duration::from_secs(4 * 3600),
+            &activity,
+            &ship,
+            three_am,
+        );
+        assert_eq!(Duration::from_secs(2 * 3600 + 3000), window.wait);
+        assert_eq!(6, window.hour);
+        assert_eq!(0.0, window.risk);
+    }
+
+    #[test]
+    fn test_plan_trip_risk_and_overlays() {
+        let universe = universe();
+        let charlie_camped = |s: &types::System| if s.id == CHARLIE { 1.0 } else { 0.0 };
+        let request = TripRequest::new(ALPHA, DELTA).risk(&charlie_camped);
+        let plan = plan_trip(&universe, &request).unwrap();
+        assert!(plan.legs.iter().all(|l| l.to != CHARLIE));
+        assert_eq!(Some((ALPHA, 0.0)), plan.risk.riskiest);
+
+        let bridge = types::Connection {
+            from: ALPHA,
+            to: DELTA,
+            type_: types::ConnectionType::Bridge(types::BridgeType::Titan(
+                types::JumpdriveSkills::new(5, 5),
+            )),
+            source: types::ConnectionSource::UserOverlay,
+        };
+        let ship = ShipProfile {
+            fuel_per_lightyear: 100,
+            ..ShipProfile::default()
+        };
+        let gates = plan_trip(&universe, &TripRequest::new(ALPHA, DELTA)).unwrap();
+        let request = TripRequest::new(ALPHA, DELTA).overlay(bridge).ship(ship);
+        let plan = plan_trip(&universe, &request).unwrap();
+        assert_eq!(1, plan.legs.len());
+        assert_eq!(600, plan.fuel);
+        assert!(plan.eta < gates.eta);
+        assert!(plan.advisories.contains(&Advisory::UsesBridge {
+            from: ALPHA,
+            to: DELTA
+        }));
+        assert_eq!(
+            Err(TripError::UnknownSystem(types::SystemId(1))),
+            plan_trip(&universe, &TripRequest::new(types::SystemId(1), DELTA))
+        );
+    }
+}
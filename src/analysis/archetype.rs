@@ -0,0 +1,220 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! What kind of trip a route is, from the security of its systems and the
+//! connections it takes, e.g. to pick an icon or a warning in a frontend.
+//!
+//! # Example
+//! ```
+//! use neweden::analysis::archetype::{classify, RouteArchetype};
+//! use neweden::builder::UniverseBuilder;
+//! use neweden::navigation::PathBuilder;
+//! use neweden::{Connection, ConnectionSource, ConnectionType, Coordinate, Navigatable, StargateType, System};
+//!
+//! let system = |id: u32, sec: f32| {
+//!     System::new(id.into(), "", Coordinate { x: 0.0, y: 0.0, z: 0.0 }, sec.into())
+//! };
+//! let gate = |from: u32, to: u32| Connection {
+//!     from: from.into(),
+//!     to: to.into(),
+//!     type_: ConnectionType::Stargate(StargateType::Local),
+//!     source: ConnectionSource::Sde,
+//! };
+//! let universe = UniverseBuilder::new()
+//!     .system(system(1, 0.9))
+//!     .system(system(2, 0.4))
+//!     .system(system(3, 0.7))
+//!     .connection(gate(1, 2))
+//!     .connection(gate(2, 3))
+//!     .build();
+//! let path = PathBuilder::new(&universe)
+//!     .waypoint(universe.get_system(&1.into()).unwrap())
+//!     .waypoint(universe.get_system(&3.into()).unwrap())
+//!     .build()
+//!     .unwrap();
+//! assert_eq!(RouteArchetype::HighsecWithLowsecCrossing, classify(&path));
+//! ```
+use crate::navigation::{Path, PathElement};
+use crate::types;
+
+/// The kind of a route, see `classify`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum RouteArchetype {
+    /// Only highsec systems.
+    PureHighsec,
+    /// Highsec and lowsec systems, but no nullsec.
+    HighsecWithLowsecCrossing,
+    /// Mostly nullsec, or nullsec reached through jump bridges.
+    NullBlocHighway,
+    /// Takes at least one wormhole.
+    WormholeShortcut,
+    /// None of the above, e.g. a lowsec run or a short hop into nullsec.
+    Mixed,
+}
+
+/// How many jumps of a route lead from one security class to another, including
+/// jumps within a class. `Path::security_crossings` lists the jumps between
+/// classes one by one instead.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SecurityTransitions {
+    counts: [[usize; 3]; 3],
+}
+
+impl SecurityTransitions {
+    pub fn from_path(path: &Path) -> Self {
+        let mut transitions = Self::default();
+        for leg in path.security_legs() {
+            transitions.counts[index(&leg.class)][index(&leg.class)] += leg.systems.len() - 1;
+        }
+        for crossing in path.security_crossings() {
+            transitions.counts[index(&crossing.from_class())][index(&crossing.to_class())] += 1;
+        }
+        transitions
+    }
+
+    /// The number of jumps from a system of class `from` into one of class `to`.
+    pub fn get(&self, from: &types::SecurityClass, to: &types::SecurityClass) -> usize {
+        self.counts[index(from)][index(to)]
+    }
+
+    /// The number of jumps into a system of this class.
+    pub fn into_class(&self, to: &types::SecurityClass) -> usize {
+        self.counts.iter().map(|row| row[index(to)]).sum()
+    }
+
+    /// The number of jumps that change the security class.
+    pub fn changes(&self) -> usize {
+        (0..3)
+            .flat_map(|from| (0..3).map(move |to| (from, to)))
+            .filter(|(from, to)| from != to)
+            .map(|(from, to)| self.counts[from][to])
+            .sum()
+    }
+}
+
+fn index(class: &types::SecurityClass) -> usize {
+    match class {
+        types::SecurityClass::Highsec => 0,
+        types::SecurityClass::Lowsec => 1,
+        types::SecurityClass::Nullsec => 2,
+    }
+}
+
+/// Classifies a route. Wormholes take precedence over everything else, as they are
+/// what users need to be warned about most.
+pub fn classify(path: &Path) -> RouteArchetype {
+    let mut wormholes = false;
+    let mut bridges = false;
+    let (mut highsec, mut lowsec, mut nullsec) = (0, 0, 0);
+    for element in path.iter() {
+        match element {
+            PathElement::System(system) | PathElement::Waypoint(system) => {
+                match types::SecurityClass::from(system.security) {
+                    types::SecurityClass::Highsec => highsec += 1,
+                    types::SecurityClass::Lowsec => lowsec += 1,
+                    types::SecurityClass::Nullsec => nullsec += 1,
+                }
+            }
+            PathElement::Connection(types::ConnectionType::Wormhole(_), _) => wormholes = true,
+            PathElement::Connection(types::ConnectionType::Bridge(_), _) => bridges = true,
+            PathElement::Connection(..) => {}
+        }
+    }
+
+    if wormholes {
+        RouteArchetype::WormholeShortcut
+    } else if lowsec == 0 && nullsec == 0 {
+        RouteArchetype::PureHighsec
+    } else if nullsec == 0 && highsec > 0 {
+        RouteArchetype::HighsecWithLowsecCrossing
+    } else if nullsec > 0 && (bridges || 2 * nullsec >= highsec + lowsec + nullsec) {
+        RouteArchetype::NullBlocHighway
+    } else {
+        RouteArchetype::Mixed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation::PathBuilder;
+    use crate::testing::*;
+    use crate::types::{Navigatable, SecurityClass};
+
+    #[test]
+    fn test_classify() {
+        let universe = universe();
+        let path = |from: &types::SystemId, to: &types::SystemId| {
+            PathBuilder::new(&universe)
+                .waypoint(universe.get_system(from).unwrap())
+                .waypoint(universe.get_system(to).unwrap())
+                .build()
+                .unwrap()
+        };
+
+        assert_eq!(
+            RouteArchetype::PureHighsec,
+            classify(&path(&ALPHA, &FOXTROT))
+        );
+        // Alpha, Bravo, Charlie, Delta
+        assert_eq!(
+            RouteArchetype::HighsecWithLowsecCrossing,
+            classify(&path(&ALPHA, &DELTA))
+        );
+        // Delta, Echo
+        assert_eq!(
+            RouteArchetype::NullBlocHighway,
+            classify(&path(&DELTA, &ECHO))
+        );
+        // Alpha, Bravo, Charlie, Delta, Echo
+        assert_eq!(RouteArchetype::Mixed, classify(&path(&ALPHA, &ECHO)));
+
+        let wormhole = universe.extend(
+            vec![types::Connection {
+                from: ALPHA,
+                to: ECHO,
+                type_: types::ConnectionType::Wormhole(types::WormholeType::Large),
+                source: types::ConnectionSource::UserOverlay,
+            }]
+            .into(),
+        );
+        let path = PathBuilder::new(&wormhole)
+            .waypoint(wormhole.get_system(&ALPHA).unwrap())
+            .waypoint(wormhole.get_system(&ECHO).unwrap())
+            .build()
+            .unwrap();
+        assert_eq!(RouteArchetype::WormholeShortcut, classify(&path));
+    }
+
+    #[test]
+    fn test_security_transitions() {
+        let universe = universe();
+        let path = PathBuilder::new(&universe)
+            .waypoint(universe.get_system(&ALPHA).unwrap())
+            .waypoint(universe.get_system(&ECHO).unwrap())
+            .build()
+            .unwrap();
+        let transitions = SecurityTransitions::from_path(&path);
+        // Alpha (high), Bravo (high), Charlie (high), Delta (low), Echo (null)
+        assert_eq!(
+            2,
+            transitions.get(&SecurityClass::Highsec, &SecurityClass::Highsec)
+        );
+        assert_eq!(
+            1,
+            transitions.get(&SecurityClass::Highsec, &SecurityClass::Lowsec)
+        );
+        assert_eq!(
+            1,
+            transitions.get(&SecurityClass::Lowsec, &SecurityClass::Nullsec)
+        );
+        assert_eq!(
+            0,
+            transitions.get(&SecurityClass::Nullsec, &SecurityClass::Highsec)
+        );
+        assert_eq!(1, transitions.into_class(&SecurityClass::Nullsec));
+        assert_eq!(2, transitions.changes());
+    }
+}
@@ -0,0 +1,316 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Structural analyses of the connection graph: how central systems are, and which
+//! systems and connections cannot be avoided.
+//!
+//! Articulation points and bridges treat connections as undirected. Betweenness
+//! and chokepoints follow connections in their direction.
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::types::{Galaxy, RegionId, SystemId};
+
+/// The connection graph of a galaxy with systems replaced by dense indices.
+struct Graph {
+    ids: Vec<SystemId>,
+    index: HashMap<SystemId, usize>,
+    outgoing: Vec<Vec<usize>>,
+}
+
+impl Graph {
+    fn new<G: Galaxy>(galaxy: &G) -> Self {
+        let mut ids = galaxy.systems().iter().map(|s| s.id).collect::<Vec<_>>();
+        ids.sort();
+        let index = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (*id, i))
+            .collect::<HashMap<_, _>>();
+        let mut outgoing = vec![Vec::new(); ids.len()];
+        for (from, to) in galaxy.connections() {
+            if let (Some(&from), Some(&to)) = (index.get(&from), index.get(&to)) {
+                outgoing[from].push(to);
+            }
+        }
+        for targets in &mut outgoing {
+            targets.sort_unstable();
+            targets.dedup();
+        }
+        Self {
+            ids,
+            index,
+            outgoing,
+        }
+    }
+
+    fn undirected(&self) -> Vec<Vec<usize>> {
+        let mut adjacent = self.outgoing.clone();
+        for (from, targets) in self.outgoing.iter().enumerate() {
+            for &to in targets {
+                adjacent[to].push(from);
+            }
+        }
+        for targets in &mut adjacent {
+            targets.sort_unstable();
+            targets.dedup();
+        }
+        adjacent
+    }
+
+    fn indices(&self, ids: &[SystemId]) -> Vec<usize> {
+        ids.iter()
+            .filter_map(|id| self.index.get(id).copied())
+            .collect()
+    }
+
+    /// Breadth first search from `sources` to any of `targets`, skipping `avoid`.
+    /// Returns the predecessors of the first target found and the target itself.
+    fn search(
+        &self,
+        sources: &[usize],
+        targets: &HashSet<usize>,
+        avoid: Option<usize>,
+    ) -> Option<(Vec<usize>, usize)> {
+        let mut previous = vec![usize::MAX; self.ids.len()];
+        let mut visited = vec![false; self.ids.len()];
+        let mut queue = VecDeque::new();
+        for &source in sources {
+            if Some(source) != avoid && !visited[source] {
+                visited[source] = true;
+                queue.push_back(source);
+            }
+        }
+        while let Some(node) = queue.pop_front() {
+            if targets.contains(&node) {
+                return Some((previous, node));
+            }
+            for &next in &self.outgoing[node] {
+                if Some(next) != avoid && !visited[next] {
+                    visited[next] = true;
+                    previous[next] = node;
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Betweenness centrality of every system: the number of shortest paths between
+/// other systems that pass through it, where paths sharing the same length split
+/// the count. Systems at the edge of the map score 0, pipes and hubs score high.
+///
+/// Runs a breadth first search from every system, which takes a few seconds on the
+/// full universe.
+pub fn betweenness_centrality<G: Galaxy>(galaxy: &G) -> HashMap<SystemId, f64> {
+    let graph = Graph::new(galaxy);
+    let n = graph.ids.len();
+    let mut centrality = vec![0.0; n];
+
+    // Brandes' algorithm for unweighted graphs.
+    for source in 0..n {
+        let mut order = Vec::with_capacity(n);
+        let mut predecessors = vec![Vec::new(); n];
+        let mut paths = vec![0.0f64; n];
+        let mut distance = vec![usize::MAX; n];
+        paths[source] = 1.0;
+        distance[source] = 0;
+        let mut queue = VecDeque::from([source]);
+        while let Some(node) = queue.pop_front() {
+            order.push(node);
+            for &next in &graph.outgoing[node] {
+                if distance[next] == usize::MAX {
+                    distance[next] = distance[node] + 1;
+                    queue.push_back(next);
+                }
+                if distance[next] == distance[node] + 1 {
+                    paths[next] += paths[node];
+                    predecessors[next].push(node);
+                }
+            }
+        }
+
+        let mut dependency = vec![0.0f64; n];
+        for &node in order.iter().rev() {
+            for &previous in &predecessors[node] {
+                dependency[previous] += paths[previous] / paths[node] * (1.0 + dependency[node]);
+            }
+            if node != source {
+                centrality[node] += dependency[node];
+            }
+        }
+    }
+
+    graph.ids.into_iter().zip(centrality).collect()
+}
+
+/// Runs Tarjan's algorithm over the undirected graph and returns the articulation
+/// points and the bridges, as pairs of indices with the smaller index first.
+fn low_links(adjacent: &[Vec<usize>]) -> (Vec<usize>, Vec<(usize, usize)>) {
+    const UNSEEN: usize = usize::MAX;
+    let n = adjacent.len();
+    let mut discovered = vec![UNSEEN; n];
+    let mut low = vec![UNSEEN; n];
+    let mut articulation = vec![false; n];
+    let mut bridges = Vec::new();
+    let mut time = 0;
+
+    for root in 0..n {
+        if discovered[root] != UNSEEN {
+            continue;
+        }
+        discovered[root] = time;
+        low[root] = time;
+        time += 1;
+        let mut root_children = 0;
+        // (node, parent, next neighbour to visit)
+        let mut stack = vec![(root, UNSEEN, 0)];
+        while let Some(top) = stack.last_mut() {
+            let (node, parent, next) = *top;
+            if next < adjacent[node].len() {
+                top.2 += 1;
+                let neighbour = adjacent[node][next];
+                if neighbour == parent {
+                    continue;
+                }
+                if discovered[neighbour] == UNSEEN {
+                    discovered[neighbour] = time;
+                    low[neighbour] = time;
+                    time += 1;
+                    stack.push((neighbour, node, 0));
+                } else {
+                    low[node] = low[node].min(discovered[neighbour]);
+                }
+                continue;
+            }
+
+            stack.pop();
+            if parent == UNSEEN {
+                continue;
+            }
+            low[parent] = low[parent].min(low[node]);
+            if low[node] > discovered[parent] {
+                bridges.push((parent.min(node), parent.max(node)));
+            }
+            if parent == root {
+                root_children += 1;
+            } else if low[node] >= discovered[parent] {
+                articulation[parent] = true;
+            }
+        }
+        if root_children > 1 {
+            articulation[root] = true;
+        }
+    }
+
+    let points = (0..n).filter(|&i| articulation[i]).collect();
+    (points, bridges)
+}
+
+/// Systems whose removal splits the map into more pieces, e.g. the system
+/// connecting a dead end pocket to the rest of the region. Sorted by id.
+pub fn articulation_points<G: Galaxy>(galaxy: &G) -> Vec<SystemId> {
+    let graph = Graph::new(galaxy);
+    let (points, _) = low_links(&graph.undirected());
+    points.into_iter().map(|i| graph.ids[i]).collect()
+}
+
+/// Connections whose removal splits the map into more pieces, e.g. the only gate
+/// into a pocket. Each connection is reported once, as a pair with the smaller id
+/// first, sorted.
+pub fn bridge_connections<G: Galaxy>(galaxy: &G) -> Vec<(SystemId, SystemId)> {
+    let graph = Graph::new(galaxy);
+    let (_, bridges) = low_links(&graph.undirected());
+    let mut bridges = bridges
+        .into_iter()
+        .map(|(a, b)| (graph.ids[a], graph.ids[b]))
+        .collect::<Vec<_>>();
+    bridges.sort();
+    bridges
+}
+
+/// Systems every route from any of the systems in `from` to any of the systems in
+/// `to` has to pass through. Removing such a system leaves no route between the
+/// remaining systems of both groups. A system that is all of `from` or all of `to`
+/// is trivially unavoidable and not reported. Returns nothing if there is no route
+/// at all. Sorted by id.
+pub fn chokepoints<G: Galaxy>(galaxy: &G, from: &[SystemId], to: &[SystemId]) -> Vec<SystemId> {
+    let graph = Graph::new(galaxy);
+    let sources = graph.indices(from);
+    let targets = graph.indices(to).into_iter().collect::<HashSet<_>>();
+    let Some((previous, end)) = graph.search(&sources, &targets, None) else {
+        return Vec::new();
+    };
+
+    // A chokepoint lies on every route, so it lies on the one just found.
+    let mut candidates = vec![end];
+    let mut node = end;
+    while previous[node] != usize::MAX {
+        node = previous[node];
+        candidates.push(node);
+    }
+
+    let mut chokepoints = candidates
+        .into_iter()
+        .filter(|&candidate| sources.iter().any(|&s| s != candidate))
+        .filter(|&candidate| targets.iter().any(|&t| t != candidate))
+        .filter(|&candidate| graph.search(&sources, &targets, Some(candidate)).is_none())
+        .map(|i| graph.ids[i])
+        .collect::<Vec<_>>();
+    chokepoints.sort();
+    chokepoints
+}
+
+/// Chokepoints between two regions, see `chokepoints`.
+pub fn region_chokepoints<G: Galaxy>(galaxy: &G, from: RegionId, to: RegionId) -> Vec<SystemId> {
+    let members = |region: RegionId| {
+        galaxy
+            .systems()
+            .into_iter()
+            .filter(|s| s.region == Some(region))
+            .map(|s| s.id)
+            .collect::<Vec<_>>()
+    };
+    chokepoints(galaxy, &members(from), &members(to))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+
+    #[test]
+    fn test_articulation_points_and_bridges() {
+        let universe = universe();
+        assert_eq!(vec![BRAVO, DELTA, GOLF], articulation_points(&universe));
+        assert_eq!(
+            vec![(ALPHA, BRAVO), (DELTA, ECHO), (GOLF, HOTEL)],
+            bridge_connections(&universe)
+        );
+    }
+
+    #[test]
+    fn test_betweenness_centrality() {
+        let universe = universe();
+        let centrality = betweenness_centrality(&universe);
+        assert_eq!(8, centrality.len());
+        for leaf in [ALPHA, ECHO, HOTEL] {
+            assert_eq!(0.0, centrality[&leaf]);
+        }
+        assert!(centrality[&BRAVO] > centrality[&FOXTROT]);
+        assert!(centrality[&GOLF] > centrality[&CHARLIE]);
+    }
+
+    #[test]
+    fn test_chokepoints() {
+        let universe = universe();
+        assert_eq!(
+            vec![BRAVO, DELTA],
+            chokepoints(&universe, &[ALPHA], &[ECHO])
+        );
+        assert_eq!(vec![DELTA], region_chokepoints(&universe, NORTH, SOUTH));
+        assert!(chokepoints(&universe, &[ALPHA], &[SystemId(1)]).is_empty());
+    }
+}
@@ -0,0 +1,119 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! How far systems are from trade hubs, to label systems with e.g. "7 jumps from
+//! Jita" in market tools.
+use std::collections::HashMap;
+
+use crate::navigation::{distance, jump_distances};
+use crate::types;
+
+/// The five main trade hubs: Jita, Amarr, Dodixie, Rens and Hek.
+pub const TRADE_HUBS: [types::SystemId; 5] = [
+    types::SystemId(30000142),
+    types::SystemId(30002187),
+    types::SystemId(30002659),
+    types::SystemId(30002510),
+    types::SystemId(30002053),
+];
+
+/// The hub the fewest jumps from `system` and the jumps to it, `None` if no hub can
+/// be reached. Ties go to the hub listed first.
+pub fn nearest_hub(
+    universe: &dyn types::Navigatable,
+    system: &types::SystemId,
+    hubs: &[types::SystemId],
+) -> Option<(types::SystemId, u32)> {
+    hubs.iter()
+        .zip(jump_distances(universe, system, hubs))
+        .filter_map(|(hub, jumps)| Some((*hub, jumps?)))
+        .min_by_key(|(_, jumps)| *jumps)
+}
+
+/// The jumps between a few hubs and every system, computed once with one search
+/// per hub. Jumps are counted from the hub, which is the same as to the hub as long
+/// as all connections go both ways, as stargates do.
+#[derive(Debug, Clone)]
+pub struct HubDistances {
+    hubs: Vec<(types::SystemId, HashMap<types::SystemId, u32>)>,
+}
+
+impl HubDistances {
+    pub fn new(universe: &dyn types::Navigatable, hubs: &[types::SystemId]) -> Self {
+        Self {
+            hubs: hubs
+                .iter()
+                .map(|hub| (*hub, distance::jumps_from(universe, hub)))
+                .collect(),
+        }
+    }
+
+    /// The distances to the `TRADE_HUBS`.
+    pub fn trade_hubs(universe: &dyn types::Navigatable) -> Self {
+        Self::new(universe, &TRADE_HUBS)
+    }
+
+    pub fn hubs(&self) -> impl Iterator<Item = types::SystemId> + '_ {
+        self.hubs.iter().map(|(hub, _)| *hub)
+    }
+
+    /// The jumps between `system` and `hub`, `None` if either is unknown or they
+    /// are not connected.
+    pub fn jumps(&self, system: &types::SystemId, hub: &types::SystemId) -> Option<u32> {
+        let (_, jumps) = self.hubs.iter().find(|(id, _)| id == hub)?;
+        jumps.get(system).copied()
+    }
+
+    /// The jumps between `system` and every hub, in the order the hubs were given.
+    pub fn all(&self, system: &types::SystemId) -> Vec<(types::SystemId, Option<u32>)> {
+        self.hubs
+            .iter()
+            .map(|(hub, jumps)| (*hub, jumps.get(system).copied()))
+            .collect()
+    }
+
+    /// Like `nearest_hub`, but without searching.
+    pub fn nearest(&self, system: &types::SystemId) -> Option<(types::SystemId, u32)> {
+        self.all(system)
+            .into_iter()
+            .filter_map(|(hub, jumps)| Some((hub, jumps?)))
+            .min_by_key(|(_, jumps)| *jumps)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+
+    #[test]
+    fn test_nearest_hub() {
+        let universe = universe();
+        assert_eq!(
+            Some((ALPHA, 1)),
+            nearest_hub(&universe, &BRAVO, &[ECHO, ALPHA])
+        );
+        // Echo and Alpha are both two jumps from Charlie
+        assert_eq!(
+            Some((ECHO, 2)),
+            nearest_hub(&universe, &CHARLIE, &[ECHO, ALPHA])
+        );
+        assert_eq!(None, nearest_hub(&universe, &HOTEL, &[]));
+
+        let distances = HubDistances::new(&universe, &[ECHO, ALPHA]);
+        for system in [ALPHA, BRAVO, CHARLIE, DELTA, ECHO, FOXTROT, GOLF, HOTEL] {
+            assert_eq!(
+                nearest_hub(&universe, &system, &[ECHO, ALPHA]),
+                distances.nearest(&system)
+            );
+        }
+        assert_eq!(
+            vec![(ECHO, Some(4)), (ALPHA, Some(0))],
+            distances.all(&ALPHA)
+        );
+        assert_eq!(None, distances.jumps(&ALPHA, &HOTEL));
+        assert_eq!(None, distances.nearest(&types::SystemId(1)));
+    }
+}
@@ -0,0 +1,15 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Aggregate analyses over a universe that go beyond a single route.
+pub mod archetype;
+pub mod chokepoints;
+pub mod hubs;
+pub mod regions;
+pub mod stats;
+
+pub use hubs::{nearest_hub, HubDistances};
+pub use regions::{border_systems, constellation_border_systems, gateways_between};
+pub use stats::UniverseStats;
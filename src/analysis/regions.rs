@@ -0,0 +1,384 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Security statistics per region, region borders and safe corridors between regions.
+use std::collections::{HashMap, HashSet};
+
+use crate::navigation::{costs_from, Cost, Path, PathBuilder};
+use crate::types::{self, Galaxy, Navigatable};
+
+/// Extra cost of entering a lowsec system when looking for corridors.
+const LOWSEC_PENALTY: Cost = 10;
+/// Extra cost of entering a nullsec system when looking for corridors.
+const NULLSEC_PENALTY: Cost = 50;
+
+/// Summary of the security ratings of the systems in a region.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegionSecurityStats {
+    pub region: types::RegionId,
+    pub systems: usize,
+    pub highsec: usize,
+    pub lowsec: usize,
+    pub nullsec: usize,
+    pub min_security: types::Security,
+    pub max_security: types::Security,
+    pub mean_security: types::Security,
+}
+
+impl RegionSecurityStats {
+    fn from_systems(region: types::RegionId, systems: &[&types::System]) -> Self {
+        let mut stats = Self {
+            region,
+            systems: systems.len(),
+            highsec: 0,
+            lowsec: 0,
+            nullsec: 0,
+            min_security: types::Security(f32::MAX),
+            max_security: types::Security(f32::MIN),
+            mean_security: types::Security(0.0),
+        };
+        let mut sum = 0.0;
+        for system in systems {
+            match types::SecurityClass::from(&system.security) {
+                types::SecurityClass::Highsec => stats.highsec += 1,
+                types::SecurityClass::Lowsec => stats.lowsec += 1,
+                types::SecurityClass::Nullsec => stats.nullsec += 1,
+            }
+            stats.min_security.0 = stats.min_security.0.min(system.security.0);
+            stats.max_security.0 = stats.max_security.0.max(system.security.0);
+            sum += system.security.0;
+        }
+        stats.mean_security.0 = sum / systems.len() as f32;
+        stats
+    }
+}
+
+fn systems_by_region<U: Galaxy>(universe: &U) -> HashMap<types::RegionId, Vec<&types::System>> {
+    let mut regions: HashMap<_, Vec<_>> = HashMap::new();
+    for system in universe.systems() {
+        if let Some(region) = system.region {
+            regions.entry(region).or_default().push(system);
+        }
+    }
+    regions
+}
+
+/// Returns security statistics for every region, ordered by region id. Systems without
+/// a known region are not included.
+pub fn region_stats<U: Galaxy>(universe: &U) -> Vec<RegionSecurityStats> {
+    let mut stats: Vec<_> = systems_by_region(universe)
+        .into_iter()
+        .map(|(region, systems)| RegionSecurityStats::from_systems(region, &systems))
+        .collect();
+    stats.sort_by_key(|s| s.region);
+    stats
+}
+
+/// Returns security statistics for a single region, or `None` if no system is known to
+/// be in it.
+pub fn region_security_stats<U: Galaxy>(
+    universe: &U,
+    region: types::RegionId,
+) -> Option<RegionSecurityStats> {
+    let systems: Vec<_> = universe
+        .systems()
+        .into_iter()
+        .filter(|s| s.region == Some(region))
+        .collect();
+    if systems.is_empty() {
+        return None;
+    }
+    Some(RegionSecurityStats::from_systems(region, &systems))
+}
+
+/// Whether a system has a stargate of one of the given types.
+fn has_stargate<U: Navigatable>(
+    universe: &U,
+    system: &types::System,
+    types: &[types::StargateType],
+) -> bool {
+    universe
+        .get_connections(&system.id)
+        .unwrap_or_default()
+        .iter()
+        .any(|c| matches!(&c.type_, types::ConnectionType::Stargate(t) if types.contains(t)))
+}
+
+/// Returns the systems of a region with a stargate into another region, ordered by id.
+pub fn border_systems<U: Galaxy + Navigatable>(
+    universe: &U,
+    region: types::RegionId,
+) -> Vec<&types::System> {
+    let mut systems: Vec<_> = universe
+        .systems()
+        .into_iter()
+        .filter(|s| s.region == Some(region))
+        .filter(|s| has_stargate(universe, s, &[types::StargateType::Regional]))
+        .collect();
+    systems.sort_by_key(|s| s.id);
+    systems
+}
+
+/// Returns the systems of a constellation with a stargate into another constellation
+/// or region, ordered by id.
+pub fn constellation_border_systems<U: Galaxy + Navigatable>(
+    universe: &U,
+    constellation: types::ConstellationId,
+) -> Vec<&types::System> {
+    let crossing = [
+        types::StargateType::Constellation,
+        types::StargateType::Regional,
+    ];
+    let mut systems: Vec<_> = universe
+        .systems()
+        .into_iter()
+        .filter(|s| s.constellation == Some(constellation))
+        .filter(|s| has_stargate(universe, s, &crossing))
+        .collect();
+    systems.sort_by_key(|s| s.id);
+    systems
+}
+
+/// Returns the pairs of systems connecting region `from` directly to region `to`, the
+/// first system of each pair being in `from`. Ordered by id.
+pub fn gateways_between<U: Galaxy + Navigatable>(
+    universe: &U,
+    from: types::RegionId,
+    to: types::RegionId,
+) -> Vec<(&types::System, &types::System)> {
+    let mut gateways = Vec::new();
+    for system in universe.systems() {
+        if system.region != Some(from) {
+            continue;
+        }
+        for connection in universe
+            .get_connections(&system.id)
+            .unwrap_or_default()
+            .iter()
+        {
+            if let Some(other) = universe.get_system(&connection.to) {
+                if other.region == Some(to) {
+                    gateways.push((system, other));
+                }
+            }
+        }
+    }
+    gateways.sort_by_key(|(a, b)| (a.id, b.id));
+    gateways.dedup_by_key(|(a, b)| (a.id, b.id));
+    gateways
+}
+
+/// A route from one region into another, as suggested by `safest_corridors`.
+pub struct Corridor<'a> {
+    pub path: Path<'a>,
+    /// Number of lowsec systems entered on the path. Like `score`, this leaves out
+    /// the gateway the path starts in.
+    pub lowsec: usize,
+    /// Number of nullsec systems entered on the path.
+    pub nullsec: usize,
+    /// Sum of the kills of the systems entered on the path.
+    pub kills: u32,
+    /// The cost the corridor was ranked by. Lower is safer.
+    pub score: Cost,
+}
+
+/// Suggests up to `limit` corridors leading from region `from` into region `to`,
+/// safest first.
+///
+/// Every system in `from` with a gate leaving the region is considered as a starting
+/// point. Corridors are ranked by jumps, with a penalty for every lowsec and nullsec
+/// system entered and for every kill in `kills`, usually the kills in the last hour or
+/// day. Corridors that differ only in their route within `from` are reported once.
+///
+/// # Example
+/// ```
+/// use std::collections::HashMap;
+/// use neweden::analysis::regions::safest_corridors;
+/// use neweden::builder::UniverseBuilder;
+/// use neweden::{Connection, ConnectionSource, ConnectionType, Coordinate, RegionId, Security, StargateType, System};
+///
+/// let mut a = System::new(1.into(), "A", Coordinate { x: 0.0, y: 0.0, z: 0.0 }, Security(0.9));
+/// a.region = Some(RegionId(1));
+/// let mut b = System::new(2.into(), "B", Coordinate { x: 1.0, y: 0.0, z: 0.0 }, Security(0.8));
+/// b.region = Some(RegionId(2));
+/// let universe = UniverseBuilder::new()
+///     .system(a)
+///     .system(b)
+///     .connection(Connection {
+///         from: 1.into(),
+///         to: 2.into(),
+///         type_: ConnectionType::Stargate(StargateType::Regional),
+///         source: ConnectionSource::Sde,
+///     })
+///     .build();
+///
+/// let corridors = safest_corridors(&universe, RegionId(1), RegionId(2), &HashMap::new(), 3);
+/// assert_eq!(1, corridors.len());
+/// assert_eq!(1, corridors[0].path.jumps());
+/// ```
+pub fn safest_corridors<'a, U: Galaxy + Navigatable>(
+    universe: &'a U,
+    from: types::RegionId,
+    to: types::RegionId,
+    kills: &HashMap<types::SystemId, u32>,
+    limit: usize,
+) -> Vec<Corridor<'a>> {
+    let recent = kills.clone();
+    let cost = move |universe: &dyn Navigatable, connection: &types::Connection| {
+        let system = universe.get_system(&connection.to)?;
+        let penalty = match types::SecurityClass::from(&system.security) {
+            types::SecurityClass::Highsec => 0,
+            types::SecurityClass::Lowsec => LOWSEC_PENALTY,
+            types::SecurityClass::Nullsec => NULLSEC_PENALTY,
+        };
+        Some(1 + penalty + recent.get(&connection.to).copied().unwrap_or(0))
+    };
+
+    let gateways = universe.systems().into_iter().filter(|s| {
+        s.region == Some(from)
+            && universe
+                .get_connections(&s.id)
+                .unwrap_or_default()
+                .iter()
+                .any(|c| {
+                    universe
+                        .get_system(&c.to)
+                        .is_some_and(|t| t.region != Some(from))
+                })
+    });
+
+    let mut builder = PathBuilder::new(universe).cost_function(cost.clone());
+    let mut seen = HashSet::new();
+    let mut corridors = Vec::new();
+    for gateway in gateways {
+        let costs = costs_from(universe, &cost, gateway.id);
+        let target = costs
+            .iter()
+            .filter(|(id, _)| {
+                universe
+                    .get_system(id)
+                    .is_some_and(|s| s.region == Some(to))
+            })
+            .min_by_key(|(id, cost)| (**cost, **id));
+        let Some((target, &score)) = target else {
+            continue;
+        };
+        let Some(target) = universe.get_system(target) else {
+            continue;
+        };
+        builder
+            .clear_waypoints()
+            .add_waypoints(vec![gateway, target]);
+        let Some(path) = builder.build() else {
+            continue;
+        };
+
+        // the route inside the origin region is what differs between gateways that
+        // share an exit, only keep the cheapest of them
+        let route: Vec<_> = path
+            .systems()
+            .skip_while(|s| s.region == Some(from))
+            .map(|s| s.id)
+            .collect();
+        let mut corridor = Corridor {
+            path,
+            lowsec: 0,
+            nullsec: 0,
+            kills: 0,
+            score,
+        };
+        for system in corridor.path.systems().skip(1) {
+            match types::SecurityClass::from(&system.security) {
+                types::SecurityClass::Highsec => {}
+                types::SecurityClass::Lowsec => corridor.lowsec += 1,
+                types::SecurityClass::Nullsec => corridor.nullsec += 1,
+            }
+            corridor.kills += kills.get(&system.id).copied().unwrap_or(0);
+        }
+        corridors.push((route, corridor));
+    }
+
+    corridors
+        .sort_by(|(a, x), (b, y)| (x.score, x.path.jumps(), a).cmp(&(y.score, y.path.jumps(), b)));
+    corridors
+        .into_iter()
+        .filter(|(route, _)| seen.insert(route.clone()))
+        .map(|(_, c)| c)
+        .take(limit)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+
+    #[test]
+    fn test_region_stats() {
+        let universe = universe();
+        let stats = region_stats(&universe);
+        assert_eq!(
+            vec![NORTH, MIDDLE, SOUTH],
+            stats.iter().map(|s| s.region).collect::<Vec<_>>()
+        );
+
+        let middle = region_security_stats(&universe, MIDDLE).unwrap();
+        assert_eq!(3, middle.systems);
+        assert_eq!((2, 1, 0), (middle.highsec, middle.lowsec, middle.nullsec));
+        assert_eq!(types::Security(0.1), middle.min_security);
+        assert_eq!(types::Security(0.6), middle.max_security);
+        assert!((middle.mean_security.0 - 0.4).abs() < 1e-6);
+
+        assert!(region_security_stats(&universe, types::RegionId(1)).is_none());
+    }
+
+    #[test]
+    fn test_borders() {
+        let universe = universe();
+        let ids = |systems: Vec<&types::System>| systems.iter().map(|s| s.id).collect::<Vec<_>>();
+        assert_eq!(vec![BRAVO, FOXTROT], ids(border_systems(&universe, NORTH)));
+        assert_eq!(vec![DELTA], ids(border_systems(&universe, SOUTH)));
+        assert_eq!(
+            vec![BRAVO],
+            ids(constellation_border_systems(
+                &universe,
+                types::ConstellationId(20000001)
+            ))
+        );
+
+        let gateways = gateways_between(&universe, MIDDLE, SOUTH)
+            .into_iter()
+            .map(|(a, b)| (a.id, b.id))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![(CHARLIE, DELTA), (GOLF, DELTA)], gateways);
+        assert!(gateways_between(&universe, NORTH, SOUTH).is_empty());
+    }
+
+    #[test]
+    fn test_safest_corridors() {
+        let universe = universe();
+        let ids = |c: &Corridor| c.path.systems().map(|s| s.id).collect::<Vec<_>>();
+
+        let corridors = safest_corridors(&universe, NORTH, SOUTH, &HashMap::new(), 5);
+        assert_eq!(2, corridors.len());
+        assert_eq!(vec![BRAVO, CHARLIE, DELTA], ids(&corridors[0]));
+        assert_eq!(vec![FOXTROT, GOLF, DELTA], ids(&corridors[1]));
+        assert!(corridors.iter().all(|c| c.lowsec == 1 && c.nullsec == 0));
+
+        // kills in Charlie send Bravo's traffic through Golf as well
+        let kills = HashMap::from([(CHARLIE, 5)]);
+        let corridors = safest_corridors(&universe, NORTH, SOUTH, &kills, 5);
+        assert_eq!(1, corridors.len());
+        assert_eq!(vec![FOXTROT, GOLF, DELTA], ids(&corridors[0]));
+        assert_eq!(0, corridors[0].kills);
+
+        let corridors = safest_corridors(&universe, SOUTH, NORTH, &HashMap::new(), 1);
+        assert_eq!(1, corridors.len());
+        assert_eq!(vec![DELTA, CHARLIE, BRAVO], ids(&corridors[0]));
+        // the lowsec gateway Delta is not entered
+        assert_eq!(0, corridors[0].lowsec);
+        assert_eq!(2, corridors[0].score);
+    }
+}
@@ -0,0 +1,138 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! A summary of a universe, to sanity check a freshly loaded static data export or
+//! to show on a dashboard.
+//!
+//! # Example
+//! ```
+//! use neweden::testing;
+//!
+//! let stats = testing::universe().stats();
+//! assert_eq!(8, stats.systems);
+//! assert_eq!(4, stats.diameter.unwrap().jumps);
+//! ```
+use std::collections::HashSet;
+
+use crate::navigation::{estimate_diameter, Diameter};
+use crate::types::{RegionId, SecurityClass, SpatialEntry, Universe};
+
+/// Counts and shape of a universe, see `Universe::stats`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UniverseStats {
+    pub systems: usize,
+    /// Connections in both directions are counted twice, as they are stored.
+    pub connections: usize,
+    /// Known space systems by security class. Wormhole and Pochven systems are only
+    /// counted by system class.
+    pub highsec: usize,
+    pub lowsec: usize,
+    pub nullsec: usize,
+    pub kspace: usize,
+    pub wspace: usize,
+    pub pochven: usize,
+    /// Systems with an id outside of known and wormhole space, e.g. abyssal space.
+    pub other: usize,
+    /// Regions with at least one system.
+    pub regions: usize,
+    /// The outgoing connections per system.
+    pub average_degree: f64,
+    /// A lower bound of the diameter, see `navigation::estimate_diameter`.
+    pub diameter: Option<Diameter>,
+    /// The approximate memory taken by the spatial index, in bytes.
+    pub spatial_index_bytes: usize,
+}
+
+impl Universe {
+    /// Summarizes the universe. Runs two breadth first searches for the diameter
+    /// and is otherwise linear in the size of the universe.
+    pub fn stats(&self) -> UniverseStats {
+        let mut stats = UniverseStats {
+            systems: self.systems.len(),
+            connections: self.connections.len(),
+            highsec: 0,
+            lowsec: 0,
+            nullsec: 0,
+            kspace: 0,
+            wspace: 0,
+            pochven: 0,
+            other: 0,
+            regions: 0,
+            average_degree: 0.0,
+            diameter: estimate_diameter(self),
+            spatial_index_bytes: self.spatial_index_bytes(),
+        };
+        let mut regions = HashSet::new();
+        for system in self.systems.iter() {
+            regions.extend(system.region);
+            match system.id.0 {
+                _ if system.region == Some(RegionId::POCHVEN) => stats.pochven += 1,
+                0..=30999999 => {
+                    stats.kspace += 1;
+                    match SecurityClass::from(system.security) {
+                        SecurityClass::Highsec => stats.highsec += 1,
+                        SecurityClass::Lowsec => stats.lowsec += 1,
+                        SecurityClass::Nullsec => stats.nullsec += 1,
+                    }
+                }
+                31000000..=31999999 => stats.wspace += 1,
+                _ => stats.other += 1,
+            }
+        }
+        stats.regions = regions.len();
+        if stats.systems > 0 {
+            stats.average_degree = stats.connections as f64 / stats.systems as f64;
+        }
+        stats
+    }
+
+    /// The nodes of the spatial index and the vectors holding them. Spare capacity
+    /// of the vectors is not known and not counted.
+    fn spatial_index_bytes(&self) -> usize {
+        fn children(node: &rstar::ParentNode<SpatialEntry>) -> usize {
+            node.children()
+                .iter()
+                .map(|child| match child {
+                    rstar::RTreeNode::Leaf(_) => 0,
+                    rstar::RTreeNode::Parent(parent) => children(parent),
+                })
+                .sum::<usize>()
+                + node.children().len()
+        }
+        let node = std::mem::size_of::<rstar::RTreeNode<SpatialEntry>>();
+        std::mem::size_of::<rstar::RTree<SpatialEntry>>() + children(self.rtree.root()) * node
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::*;
+    use crate::types::Universe;
+
+    #[test]
+    fn test_stats() {
+        let stats = universe().stats();
+        assert_eq!(8, stats.systems);
+        assert_eq!(18, stats.connections);
+        // Delta and Hotel are lowsec, Echo is nullsec
+        assert_eq!((5, 2, 1), (stats.highsec, stats.lowsec, stats.nullsec));
+        assert_eq!(
+            (8, 0, 0, 0),
+            (stats.kspace, stats.wspace, stats.pochven, stats.other)
+        );
+        assert_eq!(3, stats.regions);
+        assert_eq!(2.25, stats.average_degree);
+        assert!(stats.spatial_index_bytes >= 8 * std::mem::size_of::<crate::types::SystemId>());
+
+        let stats = cluster().stats();
+        assert_eq!(30, stats.systems);
+        assert_eq!(16, stats.diameter.unwrap().jumps);
+
+        let empty = Universe::empty().stats();
+        assert_eq!(0, empty.systems);
+        assert_eq!(0.0, empty.average_degree);
+        assert_eq!(None, empty.diameter);
+    }
+}
@@ -1,8 +1,22 @@
+use crate::navigation::ShipProfile;
 use crate::types;
+use crate::validate::IntegrityIssue;
 
 pub struct UniverseBuilder {
     systems: types::SystemMap,
     connections: types::AdjacentMap,
+    celestials: Vec<types::Celestial>,
+    stargates: Vec<types::Stargate>,
+    effects: Vec<(types::SystemId, types::SystemEffect)>,
+    wormhole_classes: Vec<(types::SystemId, types::WormholeClass)>,
+    localized_names: Vec<(types::SystemId, types::Locale, String)>,
+    strict: bool,
+}
+
+impl Default for UniverseBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl UniverseBuilder {
@@ -10,69 +24,415 @@ impl UniverseBuilder {
         Self {
             systems: types::SystemMap::empty(),
             connections: types::AdjacentMap::empty(),
+            celestials: Vec::new(),
+            stargates: Vec::new(),
+            effects: Vec::new(),
+            wormhole_classes: Vec::new(),
+            localized_names: Vec::new(),
+            strict: false,
         }
     }
 
     pub fn system(mut self, system: types::System) -> Self {
-        self.systems.0.insert(system.id, system);
+        self.add_system(system);
         self
     }
 
     pub fn connection(mut self, connection: types::Connection) -> Self {
+        self.add_connection(connection);
+        self
+    }
+
+    /// Adds the connection and the same connection in the opposite direction, like
+    /// stargates, which are stored once per direction.
+    pub fn connection_bidirectional(mut self, connection: types::Connection) -> Self {
+        self.add_connection_bidirectional(connection);
+        self
+    }
+
+    pub fn celestial(mut self, celestial: types::Celestial) -> Self {
+        self.add_celestial(celestial);
+        self
+    }
+
+    pub fn stargate(mut self, stargate: types::Stargate) -> Self {
+        self.add_stargate(stargate);
+        self
+    }
+
+    pub fn effect(mut self, system: types::SystemId, effect: types::SystemEffect) -> Self {
+        self.add_effect(system, effect);
+        self
+    }
+
+    pub fn wormhole_class(mut self, system: types::SystemId, class: types::WormholeClass) -> Self {
+        self.add_wormhole_class(system, class);
+        self
+    }
+
+    /// The name of a system in another language.
+    pub fn localized_name(
+        mut self,
+        system: types::SystemId,
+        locale: types::Locale,
+        name: &str,
+    ) -> Self {
+        self.add_localized_name(system, locale, name);
+        self
+    }
+
+    /// Makes `try_build` fail on any integrity issue, not only on those that break
+    /// routing. See `Universe::validate`.
+    pub fn strict(mut self) -> Self {
+        self.set_strict(true);
+        self
+    }
+
+    /// Like `system`, but for use when the builder is not consumed, e.g. in loops.
+    pub fn add_system(&mut self, system: types::System) -> &mut Self {
+        self.systems.0.insert(system.id, system);
+        self
+    }
+
+    /// Like `connection`, but for use when the builder is not consumed, e.g. in loops.
+    pub fn add_connection(&mut self, connection: types::Connection) -> &mut Self {
         self.connections
             .0
             .entry(connection.from)
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(connection);
         self
     }
 
+    /// Like `connection_bidirectional`, but for use when the builder is not consumed,
+    /// e.g. in loops.
+    pub fn add_connection_bidirectional(&mut self, connection: types::Connection) -> &mut Self {
+        self.add_connection(connection.reversed());
+        self.add_connection(connection)
+    }
+
+    /// Like `celestial`, but for use when the builder is not consumed, e.g. in loops.
+    pub fn add_celestial(&mut self, celestial: types::Celestial) -> &mut Self {
+        self.celestials.push(celestial);
+        self
+    }
+
+    /// Like `stargate`, but for use when the builder is not consumed, e.g. in loops.
+    pub fn add_stargate(&mut self, stargate: types::Stargate) -> &mut Self {
+        self.stargates.push(stargate);
+        self
+    }
+
+    /// Like `effect`, but for use when the builder is not consumed, e.g. in loops.
+    pub fn add_effect(
+        &mut self,
+        system: types::SystemId,
+        effect: types::SystemEffect,
+    ) -> &mut Self {
+        self.effects.push((system, effect));
+        self
+    }
+
+    /// Like `wormhole_class`, but for use when the builder is not consumed, e.g. in loops.
+    pub fn add_wormhole_class(
+        &mut self,
+        system: types::SystemId,
+        class: types::WormholeClass,
+    ) -> &mut Self {
+        self.wormhole_classes.push((system, class));
+        self
+    }
+
+    /// Like `localized_name`, but for use when the builder is not consumed, e.g. in loops.
+    pub fn add_localized_name(
+        &mut self,
+        system: types::SystemId,
+        locale: types::Locale,
+        name: &str,
+    ) -> &mut Self {
+        self.localized_names
+            .push((system, locale, name.to_string()));
+        self
+    }
+
+    /// Like `strict`, but for use when the builder is not consumed.
+    pub fn set_strict(&mut self, strict: bool) -> &mut Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Like `build`, but validates the universe. Fails with the issues that break
+    /// routing, e.g. connections to systems that were never added, or with all
+    /// issues in strict mode.
+    pub fn try_build(self) -> Result<types::Universe, Vec<IntegrityIssue>> {
+        let strict = self.strict;
+        let universe = self.build();
+        match universe.validate() {
+            Ok(()) => Ok(universe),
+            Err(issues) if strict => Err(issues),
+            Err(issues) => {
+                let fatal = issues
+                    .into_iter()
+                    .filter(|i| i.is_fatal())
+                    .collect::<Vec<_>>();
+                if fatal.is_empty() {
+                    Ok(universe)
+                } else {
+                    Err(fatal)
+                }
+            }
+        }
+    }
+
     pub fn build(self) -> types::Universe {
         types::Universe::new(self.systems, self.connections)
+            .with_celestials(self.celestials)
+            .with_stargates(self.stargates)
+            .with_effects(self.effects)
+            .with_wormhole_classes(self.wormhole_classes)
+            .with_localized_names(self.localized_names)
     }
 }
 
-pub struct ExtendedUniverseBuilder<'a, U> {
-    universe: &'a U,
+/// Problems with a connection added to an `ExtendedUniverseBuilder`.
+#[derive(Debug, Clone, Copy, PartialEq, thiserror::Error)]
+pub enum ConnectionError {
+    #[error("unknown system {0:?}")]
+    UnknownSystem(types::SystemId),
+    #[error("bridge from {from:?} to {to:?} spans {distance:?} but only reaches {range:?}")]
+    OutOfRange {
+        from: types::SystemId,
+        to: types::SystemId,
+        distance: types::Lightyears,
+        range: types::Lightyears,
+    },
+}
+
+pub struct ExtendedUniverseBuilder<U> {
+    universe: U,
     connections: types::AdjacentMap,
+    removed: Vec<(types::SystemId, types::SystemId)>,
+    warnings: Vec<ConnectionError>,
 }
 
-impl<'a, U: types::Galaxy + types::Navigatable> ExtendedUniverseBuilder<'a, U> {
-    pub fn new(universe: &'a U) -> Self {
+impl<U: types::Galaxy + types::Navigatable> ExtendedUniverseBuilder<U> {
+    /// Extends a borrowed, shared or owned universe, see `ExtendedUniverse`.
+    pub fn new(universe: U) -> Self {
         Self {
             universe,
             connections: types::AdjacentMap::empty(),
+            removed: Vec::new(),
+            warnings: Vec::new(),
         }
     }
 
     pub fn bridge(mut self, location: types::SystemId, type_: types::BridgeType) -> Self {
+        self.add_bridge(location, type_);
+        self
+    }
+
+    /// Adds the bridge the ship can open, if it can open one.
+    pub fn ship_bridge(mut self, location: types::SystemId, ship: &ShipProfile) -> Self {
+        self.add_ship_bridge(location, ship);
+        self
+    }
+
+    /// Adds the connection even if it is implausible, e.g. a bridge between systems
+    /// that are out of range of each other. Such problems are collected in `warnings`.
+    pub fn connection(mut self, connection: types::Connection) -> Self {
+        self.add_connection(connection);
+        self
+    }
+
+    /// Like `connection`, but refuses connections between unknown systems and bridges
+    /// longer than the bridge's range.
+    pub fn try_connection(
+        mut self,
+        connection: types::Connection,
+    ) -> Result<Self, ConnectionError> {
+        self.try_add_connection(connection)?;
+        Ok(self)
+    }
+
+    /// Adds the connection and the same connection in the opposite direction.
+    pub fn connection_bidirectional(mut self, connection: types::Connection) -> Self {
+        self.add_connection_bidirectional(connection);
+        self
+    }
+
+    /// Hides all connections from `from` to `to`, see `ExtendedUniverse::remove_connection`.
+    pub fn without_connection(mut self, from: types::SystemId, to: types::SystemId) -> Self {
+        self.remove_connection(from, to);
+        self
+    }
+
+    /// Like `bridge`, but for use when the builder is not consumed, e.g. in loops.
+    pub fn add_bridge(&mut self, location: types::SystemId, type_: types::BridgeType) -> &mut Self {
         let ly: types::Lightyears = type_.clone().into();
-        for end in self
+        let ends = self
             .universe
             .get_systems_by_range(&location, ly.into())
             .unwrap_or(vec![])
-        {
+            .iter()
+            .map(|s| s.id)
+            .collect::<Vec<_>>();
+        for end in ends {
             let connection = types::Connection {
                 from: location,
-                to: end.id,
+                to: end,
                 type_: types::ConnectionType::Bridge(type_.clone()),
+                source: types::ConnectionSource::UserOverlay,
             };
-            self = self.connection(connection);
+            self.add_connection(connection);
         }
 
         self
     }
 
-    pub fn connection(mut self, connection: types::Connection) -> Self {
+    /// Like `ship_bridge`, but for use when the builder is not consumed, e.g. in loops.
+    pub fn add_ship_bridge(&mut self, location: types::SystemId, ship: &ShipProfile) -> &mut Self {
+        if let Some(bridge) = ship.bridge() {
+            self.add_bridge(location, bridge);
+        }
+        self
+    }
+
+    /// Like `connection`, but for use when the builder is not consumed, e.g. in loops.
+    pub fn add_connection(&mut self, connection: types::Connection) -> &mut Self {
+        if let Err(warning) = self.validate(&connection) {
+            self.warnings.push(warning);
+        }
         self.connections
             .0
             .entry(connection.from)
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(connection);
         self
     }
 
-    pub fn build(self) -> types::ExtendedUniverse<'a, U> {
-        types::ExtendedUniverse::new(self.universe, self.connections)
+    /// Like `try_connection`, but for use when the builder is not consumed, e.g. in loops.
+    pub fn try_add_connection(
+        &mut self,
+        connection: types::Connection,
+    ) -> Result<&mut Self, ConnectionError> {
+        self.validate(&connection)?;
+        Ok(self.add_connection(connection))
+    }
+
+    /// Like `connection_bidirectional`, but for use when the builder is not consumed,
+    /// e.g. in loops.
+    pub fn add_connection_bidirectional(&mut self, connection: types::Connection) -> &mut Self {
+        self.add_connection(connection.reversed());
+        self.add_connection(connection)
+    }
+
+    /// Problems with the connections added so far.
+    pub fn warnings(&self) -> &[ConnectionError] {
+        &self.warnings
+    }
+
+    fn validate(&self, connection: &types::Connection) -> Result<(), ConnectionError> {
+        let system = |id| {
+            self.universe
+                .get_system(id)
+                .ok_or(ConnectionError::UnknownSystem(*id))
+        };
+        let from = system(&connection.from)?;
+        let to = system(&connection.to)?;
+        if let types::ConnectionType::Bridge(bridge) = &connection.type_ {
+            let range: types::Lightyears = bridge.clone().into();
+            let distance = types::Lightyears::from(from.distance(to));
+            if distance > range {
+                return Err(ConnectionError::OutOfRange {
+                    from: from.id,
+                    to: to.id,
+                    distance,
+                    range,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Like `without_connection`, but for use when the builder is not consumed, e.g. in loops.
+    pub fn remove_connection(&mut self, from: types::SystemId, to: types::SystemId) -> &mut Self {
+        self.removed.push((from, to));
+        self
+    }
+
+    pub fn build(self) -> types::ExtendedUniverse<U> {
+        let mut universe = types::ExtendedUniverse::new(self.universe, self.connections);
+        for (from, to) in self.removed {
+            universe.remove_connection(from, to);
+        }
+        universe
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+    use crate::types::Navigatable;
+
+    fn bridge(from: types::SystemId, to: types::SystemId) -> types::Connection {
+        types::Connection {
+            from,
+            to,
+            type_: types::ConnectionType::Bridge(types::BridgeType::Titan(
+                types::JumpdriveSkills::new(0, 0),
+            )),
+            source: types::ConnectionSource::UserOverlay,
+        }
+    }
+
+    #[test]
+    fn test_bridge_validation() {
+        let universe = universe();
+        // a titan without skills bridges 3 lightyears, Delta and Echo are 2 apart
+        let builder = ExtendedUniverseBuilder::new(&universe)
+            .try_connection(bridge(DELTA, ECHO))
+            .unwrap();
+        assert!(builder.warnings().is_empty());
+
+        match builder.try_connection(bridge(ALPHA, ECHO)) {
+            Err(ConnectionError::OutOfRange {
+                distance, range, ..
+            }) => {
+                assert!((distance.0 - 8.0).abs() < 1e-6);
+                assert_eq!(types::Lightyears(3.0), range);
+            }
+            _ => panic!("bridge out of range was accepted"),
+        }
+
+        let builder = ExtendedUniverseBuilder::new(&universe)
+            .connection(bridge(ALPHA, ECHO))
+            .connection(bridge(ALPHA, types::SystemId(1)));
+        assert_eq!(2, builder.warnings().len());
+        assert_eq!(
+            ConnectionError::UnknownSystem(types::SystemId(1)),
+            builder.warnings()[1]
+        );
+        // the gate to Bravo and both bridges
+        assert_eq!(3, builder.build().get_connections(&ALPHA).unwrap().len());
+
+        let titan = ShipProfile::new(types::HullClass::Titan);
+        let extended = ExtendedUniverseBuilder::new(&universe)
+            .ship_bridge(DELTA, &ShipProfile::default())
+            .build();
+        assert!(extended
+            .get_connections(&DELTA)
+            .unwrap()
+            .iter()
+            .all(|c| !matches!(c.type_, types::ConnectionType::Bridge(_))));
+        let extended = ExtendedUniverseBuilder::new(&universe)
+            .ship_bridge(DELTA, &titan)
+            .build();
+        assert!(extended
+            .get_connections(&DELTA)
+            .unwrap()
+            .iter()
+            .any(|c| c.to == ECHO
+                && c.type_ == types::ConnectionType::Bridge(titan.bridge().unwrap())));
     }
 }
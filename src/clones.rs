@@ -0,0 +1,205 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Getting a body to a system: flying the pod there, jumping into a clone closer
+//! to it, or self-destructing and waking up in the medical clone.
+//!
+//! Clone jumps are assumed to be possible from wherever the character is, i.e. the
+//! character is docked in a station with a clone bay. Self-destructing loses the
+//! implants of the active clone, which `body_options` does not weigh in.
+//!
+//! # Example
+//! ```
+//! use std::time::Duration;
+//! use neweden::builder::UniverseBuilder;
+//! use neweden::clones::{body_options, BodyOption, Character};
+//! use neweden::navigation::ShipProfile;
+//! use neweden::{Connection, ConnectionSource, ConnectionType, Coordinate, StargateType, System};
+//!
+//! let system = |id: u32| {
+//!     System::new(id.into(), "", Coordinate { x: 0.0, y: 0.0, z: 0.0 }, 0.9.into())
+//! };
+//! let gate = |from: u32, to: u32| Connection {
+//!     from: from.into(),
+//!     to: to.into(),
+//!     type_: ConnectionType::Stargate(StargateType::Local),
+//!     source: ConnectionSource::Sde,
+//! };
+//! let universe = UniverseBuilder::new()
+//!     .system(system(1))
+//!     .system(system(2))
+//!     .connection_bidirectional(gate(1, 2))
+//!     .build();
+//!
+//! let character = Character::new(1.into(), 1.into()).jump_clone(2.into());
+//! let options = body_options(&universe, &character, 2.into(), &ShipProfile::default()).unwrap();
+//! assert_eq!(BodyOption::JumpClone(2.into()), options[0].option);
+//! assert_eq!(Duration::ZERO, options[0].total);
+//! ```
+use std::time::Duration;
+
+use crate::navigation::{PathBuilder, ShipProfile};
+use crate::types::{self, Navigatable};
+
+/// The time between two clone jumps without the Infomorph Synchronizing skill.
+pub const CLONE_JUMP_COOLDOWN: Duration = Duration::from_secs(24 * 60 * 60);
+/// The self-destruct timer of a capsule.
+pub const SELF_DESTRUCT_TIME: Duration = Duration::from_secs(2 * 60);
+
+/// Errors returned by `body_options`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum CloneError {
+    #[error("unknown system {0:?}")]
+    UnknownSystem(types::SystemId),
+}
+
+/// Where a character's bodies are.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Character {
+    /// The system the character currently is in.
+    pub location: types::SystemId,
+    /// The system of the station the medical clone is set to.
+    pub home: types::SystemId,
+    /// The systems with a jump clone of the character.
+    pub jump_clones: Vec<types::SystemId>,
+    /// The time until the next clone jump is possible.
+    pub clone_jump_ready_in: Duration,
+}
+
+impl Character {
+    pub fn new(location: types::SystemId, home: types::SystemId) -> Self {
+        Self {
+            location,
+            home,
+            jump_clones: Vec::new(),
+            clone_jump_ready_in: Duration::ZERO,
+        }
+    }
+
+    pub fn jump_clone(mut self, system: types::SystemId) -> Self {
+        self.add_jump_clone(system);
+        self
+    }
+
+    /// Like `jump_clone`, but for use when the character is not consumed, e.g. in loops.
+    pub fn add_jump_clone(&mut self, system: types::SystemId) -> &mut Self {
+        self.jump_clones.push(system);
+        self
+    }
+
+    /// Sets the time until the next clone jump, e.g. `CLONE_JUMP_COOLDOWN` right
+    /// after a clone jump.
+    pub fn clone_jump_ready_in(mut self, wait: Duration) -> Self {
+        self.clone_jump_ready_in = wait;
+        self
+    }
+}
+
+/// How the character gets a body to the destination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyOption {
+    /// Fly the pod from the current location.
+    Pod,
+    /// Jump into the clone in this system and fly on from there.
+    JumpClone(types::SystemId),
+    /// Self-destruct and fly on from the medical clone.
+    SelfDestruct,
+}
+
+/// One way to get a body to the destination.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BodyRoute {
+    pub option: BodyOption,
+    /// The system the flight starts in.
+    pub start: types::SystemId,
+    pub jumps: usize,
+    /// Time spent before flying, e.g. waiting for the clone jump timer.
+    pub wait: Duration,
+    /// The estimated flight time.
+    pub travel: Duration,
+    pub total: Duration,
+}
+
+/// Lists the ways to get a body to `destination`, fastest first. Options without a
+/// route to the destination are left out.
+pub fn body_options<U: Navigatable>(
+    universe: &U,
+    character: &Character,
+    destination: types::SystemId,
+    ship: &ShipProfile,
+) -> Result<Vec<BodyRoute>, CloneError> {
+    let target = universe
+        .get_system(&destination)
+        .ok_or(CloneError::UnknownSystem(destination))?;
+
+    let mut starts = vec![
+        (BodyOption::Pod, character.location, Duration::ZERO),
+        (BodyOption::SelfDestruct, character.home, SELF_DESTRUCT_TIME),
+    ];
+    starts.extend(character.jump_clones.iter().map(|&clone| {
+        (
+            BodyOption::JumpClone(clone),
+            clone,
+            character.clone_jump_ready_in,
+        )
+    }));
+
+    let mut options = Vec::new();
+    for (option, start, wait) in starts {
+        let system = universe
+            .get_system(&start)
+            .ok_or(CloneError::UnknownSystem(start))?;
+        let Ok(path) = PathBuilder::new(universe)
+            .waypoint(system)
+            .waypoint(target)
+            .try_build()
+        else {
+            continue;
+        };
+        let travel = path.estimated_time(ship);
+        options.push(BodyRoute {
+            option,
+            start,
+            jumps: path.jumps(),
+            wait,
+            travel,
+            total: wait + travel,
+        });
+    }
+    options.sort_by_key(|o| o.total);
+    Ok(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+
+    #[test]
+    fn test_body_options() {
+        let universe = universe();
+        let ship = ShipProfile::default();
+        let character = Character::new(ALPHA, ECHO).jump_clone(DELTA);
+
+        let options = body_options(&universe, &character, ECHO, &ship).unwrap();
+        assert_eq!(3, options.len());
+        assert_eq!(BodyOption::JumpClone(DELTA), options[0].option);
+        assert_eq!(1, options[0].jumps);
+        assert_eq!(BodyOption::SelfDestruct, options[1].option);
+        assert_eq!(SELF_DESTRUCT_TIME, options[1].total);
+        assert_eq!(BodyOption::Pod, options[2].option);
+        assert_eq!(4, options[2].jumps);
+
+        let character = character.clone_jump_ready_in(CLONE_JUMP_COOLDOWN);
+        let options = body_options(&universe, &character, ECHO, &ship).unwrap();
+        assert_eq!(BodyOption::JumpClone(DELTA), options[2].option);
+        assert!(options[2].total > CLONE_JUMP_COOLDOWN);
+
+        assert_eq!(
+            Err(CloneError::UnknownSystem(types::SystemId(1))),
+            body_options(&universe, &character, types::SystemId(1), &ship)
+        );
+    }
+}
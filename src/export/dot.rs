@@ -0,0 +1,143 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Graphviz DOT export.
+//!
+//! # Example
+//! ```
+//! use neweden::builder::UniverseBuilder;
+//! use neweden::export::{dot, GraphOptions};
+//! use neweden::{Coordinate, System};
+//!
+//! let universe = UniverseBuilder::new()
+//!     .system(System::new(1.into(), "Jita", Coordinate { x: 0.0, y: 0.0, z: 0.0 }, 0.9.into()))
+//!     .build();
+//! let dot = dot::to_dot(&universe, &GraphOptions::new());
+//! assert!(dot.contains("1 [label=\"Jita\""));
+//! ```
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use super::{connection_labels, graph_of, security_color, GraphOptions};
+use crate::types::{Galaxy, Navigatable, System};
+
+/// Renders the galaxy as an undirected DOT graph. Connections that only exist in
+/// one direction are drawn with an arrow.
+pub fn to_dot<G: Galaxy + Navigatable>(galaxy: &G, options: &GraphOptions) -> String {
+    let (systems, edges) = graph_of(galaxy);
+    let mut out = String::from("graph neweden {\n");
+    if options.security_colors {
+        out.push_str("  node [style=filled];\n");
+    }
+
+    if options.cluster_regions {
+        let mut regions = BTreeMap::new();
+        for system in systems {
+            regions
+                .entry(system.region)
+                .or_insert_with(Vec::new)
+                .push(system);
+        }
+        for (region, systems) in regions {
+            match region {
+                Some(region) => {
+                    let _ = writeln!(out, "  subgraph cluster_{} {{", region.0);
+                    let _ = writeln!(out, "    label=\"{}\";", region.0);
+                    for system in systems {
+                        write_node(&mut out, "    ", system, options);
+                    }
+                    out.push_str("  }\n");
+                }
+                None => {
+                    for system in systems {
+                        write_node(&mut out, "  ", system, options);
+                    }
+                }
+            }
+        }
+    } else {
+        for system in systems {
+            write_node(&mut out, "  ", system, options);
+        }
+    }
+
+    for edge in edges {
+        let mut attributes = Vec::new();
+        if options.connection_types {
+            let (kind, class) = connection_labels(&edge.type_);
+            attributes.push(format!("type=\"{}\", class=\"{}\"", kind, class));
+            match kind {
+                "bridge" => attributes.push("style=dashed".to_string()),
+                "wormhole" => attributes.push("style=dotted".to_string()),
+                _ => {}
+            }
+        }
+        if edge.directed {
+            attributes.push("dir=forward".to_string());
+        }
+        let _ = write!(out, "  {} -- {}", edge.from.0, edge.to.0);
+        if !attributes.is_empty() {
+            let _ = write!(out, " [{}]", attributes.join(", "));
+        }
+        out.push_str(";\n");
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn write_node(out: &mut String, indent: &str, system: &System, options: &GraphOptions) {
+    let _ = write!(
+        out,
+        "{}{} [label=\"{}\"",
+        indent,
+        system.id.0,
+        escape(&system.name)
+    );
+    if options.security_colors {
+        let _ = write!(out, ", fillcolor=\"{}\"", security_color(&system.security));
+    }
+    out.push_str("];\n");
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+    use crate::types;
+
+    #[test]
+    fn test_to_dot() {
+        let universe = universe();
+        let dot = to_dot(&universe, &GraphOptions::new());
+        assert!(dot.starts_with("graph neweden {\n"));
+        assert!(dot.contains("  30000001 [label=\"Alpha\", fillcolor=\"#3a9aeb\"];\n"));
+        assert!(dot.contains("  30000002 -- 30000003 [type=\"stargate\", class=\"regional\"];\n"));
+        assert_eq!(9, dot.matches(" -- ").count());
+        assert!(!dot.contains("subgraph"));
+
+        let bridge = types::Connection {
+            from: ALPHA,
+            to: ECHO,
+            type_: types::ConnectionType::Bridge(types::BridgeType::Titan(
+                types::JumpdriveSkills::new(5, 5),
+            )),
+            source: types::ConnectionSource::UserOverlay,
+        };
+        let extended = universe.extend(vec![bridge].into());
+        let options = GraphOptions::new()
+            .security_colors(false)
+            .cluster_regions(true);
+        let dot = to_dot(&extended, &options);
+        assert!(dot.contains("  subgraph cluster_10000001 {\n    label=\"10000001\";\n"));
+        assert!(dot.contains("    30000001 [label=\"Alpha\"];\n"));
+        assert!(dot.contains(
+            "  30000001 -- 30000005 [type=\"bridge\", class=\"titan\", style=dashed, dir=forward];\n"
+        ));
+    }
+}
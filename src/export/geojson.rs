@@ -0,0 +1,307 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! GeoJSON export of a flattened 2D map for interactive map renderers.
+//!
+//! System coordinates are projected onto a plane, in lightyears, and written as a
+//! GeoJSON `FeatureCollection` containing a point per system, a line per jump and
+//! a polygon with the convex hull of every region. Every feature has a `feature`
+//! property telling which of the three it is.
+use std::collections::{BTreeMap, HashMap};
+
+use serde_json::{json, Value};
+
+use super::{connection_labels, graph_of, security_color};
+use crate::types::{Coordinate, Galaxy, Lightyears, Meters, Navigatable, RegionId, SystemId};
+
+/// How system coordinates are flattened onto the map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Projection {
+    /// Looks down onto the galactic plane and drops the y axis, like the
+    /// in-game map in its flattened mode.
+    #[default]
+    DropY,
+    /// Lays out every region along its two principal axes, so regions that are
+    /// tilted against the galactic plane are not squashed. Regions stay centered
+    /// where `DropY` puts them. Systems without a region use `DropY`.
+    PcaPerRegion,
+}
+
+/// Projects all systems of the galaxy onto the map plane, in lightyears.
+pub fn project<G: Galaxy>(galaxy: &G, projection: Projection) -> HashMap<SystemId, (f64, f64)> {
+    let systems = galaxy.systems();
+    let mut projected = systems
+        .iter()
+        .map(|s| (s.id, drop_y(&s.coordinate)))
+        .collect::<HashMap<_, _>>();
+    if projection == Projection::DropY {
+        return projected;
+    }
+
+    let mut regions: HashMap<RegionId, Vec<[f64; 3]>> = HashMap::new();
+    let mut members: HashMap<RegionId, Vec<SystemId>> = HashMap::new();
+    for system in &systems {
+        if let Some(region) = system.region {
+            regions
+                .entry(region)
+                .or_default()
+                .push(in_lightyears(&system.coordinate));
+            members.entry(region).or_default().push(system.id);
+        }
+    }
+    for (region, points) in regions {
+        let Some((centroid, axes)) = principal_axes(&points) else {
+            continue;
+        };
+        let origin = (centroid[0], centroid[2]);
+        for (id, point) in members[&region].iter().zip(&points) {
+            let d = [
+                point[0] - centroid[0],
+                point[1] - centroid[1],
+                point[2] - centroid[2],
+            ];
+            projected.insert(
+                *id,
+                (origin.0 + dot(&d, &axes[0]), origin.1 + dot(&d, &axes[1])),
+            );
+        }
+    }
+    projected
+}
+
+/// Renders the galaxy as a GeoJSON `FeatureCollection`.
+pub fn to_geojson<G: Galaxy + Navigatable>(galaxy: &G, projection: Projection) -> Value {
+    let positions = project(galaxy, projection);
+    let (systems, edges) = graph_of(galaxy);
+    let position = |id: &SystemId| {
+        let (x, y) = positions[id];
+        json!([x, y])
+    };
+
+    let mut features = Vec::new();
+    let mut regions: BTreeMap<RegionId, Vec<(f64, f64)>> = BTreeMap::new();
+    for system in &systems {
+        if let Some(region) = system.region {
+            regions
+                .entry(region)
+                .or_default()
+                .push(positions[&system.id]);
+        }
+        features.push(json!({
+            "type": "Feature",
+            "geometry": { "type": "Point", "coordinates": position(&system.id) },
+            "properties": {
+                "feature": "system",
+                "id": system.id.0,
+                "name": system.name,
+                "security": system.security.0,
+                "color": security_color(&system.security),
+                "region": system.region.map(|r| r.0),
+                "constellation": system.constellation.map(|c| c.0),
+            },
+        }));
+    }
+    for edge in &edges {
+        let (kind, class) = connection_labels(&edge.type_);
+        features.push(json!({
+            "type": "Feature",
+            "geometry": {
+                "type": "LineString",
+                "coordinates": [position(&edge.from), position(&edge.to)],
+            },
+            "properties": {
+                "feature": "jump",
+                "from": edge.from.0,
+                "to": edge.to.0,
+                "type": kind,
+                "class": class,
+                "directed": edge.directed,
+            },
+        }));
+    }
+    for (region, points) in regions {
+        let hull = convex_hull(points);
+        if hull.len() < 3 {
+            continue;
+        }
+        let mut ring = hull.iter().map(|(x, y)| json!([x, y])).collect::<Vec<_>>();
+        ring.push(ring[0].clone());
+        features.push(json!({
+            "type": "Feature",
+            "geometry": { "type": "Polygon", "coordinates": [ring] },
+            "properties": { "feature": "region", "id": region.0 },
+        }));
+    }
+
+    json!({ "type": "FeatureCollection", "features": features })
+}
+
+fn in_lightyears(coordinate: &Coordinate) -> [f64; 3] {
+    let ly = |m: f64| Lightyears::from(Meters(m)).0;
+    [ly(coordinate.x), ly(coordinate.y), ly(coordinate.z)]
+}
+
+fn drop_y(coordinate: &Coordinate) -> (f64, f64) {
+    let [x, _, z] = in_lightyears(coordinate);
+    (x, z)
+}
+
+fn dot(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// The centroid and the two largest principal axes of a point cloud, or `None`
+/// if the points do not span a plane.
+fn principal_axes(points: &[[f64; 3]]) -> Option<([f64; 3], [[f64; 3]; 2])> {
+    if points.len() < 3 {
+        return None;
+    }
+    let n = points.len() as f64;
+    let mut centroid = [0.0; 3];
+    for p in points {
+        for i in 0..3 {
+            centroid[i] += p[i] / n;
+        }
+    }
+    let mut covariance = [[0.0; 3]; 3];
+    for p in points {
+        for i in 0..3 {
+            for j in 0..3 {
+                covariance[i][j] += (p[i] - centroid[i]) * (p[j] - centroid[j]) / n;
+            }
+        }
+    }
+
+    // Orient the axes like DropY, so a region lying flat keeps its orientation.
+    let (first, value) = dominant_eigenvector(&covariance, [1.0, 0.0, 0.0])?;
+    for i in 0..3 {
+        for j in 0..3 {
+            covariance[i][j] -= value * first[i] * first[j];
+        }
+    }
+    let (second, _) = dominant_eigenvector(&covariance, [0.0, 0.0, 1.0])?;
+    Some((centroid, [first, second]))
+}
+
+/// Power iteration. The result points into the same half space as `hint`.
+fn dominant_eigenvector(matrix: &[[f64; 3]; 3], hint: [f64; 3]) -> Option<([f64; 3], f64)> {
+    let mut v = [hint[0] + 0.3, 0.2, hint[2] + 0.1];
+    let mut value = 0.0;
+    for _ in 0..100 {
+        let next = [
+            dot(&matrix[0], &v),
+            dot(&matrix[1], &v),
+            dot(&matrix[2], &v),
+        ];
+        value = dot(&next, &next).sqrt();
+        if value < 1e-9 {
+            return None;
+        }
+        v = [next[0] / value, next[1] / value, next[2] / value];
+    }
+    if dot(&v, &hint) < 0.0 {
+        v = [-v[0], -v[1], -v[2]];
+    }
+    Some((v, value))
+}
+
+/// Andrew's monotone chain. Returns the hull counter-clockwise without repeating
+/// the first point.
+fn convex_hull(mut points: Vec<(f64, f64)>) -> Vec<(f64, f64)> {
+    points.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+    let cross = |o: (f64, f64), a: (f64, f64), b: (f64, f64)| {
+        (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+    };
+    let mut hull: Vec<(f64, f64)> = Vec::with_capacity(points.len() * 2);
+    for pass in [points.clone(), points.into_iter().rev().collect()] {
+        let start = hull.len();
+        for p in pass {
+            while hull.len() >= start + 2
+                && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        hull.pop();
+    }
+    hull
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+
+    fn features<'a>(value: &'a Value, kind: &str) -> Vec<&'a Value> {
+        value["features"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|f| f["properties"]["feature"] == kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_to_geojson() {
+        let universe = universe();
+        let value = to_geojson(&universe, Projection::DropY);
+        assert_eq!("FeatureCollection", value["type"]);
+
+        let systems = features(&value, "system");
+        assert_eq!(8, systems.len());
+        let golf = systems
+            .iter()
+            .find(|f| f["properties"]["id"] == GOLF.0)
+            .unwrap();
+        let coordinates = golf["geometry"]["coordinates"].as_array().unwrap();
+        assert!((coordinates[0].as_f64().unwrap() - 4.0).abs() < 1e-9);
+        assert!((coordinates[1].as_f64().unwrap() - 2.0).abs() < 1e-9);
+
+        assert_eq!(9, features(&value, "jump").len());
+        // MIDDLE lies on a straight line and SOUTH only has two systems, so only
+        // NORTH has a hull.
+        let regions = features(&value, "region");
+        assert_eq!(1, regions.len());
+        assert_eq!(NORTH.0, regions[0]["properties"]["id"]);
+        let ring = regions[0]["geometry"]["coordinates"][0].as_array().unwrap();
+        assert_eq!(4, ring.len());
+        assert_eq!(ring[0], ring[3]);
+    }
+
+    #[test]
+    fn test_pca_projection() {
+        let universe = universe();
+        let flat = project(&universe, Projection::DropY);
+        let pca = project(&universe, Projection::PcaPerRegion);
+        assert_eq!(flat.len(), pca.len());
+        // The fixture is flat, so PCA only rotates NORTH around its centroid. The
+        // other regions do not span a plane and keep their flat positions.
+        let distance = |p: &HashMap<SystemId, (f64, f64)>, a: SystemId, b: SystemId| {
+            ((p[&a].0 - p[&b].0).powi(2) + (p[&a].1 - p[&b].1).powi(2)).sqrt()
+        };
+        for (a, b) in [(ALPHA, BRAVO), (BRAVO, FOXTROT), (ALPHA, FOXTROT)] {
+            assert!((distance(&flat, a, b) - distance(&pca, a, b)).abs() < 1e-6);
+        }
+        assert_eq!(flat[&GOLF], pca[&GOLF]);
+        assert_eq!(flat[&DELTA], pca[&DELTA]);
+    }
+
+    #[test]
+    fn test_convex_hull() {
+        let hull = convex_hull(vec![
+            (0.0, 0.0),
+            (2.0, 0.0),
+            (1.0, 1.0),
+            (2.0, 2.0),
+            (0.0, 2.0),
+        ]);
+        assert_eq!(vec![(0.0, 0.0), (2.0, 0.0), (2.0, 2.0), (0.0, 2.0)], hull);
+    }
+}
@@ -0,0 +1,137 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! GraphML export, e.g. to analyse or lay out regions in Gephi or yEd.
+//!
+//! Systems carry their name and security, plus the color, region and
+//! constellation depending on the `GraphOptions`. Connections carry their type.
+use std::fmt::Write;
+
+use super::{connection_labels, graph_of, security_color, GraphOptions};
+use crate::types::{Galaxy, Navigatable};
+
+/// Renders the galaxy as an undirected GraphML document. Connections that only
+/// exist in one direction are marked as directed edges.
+pub fn to_graphml<G: Galaxy + Navigatable>(galaxy: &G, options: &GraphOptions) -> String {
+    let (systems, edges) = graph_of(galaxy);
+    let mut out = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+        "  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n",
+        "  <key id=\"security\" for=\"node\" attr.name=\"security\" attr.type=\"double\"/>\n",
+    ));
+    if options.security_colors {
+        out.push_str(
+            "  <key id=\"color\" for=\"node\" attr.name=\"color\" attr.type=\"string\"/>\n",
+        );
+    }
+    if options.cluster_regions {
+        out.push_str(concat!(
+            "  <key id=\"region\" for=\"node\" attr.name=\"region\" attr.type=\"long\"/>\n",
+            "  <key id=\"constellation\" for=\"node\" attr.name=\"constellation\" ",
+            "attr.type=\"long\"/>\n",
+        ));
+    }
+    if options.connection_types {
+        out.push_str(concat!(
+            "  <key id=\"type\" for=\"edge\" attr.name=\"type\" attr.type=\"string\"/>\n",
+            "  <key id=\"class\" for=\"edge\" attr.name=\"class\" attr.type=\"string\"/>\n",
+        ));
+    }
+    out.push_str("  <graph id=\"neweden\" edgedefault=\"undirected\">\n");
+
+    for system in systems {
+        let _ = writeln!(out, "    <node id=\"{}\">", system.id.0);
+        let _ = writeln!(
+            out,
+            "      <data key=\"label\">{}</data>",
+            escape(&system.name)
+        );
+        let _ = writeln!(
+            out,
+            "      <data key=\"security\">{}</data>",
+            system.security.0
+        );
+        if options.security_colors {
+            let _ = writeln!(
+                out,
+                "      <data key=\"color\">{}</data>",
+                security_color(&system.security)
+            );
+        }
+        if options.cluster_regions {
+            if let Some(region) = system.region {
+                let _ = writeln!(out, "      <data key=\"region\">{}</data>", region.0);
+            }
+            if let Some(constellation) = system.constellation {
+                let _ = writeln!(
+                    out,
+                    "      <data key=\"constellation\">{}</data>",
+                    constellation.0
+                );
+            }
+        }
+        out.push_str("    </node>\n");
+    }
+
+    for edge in edges {
+        let _ = write!(
+            out,
+            "    <edge source=\"{}\" target=\"{}\"",
+            edge.from.0, edge.to.0
+        );
+        if edge.directed {
+            out.push_str(" directed=\"true\"");
+        }
+        if options.connection_types {
+            let (kind, class) = connection_labels(&edge.type_);
+            out.push_str(">\n");
+            let _ = writeln!(out, "      <data key=\"type\">{}</data>", kind);
+            let _ = writeln!(out, "      <data key=\"class\">{}</data>", class);
+            out.push_str("    </edge>\n");
+        } else {
+            out.push_str("/>\n");
+        }
+    }
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+
+    #[test]
+    fn test_to_graphml() {
+        let universe = universe();
+        let xml = to_graphml(&universe, &GraphOptions::new().cluster_regions(true));
+        assert_eq!(8, xml.matches("<node ").count());
+        assert_eq!(9, xml.matches("<edge ").count());
+        assert!(xml.contains(concat!(
+            "    <node id=\"30000005\">\n",
+            "      <data key=\"label\">Echo</data>\n",
+            "      <data key=\"security\">-0.2</data>\n",
+            "      <data key=\"color\">#8d3264</data>\n",
+            "      <data key=\"region\">10000003</data>\n",
+        )));
+        assert!(xml.contains(concat!(
+            "    <edge source=\"30000001\" target=\"30000002\">\n",
+            "      <data key=\"type\">stargate</data>\n",
+            "      <data key=\"class\">local</data>\n",
+        )));
+
+        let xml = to_graphml(&universe, &GraphOptions::new().connection_types(false));
+        assert!(!xml.contains("key=\"region\""));
+        assert!(xml.contains("    <edge source=\"30000001\" target=\"30000002\"/>\n"));
+    }
+}
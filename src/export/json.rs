@@ -0,0 +1,570 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Versioned JSON wire formats for universes, overlay snapshots, patches and paths.
+//!
+//! Every document carries a `version` field. Documents are only read back if
+//! their version matches `FORMAT_VERSION`. Each document type provides a
+//! `schema()` function returning a JSON Schema (draft 2020-12) describing the
+//! format, so consumers in other languages can validate and generate bindings.
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::navigation::{Path, PathElement};
+use crate::patch::Patch;
+use crate::types;
+use crate::validate::IntegrityIssue;
+
+/// The version of the JSON wire formats produced by this module.
+pub const FORMAT_VERSION: u32 = 1;
+
+const SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+#[derive(Debug, thiserror::Error)]
+pub enum FormatError {
+    #[error("unsupported format version {found}, expected {expected}")]
+    UnsupportedVersion { found: u32, expected: u32 },
+    #[error("document has no version field")]
+    MissingVersion,
+    #[error("path refers to unknown system {0:?}")]
+    UnknownSystem(types::SystemId),
+    /// Issues of a loaded universe that break routing, see `IntegrityIssue::is_fatal`.
+    /// Never empty.
+    #[error("universe has {} integrity issues, the first: {}", .0.len(), .0[0])]
+    Integrity(Vec<IntegrityIssue>),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+fn from_versioned<T: serde::de::DeserializeOwned>(s: &str) -> Result<T, FormatError> {
+    from_versioned_value(serde_json::from_str(s)?)
+}
+
+/// Checks the version of a parsed document before deserializing it.
+pub(crate) fn from_versioned_value<T: serde::de::DeserializeOwned>(
+    value: Value,
+) -> Result<T, FormatError> {
+    let found = value
+        .get("version")
+        .and_then(Value::as_u64)
+        .ok_or(FormatError::MissingVersion)? as u32;
+    if found != FORMAT_VERSION {
+        return Err(FormatError::UnsupportedVersion {
+            found,
+            expected: FORMAT_VERSION,
+        });
+    }
+    Ok(serde_json::from_value(value)?)
+}
+
+/// All systems and connections of a universe.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UniverseDocument {
+    pub version: u32,
+    pub systems: Vec<types::System>,
+    pub connections: Vec<types::Connection>,
+}
+
+impl UniverseDocument {
+    pub fn new<U: types::Galaxy + types::Navigatable>(universe: &U) -> Self {
+        let mut systems = universe.systems().into_iter().cloned().collect::<Vec<_>>();
+        systems.sort_by_key(|s| s.id);
+        let connections = systems
+            .iter()
+            .flat_map(|s| {
+                universe
+                    .get_connections(&s.id)
+                    .unwrap_or_default()
+                    .into_owned()
+            })
+            .collect();
+        Self {
+            version: FORMAT_VERSION,
+            systems,
+            connections,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, FormatError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, FormatError> {
+        from_versioned(s)
+    }
+
+    pub fn schema() -> Value {
+        document_schema(
+            "universe",
+            json!({
+                "systems": { "type": "array", "items": { "$ref": "#/$defs/system" } },
+                "connections": { "type": "array", "items": { "$ref": "#/$defs/connection" } },
+            }),
+        )
+    }
+}
+
+/// A snapshot of the additional connections of an `ExtendedUniverse`, e.g. a
+/// wormhole chain or a set of titan bridges, without the underlying universe.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotDocument {
+    pub version: u32,
+    pub connections: Vec<types::Connection>,
+}
+
+impl SnapshotDocument {
+    pub fn new<U>(extended: &types::ExtendedUniverse<U>) -> Self {
+        let mut connections = extended
+            .connections
+            .0
+            .values()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>();
+        connections.sort_by_key(|c| (c.from, c.to));
+        Self {
+            version: FORMAT_VERSION,
+            connections,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, FormatError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, FormatError> {
+        from_versioned(s)
+    }
+
+    /// Converts the snapshot into an adjacent map that can be passed to `Universe::extend`.
+    pub fn into_adjacent_map(self) -> types::AdjacentMap {
+        self.connections.into()
+    }
+
+    pub fn schema() -> Value {
+        document_schema(
+            "snapshot",
+            json!({
+                "connections": { "type": "array", "items": { "$ref": "#/$defs/connection" } },
+            }),
+        )
+    }
+}
+
+/// A single element of a `PathDocument`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "element", rename_all = "snake_case")]
+pub enum PathElementRecord {
+    Waypoint {
+        system: types::SystemId,
+    },
+    System {
+        system: types::SystemId,
+    },
+    Connection {
+        connection: types::ConnectionType,
+        #[serde(default)]
+        source: types::ConnectionSource,
+    },
+}
+
+/// A computed path. Systems are referenced by id only and must be resolved
+/// against a compatible universe.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathDocument {
+    pub version: u32,
+    pub jumps: usize,
+    pub waypoints: Vec<types::SystemId>,
+    pub elements: Vec<PathElementRecord>,
+}
+
+impl PathDocument {
+    pub fn new(path: &Path) -> Self {
+        let elements = path
+            .iter()
+            .map(|e| match e {
+                PathElement::Waypoint(s) => PathElementRecord::Waypoint { system: s.id },
+                PathElement::System(s) => PathElementRecord::System { system: s.id },
+                PathElement::Connection(connection, source) => {
+                    PathElementRecord::Connection { connection, source }
+                }
+            })
+            .collect();
+        Self {
+            version: FORMAT_VERSION,
+            jumps: path.jumps(),
+            waypoints: path.waypoints().iter().map(|s| s.id).collect(),
+            elements,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, FormatError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, FormatError> {
+        from_versioned(s)
+    }
+
+    /// Resolves the systems against `universe`. Connections are taken as recorded,
+    /// so the universe only needs to contain the systems.
+    pub fn into_path<'a>(
+        self,
+        universe: &'a dyn types::Navigatable,
+    ) -> Result<Path<'a>, FormatError> {
+        let system = |id: types::SystemId| {
+            universe
+                .get_system(&id)
+                .ok_or(FormatError::UnknownSystem(id))
+        };
+        let waypoints = self
+            .waypoints
+            .into_iter()
+            .map(system)
+            .collect::<Result<Vec<_>, _>>()?;
+        let elements = self
+            .elements
+            .into_iter()
+            .map(|e| {
+                Ok(match e {
+                    PathElementRecord::Waypoint { system: id } => {
+                        PathElement::Waypoint(system(id)?)
+                    }
+                    PathElementRecord::System { system: id } => PathElement::System(system(id)?),
+                    PathElementRecord::Connection { connection, source } => {
+                        PathElement::Connection(connection, source)
+                    }
+                })
+            })
+            .collect::<Result<Vec<_>, FormatError>>()?;
+        Ok(Path::from_elements(universe, waypoints, elements))
+    }
+
+    pub fn schema() -> Value {
+        document_schema(
+            "path",
+            json!({
+                "jumps": { "type": "integer", "minimum": 0 },
+                "waypoints": { "type": "array", "items": { "$ref": "#/$defs/system_id" } },
+                "elements": { "type": "array", "items": { "$ref": "#/$defs/path_element" } },
+            }),
+        )
+    }
+}
+
+impl<'a> Path<'a> {
+    /// The path as a `PathDocument`, to ship it to another service or persist it.
+    pub fn to_json(&self) -> Result<String, FormatError> {
+        PathDocument::new(self).to_json()
+    }
+
+    /// Reads a path written by `to_json` back, resolving its systems against a
+    /// compatible universe, e.g. one loaded from the same static data export.
+    pub fn from_json(universe: &'a dyn types::Navigatable, s: &str) -> Result<Self, FormatError> {
+        PathDocument::from_json(s)?.into_path(universe)
+    }
+}
+
+/// A universe patch, e.g. community maintained corrections to the static data.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatchDocument {
+    pub version: u32,
+    pub patch: Patch,
+}
+
+impl PatchDocument {
+    pub fn new(patch: Patch) -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            patch,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, FormatError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, FormatError> {
+        from_versioned(s)
+    }
+
+    pub fn schema() -> Value {
+        document_schema(
+            "patch",
+            json!({
+                "patch": { "type": "array", "items": { "$ref": "#/$defs/patch_op" } },
+            }),
+        )
+    }
+}
+
+fn document_schema(name: &str, properties: Value) -> Value {
+    let mut props = json!({ "version": { "const": FORMAT_VERSION } });
+    let mut required = vec![Value::from("version")];
+    for (key, value) in properties.as_object().unwrap() {
+        props[key] = value.clone();
+        required.push(Value::from(key.as_str()));
+    }
+    json!({
+        "$schema": SCHEMA_DIALECT,
+        "$id": format!("https://github.com/dsp/neweden/schema/{}/v{}.json", name, FORMAT_VERSION),
+        "title": format!("neweden {} v{}", name, FORMAT_VERSION),
+        "type": "object",
+        "required": required,
+        "properties": props,
+        "$defs": definitions(),
+    })
+}
+
+fn tagged(kind: &str, class: Value) -> Value {
+    json!({
+        "type": "object",
+        "required": ["kind", "class"],
+        "properties": { "kind": { "const": kind }, "class": class },
+        "additionalProperties": false,
+    })
+}
+
+fn patch_op(op: &str, properties: Value) -> Value {
+    let mut props = json!({ "op": { "const": op } });
+    let mut required = vec![Value::from("op")];
+    for (key, value) in properties.as_object().unwrap() {
+        props[key] = value.clone();
+        required.push(Value::from(key.as_str()));
+    }
+    json!({ "type": "object", "required": required, "properties": props })
+}
+
+fn single_key(key: &str, value: Value) -> Value {
+    json!({
+        "type": "object",
+        "required": [key],
+        "properties": { key: value },
+        "additionalProperties": false,
+    })
+}
+
+fn definitions() -> Value {
+    json!({
+        "system_id": { "type": "integer", "minimum": 0 },
+        "coordinate": {
+            "type": "object",
+            "required": ["x", "y", "z"],
+            "properties": {
+                "x": { "type": "number" },
+                "y": { "type": "number" },
+                "z": { "type": "number" },
+            },
+        },
+        "system": {
+            "type": "object",
+            "required": ["id", "name", "coordinate", "security"],
+            "properties": {
+                "id": { "$ref": "#/$defs/system_id" },
+                "name": { "type": "string" },
+                "coordinate": { "$ref": "#/$defs/coordinate" },
+                "security": { "type": "number", "minimum": -1.0, "maximum": 1.0 },
+            },
+        },
+        "jumpdrive_skills": {
+            "type": "object",
+            "required": ["jump_drive_calibration", "fuel_conversation"],
+            "properties": {
+                "jump_drive_calibration": { "type": "integer", "minimum": 0, "maximum": 5 },
+                "fuel_conversation": { "type": "integer", "minimum": 0, "maximum": 5 },
+            },
+        },
+        "connection_type": {
+            "oneOf": [
+                tagged("stargate", json!({ "enum": ["local", "constellation", "regional"] })),
+                tagged("bridge", json!({
+                    "oneOf": [
+                        single_key("titan", json!({ "$ref": "#/$defs/jumpdrive_skills" })),
+                        single_key("black_ops", json!({ "$ref": "#/$defs/jumpdrive_skills" })),
+                    ],
+                })),
+                tagged("wormhole", json!({
+                    "enum": ["very_large", "large", "medium", "small", "unknown"],
+                })),
+                tagged("filament", json!({
+                    "enum": ["krai", "pochven_border", "pochven_home", "noise", "signal"],
+                })),
+            ],
+        },
+        "connection_source": {
+            "enum": ["sde", "esi", "eve_scout", "user_overlay", "bridge_file"],
+        },
+        "connection": {
+            "type": "object",
+            "required": ["from", "to", "type"],
+            "properties": {
+                "from": { "$ref": "#/$defs/system_id" },
+                "to": { "$ref": "#/$defs/system_id" },
+                "type": { "$ref": "#/$defs/connection_type" },
+                "source": { "$ref": "#/$defs/connection_source" },
+            },
+        },
+        "patch_op": {
+            "oneOf": [
+                patch_op("add_system", json!({ "system": { "$ref": "#/$defs/system" } })),
+                patch_op("remove_system", json!({ "system": { "$ref": "#/$defs/system_id" } })),
+                patch_op("add_connection", json!({
+                    "connection": { "$ref": "#/$defs/connection" },
+                })),
+                patch_op("remove_connection", json!({
+                    "from": { "$ref": "#/$defs/system_id" },
+                    "to": { "$ref": "#/$defs/system_id" },
+                })),
+                patch_op("set_security", json!({
+                    "system": { "$ref": "#/$defs/system_id" },
+                    "security": { "type": "number", "minimum": -1.0, "maximum": 1.0 },
+                })),
+            ],
+        },
+        "path_element": {
+            "oneOf": [
+                {
+                    "type": "object",
+                    "required": ["element", "system"],
+                    "properties": {
+                        "element": { "enum": ["waypoint", "system"] },
+                        "system": { "$ref": "#/$defs/system_id" },
+                    },
+                },
+                {
+                    "type": "object",
+                    "required": ["element", "connection"],
+                    "properties": {
+                        "element": { "const": "connection" },
+                        "connection": { "$ref": "#/$defs/connection_type" },
+                        "source": { "$ref": "#/$defs/connection_source" },
+                    },
+                },
+            ],
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation::PathBuilder;
+    use crate::testing::*;
+    use crate::types::Navigatable;
+
+    fn required(schema: &Value) -> Vec<&str> {
+        schema["required"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_universe_roundtrip() {
+        let universe = universe();
+        let doc = UniverseDocument::new(&universe);
+        let parsed = UniverseDocument::from_json(&doc.to_json().unwrap()).unwrap();
+        assert_eq!(8, parsed.systems.len());
+        assert_eq!(18, parsed.connections.len());
+        assert_eq!(ALPHA, parsed.systems[0].id);
+    }
+
+    #[test]
+    fn test_version_mismatch() {
+        let universe = universe();
+        let mut doc = UniverseDocument::new(&universe);
+        doc.version = FORMAT_VERSION + 1;
+        match UniverseDocument::from_json(&doc.to_json().unwrap()) {
+            Err(FormatError::UnsupportedVersion { found, .. }) => {
+                assert_eq!(FORMAT_VERSION + 1, found)
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_path_document() {
+        let universe = universe();
+        let path = PathBuilder::new(&universe)
+            .waypoint(universe.get_system(&ALPHA).unwrap())
+            .waypoint(universe.get_system(&CHARLIE).unwrap())
+            .build()
+            .unwrap();
+        let doc = PathDocument::from_json(&PathDocument::new(&path).to_json().unwrap()).unwrap();
+        assert_eq!(2, doc.jumps);
+        assert_eq!(vec![ALPHA, CHARLIE], doc.waypoints);
+        assert_eq!(5, doc.elements.len());
+        assert_eq!(
+            PathElementRecord::Connection {
+                connection: types::ConnectionType::Stargate(types::StargateType::Local),
+                source: types::ConnectionSource::Sde,
+            },
+            doc.elements[1]
+        );
+    }
+
+    #[test]
+    fn test_path_roundtrip() {
+        let universe = universe();
+        let path = PathBuilder::new(&universe)
+            .waypoint(universe.get_system(&ALPHA).unwrap())
+            .waypoint(universe.get_system(&CHARLIE).unwrap())
+            .waypoint(universe.get_system(&HOTEL).unwrap())
+            .build()
+            .unwrap();
+        let json = path.to_json().unwrap();
+        let read = Path::from_json(&universe, &json).unwrap();
+        assert_eq!(path.jumps(), read.jumps());
+        assert_eq!(path.canonical_hash(), read.canonical_hash());
+        assert_eq!(
+            vec![ALPHA, CHARLIE, HOTEL],
+            read.waypoints().iter().map(|s| s.id).collect::<Vec<_>>()
+        );
+
+        let mut other = crate::testing::universe();
+        other.remove_system(&HOTEL);
+        match Path::from_json(&other, &json) {
+            Err(FormatError::UnknownSystem(id)) => assert_eq!(HOTEL, id),
+            other => panic!("unexpected result {:?}", other.map(|p| p.jumps())),
+        }
+    }
+
+    #[test]
+    fn test_patch_document() {
+        let mut universe = universe();
+        let patch = Patch::new()
+            .set_security(ECHO, types::Security(0.1))
+            .remove_connection(DELTA, ECHO)
+            .remove_system(HOTEL);
+        let doc = PatchDocument::from_json(&PatchDocument::new(patch).to_json().unwrap()).unwrap();
+        assert_eq!(3, doc.patch.len());
+        let value = serde_json::to_value(&doc).unwrap();
+        assert_eq!("set_security", value["patch"][0]["op"]);
+
+        universe.apply_patch(&doc.patch).unwrap();
+        assert!(universe.get_system(&HOTEL).is_none());
+    }
+
+    #[test]
+    fn test_schema_matches_documents() {
+        let universe = universe();
+        let value = serde_json::to_value(UniverseDocument::new(&universe)).unwrap();
+        let schema = UniverseDocument::schema();
+        for key in required(&schema) {
+            assert!(value.get(key).is_some(), "missing {}", key);
+        }
+        assert_eq!(
+            json!(FORMAT_VERSION),
+            schema["properties"]["version"]["const"]
+        );
+        let connection = &value["connections"][0];
+        for key in required(&schema["$defs"]["connection"]) {
+            assert!(connection.get(key).is_some(), "missing {}", key);
+        }
+        assert_eq!("stargate", connection["type"]["kind"]);
+    }
+}
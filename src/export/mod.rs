@@ -0,0 +1,172 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Exports of universes, overlays and paths into formats consumed by other tools.
+//!
+//! `dot` and `graphml` render any galaxy for Graphviz and graph tools like Gephi.
+//! The JSON wire formats and the `geojson` map export require the `json` feature.
+use std::collections::HashMap;
+
+use crate::types::{
+    BridgeType, ConnectionType, FilamentKind, Galaxy, Navigatable, Security, StargateType, System,
+    SystemId, WormholeType,
+};
+
+pub mod dot;
+#[cfg(feature = "json")]
+pub mod geojson;
+pub mod graphml;
+#[cfg(feature = "json")]
+pub mod json;
+
+/// Controls which attributes the graph exports write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GraphOptions {
+    /// Color systems by their security rating like the in-game map.
+    pub security_colors: bool,
+    /// Group systems by region. DOT draws a cluster per region, GraphML only
+    /// records the region as a node attribute, as it has no notion of clusters.
+    pub cluster_regions: bool,
+    /// Annotate connections with their type, e.g. `stargate` and `regional`.
+    pub connection_types: bool,
+}
+
+impl Default for GraphOptions {
+    fn default() -> Self {
+        Self {
+            security_colors: true,
+            cluster_regions: false,
+            connection_types: true,
+        }
+    }
+}
+
+impl GraphOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn security_colors(mut self, enabled: bool) -> Self {
+        self.security_colors = enabled;
+        self
+    }
+
+    pub fn cluster_regions(mut self, enabled: bool) -> Self {
+        self.cluster_regions = enabled;
+        self
+    }
+
+    pub fn connection_types(mut self, enabled: bool) -> Self {
+        self.connection_types = enabled;
+        self
+    }
+}
+
+/// The color the in-game map uses for a security rating, as `#rrggbb`.
+pub fn security_color(security: &Security) -> &'static str {
+    match ((security.0 * 10.0).round() as i32).clamp(0, 10) {
+        10 => "#2c74df",
+        9 => "#3a9aeb",
+        8 => "#4ecef8",
+        7 => "#60dba3",
+        6 => "#71e554",
+        5 => "#f3fd82",
+        4 => "#dc6d07",
+        3 => "#ce440f",
+        2 => "#bc1117",
+        1 => "#722020",
+        _ => "#8d3264",
+    }
+}
+
+/// The kind and class of a connection type, e.g. `("stargate", "regional")`.
+pub(crate) fn connection_labels(type_: &ConnectionType) -> (&'static str, &'static str) {
+    match type_ {
+        ConnectionType::Stargate(class) => (
+            "stargate",
+            match class {
+                StargateType::Local => "local",
+                StargateType::Constellation => "constellation",
+                StargateType::Regional => "regional",
+            },
+        ),
+        ConnectionType::Bridge(class) => (
+            "bridge",
+            match class {
+                BridgeType::Titan(_) => "titan",
+                BridgeType::BlackOps(_) => "black_ops",
+            },
+        ),
+        ConnectionType::Wormhole(class) => (
+            "wormhole",
+            match class {
+                WormholeType::VeryLarge => "very_large",
+                WormholeType::Large => "large",
+                WormholeType::Medium => "medium",
+                WormholeType::Small => "small",
+                WormholeType::Unknown => "unknown",
+            },
+        ),
+        ConnectionType::Filament(kind) => (
+            "filament",
+            match kind {
+                FilamentKind::Krai => "krai",
+                FilamentKind::PochvenBorder => "pochven_border",
+                FilamentKind::PochvenHome => "pochven_home",
+                FilamentKind::Noise => "noise",
+                FilamentKind::Signal => "signal",
+            },
+        ),
+    }
+}
+
+/// An edge of an exported graph. Connections existing in both directions with the
+/// same type are merged into one undirected edge.
+pub(crate) struct Edge {
+    pub from: SystemId,
+    pub to: SystemId,
+    pub type_: ConnectionType,
+    pub directed: bool,
+}
+
+/// The systems sorted by id and the edges of a galaxy, so exports are stable.
+pub(crate) fn graph_of<G: Galaxy + Navigatable>(galaxy: &G) -> (Vec<&System>, Vec<Edge>) {
+    let mut systems = galaxy.systems();
+    systems.sort_by_key(|s| s.id);
+
+    let connections = systems
+        .iter()
+        .flat_map(|s| {
+            galaxy
+                .get_connections(&s.id)
+                .unwrap_or_default()
+                .into_owned()
+        })
+        .collect::<Vec<_>>();
+    let mut by_pair: HashMap<(SystemId, SystemId), Vec<&ConnectionType>> = HashMap::new();
+    for conn in &connections {
+        by_pair
+            .entry((conn.from, conn.to))
+            .or_default()
+            .push(&conn.type_);
+    }
+    let mut edges = Vec::new();
+    for conn in &connections {
+        let reverse = by_pair
+            .get(&(conn.to, conn.from))
+            .is_some_and(|types| types.contains(&&conn.type_));
+        if reverse && conn.from > conn.to {
+            continue;
+        }
+        edges.push(Edge {
+            from: conn.from,
+            to: conn.to,
+            type_: conn.type_.clone(),
+            directed: !reverse,
+        });
+    }
+    edges.sort_by_key(|e| (e.from, e.to));
+    (systems, edges)
+}
@@ -0,0 +1,287 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! C bindings for loading a universe and finding routes, so tools written in other
+//! languages, e.g. Python through ctypes or C# through P/Invoke, can use the
+//! pathfinding without a rewrite.
+//!
+//! Build a shared library with `cargo rustc --release --lib --features ffi
+//! --crate-type cdylib` and include `include/neweden.h`, which `cbindgen`
+//! regenerates from this module with the `cbindgen.toml` at the root of the
+//! repository.
+//!
+//! Everything returned by a `neweden_*_load` or `neweden_route` call is owned by
+//! the caller and released with the matching `*_free` function. On failure they
+//! return null and `neweden_last_error` describes what went wrong. Panics do not
+//! cross into the caller: they are reported the same way, as an internal error.
+//!
+//! ```c
+//! NewedenUniverse *universe = neweden_universe_load("universe.json");
+//! NewedenPath *path = neweden_route(universe, 30000142, 30002187, NEWEDEN_SHORTEST);
+//! for (size_t i = 0; path && i < path->len; i++) {
+//!     printf("%u\n", path->systems[i]);
+//! }
+//! neweden_path_free(path);
+//! neweden_universe_free(universe);
+//! ```
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::fmt::Display;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use crate::navigation::{PathBuilder, Preference};
+use crate::types::{self, Navigatable};
+
+/// `Preference::Shortest`.
+pub const NEWEDEN_SHORTEST: u32 = 0;
+/// `Preference::Highsec`.
+pub const NEWEDEN_HIGHSEC: u32 = 1;
+/// `Preference::LowsecAndNullsec`.
+pub const NEWEDEN_LOWSEC_AND_NULLSEC: u32 = 2;
+
+/// A loaded universe. Opaque to C.
+pub struct NewedenUniverse {
+    universe: types::Universe,
+}
+
+/// The systems of a route, from the origin to the destination.
+#[repr(C)]
+pub struct NewedenPath {
+    /// `len` system ids.
+    pub systems: *const u32,
+    pub len: usize,
+    pub jumps: usize,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn fail<T>(error: impl Display) -> *mut T {
+    // interior nul bytes can not be represented and are dropped
+    let message = error.to_string().replace('\0', "");
+    LAST_ERROR.with(|e| *e.borrow_mut() = CString::new(message).ok());
+    std::ptr::null_mut()
+}
+
+/// Runs the body of an entry point, turning a panic into a failure, as unwinding
+/// into the caller aborts the host process.
+fn guard<T>(f: impl FnOnce() -> *mut T) -> *mut T {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|panic| {
+        let message = panic
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown panic");
+        fail(format!("internal error: {}", message))
+    })
+}
+
+/// The message of the last failed call on this thread, or null. The string is valid
+/// until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn neweden_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(std::ptr::null(), |m| m.as_ptr()))
+}
+
+/// Loads a universe from a JSON bundle file, see `source::json`.
+///
+/// # Safety
+/// `path` must be null or a nul terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn neweden_universe_load(path: *const c_char) -> *mut NewedenUniverse {
+    guard(|| {
+        let Some(path) = str_arg(path) else {
+            return fail("path is null or not UTF-8");
+        };
+        let json = match std::fs::read(path) {
+            Ok(json) => json,
+            Err(e) => return fail(e),
+        };
+        match crate::source::json::load_slice(&json) {
+            Ok(universe) => Box::into_raw(Box::new(NewedenUniverse { universe })),
+            Err(e) => fail(e),
+        }
+    })
+}
+
+/// Loads a universe from a SQLite static data export.
+///
+/// # Safety
+/// `path` must be null or a nul terminated string.
+#[cfg(feature = "sqlite")]
+#[no_mangle]
+pub unsafe extern "C" fn neweden_universe_load_sqlite(path: *const c_char) -> *mut NewedenUniverse {
+    guard(|| {
+        let Some(path) = str_arg(path) else {
+            return fail("path is null or not UTF-8");
+        };
+        match crate::source::sqlite::DatabaseBuilder::new(path).build() {
+            Ok(universe) => Box::into_raw(Box::new(NewedenUniverse { universe })),
+            Err(e) => fail(e),
+        }
+    })
+}
+
+/// Releases a universe. Null is ignored.
+///
+/// # Safety
+/// `universe` must be null or returned by a `neweden_universe_load` function and
+/// not released before.
+#[no_mangle]
+pub unsafe extern "C" fn neweden_universe_free(universe: *mut NewedenUniverse) {
+    if !universe.is_null() {
+        drop(Box::from_raw(universe));
+    }
+}
+
+/// The route from `from` to `to` with one of the `NEWEDEN_*` preferences, or null
+/// if there is none.
+///
+/// # Safety
+/// `universe` must be null or a universe that was not released.
+#[no_mangle]
+pub unsafe extern "C" fn neweden_route(
+    universe: *const NewedenUniverse,
+    from: u32,
+    to: u32,
+    preference: u32,
+) -> *mut NewedenPath {
+    guard(|| {
+        let Some(universe) = universe.as_ref().map(|u| &u.universe) else {
+            return fail("universe is null");
+        };
+        let preference = match preference {
+            NEWEDEN_SHORTEST => Preference::Shortest,
+            NEWEDEN_HIGHSEC => Preference::Highsec,
+            NEWEDEN_LOWSEC_AND_NULLSEC => Preference::LowsecAndNullsec,
+            other => return fail(format!("unknown preference {}", other)),
+        };
+        let system = |id: u32| {
+            universe
+                .get_system(&id.into())
+                .ok_or_else(|| format!("unknown system {}", id))
+        };
+        let (from, to) = match (system(from), system(to)) {
+            (Ok(from), Ok(to)) => (from, to),
+            (Err(e), _) | (_, Err(e)) => return fail(e),
+        };
+        let path = match PathBuilder::new(universe)
+            .waypoint(from)
+            .waypoint(to)
+            .prefer(preference)
+            .try_build()
+        {
+            Ok(path) => path,
+            Err(e) => return fail(e),
+        };
+        let systems = path.systems().map(|s| s.id.0).collect::<Box<[u32]>>();
+        let len = systems.len();
+        Box::into_raw(Box::new(NewedenPath {
+            systems: Box::into_raw(systems) as *const u32,
+            len,
+            jumps: path.jumps(),
+        }))
+    })
+}
+
+/// Releases a path. Null is ignored.
+///
+/// # Safety
+/// `path` must be null or returned by `neweden_route` and not released before.
+#[no_mangle]
+pub unsafe extern "C" fn neweden_path_free(path: *mut NewedenPath) {
+    if path.is_null() {
+        return;
+    }
+    let path = Box::from_raw(path);
+    let systems = std::ptr::slice_from_raw_parts_mut(path.systems as *mut u32, path.len);
+    drop(Box::from_raw(systems));
+}
+
+unsafe fn str_arg<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    CStr::from_ptr(s).to_str().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::json::UniverseDocument;
+    use crate::testing::*;
+
+    fn last_error() -> String {
+        let error = neweden_last_error();
+        assert!(!error.is_null());
+        unsafe { CStr::from_ptr(error) }
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_route() {
+        let file = std::env::temp_dir().join(format!("neweden-ffi-{}.json", std::process::id()));
+        let bundle = UniverseDocument::new(&universe()).to_json().unwrap();
+        std::fs::write(&file, bundle).unwrap();
+        let name = CString::new(file.to_str().unwrap()).unwrap();
+
+        unsafe {
+            let universe = neweden_universe_load(name.as_ptr());
+            std::fs::remove_file(&file).unwrap();
+            assert!(!universe.is_null());
+
+            let path = neweden_route(universe, ALPHA.0, ECHO.0, NEWEDEN_SHORTEST);
+            assert!(!path.is_null());
+            let systems = std::slice::from_raw_parts((*path).systems, (*path).len);
+            assert_eq!(&[ALPHA.0, BRAVO.0, CHARLIE.0, DELTA.0, ECHO.0][..], systems);
+            assert_eq!(4, (*path).jumps);
+            neweden_path_free(path);
+
+            assert!(neweden_route(universe, ALPHA.0, 1, NEWEDEN_SHORTEST).is_null());
+            assert_eq!("unknown system 1", last_error());
+            assert!(neweden_route(universe, ALPHA.0, ECHO.0, 7).is_null());
+            assert_eq!("unknown preference 7", last_error());
+            neweden_universe_free(universe);
+
+            assert!(neweden_universe_load(name.as_ptr()).is_null());
+            assert!(neweden_universe_load(std::ptr::null()).is_null());
+            assert_eq!("path is null or not UTF-8", last_error());
+            neweden_path_free(std::ptr::null_mut());
+            neweden_universe_free(std::ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn test_dangling_connection() {
+        let mut document = UniverseDocument::new(&universe());
+        let mut dangling = document.connections[0].clone();
+        dangling.from = ALPHA;
+        dangling.to = 30009999.into();
+        document.connections.push(dangling);
+
+        let file =
+            std::env::temp_dir().join(format!("neweden-ffi-dangling-{}.json", std::process::id()));
+        std::fs::write(&file, document.to_json().unwrap()).unwrap();
+        let name = CString::new(file.to_str().unwrap()).unwrap();
+        let loaded = unsafe { neweden_universe_load(name.as_ptr()) };
+        std::fs::remove_file(&file).unwrap();
+        assert!(loaded.is_null());
+        assert!(last_error().starts_with("universe has 1 integrity issues"));
+
+        // universes from other sources are not validated, routing on them panics
+        let universe = NewedenUniverse {
+            universe: types::Universe::new(
+                types::SystemMap::from(document.systems),
+                types::AdjacentMap::from(document.connections),
+            ),
+        };
+        let path = unsafe { neweden_route(&universe, ALPHA.0, ECHO.0, NEWEDEN_HIGHSEC) };
+        assert!(path.is_null());
+        assert!(last_error().starts_with("internal error: "));
+    }
+}
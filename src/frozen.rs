@@ -0,0 +1,289 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! A read-only universe with a compact adjacency representation.
+//!
+//! `Universe` keeps a `Vec<Connection>` per system in a hash map, which costs about
+//! 40 bytes per connection plus the allocations. A `FrozenUniverse` stores all
+//! connections in a single byte buffer instead: the neighbours of every system
+//! sorted by id and delta encoded as varints, each followed by an index into a small
+//! table of the distinct connection types. Most connections take two or three bytes.
+//! Connections are decoded on access, which is cheap compared to the cache misses
+//! saved. Use it for servers that load the map once and only route on it.
+use std::borrow::Cow;
+
+use crate::types::{
+    Celestial, Connection, ConnectionSource, ConnectionType, Galaxy, Locale, Meters, NameIndex,
+    Navigatable, Sovereignty, Stargate, System, SystemId, Universe,
+};
+
+/// A universe whose connections can no longer change. Created by `Universe::freeze`.
+#[derive(Debug)]
+pub struct FrozenUniverse {
+    /// Everything but the connections, which are moved into `adjacency`.
+    universe: Universe,
+    /// Systems with outgoing connections, sorted.
+    ids: Vec<SystemId>,
+    /// Start of every system's connections in `adjacency`, one more than `ids`.
+    offsets: Vec<u32>,
+    adjacency: Vec<u8>,
+    /// The distinct connection types and sources, referenced from `adjacency`.
+    palette: Vec<(ConnectionType, ConnectionSource)>,
+}
+
+impl FrozenUniverse {
+    pub(crate) fn new(mut universe: Universe) -> Self {
+        let connections = std::mem::take(&mut universe.connections_mut().0);
+        let mut ids = connections
+            .iter()
+            .filter(|(_, c)| !c.is_empty())
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        ids.sort();
+
+        let mut palette: Vec<(ConnectionType, ConnectionSource)> = Vec::new();
+        let mut offsets = Vec::with_capacity(ids.len() + 1);
+        let mut adjacency = Vec::new();
+        for id in &ids {
+            offsets.push(adjacency.len() as u32);
+            let mut entries = connections[id]
+                .iter()
+                .map(|c| {
+                    let key = (c.type_.clone(), c.source);
+                    let index = match palette.iter().position(|p| *p == key) {
+                        Some(index) => index,
+                        None => {
+                            palette.push(key);
+                            palette.len() - 1
+                        }
+                    };
+                    (c.to.0, index as u32)
+                })
+                .collect::<Vec<_>>();
+            entries.sort_unstable();
+
+            write_varint(&mut adjacency, entries.len() as u32);
+            let mut previous = 0;
+            for (to, index) in entries {
+                write_varint(&mut adjacency, to - previous);
+                write_varint(&mut adjacency, index);
+                previous = to;
+            }
+        }
+        offsets.push(adjacency.len() as u32);
+        adjacency.shrink_to_fit();
+
+        Self {
+            universe,
+            ids,
+            offsets,
+            adjacency,
+            palette,
+        }
+    }
+
+    /// Turns the universe back into a mutable one.
+    pub fn thaw(mut self) -> Universe {
+        let connections = self.all_connections();
+        for connection in connections {
+            self.universe
+                .connections_mut()
+                .0
+                .entry(connection.from)
+                .or_default()
+                .push(connection);
+        }
+        self.universe
+    }
+
+    /// The size of the encoded connections in bytes.
+    pub fn adjacency_size(&self) -> usize {
+        self.adjacency.len() + self.offsets.len() * std::mem::size_of::<u32>()
+    }
+
+    /// The universe without its connections, e.g. for celestial and effect queries.
+    pub fn universe(&self) -> &Universe {
+        &self.universe
+    }
+
+    fn decode(&self, from: &SystemId) -> Vec<Connection> {
+        let Ok(i) = self.ids.binary_search(from) else {
+            return Vec::new();
+        };
+        let mut bytes = &self.adjacency[self.offsets[i] as usize..self.offsets[i + 1] as usize];
+        let count = read_varint(&mut bytes);
+        let mut connections = Vec::with_capacity(count as usize);
+        let mut to = 0;
+        for _ in 0..count {
+            to += read_varint(&mut bytes);
+            let (type_, source) = &self.palette[read_varint(&mut bytes) as usize];
+            connections.push(Connection {
+                from: *from,
+                to: SystemId(to),
+                type_: type_.clone(),
+                source: *source,
+            });
+        }
+        connections
+    }
+
+    fn all_connections(&self) -> Vec<Connection> {
+        self.ids.iter().flat_map(|id| self.decode(id)).collect()
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        bytes.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn read_varint(bytes: &mut &[u8]) -> u32 {
+    let mut value = 0;
+    let mut shift = 0;
+    loop {
+        let byte = bytes[0];
+        *bytes = &bytes[1..];
+        value |= u32::from(byte & 0x7f) << shift;
+        if byte < 0x80 {
+            return value;
+        }
+        shift += 7;
+    }
+}
+
+impl Galaxy for FrozenUniverse {
+    fn systems(&self) -> Vec<&System> {
+        self.universe.systems()
+    }
+
+    fn connections(&self) -> Vec<(SystemId, SystemId)> {
+        self.iter_connections().collect()
+    }
+
+    /// Decodes the connections of one system at a time.
+    fn iter_connections(&self) -> Box<dyn Iterator<Item = (SystemId, SystemId)> + '_> {
+        Box::new(
+            self.ids
+                .iter()
+                .flat_map(|id| self.decode(id))
+                .map(|c| (c.from, c.to)),
+        )
+    }
+
+    fn iter_systems(&self) -> Box<dyn Iterator<Item = &System> + '_> {
+        self.universe.iter_systems()
+    }
+}
+
+impl Navigatable for FrozenUniverse {
+    fn get_system(&self, id: &SystemId) -> Option<&System> {
+        self.universe.get_system(id)
+    }
+
+    fn get_connections(&self, from: &SystemId) -> Option<Cow<'_, [Connection]>> {
+        let connections = self.decode(from);
+        if connections.is_empty() {
+            None
+        } else {
+            Some(Cow::Owned(connections))
+        }
+    }
+
+    fn get_systems_by_range(&self, from: &SystemId, range: Meters) -> Option<Vec<&System>> {
+        self.universe.get_systems_by_range(from, range)
+    }
+
+    fn get_stargates(&self, system: &SystemId) -> &[Stargate] {
+        self.universe.get_stargates(system)
+    }
+
+    fn get_localized_name(&self, system: &SystemId, locale: Locale) -> Option<&str> {
+        self.universe.get_localized_name(system, locale)
+    }
+
+    fn get_sovereignty(&self, system: &SystemId) -> Option<&Sovereignty> {
+        self.universe.get_sovereignty(system)
+    }
+
+    fn get_celestials(&self, system: &SystemId) -> &[Celestial] {
+        self.universe.get_celestials(system)
+    }
+
+    fn name_index(&self) -> Option<&dyn NameIndex> {
+        self.universe.name_index()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation::PathBuilder;
+    use crate::testing::*;
+
+    fn targets(connections: Option<Cow<'_, [Connection]>>) -> Vec<(SystemId, ConnectionType)> {
+        let mut targets = connections
+            .unwrap_or_default()
+            .iter()
+            .map(|c| (c.to, c.type_.clone()))
+            .collect::<Vec<_>>();
+        targets.sort_by_key(|t| t.0);
+        targets
+    }
+
+    #[test]
+    fn test_freeze() {
+        let original = universe();
+        let frozen = universe().freeze();
+        for system in original.systems() {
+            assert_eq!(
+                targets(original.get_connections(&system.id)),
+                targets(frozen.get_connections(&system.id))
+            );
+        }
+        assert_eq!(original.connections().len(), frozen.connections().len());
+        assert!(frozen.adjacency_size() < 18 * std::mem::size_of::<Connection>());
+
+        let path = PathBuilder::new(&frozen)
+            .waypoint(frozen.get_system(&ALPHA).unwrap())
+            .waypoint(frozen.get_system(&ECHO).unwrap())
+            .build()
+            .unwrap();
+        assert_eq!(4, path.jumps());
+        assert_eq!(
+            Some(ALPHA),
+            frozen.get_system_by_name("Alpha").map(|s| s.id)
+        );
+
+        let frozen = universe()
+            .with_localized_names(vec![(ALPHA, Locale::German, "Alfa".to_string())])
+            .freeze();
+        assert_eq!(
+            Some("Alfa"),
+            frozen.get_localized_name(&ALPHA, Locale::German)
+        );
+
+        let thawed = frozen.thaw();
+        assert_eq!(
+            targets(original.get_connections(&GOLF)),
+            targets(thawed.get_connections(&GOLF))
+        );
+    }
+
+    #[test]
+    fn test_varint() {
+        let mut bytes = Vec::new();
+        for value in [0, 127, 128, 30000142, u32::MAX] {
+            write_varint(&mut bytes, value);
+        }
+        let mut slice = bytes.as_slice();
+        for value in [0, 127, 128, 30000142, u32::MAX] {
+            assert_eq!(value, read_varint(&mut slice));
+        }
+        assert!(slice.is_empty());
+    }
+}
@@ -0,0 +1,221 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! The routing core: system ids, a compact weighted graph and Dijkstra.
+//!
+//! This module only depends on `core` and `alloc`. Building the crate with
+//! `default-features = false, features = ["core-only"]` compiles it as `no_std`
+//! with nothing but this module, so routing can run in environments without
+//! std, such as plugin sandboxes. With std available, use
+//! `Universe::to_graph` to obtain a graph from a universe.
+//!
+//! # Example
+//! ```
+//! use neweden::graph::{Graph, SystemId};
+//!
+//! let graph = Graph::from_edges(vec![
+//!     (SystemId(1), SystemId(2), 1),
+//!     (SystemId(2), SystemId(3), 1),
+//!     (SystemId(1), SystemId(3), 5),
+//! ]);
+//! let (path, cost) = graph.shortest_path(SystemId(1), SystemId(3)).unwrap();
+//! assert_eq!(vec![SystemId(1), SystemId(2), SystemId(3)], path);
+//! assert_eq!(2, cost);
+//! ```
+use alloc::collections::{BTreeMap, BinaryHeap};
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+
+/// Describes the ID of a solar system. Can be casted to from i32 or u32 using .into()
+///
+/// # Example
+/// ```
+/// use neweden::graph::SystemId;
+///
+/// let system_id: SystemId = 30000142.into(); // returns a SystemId
+/// assert_eq!(system_id, SystemId(30000142));
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SystemId(pub u32);
+
+impl From<u32> for SystemId {
+    fn from(other: u32) -> Self {
+        SystemId(other)
+    }
+}
+
+impl From<i32> for SystemId {
+    fn from(other: i32) -> Self {
+        SystemId(other as u32)
+    }
+}
+
+/// A directed graph with `u32` edge costs, stored in compressed sparse row form.
+#[derive(Debug, Clone, Default)]
+pub struct Graph {
+    ids: Vec<SystemId>,
+    index: BTreeMap<SystemId, usize>,
+    offsets: Vec<usize>,
+    targets: Vec<usize>,
+    costs: Vec<u32>,
+}
+
+impl Graph {
+    /// Builds a graph from `(from, to, cost)` edges. Systems are implied by the edges.
+    pub fn from_edges<I>(edges: I) -> Self
+    where
+        I: IntoIterator<Item = (SystemId, SystemId, u32)>,
+    {
+        let mut edges = edges.into_iter().collect::<Vec<_>>();
+        edges.sort_unstable();
+
+        let mut index = BTreeMap::new();
+        for (from, to, _) in &edges {
+            index.entry(*from).or_insert(0);
+            index.entry(*to).or_insert(0);
+        }
+        let mut ids = Vec::with_capacity(index.len());
+        for (i, (id, slot)) in index.iter_mut().enumerate() {
+            *slot = i;
+            ids.push(*id);
+        }
+
+        let mut offsets = alloc::vec![0; ids.len() + 1];
+        let mut targets = Vec::with_capacity(edges.len());
+        let mut costs = Vec::with_capacity(edges.len());
+        for (from, to, cost) in &edges {
+            offsets[index[from] + 1] += 1;
+            targets.push(index[to]);
+            costs.push(*cost);
+        }
+        for i in 1..offsets.len() {
+            offsets[i] += offsets[i - 1];
+        }
+
+        Self {
+            ids,
+            index,
+            offsets,
+            targets,
+            costs,
+        }
+    }
+
+    /// Returns the number of systems in the graph.
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    pub fn contains(&self, id: &SystemId) -> bool {
+        self.index.contains_key(id)
+    }
+
+    /// Returns the systems reachable in one jump from `id` together with the cost.
+    pub fn neighbours(&self, id: &SystemId) -> impl Iterator<Item = (SystemId, u32)> + '_ {
+        let range = match self.index.get(id) {
+            Some(&i) => self.offsets[i]..self.offsets[i + 1],
+            None => 0..0,
+        };
+        range.map(move |e| (self.ids[self.targets[e]], self.costs[e]))
+    }
+
+    /// Finds the cheapest path from `from` to `to` using Dijkstra. Returns the systems
+    /// along the path, including both ends, and its total cost.
+    pub fn shortest_path(&self, from: SystemId, to: SystemId) -> Option<(Vec<SystemId>, u32)> {
+        let start = *self.index.get(&from)?;
+        let goal = *self.index.get(&to)?;
+
+        let mut dist = alloc::vec![u32::MAX; self.len()];
+        let mut prev = alloc::vec![usize::MAX; self.len()];
+        let mut heap = BinaryHeap::new();
+        dist[start] = 0;
+        heap.push(Reverse((0u32, start)));
+
+        while let Some(Reverse((cost, node))) = heap.pop() {
+            if node == goal {
+                let mut path = alloc::vec![self.ids[goal]];
+                let mut current = goal;
+                while current != start {
+                    current = prev[current];
+                    path.push(self.ids[current]);
+                }
+                path.reverse();
+                return Some((path, cost));
+            }
+            if cost > dist[node] {
+                continue;
+            }
+            for e in self.offsets[node]..self.offsets[node + 1] {
+                let next = self.targets[e];
+                let next_cost = cost.saturating_add(self.costs[e]);
+                if next_cost < dist[next] {
+                    dist[next] = next_cost;
+                    prev[next] = node;
+                    heap.push(Reverse((next_cost, next)));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shortest_path() {
+        let graph = Graph::from_edges(alloc::vec![
+            (SystemId(1), SystemId(2), 1),
+            (SystemId(2), SystemId(3), 1),
+            (SystemId(1), SystemId(3), 5),
+            (SystemId(3), SystemId(4), 2),
+        ]);
+        assert_eq!(4, graph.len());
+        assert_eq!(
+            Some((alloc::vec![SystemId(1)], 0)),
+            graph.shortest_path(SystemId(1), SystemId(1))
+        );
+        assert_eq!(
+            Some((
+                alloc::vec![SystemId(1), SystemId(2), SystemId(3), SystemId(4)],
+                4
+            )),
+            graph.shortest_path(SystemId(1), SystemId(4))
+        );
+        assert_eq!(None, graph.shortest_path(SystemId(4), SystemId(1)));
+        assert_eq!(None, graph.shortest_path(SystemId(1), SystemId(9)));
+        assert_eq!(
+            alloc::vec![(SystemId(2), 1), (SystemId(3), 5)],
+            graph.neighbours(&SystemId(1)).collect::<Vec<_>>()
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_matches_path_builder() {
+        use crate::navigation::PathBuilder;
+        use crate::testing::*;
+        use crate::types::Navigatable;
+
+        let universe = universe();
+        let graph = universe.to_graph();
+        for to in [CHARLIE, ECHO, HOTEL] {
+            let path = PathBuilder::new(&universe)
+                .waypoint(universe.get_system(&ALPHA).unwrap())
+                .waypoint(universe.get_system(&to).unwrap())
+                .build()
+                .unwrap();
+            let (systems, cost) = graph.shortest_path(ALPHA, to).unwrap();
+            assert_eq!(path.jumps(), cost as usize);
+            assert_eq!(path.jumps() + 1, systems.len());
+        }
+    }
+}
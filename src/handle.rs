@@ -0,0 +1,199 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Replacing the universe of a running service.
+//!
+//! A `UniverseHandle` holds the current universe behind an `Arc`. Queries take a
+//! snapshot with `load` and keep routing on it, while `swap` or `reload` install a
+//! new universe for every later query, e.g. after a new static data export was
+//! published. The old universe is dropped once the last query using it finishes.
+//!
+//! # Example
+//! ```no_run
+//! use neweden::handle::UniverseHandle;
+//! use neweden::navigation::PathBuilder;
+//! use neweden::Navigatable;
+//! # fn load_from_sde() -> anyhow::Result<neweden::Universe> { unimplemented!() }
+//!
+//! // e.g. move || DatabaseBuilder::new(&uri).build()
+//! let handle = UniverseHandle::from_source(load_from_sde).unwrap();
+//!
+//! // per request
+//! let universe = handle.load();
+//! let path = PathBuilder::new(&universe)
+//!     .waypoint(universe.get_system(&30000142.into()).unwrap())
+//!     .waypoint(universe.get_system(&30002187.into()).unwrap())
+//!     .build();
+//!
+//! // once the database file was replaced
+//! handle.reload().unwrap();
+//! ```
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+use crate::types;
+
+type Source<U> = Box<dyn Fn() -> anyhow::Result<U> + Send + Sync>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ReloadError {
+    #[error("the handle was created without a source to reload from")]
+    NoSource,
+    #[error("loading the universe failed: {0}")]
+    Source(#[from] anyhow::Error),
+}
+
+/// A universe that can be replaced while queries are running on it.
+pub struct UniverseHandle<U = types::Universe> {
+    current: RwLock<(u64, Arc<U>)>,
+    source: Option<Source<U>>,
+}
+
+impl<U> UniverseHandle<U> {
+    /// A handle on a universe that can only be replaced with `swap`.
+    pub fn new(universe: U) -> Self {
+        Self {
+            current: RwLock::new((0, Arc::new(universe))),
+            source: None,
+        }
+    }
+
+    /// A handle on the universe built by `source`, which `reload` calls again to
+    /// rebuild it.
+    pub fn from_source<F>(source: F) -> anyhow::Result<Self>
+    where
+        F: Fn() -> anyhow::Result<U> + Send + Sync + 'static,
+    {
+        let universe = source()?;
+        Ok(Self {
+            current: RwLock::new((0, Arc::new(universe))),
+            source: Some(Box::new(source)),
+        })
+    }
+
+    /// The current universe. It stays valid, and unchanged, for as long as it is
+    /// held, regardless of later swaps.
+    pub fn load(&self) -> Arc<U> {
+        self.read().1.clone()
+    }
+
+    /// The current universe with its version, see `version`.
+    pub fn load_versioned(&self) -> (u64, Arc<U>) {
+        let current = self.read();
+        (current.0, current.1.clone())
+    }
+
+    /// How often the universe was replaced, e.g. to invalidate caches built on an
+    /// older universe.
+    pub fn version(&self) -> u64 {
+        self.read().0
+    }
+
+    /// Replaces the universe for all later `load`s and returns the previous one.
+    pub fn swap(&self, universe: U) -> Arc<U> {
+        self.swap_arc(Arc::new(universe))
+    }
+
+    /// Like `swap`, but for a universe that is already shared.
+    pub fn swap_arc(&self, universe: Arc<U>) -> Arc<U> {
+        self.replace(universe).1
+    }
+
+    /// Rebuilds the universe from the source the handle was created with and swaps
+    /// it in, returning the new version. The current universe is kept if loading
+    /// fails. Building happens outside of the lock, so `load` is never blocked by it.
+    pub fn reload(&self) -> Result<u64, ReloadError> {
+        let source = self.source.as_ref().ok_or(ReloadError::NoSource)?;
+        let universe = source()?;
+        crate::metrics::increment(crate::metrics::UNIVERSE_RELOADS);
+        Ok(self.replace(Arc::new(universe)).0)
+    }
+
+    /// Installs a universe, returning the new version and the previous universe.
+    fn replace(&self, universe: Arc<U>) -> (u64, Arc<U>) {
+        let mut current = self.current.write().unwrap_or_else(|e| e.into_inner());
+        current.0 += 1;
+        (current.0, std::mem::replace(&mut current.1, universe))
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, (u64, Arc<U>)> {
+        // the lock only guards a swap of two fields, a panic cannot leave it torn
+        self.current.read().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<U> fmt::Debug for UniverseHandle<U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UniverseHandle")
+            .field("version", &self.version())
+            .field("reloadable", &self.source.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation::PathBuilder;
+    use crate::testing::*;
+    use crate::types::Navigatable;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    fn jumps(universe: &types::DynamicUniverse) -> Option<usize> {
+        PathBuilder::new(universe)
+            .waypoint(universe.get_system(&ALPHA).unwrap())
+            .waypoint(universe.get_system(&HOTEL).unwrap())
+            .build()
+            .map(|p| p.jumps())
+    }
+
+    #[test]
+    fn test_swap() {
+        let handle = UniverseHandle::new(types::DynamicUniverse::from(universe()));
+        let snapshot = handle.load();
+        assert_eq!(0, handle.version());
+
+        // Hotel is only reachable through Golf
+        let mut changed = types::DynamicUniverse::from(universe());
+        changed.remove_connections(|c| c.from == GOLF || c.to == GOLF);
+        let old = handle.swap(changed);
+        assert!(Arc::ptr_eq(&old, &snapshot));
+        assert_eq!(1, handle.version());
+
+        // queries holding the old snapshot keep routing on it
+        assert_eq!(Some(4), jumps(&snapshot));
+        assert_eq!(None, jumps(&handle.load()));
+    }
+
+    #[test]
+    fn test_reload() {
+        assert!(matches!(
+            UniverseHandle::new(universe()).reload(),
+            Err(ReloadError::NoSource)
+        ));
+
+        let fail = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let fail = fail.clone();
+            UniverseHandle::from_source(move || {
+                if fail.load(Ordering::SeqCst) {
+                    anyhow::bail!("database is locked");
+                }
+                Ok(universe())
+            })
+            .unwrap()
+        };
+        let first = handle.load();
+        assert_eq!(1, handle.reload().unwrap());
+        assert!(!Arc::ptr_eq(&first, &handle.load()));
+
+        fail.store(true, Ordering::SeqCst);
+        let current = handle.load();
+        assert!(matches!(handle.reload(), Err(ReloadError::Source(_))));
+        let (version, loaded) = handle.load_versioned();
+        assert_eq!(1, version);
+        assert!(Arc::ptr_eq(&current, &loaded));
+    }
+}
@@ -0,0 +1,260 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Webhooks for changes to a `DynamicUniverse`.
+//!
+//! A `HookRegistry` posts a JSON payload to every registered URL when wormholes are
+//! added or expire, and when a watched route loses one of its connections. Discord
+//! bots and dashboards can react to the payloads instead of polling the universe.
+//! Changes have to go through the registry to be announced.
+//!
+//! # Example
+//! ```no_run
+//! use neweden::builder::UniverseBuilder;
+//! use neweden::hooks::HookRegistry;
+//! use neweden::{Connection, ConnectionSource, ConnectionType, DynamicUniverse, WormholeType};
+//!
+//! let mut universe = DynamicUniverse::from(UniverseBuilder::new().build());
+//! let mut hooks = HookRegistry::new().url("https://discord.com/api/webhooks/1/token");
+//! hooks
+//!     .add_connection(
+//!         &mut universe,
+//!         Connection {
+//!             from: 30000142.into(),
+//!             to: 31000005.into(),
+//!             type_: ConnectionType::Wormhole(WormholeType::Large),
+//!             source: ConnectionSource::EveScout,
+//!         },
+//!     )
+//!     .unwrap();
+//! ```
+use anyhow;
+use serde::Serialize;
+
+use crate::navigation::Path;
+use crate::types;
+
+/// Sends a payload to a URL.
+type Sender = Box<dyn Fn(&str, &str) -> anyhow::Result<()>>;
+
+/// The payload posted to the hooks, tagged by `event`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum HookEvent {
+    WormholeAdded {
+        connection: types::Connection,
+    },
+    WormholeExpired {
+        connection: types::Connection,
+    },
+    /// A watched route used a connection that was removed.
+    RouteInvalidated {
+        route: String,
+        from: types::SystemId,
+        to: types::SystemId,
+    },
+}
+
+/// The URLs to notify and the routes to watch.
+pub struct HookRegistry {
+    urls: Vec<String>,
+    routes: Vec<(String, Vec<types::SystemId>)>,
+    sender: Sender,
+}
+
+impl Default for HookRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HookRegistry {
+    pub fn new() -> Self {
+        Self {
+            urls: Vec::new(),
+            routes: Vec::new(),
+            sender: Box::new(post),
+        }
+    }
+
+    pub fn url(mut self, url: &str) -> Self {
+        self.add_url(url);
+        self
+    }
+
+    /// Delivers payloads with the given function instead of an HTTP POST, e.g. to
+    /// queue them or in tests.
+    pub fn sender(mut self, sender: impl Fn(&str, &str) -> anyhow::Result<()> + 'static) -> Self {
+        self.sender = Box::new(sender);
+        self
+    }
+
+    /// Like `url`, but for use when the registry is not consumed, e.g. in loops.
+    pub fn add_url(&mut self, url: &str) -> &mut Self {
+        self.urls.push(url.to_string());
+        self
+    }
+
+    /// Announces when a connection of the path is removed. Watching a route under a
+    /// name already watched replaces it.
+    pub fn watch_route(&mut self, name: &str, path: &Path) -> &mut Self {
+        self.unwatch_route(name);
+        let systems = path.systems().map(|s| s.id).collect();
+        self.routes.push((name.to_string(), systems));
+        self
+    }
+
+    pub fn unwatch_route(&mut self, name: &str) -> &mut Self {
+        self.routes.retain(|(n, _)| n != name);
+        self
+    }
+
+    /// Adds the connection to the universe and announces it if it is a wormhole.
+    pub fn add_connection(
+        &self,
+        universe: &mut types::DynamicUniverse,
+        connection: types::Connection,
+    ) -> anyhow::Result<()> {
+        let event = is_wormhole(&connection).then(|| HookEvent::WormholeAdded {
+            connection: connection.clone(),
+        });
+        universe.add_connection(connection);
+        match event {
+            Some(event) => self.notify(&event),
+            None => Ok(()),
+        }
+    }
+
+    /// Removes the matching connections from the universe, announces the wormholes
+    /// among them as expired and invalidates the watched routes using any of them.
+    /// Invalidated routes are no longer watched.
+    pub fn remove_connections<F>(
+        &mut self,
+        universe: &mut types::DynamicUniverse,
+        predicate: F,
+    ) -> anyhow::Result<Vec<types::Connection>>
+    where
+        F: Fn(&types::Connection) -> bool,
+    {
+        let removed = universe.remove_connections(predicate);
+        let mut events = removed
+            .iter()
+            .filter(|c| is_wormhole(c))
+            .map(|c| HookEvent::WormholeExpired {
+                connection: c.clone(),
+            })
+            .collect::<Vec<_>>();
+        self.routes.retain(|(name, systems)| {
+            let broken = systems
+                .windows(2)
+                .find(|leg| removed.iter().any(|c| c.from == leg[0] && c.to == leg[1]));
+            if let Some(leg) = broken {
+                events.push(HookEvent::RouteInvalidated {
+                    route: name.clone(),
+                    from: leg[0],
+                    to: leg[1],
+                });
+            }
+            broken.is_none()
+        });
+
+        // the connections are gone either way, announce as much as possible
+        let mut result = Ok(());
+        for event in &events {
+            let sent = self.notify(event);
+            if result.is_ok() {
+                result = sent;
+            }
+        }
+        result.map(|_| removed)
+    }
+
+    /// Posts the event to every URL. All URLs are tried, the first failure is
+    /// returned.
+    pub fn notify(&self, event: &HookEvent) -> anyhow::Result<()> {
+        let payload = serde_json::to_string(event)?;
+        let mut result = Ok(());
+        for url in &self.urls {
+            let sent = (self.sender)(url, &payload);
+            if result.is_ok() {
+                result = sent;
+            }
+        }
+        result
+    }
+}
+
+fn is_wormhole(connection: &types::Connection) -> bool {
+    matches!(connection.type_, types::ConnectionType::Wormhole(_))
+}
+
+fn post(url: &str, payload: &str) -> anyhow::Result<()> {
+    ureq::post(url)
+        .set("Content-Type", "application/json")
+        .send_string(payload)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::navigation::PathBuilder;
+    use crate::testing::*;
+    use crate::types::Navigatable;
+
+    #[test]
+    fn test_hooks() {
+        let sent = Rc::new(RefCell::new(Vec::new()));
+        let outbox = sent.clone();
+        let mut hooks =
+            HookRegistry::new()
+                .url("https://example.com/hook")
+                .sender(move |url, payload| {
+                    outbox
+                        .borrow_mut()
+                        .push((url.to_string(), payload.to_string()));
+                    Ok(())
+                });
+
+        let mut universe = types::DynamicUniverse::from(universe());
+        let hole = types::Connection {
+            from: ALPHA,
+            to: ECHO,
+            type_: types::ConnectionType::Wormhole(types::WormholeType::Large),
+            source: types::ConnectionSource::EveScout,
+        };
+        hooks.add_connection(&mut universe, hole).unwrap();
+        assert_eq!(1, sent.borrow().len());
+        assert!(sent.borrow()[0].1.contains(r#""event":"wormhole_added""#));
+
+        let systems = (
+            universe.get_system(&ALPHA).unwrap().clone(),
+            universe.get_system(&ECHO).unwrap().clone(),
+        );
+        let path = PathBuilder::new(&universe)
+            .waypoint(&systems.0)
+            .waypoint(&systems.1)
+            .build()
+            .unwrap();
+        assert_eq!(1, path.jumps());
+        hooks.watch_route("staging", &path);
+        drop(path);
+
+        let removed = hooks
+            .remove_connections(&mut universe, |c| {
+                c.source == types::ConnectionSource::EveScout
+            })
+            .unwrap();
+        assert_eq!(1, removed.len());
+        let sent = sent.borrow();
+        assert_eq!(3, sent.len());
+        assert!(sent[1].1.contains("wormhole_expired"));
+        assert!(sent[2].1.contains(r#""route":"staging""#));
+        assert!(hooks.routes.is_empty());
+    }
+}
@@ -2,7 +2,8 @@
  * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
  * All rights reserved.
  */
-#![cfg_attr(test, feature(test))]
+#![cfg_attr(all(test, feature = "std"), feature(test))]
+#![cfg_attr(all(feature = "core-only", not(feature = "std"), not(test)), no_std)]
 //! neweden is a rust library for system information, wayfinding and
 //! range queries for the MMORPG Eve Online from CCP Games.
 //!
@@ -13,24 +14,69 @@
 //! accepted flags are `database` and `rpc`. `database` offers a Postgres
 //! backend using the diesel ORM wrapper. `rpc` is for internal use at
 //! the moment as the dependent crate is not open sourced.
+//!
+//! Everything except the `graph` module requires the default `std` feature.
+//! Disabling default features and enabling `core-only` builds a `no_std + alloc`
+//! crate containing only the routing core.
+
+extern crate alloc;
 
 // Must be at the crate root
-#[cfg(feature = "postgres")]
+#[cfg(any(feature = "postgres", feature = "mysql"))]
 #[macro_use]
 extern crate diesel;
 
+pub mod graph;
+
+#[cfg(feature = "std")]
 pub mod source;
 
+#[cfg(feature = "std")]
+pub mod adapter;
+#[cfg(feature = "std")]
+pub mod advisor;
+#[cfg(feature = "std")]
+pub mod analysis;
+#[cfg(feature = "std")]
 pub mod builder;
+#[cfg(feature = "std")]
+pub mod clones;
+#[cfg(feature = "std")]
+pub mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+pub mod frozen;
+#[cfg(feature = "std")]
+pub mod handle;
+#[cfg(feature = "webhooks")]
+pub mod hooks;
+#[cfg(feature = "std")]
+pub mod metrics;
+#[cfg(feature = "std")]
+pub mod opsec;
+#[cfg(feature = "std")]
+pub mod patch;
+#[cfg(feature = "std")]
+pub mod store;
+#[cfg(feature = "std")]
+pub mod validate;
+#[cfg(feature = "std")]
 pub use types::*;
+#[cfg(feature = "std")]
 #[allow(dead_code)]
 pub mod rules;
+#[cfg(feature = "std")]
 #[allow(dead_code)]
 mod types;
 
+#[cfg(feature = "std")]
 #[allow(dead_code)]
 pub mod navigation;
 
+#[cfg(feature = "std")]
+pub mod testing;
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -0,0 +1,231 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Telemetry counters, so operators can see what the library does inside their
+//! services.
+//!
+//! The library reports to the `Metrics` installed with `set_metrics`, which does
+//! nothing by default. With the `prometheus` feature `PrometheusMetrics` collects
+//! everything and renders it in the Prometheus text format, e.g. for a `/metrics`
+//! endpoint.
+//!
+//! # Example
+//! ```
+//! use std::sync::Arc;
+//! use neweden::metrics::{set_metrics, Metrics};
+//!
+//! struct Log;
+//!
+//! impl Metrics for Log {
+//!     fn increment(&self, name: &str) {
+//!         println!("{} += 1", name);
+//!     }
+//!
+//!     fn observe(&self, name: &str, value: f64) {
+//!         println!("{} = {}", name, value);
+//!     }
+//! }
+//!
+//! set_metrics(Arc::new(Log));
+//! ```
+use std::sync::{Arc, RwLock};
+
+/// Universes loaded by a SQL source or `source::json`.
+pub const UNIVERSE_LOADS: &str = "neweden_universe_loads_total";
+/// Universe loads by a SQL source or `source::json` that failed.
+pub const UNIVERSE_LOAD_FAILURES: &str = "neweden_universe_load_failures_total";
+/// The seconds a SQL source took to load a universe. `source::json` loads are not
+/// timed.
+pub const UNIVERSE_LOAD_SECONDS: &str = "neweden_universe_load_seconds";
+/// Universes replaced by `UniverseHandle::reload`.
+pub const UNIVERSE_RELOADS: &str = "neweden_universe_reloads_total";
+/// Routes `RouteCache` served from its store.
+pub const ROUTE_CACHE_HITS: &str = "neweden_route_cache_hits_total";
+/// Routes `RouteCache` had to compute.
+pub const ROUTE_CACHE_MISSES: &str = "neweden_route_cache_misses_total";
+/// Paths `PathBuilder` built.
+pub const ROUTES_FOUND: &str = "neweden_routes_found_total";
+/// Paths `PathBuilder` found no route for.
+pub const ROUTES_NOT_FOUND: &str = "neweden_routes_not_found_total";
+/// Path computations cancelled or timed out.
+pub const ROUTES_INTERRUPTED: &str = "neweden_routes_interrupted_total";
+/// The systems a path computation expanded, a measure of its cost.
+pub const ROUTE_SEARCH_SIZE: &str = "neweden_route_search_expanded_systems";
+
+/// Receives the counters and observations of the library. Names are one of the
+/// constants of this module.
+pub trait Metrics: Send + Sync {
+    /// Adds one to a counter.
+    fn increment(&self, name: &str);
+
+    /// Records a value of a distribution, e.g. a duration in seconds.
+    fn observe(&self, name: &str, value: f64);
+}
+
+/// Discards everything, the default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopMetrics;
+
+impl Metrics for NoopMetrics {
+    fn increment(&self, _name: &str) {}
+
+    fn observe(&self, _name: &str, _value: f64) {}
+}
+
+static METRICS: RwLock<Option<Arc<dyn Metrics>>> = RwLock::new(None);
+
+/// Reports all later metrics of the process to `metrics`.
+pub fn set_metrics(metrics: Arc<dyn Metrics>) {
+    *METRICS.write().unwrap_or_else(|e| e.into_inner()) = Some(metrics);
+}
+
+/// Goes back to discarding metrics.
+pub fn clear_metrics() {
+    *METRICS.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+pub(crate) fn increment(name: &str) {
+    if let Some(metrics) = METRICS.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        metrics.increment(name);
+    }
+}
+
+pub(crate) fn observe(name: &str, value: f64) {
+    if let Some(metrics) = METRICS.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        metrics.observe(name, value);
+    }
+}
+
+/// Collects counters and observations for Prometheus. Observations become
+/// summaries without quantiles, i.e. a sum and a count.
+#[cfg(feature = "prometheus")]
+#[derive(Debug, Default)]
+pub struct PrometheusMetrics {
+    counters: std::sync::Mutex<std::collections::BTreeMap<String, u64>>,
+    summaries: std::sync::Mutex<std::collections::BTreeMap<String, (f64, u64)>>,
+}
+
+#[cfg(feature = "prometheus")]
+impl PrometheusMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The current value of a counter.
+    pub fn counter(&self, name: &str) -> u64 {
+        let counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        counters.get(name).copied().unwrap_or(0)
+    }
+
+    /// Everything collected so far in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        use std::fmt::Write;
+
+        let mut out = String::new();
+        let counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        for (name, value) in counters.iter() {
+            let _ = writeln!(out, "# TYPE {} counter\n{} {}", name, name, value);
+        }
+        let summaries = self.summaries.lock().unwrap_or_else(|e| e.into_inner());
+        for (name, (sum, count)) in summaries.iter() {
+            let _ = writeln!(
+                out,
+                "# TYPE {} summary\n{}_sum {}\n{}_count {}",
+                name, name, sum, name, count
+            );
+        }
+        out
+    }
+}
+
+#[cfg(feature = "prometheus")]
+impl Metrics for PrometheusMetrics {
+    fn increment(&self, name: &str) {
+        let mut counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        *counters.entry(name.to_string()).or_default() += 1;
+    }
+
+    fn observe(&self, name: &str, value: f64) {
+        let mut summaries = self.summaries.lock().unwrap_or_else(|e| e.into_inner());
+        let (sum, count) = summaries.entry(name.to_string()).or_default();
+        *sum += value;
+        *count += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation::{PathBuilder, Preference, RouteCache};
+    use crate::store::MemoryStore;
+    use crate::testing::*;
+    use crate::types::Navigatable;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Recorder(Mutex<HashMap<String, f64>>);
+
+    impl Metrics for Recorder {
+        fn increment(&self, name: &str) {
+            *self.0.lock().unwrap().entry(name.to_string()).or_default() += 1.0;
+        }
+
+        fn observe(&self, name: &str, value: f64) {
+            *self.0.lock().unwrap().entry(name.to_string()).or_default() += value;
+        }
+    }
+
+    impl Recorder {
+        fn get(&self, name: &str) -> f64 {
+            self.0.lock().unwrap().get(name).copied().unwrap_or(0.0)
+        }
+    }
+
+    #[test]
+    fn test_metrics() {
+        // other tests run concurrently and report as well, so only lower bounds hold
+        let recorder = Arc::new(Recorder::default());
+        set_metrics(recorder.clone());
+        let universe = universe();
+
+        let path = PathBuilder::new(&universe)
+            .waypoint(universe.get_system(&ALPHA).unwrap())
+            .waypoint(universe.get_system(&ECHO).unwrap())
+            .build();
+        assert!(path.is_some());
+        assert!(recorder.get(ROUTES_FOUND) >= 1.0);
+        assert!(recorder.get(ROUTE_SEARCH_SIZE) >= 4.0);
+
+        let cache = RouteCache::new(MemoryStore::new());
+        for _ in 0..2 {
+            cache
+                .route(&universe, &ALPHA, &ECHO, Preference::Shortest)
+                .unwrap();
+        }
+        assert!(recorder.get(ROUTE_CACHE_MISSES) >= 1.0);
+        assert!(recorder.get(ROUTE_CACHE_HITS) >= 1.0);
+        clear_metrics();
+    }
+
+    #[cfg(feature = "prometheus")]
+    #[test]
+    fn test_prometheus() {
+        let metrics = PrometheusMetrics::new();
+        metrics.increment(ROUTES_FOUND);
+        metrics.increment(ROUTES_FOUND);
+        metrics.observe(UNIVERSE_LOAD_SECONDS, 1.5);
+        metrics.observe(UNIVERSE_LOAD_SECONDS, 0.5);
+        assert_eq!(2, metrics.counter(ROUTES_FOUND));
+        assert_eq!(
+            "# TYPE neweden_routes_found_total counter\n\
+             neweden_routes_found_total 2\n\
+             # TYPE neweden_universe_load_seconds summary\n\
+             neweden_universe_load_seconds_sum 2\n\
+             neweden_universe_load_seconds_count 2\n",
+            metrics.render()
+        );
+    }
+}
@@ -0,0 +1,174 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Avoid-lists in the format of the EVE client.
+//!
+//! The client shares avoided systems as system names, one per line, or as chat links
+//! like `<url=showinfo:5//30000142>Jita</url>` when dragged into a chat window. Both are
+//! read, names are written, so lists can be pasted into the client and back.
+use std::collections::BTreeSet;
+
+use crate::navigation::{Cost, CostFunction, Preference};
+use crate::types;
+
+/// Errors returned when parsing an avoid-list.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AvoidListError {
+    #[error("unknown system `{0}` on line {1}")]
+    UnknownSystem(String, usize),
+}
+
+/// The systems a pilot refuses to travel through.
+///
+/// # Example
+/// ```
+/// use neweden::builder::UniverseBuilder;
+/// use neweden::navigation::avoid::AvoidList;
+/// use neweden::{Coordinate, System};
+///
+/// let origin = Coordinate { x: 0.0, y: 0.0, z: 0.0 };
+/// let universe = UniverseBuilder::new()
+///     .system(System::new(30002813.into(), "Tama", origin.clone(), 0.3.into()))
+///     .system(System::new(30003068.into(), "Rancer", origin, 0.4.into()))
+///     .build();
+///
+/// let list = AvoidList::parse(&universe, "tama\n<url=showinfo:5//30003068>Rancer</url>").unwrap();
+/// assert_eq!("Tama\nRancer\n", list.to_client_format(&universe));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AvoidList {
+    systems: BTreeSet<types::SystemId>,
+}
+
+impl AvoidList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads a list copied from the client. Lines are system names, ignoring case, or
+    /// chat links. Empty lines are skipped.
+    pub fn parse(universe: &dyn types::Navigatable, text: &str) -> Result<Self, AvoidListError> {
+        let mut list = Self::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let system = match showinfo_id(line) {
+                Some(id) => universe.get_system(&id),
+                None => universe.get_system_by_name(line),
+            };
+            match system {
+                Some(system) => list.add(system.id),
+                None => return Err(AvoidListError::UnknownSystem(line.to_string(), i + 1)),
+            };
+        }
+        Ok(list)
+    }
+
+    /// Writes the list as system names, one per line, in the order of system ids.
+    /// Systems unknown to the universe are left out.
+    pub fn to_client_format(&self, universe: &dyn types::Navigatable) -> String {
+        self.systems
+            .iter()
+            .filter_map(|id| universe.get_system(id))
+            .map(|system| format!("{}\n", system.name))
+            .collect()
+    }
+
+    pub fn system(mut self, id: types::SystemId) -> Self {
+        self.add(id);
+        self
+    }
+
+    /// Like `system`, but for use when the list is not consumed, e.g. in loops.
+    pub fn add(&mut self, id: types::SystemId) -> &mut Self {
+        self.systems.insert(id);
+        self
+    }
+
+    pub fn remove(&mut self, id: &types::SystemId) -> &mut Self {
+        self.systems.remove(id);
+        self
+    }
+
+    pub fn contains(&self, id: &types::SystemId) -> bool {
+        self.systems.contains(id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &types::SystemId> {
+        self.systems.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.systems.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.systems.is_empty()
+    }
+
+    /// Routes by the preference, never entering an avoided system.
+    pub fn cost_function(&self, preference: Preference) -> impl CostFunction + '_ {
+        move |universe: &dyn types::Navigatable, connection: &types::Connection| -> Option<Cost> {
+            if self.contains(&connection.to) {
+                None
+            } else {
+                preference.cost(universe, connection)
+            }
+        }
+    }
+}
+
+/// The system id of a `<url=showinfo:5//id>name</url>` chat link.
+fn showinfo_id(line: &str) -> Option<types::SystemId> {
+    let rest = line.strip_prefix("<url=showinfo:5//")?;
+    let end = rest.find('>')?;
+    rest[..end].parse::<u32>().ok().map(types::SystemId)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation::PathBuilder;
+    use crate::testing::*;
+    use crate::types::Navigatable;
+
+    #[test]
+    fn test_avoid_list() {
+        let universe = universe();
+        let list = AvoidList::parse(
+            &universe,
+            "  charlie\n\n<url=showinfo:5//30000006>Foxtrot</url>\r\nCHARLIE\n",
+        )
+        .unwrap();
+        assert_eq!(vec![&CHARLIE, &FOXTROT], list.iter().collect::<Vec<_>>());
+        assert_eq!("Charlie\nFoxtrot\n", list.to_client_format(&universe));
+        assert_eq!(
+            Err(AvoidListError::UnknownSystem("Jita".to_string(), 2)),
+            AvoidList::parse(&universe, "Alpha\nJita")
+        );
+
+        // with Charlie and Foxtrot avoided nothing leads from Bravo onwards
+        let path = PathBuilder::new(&universe)
+            .waypoint(universe.get_system(&ALPHA).unwrap())
+            .waypoint(universe.get_system(&ECHO).unwrap())
+            .cost_function(list.cost_function(Preference::Shortest))
+            .build();
+        assert!(path.is_none());
+        let mut list = list.system(DELTA);
+        list.remove(&FOXTROT);
+        let path = PathBuilder::new(&universe)
+            .waypoint(universe.get_system(&ALPHA).unwrap())
+            .waypoint(universe.get_system(&HOTEL).unwrap())
+            .cost_function(list.cost_function(Preference::Shortest))
+            .build()
+            .unwrap();
+        assert_eq!(
+            vec![ALPHA, BRAVO, FOXTROT, GOLF, HOTEL],
+            path.systems().map(|s| s.id).collect::<Vec<_>>()
+        );
+    }
+}
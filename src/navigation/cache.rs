@@ -0,0 +1,179 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Routes cached in a `KvStore`.
+use std::time::Duration;
+
+use crate::metrics;
+use crate::navigation::{PathBuilder, Preference};
+use crate::store::{KvStore, StoreError};
+use crate::types;
+
+/// Caches the systems of routes by origin, destination and preference, e.g. to share
+/// routes between the instances of a service through redis. Only the system ids are
+/// stored; routes that do not exist are cached as well.
+///
+/// The cache knows nothing about the universe the routes were built on. Use a
+/// namespace per universe, e.g. including `UniverseHandle::version`, to keep routes
+/// of an old universe from being served.
+///
+/// # Example
+/// ```
+/// use neweden::builder::UniverseBuilder;
+/// use neweden::navigation::{Preference, RouteCache};
+/// use neweden::store::MemoryStore;
+/// use neweden::{Coordinate, System};
+///
+/// let universe = UniverseBuilder::new()
+///     .system(System::new(1.into(), "Lonely", Coordinate { x: 0.0, y: 0.0, z: 0.0 }, 0.5.into()))
+///     .build();
+/// let cache = RouteCache::new(MemoryStore::new()).namespace("sde-2024-01");
+/// let route = cache.route(&universe, &1.into(), &1.into(), Preference::Shortest).unwrap();
+/// assert_eq!(Some(vec![1.into()]), route);
+/// ```
+#[derive(Debug, Clone)]
+pub struct RouteCache<S> {
+    store: S,
+    namespace: String,
+    ttl: Option<Duration>,
+}
+
+impl<S: KvStore> RouteCache<S> {
+    pub fn new(store: S) -> Self {
+        Self {
+            store,
+            namespace: String::new(),
+            ttl: None,
+        }
+    }
+
+    /// Prefix the keys with this, e.g. to tell universes apart.
+    pub fn namespace(mut self, namespace: &str) -> Self {
+        self.namespace = namespace.to_string();
+        self
+    }
+
+    /// Let cached routes expire after this long. They are kept forever by default.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// The systems of the route from `from` to `to`, including both, or `None` if
+    /// there is no route or one of the systems is unknown. Computed and stored on a
+    /// miss.
+    pub fn route(
+        &self,
+        universe: &dyn types::Navigatable,
+        from: &types::SystemId,
+        to: &types::SystemId,
+        preference: Preference,
+    ) -> Result<Option<Vec<types::SystemId>>, StoreError> {
+        let key = self.key(from, to, preference);
+        if let Some(value) = self.store.get(&key)? {
+            metrics::increment(metrics::ROUTE_CACHE_HITS);
+            return decode(&value).map(|systems| (!systems.is_empty()).then_some(systems));
+        }
+        metrics::increment(metrics::ROUTE_CACHE_MISSES);
+        let systems = match (universe.get_system(from), universe.get_system(to)) {
+            (Some(from), Some(to)) => PathBuilder::new(universe)
+                .waypoint(from)
+                .waypoint(to)
+                .prefer(preference)
+                .build()
+                .map(|path| path.systems().map(|s| s.id).collect::<Vec<_>>()),
+            _ => None,
+        };
+        self.store.put(
+            &key,
+            &encode(systems.as_deref().unwrap_or_default()),
+            self.ttl,
+        )?;
+        Ok(systems)
+    }
+
+    fn key(&self, from: &types::SystemId, to: &types::SystemId, preference: Preference) -> Vec<u8> {
+        format!(
+            "{}route:{}:{}:{:?}",
+            self.namespace, from.0, to.0, preference
+        )
+        .into_bytes()
+    }
+}
+
+/// The system ids as little endian bytes, no route is empty.
+fn encode(systems: &[types::SystemId]) -> Vec<u8> {
+    systems.iter().flat_map(|s| s.0.to_le_bytes()).collect()
+}
+
+fn decode(value: &[u8]) -> Result<Vec<types::SystemId>, StoreError> {
+    if !value.len().is_multiple_of(4) {
+        return Err(StoreError::Malformed);
+    }
+    Ok(value
+        .chunks_exact(4)
+        .map(|id| types::SystemId(u32::from_le_bytes(id.try_into().unwrap())))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStore;
+    use crate::testing::*;
+
+    #[test]
+    fn test_route_cache() {
+        let universe = universe();
+        let cache = RouteCache::new(MemoryStore::new()).namespace("test:");
+        let route = cache
+            .route(&universe, &ALPHA, &DELTA, Preference::Shortest)
+            .unwrap();
+        assert_eq!(Some(vec![ALPHA, BRAVO, CHARLIE, DELTA]), route);
+        assert_eq!(1, cache.store().len());
+
+        // served from the store, even on a universe without the systems
+        let empty = crate::builder::UniverseBuilder::new().build();
+        assert_eq!(
+            route,
+            cache
+                .route(&empty, &ALPHA, &DELTA, Preference::Shortest)
+                .unwrap()
+        );
+        assert_eq!(
+            None,
+            cache
+                .route(&empty, &ALPHA, &ECHO, Preference::Shortest)
+                .unwrap()
+        );
+        assert_eq!(
+            None,
+            cache
+                .route(&universe, &ALPHA, &ECHO, Preference::Shortest)
+                .unwrap()
+        );
+        assert_eq!(
+            Some(b"test:route:30000001:30000005:Shortest".to_vec()),
+            cache.key(&ALPHA, &ECHO, Preference::Shortest).into()
+        );
+
+        cache
+            .store()
+            .put(
+                &cache.key(&ALPHA, &BRAVO, Preference::Shortest),
+                b"abc",
+                None,
+            )
+            .unwrap();
+        assert!(matches!(
+            cache.route(&universe, &ALPHA, &BRAVO, Preference::Shortest),
+            Err(StoreError::Malformed)
+        ));
+    }
+}
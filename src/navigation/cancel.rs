@@ -0,0 +1,57 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Cooperative cancellation of route computations.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A token that can be used to abandon a running route computation, e.g. when the
+/// client of a web service disconnects. Clones share the same state, so one clone
+/// can be handed to the `PathBuilder` while another one is kept to cancel it.
+///
+/// # Example
+/// ```
+/// use neweden::navigation::CancellationToken;
+///
+/// let token = CancellationToken::new();
+/// let handle = token.clone();
+/// handle.cancel();
+/// assert!(token.is_cancelled());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A point in time after which a route computation is abandoned.
+///
+/// Checking a deadline reads the clock, which panics on wasm32-unknown-unknown. Use a
+/// `CancellationToken` there instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deadline(pub Instant);
+
+impl Deadline {
+    /// A deadline `timeout` from now.
+    pub fn after(timeout: Duration) -> Self {
+        Self(Instant::now() + timeout)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        Instant::now() >= self.0
+    }
+}
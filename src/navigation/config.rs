@@ -0,0 +1,118 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! The knobs of `PathBuilder` as one plain value.
+use std::time::Duration;
+
+use crate::navigation::{Deadline, PathBuilder, Preference, SearchStrategy};
+use crate::types;
+
+/// The settings of a `PathBuilder` besides waypoints and custom cost functions, for
+/// services that let their clients choose them per request. With the `serde`
+/// feature it can be part of a request body; missing fields take their defaults.
+///
+/// # Example
+/// ```
+/// use neweden::builder::UniverseBuilder;
+/// use neweden::navigation::{PathBuilder, Preference, RouterConfig};
+///
+/// let universe = UniverseBuilder::new().build();
+/// let config = RouterConfig {
+///     preference: Preference::Highsec,
+///     timeout_ms: Some(250),
+///     ..Default::default()
+/// };
+/// let builder = PathBuilder::with_config(&universe, &config);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct RouterConfig {
+    pub preference: Preference,
+    pub strategy: SearchStrategy,
+    /// Abandon the computation this many milliseconds after the config was applied.
+    pub timeout_ms: Option<u64>,
+    /// See `PathBuilder::ship_mass`.
+    pub ship_mass: Option<types::Kilotons>,
+    /// See `PathBuilder::max_detour`.
+    pub max_detour: Option<types::Lightyears>,
+}
+
+impl<'a> PathBuilder<'a> {
+    /// A builder with the settings of `config`.
+    pub fn with_config(universe: &'a dyn types::Navigatable, config: &RouterConfig) -> Self {
+        let mut builder = Self::new(universe);
+        builder.set_config(config);
+        builder
+    }
+
+    /// Replaces the settings covered by `config`, starting its timeout now. A ship
+    /// set with `ship` is replaced by the ship mass of the config.
+    pub fn set_config(&mut self, config: &RouterConfig) -> &mut Self {
+        self.set_preference(config.preference)
+            .set_strategy(config.strategy)
+            .set_deadline(
+                config
+                    .timeout_ms
+                    .map(|ms| Deadline::after(Duration::from_millis(ms))),
+            )
+            .set_ship(None)
+            .set_ship_mass(config.ship_mass)
+            .set_max_detour(config.max_detour)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+    use crate::types::Navigatable;
+
+    #[test]
+    fn test_with_config() {
+        let universe = universe();
+        let config = RouterConfig {
+            preference: Preference::LowsecAndNullsec,
+            strategy: SearchStrategy::IterativeDeepening,
+            ..Default::default()
+        };
+        let mut builder = PathBuilder::with_config(&universe, &config);
+        assert_eq!(Preference::LowsecAndNullsec, builder.preference);
+        assert_eq!(SearchStrategy::IterativeDeepening, builder.strategy);
+        assert!(builder.deadline.is_none());
+
+        builder.set_ship(Some(&crate::navigation::ShipProfile::default()));
+        builder.set_config(&RouterConfig::default());
+        assert!(builder.ship.is_none());
+        assert!(builder.ship_mass.is_none());
+
+        let timed_out = PathBuilder::with_config(
+            &universe,
+            &RouterConfig {
+                timeout_ms: Some(0),
+                ..Default::default()
+            },
+        )
+        .waypoint(universe.get_system(&ALPHA).unwrap())
+        .waypoint(universe.get_system(&HOTEL).unwrap())
+        .try_build();
+        assert!(matches!(
+            timed_out,
+            Err(crate::navigation::RouteError::TimedOut)
+        ));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json() {
+        let config: RouterConfig =
+            serde_json::from_str(r#"{"preference": "highsec", "max_detour": 5.0}"#).unwrap();
+        assert_eq!(Preference::Highsec, config.preference);
+        assert_eq!(SearchStrategy::Dijkstra, config.strategy);
+        assert_eq!(Some(types::Lightyears(5.0)), config.max_detour);
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(config, serde_json::from_str(&json).unwrap());
+    }
+}
@@ -0,0 +1,283 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Staggered departures for convoys of ships sharing one route.
+//!
+//! Ships flying together are easy to catch at the same gate. A `Convoy` spaces the
+//! departures so that no two ships are in a chokepoint at the same time, keeping at
+//! least the configured spacing between them.
+//!
+//! # Example
+//! ```
+//! use std::time::Duration;
+//! use neweden::builder::UniverseBuilder;
+//! use neweden::navigation::convoy::Convoy;
+//! use neweden::navigation::{PathBuilder, ShipProfile};
+//! use neweden::{Connection, ConnectionSource, ConnectionType, Coordinate, StargateType, System};
+//! use neweden::Navigatable;
+//!
+//! let system = |id: u32| {
+//!     System::new(id.into(), "", Coordinate { x: 0.0, y: 0.0, z: 0.0 }, 0.9.into())
+//! };
+//! let gate = |from: u32, to: u32| Connection {
+//!     from: from.into(),
+//!     to: to.into(),
+//!     type_: ConnectionType::Stargate(StargateType::Local),
+//!     source: ConnectionSource::Sde,
+//! };
+//! let universe = UniverseBuilder::new()
+//!     .system(system(1))
+//!     .system(system(2))
+//!     .system(system(3))
+//!     .connection(gate(1, 2))
+//!     .connection(gate(2, 3))
+//!     .build();
+//! let path = PathBuilder::new(&universe)
+//!     .waypoint(universe.get_system(&1.into()).unwrap())
+//!     .waypoint(universe.get_system(&3.into()).unwrap())
+//!     .build()
+//!     .unwrap();
+//!
+//! let timetable = Convoy::new()
+//!     .ships(3, ShipProfile::default())
+//!     .chokepoint(2.into())
+//!     .spacing(Duration::from_secs(30))
+//!     .schedule(&path);
+//! assert_eq!(3, timetable.ships.len());
+//! assert_eq!(Duration::ZERO, timetable.ships[0].departure);
+//! ```
+use std::collections::HashSet;
+use std::time::Duration;
+
+use crate::navigation::{Path, ShipProfile, TravelTime};
+use crate::types;
+
+/// When a ship is in a system of the route.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stop {
+    pub system: types::SystemId,
+    /// Time since the convoy's first departure at which the ship enters the system.
+    pub arrival: Duration,
+    /// Time at which the ship jumps out. Equal to the arrival at the destination.
+    pub departure: Duration,
+}
+
+/// The schedule of a single ship.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShipSchedule {
+    pub departure: Duration,
+    pub stops: Vec<Stop>,
+}
+
+impl ShipSchedule {
+    /// The time the ship reaches the destination.
+    pub fn arrival(&self) -> Duration {
+        self.stops.last().map_or(self.departure, |s| s.arrival)
+    }
+}
+
+/// The result of `Convoy::schedule`, in the order the ships were added.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Timetable {
+    pub ships: Vec<ShipSchedule>,
+}
+
+impl Timetable {
+    /// The time the last ship reaches the destination.
+    pub fn arrival(&self) -> Duration {
+        self.ships
+            .iter()
+            .map(ShipSchedule::arrival)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// The largest number of ships in a system at the same time.
+    pub fn max_exposure(&self, system: &types::SystemId) -> usize {
+        let intervals = self
+            .ships
+            .iter()
+            .flat_map(|s| s.stops.iter().filter(|stop| stop.system == *system))
+            .collect::<Vec<_>>();
+        intervals
+            .iter()
+            .map(|a| {
+                intervals
+                    .iter()
+                    .filter(|b| b.arrival <= a.arrival && a.arrival <= b.departure)
+                    .count()
+            })
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Ships flying the same route and the rules for spacing them.
+#[derive(Debug, Clone, Default)]
+pub struct Convoy {
+    ships: Vec<ShipProfile>,
+    chokepoints: HashSet<types::SystemId>,
+    spacing: Duration,
+}
+
+impl Convoy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn ship(mut self, ship: ShipProfile) -> Self {
+        self.add_ship(ship);
+        self
+    }
+
+    /// Adds `count` ships of the same kind, e.g. a wing of freighters.
+    pub fn ships(mut self, count: usize, ship: ShipProfile) -> Self {
+        for _ in 0..count {
+            self.add_ship(ship.clone());
+        }
+        self
+    }
+
+    /// A system only one ship may be in at a time, e.g. one found with
+    /// `analysis::chokepoints`. Without chokepoints every system of the route is
+    /// treated as one.
+    pub fn chokepoint(mut self, system: types::SystemId) -> Self {
+        self.add_chokepoint(system);
+        self
+    }
+
+    /// The minimum time between two ships leaving and entering a chokepoint.
+    pub fn spacing(mut self, spacing: Duration) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Like `ship`, but for use when the convoy is not consumed, e.g. in loops.
+    pub fn add_ship(&mut self, ship: ShipProfile) -> &mut Self {
+        self.ships.push(ship);
+        self
+    }
+
+    /// Like `chokepoint`, but for use when the convoy is not consumed, e.g. in loops.
+    pub fn add_chokepoint(&mut self, system: types::SystemId) -> &mut Self {
+        self.chokepoints.insert(system);
+        self
+    }
+
+    /// Schedules the ships along the path in the order they were added. Every ship
+    /// departs as early as possible without sharing a chokepoint with a ship that
+    /// departed before it.
+    pub fn schedule(&self, path: &Path) -> Timetable {
+        let systems = path.systems().map(|s| s.id).collect::<Vec<_>>();
+        let exposed = |system: &types::SystemId| {
+            self.chokepoints.is_empty() || self.chokepoints.contains(system)
+        };
+
+        let mut timetable = Timetable::default();
+        let mut departure = Duration::ZERO;
+        for ship in &self.ships {
+            let offsets = stop_offsets(&systems, &TravelTime::new(ship).legs(path));
+            // Delay the departure until no chokepoint interval overlaps an earlier
+            // ship's. Every step moves the departure past one conflict, and the
+            // departure only grows, so this terminates.
+            while let Some(delay) = timetable
+                .ships
+                .iter()
+                .flat_map(|other| &other.stops)
+                .filter(|stop| exposed(&stop.system))
+                .filter_map(|stop| {
+                    let (_, arrival, leaving) = offsets.iter().find(|o| o.0 == stop.system)?;
+                    let earliest = stop.departure + self.spacing;
+                    let conflicts = departure + *arrival < earliest
+                        && stop.arrival < departure + *leaving + self.spacing;
+                    conflicts.then(|| earliest - *arrival)
+                })
+                .max()
+            {
+                departure = delay;
+            }
+
+            timetable.ships.push(ShipSchedule {
+                departure,
+                stops: offsets
+                    .iter()
+                    .map(|(system, arrival, leaving)| Stop {
+                        system: *system,
+                        arrival: departure + *arrival,
+                        departure: departure + *leaving,
+                    })
+                    .collect(),
+            });
+        }
+        timetable
+    }
+}
+
+/// The arrival and departure of a ship in every system, relative to its departure.
+fn stop_offsets(
+    systems: &[types::SystemId],
+    legs: &[Duration],
+) -> Vec<(types::SystemId, Duration, Duration)> {
+    let mut clock = Duration::ZERO;
+    systems
+        .iter()
+        .enumerate()
+        .map(|(i, system)| {
+            let arrival = clock;
+            clock += legs.get(i).copied().unwrap_or_default();
+            (*system, arrival, clock)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation::PathBuilder;
+    use crate::testing::*;
+    use crate::types::Navigatable;
+
+    #[test]
+    fn test_convoy_schedule() {
+        let universe = universe();
+        let path = PathBuilder::new(&universe)
+            .waypoint(universe.get_system(&ALPHA).unwrap())
+            .waypoint(universe.get_system(&ECHO).unwrap())
+            .build()
+            .unwrap();
+        let freighter = ShipProfile {
+            align_time: Duration::from_secs(40),
+            warp_speed: 1.4,
+            ..ShipProfile::default()
+        };
+        let spacing = Duration::from_secs(20);
+
+        let timetable = Convoy::new()
+            .ships(3, freighter.clone())
+            .chokepoint(DELTA)
+            .spacing(spacing)
+            .schedule(&path);
+        assert_eq!(3, timetable.ships.len());
+        assert_eq!(1, timetable.max_exposure(&DELTA));
+        // the second ship leaves before the first one arrives
+        assert!(timetable.ships[1].departure < timetable.ships[0].arrival());
+
+        let delta = |ship: &ShipSchedule| ship.stops.iter().find(|s| s.system == DELTA).cloned();
+        let (first, second) = (
+            delta(&timetable.ships[0]).unwrap(),
+            delta(&timetable.ships[1]).unwrap(),
+        );
+        assert_eq!(first.departure + spacing, second.arrival);
+
+        // without chokepoints no system is shared
+        let timetable = Convoy::new()
+            .ships(2, freighter)
+            .spacing(spacing)
+            .schedule(&path);
+        for system in [ALPHA, BRAVO, CHARLIE, DELTA, ECHO] {
+            assert_eq!(1, timetable.max_exposure(&system));
+        }
+    }
+}
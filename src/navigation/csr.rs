@@ -0,0 +1,164 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! The connections of a universe in compressed sparse row form.
+use std::collections::HashMap;
+
+use crate::navigation::router::Via;
+use crate::types;
+
+/// The connections of a universe in compressed sparse row form: systems are
+/// numbered densely and the connections of every system are stored back to back,
+/// so `PathBuilder` can expand a system without any hash map lookups. Obtained from
+/// `Navigatable::compact_adjacency`.
+///
+/// The connections of a system are kept in the order `get_connections` returns
+/// them.
+#[derive(Debug, Clone, Default)]
+pub struct CompactAdjacency {
+    ids: Vec<types::SystemId>,
+    index: HashMap<types::SystemId, u32>,
+    offsets: Vec<u32>,
+    targets: Vec<u32>,
+    connections: Vec<types::Connection>,
+}
+
+impl CompactAdjacency {
+    pub(crate) fn new(adjacent: &types::AdjacentMap) -> Self {
+        let mut ids = adjacent
+            .0
+            .iter()
+            .flat_map(|(from, connections)| {
+                std::iter::once(*from).chain(connections.iter().map(|c| c.to))
+            })
+            .collect::<Vec<_>>();
+        ids.sort();
+        ids.dedup();
+        let index = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (*id, i as u32))
+            .collect::<HashMap<_, _>>();
+
+        let mut offsets = Vec::with_capacity(ids.len() + 1);
+        let mut targets = Vec::new();
+        let mut connections = Vec::new();
+        for id in &ids {
+            offsets.push(targets.len() as u32);
+            for connection in adjacent.0.get(id).into_iter().flatten() {
+                targets.push(index[&connection.to]);
+                connections.push(connection.clone());
+            }
+        }
+        offsets.push(targets.len() as u32);
+
+        Self {
+            ids,
+            index,
+            offsets,
+            targets,
+            connections,
+        }
+    }
+
+    /// The number of systems with connections from or to them.
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// The connections leaving a system, like `Navigatable::get_connections`.
+    pub fn connections(&self, id: &types::SystemId) -> &[types::Connection] {
+        match self.index.get(id) {
+            Some(node) => &self.connections[self.range(*node)],
+            None => &[],
+        }
+    }
+
+    pub(crate) fn node(&self, id: &types::SystemId) -> Option<u32> {
+        self.index.get(id).copied()
+    }
+
+    pub(crate) fn id(&self, node: u32) -> types::SystemId {
+        self.ids[node as usize]
+    }
+
+    /// The connections leaving a node with their index among them and the node
+    /// they lead to.
+    pub(crate) fn edges(&self, node: u32) -> impl Iterator<Item = (Via, u32, &types::Connection)> {
+        let range = self.range(node);
+        self.targets[range.clone()]
+            .iter()
+            .zip(&self.connections[range])
+            .enumerate()
+            .map(|(i, (target, connection))| (i as Via, *target, connection))
+    }
+
+    fn range(&self, node: u32) -> std::ops::Range<usize> {
+        self.offsets[node as usize] as usize..self.offsets[node as usize + 1] as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation::PathBuilder;
+    use crate::testing::*;
+    use crate::types::{Galaxy, Navigatable};
+
+    #[test]
+    fn test_compact_adjacency() {
+        let universe = universe();
+        let compact = universe.compact_adjacency().unwrap();
+        assert_eq!(8, compact.len());
+        for system in universe.systems() {
+            let targets = |connections: &[types::Connection]| {
+                connections.iter().map(|c| c.to).collect::<Vec<_>>()
+            };
+            assert_eq!(
+                targets(&universe.get_connections(&system.id).unwrap()),
+                targets(compact.connections(&system.id))
+            );
+        }
+        assert!(compact.connections(&types::SystemId(1)).is_empty());
+
+        // changes to the connections are picked up
+        let mut universe = types::DynamicUniverse::from(universe);
+        let route = |u: &types::DynamicUniverse| {
+            PathBuilder::new(u)
+                .waypoint(u.get_system(&ALPHA).unwrap())
+                .waypoint(u.get_system(&HOTEL).unwrap())
+                .build()
+                .unwrap()
+                .jumps()
+        };
+        assert_eq!(4, route(&universe));
+        universe.add_connection(types::Connection {
+            from: ALPHA,
+            to: HOTEL,
+            type_: types::ConnectionType::Wormhole(types::WormholeType::Large),
+            source: types::ConnectionSource::UserOverlay,
+        });
+        assert_eq!(1, route(&universe));
+        assert_eq!(
+            2,
+            universe
+                .compact_adjacency()
+                .unwrap()
+                .connections(&ALPHA)
+                .len()
+        );
+        universe.remove_connections(|c| c.to == HOTEL);
+        assert!(universe
+            .compact_adjacency()
+            .unwrap()
+            .connections(&GOLF)
+            .iter()
+            .all(|c| c.to != HOTEL));
+    }
+}
@@ -0,0 +1,360 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Routing around recent kill activity, for the safest route right now.
+//!
+//! A `DangerProvider` reports the recent kills in a system, e.g. from the
+//! `source::zkillboard` feed. Routes built with `Preference::AvoidDanger` and a
+//! provider set through `PathBuilder::danger` take a detour around systems with
+//! kills, the more so the more pods died there. `Path::risk_report` scores the
+//! jumps of a path the same way, e.g. to color-code routes.
+//!
+//! # Example
+//! ```
+//! use std::collections::HashMap;
+//! use neweden::builder::UniverseBuilder;
+//! use neweden::navigation::danger::Activity;
+//! use neweden::navigation::{PathBuilder, Preference};
+//! use neweden::{Coordinate, Navigatable, System, SystemId};
+//!
+//! let universe = UniverseBuilder::new()
+//!     .system(System::new(1.into(), "Lonely", Coordinate { x: 0.0, y: 0.0, z: 0.0 }, 0.5.into()))
+//!     .build();
+//! let mut kills: HashMap<SystemId, Activity> = HashMap::new();
+//! kills.insert(1.into(), Activity { ship_kills: 3, pod_kills: 2 });
+//! let lonely = universe.get_system(&1.into()).unwrap();
+//! let path = PathBuilder::new(&universe)
+//!     .waypoint(lonely)
+//!     .waypoint(lonely)
+//!     .prefer(Preference::AvoidDanger)
+//!     .danger(kills)
+//!     .build();
+//! ```
+use std::collections::{HashMap, HashSet};
+
+use crate::navigation::{Cost, CostFunction, Path, Preference};
+use crate::types;
+
+/// The cost added per ship killed in the destination system.
+pub const SHIP_KILL_COST: Cost = 10;
+/// The cost added per pod killed. Pod kills mean gate camps and smartbombs that catch
+/// everything, so they weigh more than ship kills.
+pub const POD_KILL_COST: Cost = 25;
+/// The most a system costs for its activity, as much as a system of the wrong
+/// security for `Preference::Highsec`, so busy systems are avoided but not forbidden.
+pub const MAX_DANGER_COST: Cost = 1000;
+
+/// Recent kills in a system.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Activity {
+    pub ship_kills: u32,
+    pub pod_kills: u32,
+}
+
+impl Activity {
+    /// The cost added for entering a system with this activity.
+    pub fn cost(&self) -> Cost {
+        self.ship_kills
+            .saturating_mul(SHIP_KILL_COST)
+            .saturating_add(self.pod_kills.saturating_mul(POD_KILL_COST))
+            .min(MAX_DANGER_COST)
+    }
+}
+
+/// Reports the recent kill activity of systems. Called for every connection the
+/// search considers, so implementations should answer from memory.
+pub trait DangerProvider {
+    fn activity(&self, system: &types::SystemId) -> Activity;
+
+    /// Whether the system is known for camps regardless of recent activity, e.g.
+    /// smartbomb camps on trade routes. See `Notorious`.
+    fn is_notorious(&self, _system: &types::SystemId) -> bool {
+        false
+    }
+}
+
+impl DangerProvider for HashMap<types::SystemId, Activity> {
+    fn activity(&self, system: &types::SystemId) -> Activity {
+        self.get(system).copied().unwrap_or_default()
+    }
+}
+
+impl<D: DangerProvider + ?Sized> DangerProvider for &D {
+    fn activity(&self, system: &types::SystemId) -> Activity {
+        (**self).activity(system)
+    }
+
+    fn is_notorious(&self, system: &types::SystemId) -> bool {
+        (**self).is_notorious(system)
+    }
+}
+
+/// Adds a list of notorious systems to a provider, e.g. loaded from a file the
+/// users of a service maintain.
+pub struct Notorious<D> {
+    inner: D,
+    systems: HashSet<types::SystemId>,
+}
+
+impl<D: DangerProvider> Notorious<D> {
+    pub fn new(inner: D) -> Self {
+        Self {
+            inner,
+            systems: HashSet::new(),
+        }
+    }
+
+    pub fn system(mut self, system: types::SystemId) -> Self {
+        self.add_system(system);
+        self
+    }
+
+    pub fn systems(mut self, systems: impl IntoIterator<Item = types::SystemId>) -> Self {
+        self.systems.extend(systems);
+        self
+    }
+
+    pub fn add_system(&mut self, system: types::SystemId) -> &mut Self {
+        self.systems.insert(system);
+        self
+    }
+}
+
+impl<D: DangerProvider> DangerProvider for Notorious<D> {
+    fn activity(&self, system: &types::SystemId) -> Activity {
+        self.inner.activity(system)
+    }
+
+    fn is_notorious(&self, system: &types::SystemId) -> bool {
+        self.systems.contains(system) || self.inner.is_notorious(system)
+    }
+}
+
+/// The activity cost at which the risk of a jump is about two thirds.
+const RISK_SCALE: f32 = 100.0;
+/// The least risk of entering a notorious system.
+const NOTORIOUS_RISK: f32 = 0.5;
+
+/// How risky a jump or a route is, for color coding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RiskLevel {
+    Low,
+    Elevated,
+    High,
+}
+
+impl RiskLevel {
+    pub fn from_risk(risk: f32) -> Self {
+        if risk >= 0.5 {
+            Self::High
+        } else if risk >= 0.1 {
+            Self::Elevated
+        } else {
+            Self::Low
+        }
+    }
+}
+
+/// The risk of a jump into a system.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JumpRisk {
+    pub system: types::SystemId,
+    pub activity: Activity,
+    pub notorious: bool,
+    /// From 0.0 (nothing happened) towards 1.0.
+    pub risk: f32,
+}
+
+impl JumpRisk {
+    pub fn level(&self) -> RiskLevel {
+        RiskLevel::from_risk(self.risk)
+    }
+}
+
+/// The risk of every jump of a path, see `Path::risk_report`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RiskReport {
+    pub jumps: Vec<JumpRisk>,
+}
+
+impl RiskReport {
+    /// The risk of the route as a whole, taking the jump risks as independent
+    /// chances of running into trouble.
+    pub fn risk(&self) -> f32 {
+        1.0 - self.jumps.iter().map(|j| 1.0 - j.risk).product::<f32>()
+    }
+
+    pub fn level(&self) -> RiskLevel {
+        RiskLevel::from_risk(self.risk())
+    }
+
+    /// The jumps into notorious systems.
+    pub fn notorious(&self) -> impl Iterator<Item = &JumpRisk> {
+        self.jumps.iter().filter(|j| j.notorious)
+    }
+
+    /// The riskiest jump, `None` for a path without jumps.
+    pub fn worst(&self) -> Option<&JumpRisk> {
+        self.jumps.iter().max_by(|a, b| a.risk.total_cmp(&b.risk))
+    }
+}
+
+impl Path<'_> {
+    /// Scores every jump of the path by the activity in the system it enters.
+    pub fn risk_report(&self, danger: &impl DangerProvider) -> RiskReport {
+        let jumps = self
+            .systems()
+            .skip(1)
+            .map(|system| {
+                let activity = danger.activity(&system.id);
+                let notorious = danger.is_notorious(&system.id);
+                let mut risk = 1.0 - (-(activity.cost() as f32) / RISK_SCALE).exp();
+                if notorious {
+                    risk = risk.max(NOTORIOUS_RISK);
+                }
+                JumpRisk {
+                    system: system.id,
+                    activity,
+                    notorious,
+                    risk,
+                }
+            })
+            .collect();
+        RiskReport { jumps }
+    }
+}
+
+/// The cost function of `Preference::AvoidDanger`: every jump costs one, plus the
+/// cost of the activity in the system it leads into.
+pub struct DangerCost<'a> {
+    provider: &'a dyn DangerProvider,
+}
+
+impl<'a> DangerCost<'a> {
+    pub fn new(provider: &'a dyn DangerProvider) -> Self {
+        Self { provider }
+    }
+}
+
+impl CostFunction for DangerCost<'_> {
+    fn cost(
+        &self,
+        universe: &dyn types::Navigatable,
+        connection: &types::Connection,
+    ) -> Option<Cost> {
+        let base = Preference::AvoidDanger.cost(universe, connection)?;
+        Some(base + self.provider.activity(&connection.to).cost())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation::PathBuilder;
+    use crate::testing::*;
+    use crate::types::Navigatable;
+
+    #[test]
+    fn test_avoid_danger() {
+        let universe = universe();
+        let route = |danger: Option<&HashMap<types::SystemId, Activity>>| {
+            let mut builder = PathBuilder::new(&universe)
+                .waypoint(universe.get_system(&BRAVO).unwrap())
+                .waypoint(universe.get_system(&DELTA).unwrap())
+                .prefer(Preference::AvoidDanger);
+            if let Some(danger) = danger {
+                builder.set_danger(danger);
+            }
+            builder
+                .build()
+                .unwrap()
+                .systems()
+                .map(|s| s.id)
+                .collect::<Vec<_>>()
+        };
+        // without a provider every system is equally safe
+        assert_eq!(vec![BRAVO, CHARLIE, DELTA], route(None));
+
+        let mut danger = HashMap::new();
+        danger.insert(
+            CHARLIE,
+            Activity {
+                ship_kills: 1,
+                pod_kills: 1,
+            },
+        );
+        assert_eq!(vec![BRAVO, FOXTROT, GOLF, DELTA], route(Some(&danger)));
+
+        // the detour is dangerous as well, pod kills weigh more
+        danger.insert(
+            CHARLIE,
+            Activity {
+                ship_kills: 2,
+                pod_kills: 0,
+            },
+        );
+        danger.insert(
+            FOXTROT,
+            Activity {
+                ship_kills: 0,
+                pod_kills: 1,
+            },
+        );
+        assert_eq!(vec![BRAVO, CHARLIE, DELTA], route(Some(&danger)));
+    }
+
+    #[test]
+    fn test_activity_cost() {
+        assert_eq!(0, Activity::default().cost());
+        let camp = Activity {
+            ship_kills: 4,
+            pod_kills: 2,
+        };
+        assert_eq!(90, camp.cost());
+        let war = Activity {
+            ship_kills: u32::MAX,
+            pod_kills: 1,
+        };
+        assert_eq!(MAX_DANGER_COST, war.cost());
+    }
+
+    #[test]
+    fn test_risk_report() {
+        let universe = universe();
+        let path = PathBuilder::new(&universe)
+            .waypoint(universe.get_system(&ALPHA).unwrap())
+            .waypoint(universe.get_system(&DELTA).unwrap())
+            .build()
+            .unwrap();
+        let mut kills = HashMap::new();
+        kills.insert(
+            CHARLIE,
+            Activity {
+                ship_kills: 4,
+                pod_kills: 2,
+            },
+        );
+        let danger = Notorious::new(&kills).system(DELTA);
+
+        let report = path.risk_report(&danger);
+        // Bravo, Charlie, Delta
+        assert_eq!(3, report.jumps.len());
+        assert_eq!(0.0, report.jumps[0].risk);
+        assert_eq!(RiskLevel::Low, report.jumps[0].level());
+        assert_eq!(CHARLIE, report.worst().unwrap().system);
+        assert_eq!(RiskLevel::High, report.jumps[1].level());
+        assert_eq!(
+            vec![DELTA],
+            report.notorious().map(|j| j.system).collect::<Vec<_>>()
+        );
+        assert_eq!(NOTORIOUS_RISK, report.jumps[2].risk);
+        assert!(report.risk() > report.worst().unwrap().risk);
+        assert_eq!(RiskLevel::High, report.level());
+
+        let quiet = path.risk_report(&HashMap::new());
+        assert_eq!(0.0, quiet.risk());
+        assert_eq!(RiskLevel::Low, quiet.level());
+    }
+}
@@ -0,0 +1,167 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Iterative deepening on the route cost, a search that only remembers the route it
+//! is currently following.
+use crate::navigation::router::Via;
+use crate::navigation::Cost;
+use crate::types;
+
+/// How `PathBuilder` searches for routes. Both find a cheapest route.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum SearchStrategy {
+    /// Dijkstra's algorithm. Memory grows with the number of systems reached, which
+    /// is every system cheaper than the route.
+    #[default]
+    Dijkstra,
+    /// Depth first searches with an increasing cost bound, like IDA* without a
+    /// heuristic. Memory only grows with the length of the route, but systems are
+    /// expanded many times over, so long routes take far longer. Meant for embedded
+    /// targets with little memory, together with a `Deadline`.
+    IterativeDeepening,
+}
+
+/// A system on the route being followed, with the successors not tried yet.
+struct Frame {
+    id: types::SystemId,
+    via: Option<Via>,
+    cost: Cost,
+    successors: Vec<(types::SystemId, Via, Cost)>,
+    next: usize,
+}
+
+/// Finds the cheapest route from `from` to `to` like `Router::route`.
+pub(crate) fn route<F>(
+    from: types::SystemId,
+    to: types::SystemId,
+    mut successors: F,
+) -> Option<Vec<(types::SystemId, Option<Via>)>>
+where
+    F: FnMut(&types::SystemId) -> Vec<(types::SystemId, Via, Cost)>,
+{
+    if from == to {
+        return Some(vec![(from, None)]);
+    }
+    let mut stack = Vec::new();
+    let mut bound = 0;
+    loop {
+        match search(from, to, bound, &mut stack, &mut successors) {
+            Ok(route) => return Some(route),
+            Err(Some(exceeded)) => bound = exceeded,
+            Err(None) => return None,
+        }
+    }
+}
+
+/// A depth first search for a route costing at most `bound`. Fails with the cheapest
+/// cost above the bound that was seen, which is the bound for the next search.
+fn search<F>(
+    from: types::SystemId,
+    to: types::SystemId,
+    bound: Cost,
+    stack: &mut Vec<Frame>,
+    successors: &mut F,
+) -> Result<Vec<(types::SystemId, Option<Via>)>, Option<Cost>>
+where
+    F: FnMut(&types::SystemId) -> Vec<(types::SystemId, Via, Cost)>,
+{
+    let mut exceeded: Option<Cost> = None;
+    stack.clear();
+    stack.push(Frame {
+        id: from,
+        via: None,
+        cost: 0,
+        successors: successors(&from),
+        next: 0,
+    });
+    while let Some(top) = stack.last_mut() {
+        let Some(&(next, via, step)) = top.successors.get(top.next) else {
+            stack.pop();
+            continue;
+        };
+        top.next += 1;
+        let cost = top.cost.saturating_add(step);
+        if cost > bound {
+            exceeded = Some(exceeded.map_or(cost, |e| e.min(cost)));
+            continue;
+        }
+        if next == to {
+            let mut route = stack.iter().map(|f| (f.id, f.via)).collect::<Vec<_>>();
+            route.push((next, Some(via)));
+            return Ok(route);
+        }
+        if stack.iter().any(|f| f.id == next) {
+            continue;
+        }
+        let successors = successors(&next);
+        stack.push(Frame {
+            id: next,
+            via: Some(via),
+            cost,
+            successors,
+            next: 0,
+        });
+    }
+    Err(exceeded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation::{PathBuilder, Preference};
+    use crate::testing::*;
+    use crate::types::{Galaxy, Navigatable};
+
+    #[test]
+    fn test_iterative_deepening() {
+        let universe = universe();
+        for preference in [
+            Preference::Shortest,
+            Preference::Highsec,
+            Preference::LowsecAndNullsec,
+        ] {
+            for from in universe.systems() {
+                for to in universe.systems() {
+                    let builder = PathBuilder::new(&universe)
+                        .waypoint(from)
+                        .waypoint(to)
+                        .prefer(preference);
+                    let dijkstra = builder.build().unwrap();
+                    let deepening = builder
+                        .strategy(SearchStrategy::IterativeDeepening)
+                        .build()
+                        .unwrap();
+                    assert_eq!(dijkstra.jumps(), deepening.jumps());
+                    let cost = |path: &crate::navigation::Path| {
+                        path.systems()
+                            .skip(1)
+                            .map(|s| match (preference, s.security.into()) {
+                                (Preference::Highsec, types::SecurityClass::Highsec) => 1,
+                                (Preference::Highsec, _) => 1000,
+                                (Preference::LowsecAndNullsec, types::SecurityClass::Highsec) => {
+                                    1000
+                                }
+                                _ => 1,
+                            })
+                            .sum::<u32>()
+                    };
+                    assert_eq!(cost(&dijkstra), cost(&deepening));
+                }
+            }
+        }
+
+        let unreachable = PathBuilder::new(&universe)
+            .waypoint(universe.get_system(&ALPHA).unwrap())
+            .waypoint(universe.get_system(&ECHO).unwrap())
+            .cost_function(|_: &dyn Navigatable, c: &types::Connection| {
+                (c.to != DELTA).then_some(1)
+            })
+            .strategy(SearchStrategy::IterativeDeepening)
+            .build();
+        assert!(unreachable.is_none());
+    }
+}
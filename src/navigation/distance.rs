@@ -0,0 +1,129 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Jump counts between systems without building paths, for the common "how many
+//! jumps is it from Jita" question where the route itself does not matter.
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+
+use crate::types;
+
+/// The fewest jumps from `from` to `to` over any connection, `None` if `to` cannot
+/// be reached.
+///
+/// # Example
+/// ```
+/// use neweden::builder::UniverseBuilder;
+/// use neweden::navigation::jump_distance;
+/// use neweden::{Connection, ConnectionSource, ConnectionType, Coordinate, StargateType, System};
+///
+/// let system = |id: u32| System::new(id.into(), "", Coordinate { x: 0.0, y: 0.0, z: 0.0 }, 0.5.into());
+/// let gate = |from: u32, to: u32| Connection {
+///     from: from.into(),
+///     to: to.into(),
+///     type_: ConnectionType::Stargate(StargateType::Local),
+///     source: ConnectionSource::Sde,
+/// };
+/// let universe = UniverseBuilder::new()
+///     .system(system(1))
+///     .system(system(2))
+///     .system(system(3))
+///     .connection_bidirectional(gate(1, 2))
+///     .connection_bidirectional(gate(2, 3))
+///     .build();
+/// assert_eq!(Some(2), jump_distance(&universe, &1.into(), &3.into()));
+/// ```
+pub fn jump_distance(
+    universe: &dyn types::Navigatable,
+    from: &types::SystemId,
+    to: &types::SystemId,
+) -> Option<u32> {
+    jump_distances(universe, from, std::slice::from_ref(to))[0]
+}
+
+/// The fewest jumps from `from` to each of `to`, in the same order, in one breadth
+/// first search that stops once all are found.
+pub fn jump_distances(
+    universe: &dyn types::Navigatable,
+    from: &types::SystemId,
+    to: &[types::SystemId],
+) -> Vec<Option<u32>> {
+    let mut missing = to.len();
+    let jumps = breadth_first(universe, from, |reached| {
+        missing -= to.iter().filter(|id| *id == reached).count();
+        missing == 0
+    });
+    to.iter().map(|id| jumps.get(id).copied()).collect()
+}
+
+/// The fewest jumps from `from` to every system reachable from it, including itself.
+pub(crate) fn jumps_from(
+    universe: &dyn types::Navigatable,
+    from: &types::SystemId,
+) -> HashMap<types::SystemId, u32> {
+    breadth_first(universe, from, |_| false)
+}
+
+/// Searches until `done` returns true for a newly reached system.
+fn breadth_first<F>(
+    universe: &dyn types::Navigatable,
+    from: &types::SystemId,
+    mut done: F,
+) -> HashMap<types::SystemId, u32>
+where
+    F: FnMut(&types::SystemId) -> bool,
+{
+    let mut jumps = HashMap::from([(*from, 0)]);
+    if done(from) {
+        return jumps;
+    }
+    let mut queue = VecDeque::from([*from]);
+    while let Some(id) = queue.pop_front() {
+        let next = jumps[&id] + 1;
+        for conn in universe.get_connections(&id).unwrap_or_default().iter() {
+            if let Entry::Vacant(entry) = jumps.entry(conn.to) {
+                entry.insert(next);
+                if done(&conn.to) {
+                    return jumps;
+                }
+                queue.push_back(conn.to);
+            }
+        }
+    }
+    jumps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation::PathBuilder;
+    use crate::testing::*;
+    use crate::types::Navigatable;
+
+    #[test]
+    fn test_jump_distances() {
+        let universe = universe();
+        assert_eq!(Some(0), jump_distance(&universe, &ALPHA, &ALPHA));
+        assert_eq!(
+            vec![Some(4), Some(4), None, Some(1), Some(4)],
+            jump_distances(
+                &universe,
+                &ALPHA,
+                &[ECHO, HOTEL, types::SystemId(1), BRAVO, ECHO]
+            )
+        );
+        for to in [BRAVO, CHARLIE, DELTA, ECHO, FOXTROT, GOLF, HOTEL] {
+            let path = PathBuilder::new(&universe)
+                .waypoint(universe.get_system(&ALPHA).unwrap())
+                .waypoint(universe.get_system(&to).unwrap())
+                .build()
+                .unwrap();
+            assert_eq!(
+                Some(path.jumps() as u32),
+                jump_distance(&universe, &ALPHA, &to)
+            );
+        }
+    }
+}
@@ -0,0 +1,235 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Long warps in lowsec. Smartbombing camps sit on gates, and the longer a slow ship
+//! warps between the gates of a lowsec system, the more time the campers have to
+//! notice it and be ready when it lands.
+use std::time::Duration;
+
+use crate::navigation::{Cost, CostFunction, Path, ShipProfile, TravelTime};
+use crate::types;
+
+/// Warps taking longer than this are considered dangerous by default.
+const DEFAULT_THRESHOLD: Duration = Duration::from_secs(30);
+/// Entering a system with a dangerous warp costs as much as this many extra jumps
+/// by default.
+const DEFAULT_PENALTY: Cost = 10;
+
+/// A lowsec system in which a path warps for a long time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dwell {
+    pub system: types::SystemId,
+    pub distance: types::Au,
+    pub warp: Duration,
+}
+
+impl Path<'_> {
+    /// The lowsec systems in which the ship warps longer than `threshold` between the
+    /// gate it arrives at and the gate it leaves through. Systems where either gate
+    /// is unknown are not reported.
+    pub fn long_lowsec_warps(&self, ship: &ShipProfile, threshold: Duration) -> Vec<Dwell> {
+        let estimator = TravelTime::new(ship);
+        estimator
+            .warps(self)
+            .into_iter()
+            .filter_map(|(system, distance)| {
+                let distance = distance?;
+                let security = self.universe.get_system(&system)?.security;
+                if types::SecurityClass::from(security) != types::SecurityClass::Lowsec {
+                    return None;
+                }
+                let warp = estimator.warp_time(distance);
+                (warp > threshold).then_some(Dwell {
+                    system,
+                    distance,
+                    warp,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Wraps a cost function and penalizes entering lowsec systems through a gate that
+/// is far from the other gates of the system.
+///
+/// The gate a route leaves through is not known when entering a system, so the
+/// farthest other gate is assumed. Systems with unknown gates are not penalized.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use neweden::navigation::{LowsecWarpPenalty, Preference, ShipProfile};
+///
+/// // pass to PathBuilder::cost_function
+/// let _cost = LowsecWarpPenalty::new(Preference::Shortest, ShipProfile::default())
+///     .threshold(Duration::from_secs(20));
+/// ```
+pub struct LowsecWarpPenalty<C> {
+    inner: C,
+    ship: ShipProfile,
+    threshold: Duration,
+    penalty: Cost,
+}
+
+impl<C: CostFunction> LowsecWarpPenalty<C> {
+    pub fn new(inner: C, ship: ShipProfile) -> Self {
+        Self {
+            inner,
+            ship,
+            threshold: DEFAULT_THRESHOLD,
+            penalty: DEFAULT_PENALTY,
+        }
+    }
+
+    /// Warps longer than this are penalized.
+    pub fn threshold(mut self, threshold: Duration) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// The cost added for entering a system with a long warp.
+    pub fn penalty(mut self, penalty: Cost) -> Self {
+        self.penalty = penalty;
+        self
+    }
+
+    fn longest_warp(
+        &self,
+        universe: &dyn types::Navigatable,
+        connection: &types::Connection,
+    ) -> Option<Duration> {
+        let to = universe.get_system(&connection.to)?;
+        if types::SecurityClass::from(to.security) != types::SecurityClass::Lowsec {
+            return None;
+        }
+        let gates = universe.get_stargates(&connection.to);
+        let entry = gates
+            .iter()
+            .find(|g| g.destination_system == connection.from)?;
+        let farthest = gates
+            .iter()
+            .filter(|g| g.id != entry.id)
+            .map(|g| entry.distance_to(&g.coordinate))
+            .max_by(|a, b| a.0.total_cmp(&b.0))?;
+        Some(TravelTime::new(&self.ship).warp_time(farthest))
+    }
+}
+
+impl<C: CostFunction> CostFunction for LowsecWarpPenalty<C> {
+    fn cost(
+        &self,
+        universe: &dyn types::Navigatable,
+        connection: &types::Connection,
+    ) -> Option<Cost> {
+        let base = self.inner.cost(universe, connection)?;
+        let long = self
+            .longest_warp(universe, connection)
+            .is_some_and(|warp| warp > self.threshold);
+        Some(if long { base + self.penalty } else { base })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::UniverseBuilder;
+    use crate::navigation::{PathBuilder, Preference};
+    use crate::types::Navigatable;
+
+    fn universe() -> types::Universe {
+        let system = |id: u32, security: f32| {
+            types::System::new(
+                id.into(),
+                "",
+                types::Coordinate {
+                    x: 0.0,
+                    y: 0.0,
+                    z: 0.0,
+                },
+                security.into(),
+            )
+        };
+        let gate = |from: u32, to: u32| types::Connection {
+            from: from.into(),
+            to: to.into(),
+            type_: types::ConnectionType::Stargate(types::StargateType::Local),
+            source: types::ConnectionSource::Sde,
+        };
+        // gate ids are system * 10 + destination, positions are in AU along x
+        let stargate = |system: u32, destination: u32, au: f64| types::Stargate {
+            id: (system * 10 + destination).into(),
+            system: system.into(),
+            coordinate: types::Coordinate {
+                x: types::Meters::from(types::Au(au)).0,
+                y: 0.0,
+                z: 0.0,
+            },
+            destination: (destination * 10 + system).into(),
+            destination_system: destination.into(),
+        };
+
+        // two lowsec systems between 1 and 4, 2 with gates 60 AU apart
+        let mut builder = UniverseBuilder::new();
+        for (id, security) in [(1, 0.9), (2, 0.3), (3, 0.4), (4, 0.9)] {
+            builder.add_system(system(id, security));
+        }
+        for (a, b, au_a, au_b) in [
+            (1, 2, 0.0, 0.0),
+            (2, 4, 60.0, 0.0),
+            (1, 3, 5.0, 0.0),
+            (3, 4, 2.0, 5.0),
+        ] {
+            builder
+                .add_connection_bidirectional(gate(a, b))
+                .add_stargate(stargate(a, b, au_a))
+                .add_stargate(stargate(b, a, au_b));
+        }
+        builder.build()
+    }
+
+    #[test]
+    fn test_long_lowsec_warps() {
+        let universe = universe();
+        let sys = |id: u32| universe.get_system(&id.into()).unwrap();
+        let ship = ShipProfile::default();
+        let path = PathBuilder::new(&universe)
+            .waypoints(vec![sys(1), sys(2), sys(4)])
+            .build()
+            .unwrap();
+        let dwells = path.long_lowsec_warps(&ship, DEFAULT_THRESHOLD);
+        assert_eq!(1, dwells.len());
+        assert_eq!(types::SystemId(2), dwells[0].system);
+        assert!((dwells[0].distance.0 - 60.0).abs() < 1e-6);
+
+        let path = PathBuilder::new(&universe)
+            .waypoints(vec![sys(1), sys(3), sys(4)])
+            .build()
+            .unwrap();
+        assert!(path.long_lowsec_warps(&ship, DEFAULT_THRESHOLD).is_empty());
+    }
+
+    #[test]
+    fn test_lowsec_warp_penalty() {
+        let universe = universe();
+        let sys = |id: u32| universe.get_system(&id.into()).unwrap();
+        let penalty = LowsecWarpPenalty::new(Preference::Shortest, ShipProfile::default());
+        let entering = |to: u32| {
+            let connections = universe.get_connections(&1.into()).unwrap();
+            let connection = connections.iter().find(|c| c.to == to.into()).unwrap();
+            penalty.cost(&universe, connection)
+        };
+        assert_eq!(Some(1 + DEFAULT_PENALTY), entering(2));
+        assert_eq!(Some(1), entering(3));
+
+        let path = PathBuilder::new(&universe)
+            .waypoint(sys(1))
+            .waypoint(sys(4))
+            .cost_function(penalty)
+            .build()
+            .unwrap();
+        assert_eq!(2, path.jumps());
+        assert!(path.systems().any(|s| s.id == 3.into()));
+    }
+}
@@ -0,0 +1,223 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! How far systems are from each other in jumps: the eccentricity of a system is
+//! the number of jumps to the system farthest from it, the diameter of a galaxy is
+//! the largest eccentricity. Systems that cannot be reached are ignored.
+use std::collections::{HashMap, VecDeque};
+use std::thread;
+
+use crate::types::{self, Galaxy};
+
+/// Searches from fewer sources than this are not worth spreading over threads.
+const PARALLEL_THRESHOLD: usize = 256;
+
+/// The two systems farthest apart, see `diameter`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Diameter {
+    pub from: types::SystemId,
+    pub to: types::SystemId,
+    pub jumps: usize,
+}
+
+/// The connections of a galaxy by system index.
+struct Adjacency {
+    ids: Vec<types::SystemId>,
+    outgoing: Vec<Vec<u32>>,
+}
+
+impl Adjacency {
+    fn new<G: Galaxy>(galaxy: &G) -> Self {
+        let mut ids = galaxy.iter_systems().map(|s| s.id).collect::<Vec<_>>();
+        ids.sort();
+        let index = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (*id, i as u32))
+            .collect::<HashMap<_, _>>();
+        let mut outgoing = vec![Vec::new(); ids.len()];
+        for (from, to) in galaxy.iter_connections() {
+            if let (Some(&from), Some(&to)) = (index.get(&from), index.get(&to)) {
+                outgoing[from as usize].push(to);
+            }
+        }
+        Self { ids, outgoing }
+    }
+
+    /// The farthest system from every system, as `(system, jumps)`. The sources are
+    /// split across all available cores.
+    fn farthest(&self) -> Vec<(u32, usize)> {
+        let n = self.ids.len();
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        if threads == 1 || n < PARALLEL_THRESHOLD {
+            return self.farthest_from(0..n);
+        }
+        let chunk = n.div_ceil(threads);
+        thread::scope(|scope| {
+            let handles = (0..n)
+                .step_by(chunk)
+                .map(|start| scope.spawn(move || self.farthest_from(start..n.min(start + chunk))))
+                .collect::<Vec<_>>();
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().expect("eccentricity search panicked"))
+                .collect()
+        })
+    }
+
+    fn farthest_from(&self, sources: std::ops::Range<usize>) -> Vec<(u32, usize)> {
+        let mut distance = vec![usize::MAX; self.ids.len()];
+        let mut queue = VecDeque::new();
+        sources
+            .map(|source| {
+                distance.fill(usize::MAX);
+                distance[source] = 0;
+                queue.push_back(source as u32);
+                let mut farthest = (source as u32, 0);
+                while let Some(node) = queue.pop_front() {
+                    let d = distance[node as usize];
+                    if d > farthest.1 || (d == farthest.1 && node < farthest.0) {
+                        farthest = (node, d);
+                    }
+                    for &next in &self.outgoing[node as usize] {
+                        if distance[next as usize] == usize::MAX {
+                            distance[next as usize] = d + 1;
+                            queue.push_back(next);
+                        }
+                    }
+                }
+                farthest
+            })
+            .collect()
+    }
+}
+
+/// The eccentricity of every system: the jumps to the farthest system reachable from
+/// it. Runs a breadth first search from every system, in parallel.
+///
+/// # Example
+/// ```
+/// use neweden::builder::UniverseBuilder;
+/// use neweden::navigation::eccentricities;
+/// use neweden::{Connection, ConnectionSource, ConnectionType, Coordinate, StargateType, System};
+///
+/// let system = |id: u32| System::new(id.into(), "", Coordinate { x: 0.0, y: 0.0, z: 0.0 }, 0.5.into());
+/// let gate = |from: u32, to: u32| Connection {
+///     from: from.into(),
+///     to: to.into(),
+///     type_: ConnectionType::Stargate(StargateType::Local),
+///     source: ConnectionSource::Sde,
+/// };
+/// let universe = UniverseBuilder::new()
+///     .system(system(1))
+///     .system(system(2))
+///     .system(system(3))
+///     .connection_bidirectional(gate(1, 2))
+///     .connection_bidirectional(gate(2, 3))
+///     .build();
+/// let eccentricities = eccentricities(&universe);
+/// assert_eq!(2, eccentricities[&1.into()]);
+/// assert_eq!(1, eccentricities[&2.into()]);
+/// ```
+pub fn eccentricities<G: Galaxy>(galaxy: &G) -> HashMap<types::SystemId, usize> {
+    let graph = Adjacency::new(galaxy);
+    graph
+        .farthest()
+        .into_iter()
+        .enumerate()
+        .map(|(i, (_, jumps))| (graph.ids[i], jumps))
+        .collect()
+}
+
+/// The eccentricity of a single system, `None` if the galaxy does not contain it.
+pub fn eccentricity<G: Galaxy>(galaxy: &G, system: &types::SystemId) -> Option<usize> {
+    let graph = Adjacency::new(galaxy);
+    let source = graph.ids.binary_search(system).ok()?;
+    graph.farthest_from(source..source + 1).first().map(|f| f.1)
+}
+
+/// The two systems farthest apart in jumps, i.e. the longest of all shortest routes.
+/// Ties go to the pair with the lowest system ids. `None` for an empty galaxy.
+pub fn diameter<G: Galaxy>(galaxy: &G) -> Option<Diameter> {
+    let graph = Adjacency::new(galaxy);
+    graph
+        .farthest()
+        .into_iter()
+        .enumerate()
+        .map(|(i, (to, jumps))| Diameter {
+            from: graph.ids[i],
+            to: graph.ids[to as usize],
+            jumps,
+        })
+        .min_by_key(|d| (std::cmp::Reverse(d.jumps), d.from, d.to))
+}
+
+/// A quick lower bound of the diameter: the farthest system from the system with
+/// the lowest id, and the farthest system from that one. Exact on trees and usually
+/// close on real maps, at the cost of two searches instead of one per system. Only
+/// considers the systems reachable from the system with the lowest id.
+pub fn estimate_diameter<G: Galaxy>(galaxy: &G) -> Option<Diameter> {
+    let graph = Adjacency::new(galaxy);
+    let (start, _) = *graph.farthest_from(0..graph.ids.len().min(1)).first()?;
+    let (end, jumps) = graph.farthest_from(start as usize..start as usize + 1)[0];
+    Some(Diameter {
+        from: graph.ids[start as usize],
+        to: graph.ids[end as usize],
+        jumps,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+
+    #[test]
+    fn test_diameter() {
+        let universe = universe();
+        // Alpha - Bravo - Charlie - Delta - Echo, Hotel hangs off Golf
+        assert_eq!(
+            Some(Diameter {
+                from: ALPHA,
+                to: ECHO,
+                jumps: 4,
+            }),
+            diameter(&universe)
+        );
+        let eccentricities = eccentricities(&universe);
+        assert_eq!(8, eccentricities.len());
+        assert_eq!(3, eccentricities[&GOLF]);
+        assert_eq!(Some(4), eccentricity(&universe, &HOTEL));
+        assert_eq!(None, eccentricity(&universe, &types::SystemId(1)));
+
+        let cluster = cluster();
+        let (west, east) = (cluster_system(0, 0, 0), cluster_system(2, 0, 4));
+        assert_eq!(
+            Some(Diameter {
+                from: west,
+                to: east,
+                jumps: 16,
+            }),
+            diameter(&cluster)
+        );
+        assert_eq!(Some(16), eccentricity(&cluster, &west));
+        // from Echo back to Alpha
+        assert_eq!(
+            Some(Diameter {
+                from: ECHO,
+                to: ALPHA,
+                jumps: 4,
+            }),
+            estimate_diameter(&universe)
+        );
+        assert_eq!(16, estimate_diameter(&cluster).map_or(0, |d| d.jumps));
+        assert_eq!(None, estimate_diameter(&types::Universe::empty()));
+
+        // enough systems to search in parallel, which must agree with a single search
+        let universe = pseudo_universe(7, 400, 1200);
+        let graph = Adjacency::new(&universe);
+        assert_eq!(graph.farthest_from(0..400), graph.farthest());
+    }
+}
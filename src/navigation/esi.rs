@@ -0,0 +1,261 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Pushes a path into the game client's autopilot through ESI and compares paths
+//! with the routes ESI computes.
+//!
+//! # Example
+//! ```no_run
+//! use neweden::navigation::esi::Autopilot;
+//! # use neweden::navigation::PathBuilder;
+//! # use neweden::builder::UniverseBuilder;
+//! # use neweden::Navigatable;
+//! # let universe = UniverseBuilder::new().build();
+//! # let jita = universe.get_system(&30000142.into()).unwrap();
+//! # let path = PathBuilder::new(&universe).waypoint(jita).waypoint(jita).build().unwrap();
+//!
+//! // the token needs the esi-ui.write_waypoint.v1 scope
+//! Autopilot::new("access token").set_route(&path).unwrap();
+//!
+//! for divergence in neweden::navigation::verify_against_esi(&path).unwrap() {
+//!     println!("{:?}", divergence);
+//! }
+//! ```
+use anyhow;
+
+use crate::navigation::{Path, Preference};
+use crate::types;
+
+/// The ESI endpoint setting an autopilot waypoint.
+pub const WAYPOINT_URL: &str = "https://esi.evetech.net/latest/ui/autopilot/waypoint/";
+
+/// The ESI endpoint computing routes.
+pub const ROUTE_URL: &str = "https://esi.evetech.net/latest/route/";
+
+/// Sets the autopilot waypoints of the character the access token belongs to.
+pub struct Autopilot {
+    url: String,
+    token: String,
+}
+
+impl Autopilot {
+    pub fn new(token: &str) -> Self {
+        Self {
+            url: WAYPOINT_URL.to_string(),
+            token: token.to_string(),
+        }
+    }
+
+    /// Uses a different endpoint, e.g. the Singularity test server.
+    pub fn url(mut self, url: &str) -> Self {
+        self.url = url.to_string();
+        self
+    }
+
+    /// Replaces the current waypoints with the path. See `Path::to_esi_waypoints`.
+    pub fn set_route(&self, path: &Path) -> anyhow::Result<()> {
+        self.set_waypoints(&path.to_esi_waypoints())
+    }
+
+    /// Replaces the current waypoints with the systems, one call per system.
+    pub fn set_waypoints(&self, systems: &[types::SystemId]) -> anyhow::Result<()> {
+        let auth = format!("Bearer {}", self.token);
+        for url in self.requests(systems) {
+            ureq::post(&url).set("Authorization", &auth).call()?;
+        }
+        Ok(())
+    }
+
+    /// The `set_waypoint` calls, the first one clearing the existing waypoints.
+    fn requests(&self, systems: &[types::SystemId]) -> Vec<String> {
+        systems
+            .iter()
+            .enumerate()
+            .map(|(i, system)| {
+                format!(
+                    "{}?add_to_beginning=false&clear_other_waypoints={}&destination_id={}",
+                    self.url,
+                    i == 0,
+                    system.0
+                )
+            })
+            .collect()
+    }
+}
+
+/// A leg of a path that differs from the route ESI computes between the same
+/// waypoints. Both routes include the waypoints.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    pub from: types::SystemId,
+    pub to: types::SystemId,
+    pub ours: Vec<types::SystemId>,
+    pub esi: Vec<types::SystemId>,
+}
+
+impl Divergence {
+    /// How many more jumps the path takes than ESI, negative if it takes fewer.
+    pub fn extra_jumps(&self) -> isize {
+        self.ours.len() as isize - self.esi.len() as isize
+    }
+}
+
+/// Compares paths with the routes of the ESI route endpoint, leg by leg.
+///
+/// ESI only knows stargates, so legs using wormholes or jump bridges diverge by
+/// design. Routes of equal cost may also differ in the systems they pass through;
+/// use `Divergence::extra_jumps` to tell those apart.
+pub struct RouteCheck {
+    url: String,
+    preference: Preference,
+}
+
+impl RouteCheck {
+    pub fn new() -> Self {
+        Self {
+            url: ROUTE_URL.to_string(),
+            preference: Preference::Shortest,
+        }
+    }
+
+    /// Uses a different endpoint, e.g. the Singularity test server.
+    pub fn url(mut self, url: &str) -> Self {
+        self.url = url.to_string();
+        self
+    }
+
+    /// The preference the path was built with, sent as the route flag.
+    pub fn prefer(mut self, preference: Preference) -> Self {
+        self.preference = preference;
+        self
+    }
+
+    /// The legs of the path that differ from ESI, one request per leg.
+    pub fn verify(&self, path: &Path) -> anyhow::Result<Vec<Divergence>> {
+        let mut divergences = Vec::new();
+        for ours in legs(path) {
+            let (from, to) = (ours[0], ours[ours.len() - 1]);
+            let esi = ureq::get(&self.request(&from, &to))
+                .call()?
+                .into_json::<Vec<u32>>()?
+                .into_iter()
+                .map(types::SystemId)
+                .collect::<Vec<_>>();
+            if ours != esi {
+                divergences.push(Divergence {
+                    from,
+                    to,
+                    ours,
+                    esi,
+                });
+            }
+        }
+        Ok(divergences)
+    }
+
+    fn request(&self, from: &types::SystemId, to: &types::SystemId) -> String {
+        let flag = match self.preference {
+            // ESI knows nothing about kills
+            Preference::Shortest | Preference::AvoidDanger => "shortest",
+            Preference::Highsec => "secure",
+            Preference::LowsecAndNullsec => "insecure",
+        };
+        format!("{}{}/{}/?flag={}", self.url, from.0, to.0, flag)
+    }
+}
+
+impl Default for RouteCheck {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The legs of the path that differ from the shortest routes ESI computes. See
+/// `RouteCheck` to compare paths built with other preferences.
+pub fn verify_against_esi(path: &Path) -> anyhow::Result<Vec<Divergence>> {
+    RouteCheck::new().verify(path)
+}
+
+/// The systems of every leg between two waypoints.
+fn legs(path: &Path) -> Vec<Vec<types::SystemId>> {
+    let systems = path.systems().map(|s| s.id).collect::<Vec<_>>();
+    let mut legs = Vec::new();
+    let mut start = 0;
+    for pair in path.waypoints().windows(2) {
+        let Some(end) = systems[start..].iter().position(|id| *id == pair[1].id) else {
+            break;
+        };
+        legs.push(systems[start..=start + end].to_vec());
+        start += end;
+    }
+    legs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation::PathBuilder;
+    use crate::testing::*;
+    use crate::types::Navigatable;
+
+    #[test]
+    fn test_requests() {
+        let universe = universe();
+        let path = PathBuilder::new(&universe)
+            .waypoint(universe.get_system(&ALPHA).unwrap())
+            .waypoint(universe.get_system(&CHARLIE).unwrap())
+            .build()
+            .unwrap();
+        let waypoints = path.to_esi_waypoints();
+        assert_eq!(vec![BRAVO, CHARLIE], waypoints);
+
+        let requests = Autopilot::new("token")
+            .url("http://esi/")
+            .requests(&waypoints);
+        assert_eq!(
+            vec![
+                "http://esi/?add_to_beginning=false&clear_other_waypoints=true&destination_id=30000002",
+                "http://esi/?add_to_beginning=false&clear_other_waypoints=false&destination_id=30000003",
+            ],
+            requests
+        );
+    }
+
+    #[test]
+    fn test_route_check() {
+        let universe = universe();
+        let path = PathBuilder::new(&universe)
+            .waypoint(universe.get_system(&ALPHA).unwrap())
+            .waypoint(universe.get_system(&CHARLIE).unwrap())
+            .waypoint(universe.get_system(&CHARLIE).unwrap())
+            .waypoint(universe.get_system(&HOTEL).unwrap())
+            .build()
+            .unwrap();
+        assert_eq!(
+            vec![
+                vec![ALPHA, BRAVO, CHARLIE],
+                vec![CHARLIE],
+                vec![CHARLIE, GOLF, HOTEL],
+            ],
+            legs(&path)
+        );
+
+        let check = RouteCheck::new()
+            .url("http://esi/")
+            .prefer(Preference::Highsec);
+        assert_eq!(
+            "http://esi/30000001/30000003/?flag=secure",
+            check.request(&ALPHA, &CHARLIE)
+        );
+
+        let divergence = Divergence {
+            from: CHARLIE,
+            to: HOTEL,
+            ours: vec![CHARLIE, GOLF, HOTEL],
+            esi: vec![CHARLIE, DELTA, GOLF, HOTEL],
+        };
+        assert_eq!(-1, divergence.extra_jumps());
+    }
+}
@@ -0,0 +1,310 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Filaments as wildcard exits. A filament moves the fleet to a random system out of
+//! a set that depends on its kind, so it is modeled as a connection to every system
+//! it may land in. Routing over these connections yields the best case, while
+//! `FilamentPlanner::shortcuts` weighs the best case against the expected one.
+//!
+//! # Example
+//! ```
+//! use neweden::builder::UniverseBuilder;
+//! use neweden::navigation::filaments::FilamentPlanner;
+//! use neweden::navigation::PathBuilder;
+//! use neweden::{FilamentKind, Navigatable};
+//!
+//! # let universe = UniverseBuilder::new().build();
+//! let filaments = FilamentPlanner::new(&universe).kind(FilamentKind::Noise);
+//! if let Some(jita) = universe.get_system(&30000142.into()) {
+//!     let extended = universe.extend(filaments.connections(jita).into());
+//! }
+//! ```
+use crate::rules;
+use crate::types;
+
+use super::{costs_from, Path, Preference};
+
+pub use crate::types::FilamentKind;
+
+/// A place along a route where activating a filament may save jumps. All jump
+/// counts are from `at` to the destination of the route, the filament counts as
+/// one jump.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilamentShortcut {
+    pub at: types::SystemId,
+    pub kind: FilamentKind,
+    /// The jumps left on the route without the filament.
+    pub remaining: usize,
+    /// The jumps if the filament lands in the best possible system.
+    pub best: usize,
+    /// The average jumps over all systems the filament may land in.
+    pub expected: f64,
+}
+
+/// Where the filaments a pilot carries can take them.
+pub struct FilamentPlanner<'a, U> {
+    universe: &'a U,
+    kinds: Vec<FilamentKind>,
+    home: Option<types::RegionId>,
+}
+
+impl<'a, U: types::Galaxy + types::Navigatable> FilamentPlanner<'a, U> {
+    pub fn new(universe: &'a U) -> Self {
+        Self {
+            universe,
+            kinds: Vec::new(),
+            home: None,
+        }
+    }
+
+    /// A kind of filament the pilot carries.
+    pub fn kind(mut self, kind: FilamentKind) -> Self {
+        self.add_kind(kind);
+        self
+    }
+
+    /// The region `FilamentKind::PochvenHome` filaments lead to. Without a home
+    /// region they lead nowhere.
+    pub fn home_region(mut self, region: types::RegionId) -> Self {
+        self.home = Some(region);
+        self
+    }
+
+    /// Like `kind`, but for use when the planner is not consumed, e.g. in loops.
+    pub fn add_kind(&mut self, kind: FilamentKind) -> &mut Self {
+        if !self.kinds.contains(&kind) {
+            self.kinds.push(kind);
+        }
+        self
+    }
+
+    /// Whether a filament of this kind can be activated in the system.
+    pub fn allowed_from(&self, kind: FilamentKind, system: &types::System) -> bool {
+        let class = types::SystemClass::from(system);
+        match kind {
+            FilamentKind::Krai => rules::allows_pochven_filament(system),
+            FilamentKind::PochvenBorder | FilamentKind::PochvenHome => {
+                class == types::SystemClass::Pochven
+            }
+            FilamentKind::Noise | FilamentKind::Signal => class == types::SystemClass::KSpace,
+        }
+    }
+
+    /// The systems a filament of this kind may land in when activated in `from`.
+    pub fn destinations(&self, kind: FilamentKind, from: &types::System) -> Vec<&'a types::System> {
+        if !self.allowed_from(kind, from) {
+            return Vec::new();
+        }
+        let kspace = |s: &types::System| types::SystemClass::from(s) == types::SystemClass::KSpace;
+        let pochven =
+            |s: &types::System| types::SystemClass::from(s) == types::SystemClass::Pochven;
+        let universe = self.universe;
+        universe
+            .systems()
+            .into_iter()
+            .filter(|s| s.id != from.id)
+            .filter(|s| match kind {
+                FilamentKind::Krai => pochven(s),
+                // bordering systems have a connection to or from Pochven
+                FilamentKind::PochvenBorder => {
+                    kspace(s)
+                        && universe
+                            .get_connections(&s.id)
+                            .unwrap_or_default()
+                            .iter()
+                            .filter_map(|c| universe.get_system(&c.to))
+                            .any(pochven)
+                }
+                FilamentKind::PochvenHome => {
+                    kspace(s) && self.home.is_some() && s.region == self.home
+                }
+                FilamentKind::Noise | FilamentKind::Signal => {
+                    kspace(s)
+                        && types::SecurityClass::from(s.security) == types::SecurityClass::Nullsec
+                }
+            })
+            .collect()
+    }
+
+    /// A filament connection from `from` to every system a carried filament may land
+    /// in, ready for `Universe::extend`. Paths over them contain the filament as
+    /// `PathElement::Connection(ConnectionType::Filament(kind), _)`.
+    pub fn connections(&self, from: &types::System) -> Vec<types::Connection> {
+        self.kinds
+            .iter()
+            .flat_map(|kind| {
+                self.destinations(*kind, from)
+                    .into_iter()
+                    .map(|to| types::Connection {
+                        from: from.id,
+                        to: to.id,
+                        type_: types::ConnectionType::Filament(*kind),
+                        source: types::ConnectionSource::UserOverlay,
+                    })
+            })
+            .collect()
+    }
+
+    /// The places along the path where a carried filament could shorten it in the best
+    /// case, the most promising on average first. Distances from the landing systems
+    /// are computed from the destination, assuming connections exist in both
+    /// directions like stargates. Landing systems without a route to the destination
+    /// make the expectation infinite.
+    pub fn shortcuts(&self, path: &Path) -> Vec<FilamentShortcut> {
+        let Some(destination) = path.to() else {
+            return Vec::new();
+        };
+        let costs = costs_from(self.universe, &Preference::Shortest, destination.id);
+        let systems = path.systems().collect::<Vec<_>>();
+
+        let mut shortcuts = Vec::new();
+        for (i, system) in systems.iter().enumerate() {
+            let remaining = systems.len() - 1 - i;
+            for kind in &self.kinds {
+                let landings = self.destinations(*kind, system);
+                if landings.is_empty() {
+                    continue;
+                }
+                let jumps = landings
+                    .iter()
+                    .map(|s| costs.get(&s.id).map(|c| 1 + *c as usize))
+                    .collect::<Vec<_>>();
+                let Some(best) = jumps.iter().flatten().min().copied() else {
+                    continue;
+                };
+                if best >= remaining {
+                    continue;
+                }
+                let expected = jumps
+                    .iter()
+                    .map(|j| j.map_or(f64::INFINITY, |j| j as f64))
+                    .sum::<f64>()
+                    / jumps.len() as f64;
+                shortcuts.push((
+                    i as f64 + expected,
+                    FilamentShortcut {
+                        at: system.id,
+                        kind: *kind,
+                        remaining,
+                        best,
+                        expected,
+                    },
+                ));
+            }
+        }
+        shortcuts.sort_by(|a, b| a.0.total_cmp(&b.0));
+        shortcuts.into_iter().map(|(_, s)| s).collect()
+    }
+
+    /// Whether a carried filament could shorten the path, at least in the best case.
+    pub fn could_shorten(&self, path: &Path) -> bool {
+        !self.shortcuts(path).is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::UniverseBuilder;
+    use crate::navigation::PathBuilder;
+    use crate::testing::*;
+    use crate::types::Navigatable;
+
+    #[test]
+    fn test_shortcuts() {
+        let universe = universe();
+        let sys = |id| universe.get_system(&id).unwrap();
+        let path = PathBuilder::new(&universe)
+            .waypoint(sys(ALPHA))
+            .waypoint(sys(ECHO))
+            .build()
+            .unwrap();
+        let planner = FilamentPlanner::new(&universe);
+        assert!(!planner.could_shorten(&path));
+
+        // Echo is the only nullsec system
+        let planner = planner.kind(FilamentKind::Noise);
+        let shortcuts = planner.shortcuts(&path);
+        assert_eq!(ALPHA, shortcuts[0].at);
+        assert_eq!(4, shortcuts[0].remaining);
+        assert_eq!(1, shortcuts[0].best);
+        assert_eq!(1.0, shortcuts[0].expected);
+        // not from Echo itself, and not from Delta where it saves nothing
+        assert_eq!(3, shortcuts.len());
+
+        let extended = universe.extend(planner.connections(sys(ALPHA)).into());
+        let path = PathBuilder::new(&extended)
+            .waypoint(sys(ALPHA))
+            .waypoint(sys(ECHO))
+            .build()
+            .unwrap();
+        assert_eq!(1, path.jumps());
+        assert!(path.iter().any(|e| matches!(
+            e,
+            crate::navigation::PathElement::Connection(
+                types::ConnectionType::Filament(FilamentKind::Noise),
+                _
+            )
+        )));
+    }
+
+    #[test]
+    fn test_pochven_destinations() {
+        let system = |id: u32, region: types::RegionId, security: f32| {
+            let mut system = types::System::new(
+                id.into(),
+                "",
+                types::Coordinate {
+                    x: 0.0,
+                    y: 0.0,
+                    z: 0.0,
+                },
+                security.into(),
+            );
+            system.region = Some(region);
+            system
+        };
+        let (home, away) = (types::RegionId(10000001), types::RegionId(10000002));
+        let universe = UniverseBuilder::new()
+            .system(system(30000001, home, 0.9))
+            .system(system(30000002, types::RegionId::POCHVEN, -1.0))
+            .system(system(30000003, away, -0.5))
+            .connection_bidirectional(types::Connection {
+                from: 30000001.into(),
+                to: 30000002.into(),
+                type_: types::ConnectionType::Stargate(types::StargateType::Regional),
+                source: types::ConnectionSource::UserOverlay,
+            })
+            .build();
+        let sys = |id: u32| universe.get_system(&id.into()).unwrap();
+        let ids = |systems: Vec<&types::System>| systems.iter().map(|s| s.id.0).collect::<Vec<_>>();
+
+        let planner = FilamentPlanner::new(&universe);
+        assert_eq!(
+            vec![30000002],
+            ids(planner.destinations(FilamentKind::Krai, sys(30000001)))
+        );
+        assert!(planner
+            .destinations(FilamentKind::Krai, sys(30000002))
+            .is_empty());
+        assert_eq!(
+            vec![30000001],
+            ids(planner.destinations(FilamentKind::PochvenBorder, sys(30000002)))
+        );
+        assert!(planner
+            .destinations(FilamentKind::PochvenHome, sys(30000002))
+            .is_empty());
+        assert_eq!(
+            vec![30000003],
+            ids(planner.destinations(FilamentKind::Signal, sys(30000001)))
+        );
+
+        let planner = planner.home_region(home);
+        assert_eq!(
+            vec![30000001],
+            ids(planner.destinations(FilamentKind::PochvenHome, sys(30000002)))
+        );
+    }
+}
@@ -0,0 +1,312 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Route text for humans, in the language of the pilot. System names come from the
+//! translations loaded with the universe (see `DatabaseBuilder::translations`) and
+//! fall back to the English name. Numbers and durations use the separators and units
+//! of the locale.
+//!
+//! # Example
+//! ```
+//! use neweden::builder::UniverseBuilder;
+//! use neweden::navigation::{format, PathBuilder};
+//! use neweden::{Coordinate, Locale, Navigatable, System};
+//!
+//! let jita = System::new(30000142.into(), "Jita", Coordinate { x: 0.0, y: 0.0, z: 0.0 }, 0.9459.into());
+//! let universe = UniverseBuilder::new()
+//!     .system(jita)
+//!     .localized_name(30000142.into(), Locale::Chinese, "吉他")
+//!     .build();
+//! let jita = universe.get_system(&30000142.into()).unwrap();
+//! let path = PathBuilder::new(&universe).waypoint(jita).waypoint(jita).build().unwrap();
+//! assert_eq!("吉他 (0.9)\n0跳", format::route(&path, Locale::Chinese));
+//! assert_eq!("Jita (0,9)\n0 Sprünge", format::route(&path, Locale::German));
+//! ```
+use std::time::Duration;
+
+use crate::advisor::Advisory;
+use crate::navigation::Path;
+use crate::types;
+
+pub use crate::types::Locale;
+
+/// The name of the system in the language of the locale, or its English name if no
+/// translation is loaded.
+pub fn system_name<'a>(
+    universe: &'a dyn types::Navigatable,
+    system: &'a types::System,
+    locale: Locale,
+) -> &'a str {
+    universe
+        .get_localized_name(&system.id, locale)
+        .unwrap_or(&system.name)
+}
+
+/// Formats a number with the given number of decimals and the digit grouping and
+/// decimal separator of the locale.
+pub fn number(value: f64, decimals: usize, locale: Locale) -> String {
+    let (group, decimal) = match locale {
+        Locale::English | Locale::Japanese | Locale::Chinese | Locale::Korean => (",", "."),
+        Locale::German | Locale::Spanish => (".", ","),
+        Locale::French | Locale::Russian => ("\u{a0}", ","),
+    };
+    let formatted = format!("{:.*}", decimals, value.abs());
+    let (integer, fraction) = formatted
+        .split_once('.')
+        .map_or((formatted.as_str(), None), |(i, f)| (i, Some(f)));
+
+    let mut result = String::new();
+    if value.is_sign_negative() && formatted.chars().any(|c| c.is_ascii_digit() && c != '0') {
+        result.push('-');
+    }
+    for (i, digit) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i).is_multiple_of(3) {
+            result.push_str(group);
+        }
+        result.push(digit);
+    }
+    if let Some(fraction) = fraction {
+        result.push_str(decimal);
+        result.push_str(fraction);
+    }
+    result
+}
+
+/// Formats a duration in hours, minutes and seconds, leaving out the parts that are
+/// zero.
+pub fn duration(duration: Duration, locale: Locale) -> String {
+    let (hours, minutes, seconds, separator) = match locale {
+        Locale::English | Locale::French | Locale::Spanish => ("h", "min", "s", " "),
+        Locale::German => ("Std.", "Min.", "Sek.", " "),
+        Locale::Russian => ("ч", "мин", "с", " "),
+        Locale::Japanese => ("時間", "分", "秒", ""),
+        Locale::Chinese => ("小时", "分", "秒", ""),
+        Locale::Korean => ("시간", "분", "초", " "),
+    };
+    let total = duration.as_secs_f64().round() as u64;
+    let parts = [
+        (total / 3600, hours),
+        (total % 3600 / 60, minutes),
+        (total % 60, seconds),
+    ];
+    let mut result = parts
+        .iter()
+        .filter(|(n, _)| *n > 0)
+        .map(|(n, unit)| format!("{}{}{}", n, separator, unit))
+        .collect::<Vec<_>>();
+    if result.is_empty() {
+        result.push(format!("0{}{}", separator, seconds));
+    }
+    result.join(" ")
+}
+
+/// Formats a security status rounded like in the client.
+pub fn security(security: types::Security, locale: Locale) -> String {
+    number(f64::from(security.rounded().0), 1, locale)
+}
+
+/// One line per system of the path with its security, followed by the number of
+/// jumps.
+pub fn route(path: &Path, locale: Locale) -> String {
+    let mut lines = path
+        .systems()
+        .map(|s| {
+            format!(
+                "{} ({})",
+                system_name(path.universe, s, locale),
+                security(s.security, locale)
+            )
+        })
+        .collect::<Vec<_>>();
+    let one = path.jumps() == 1;
+    let summary = match locale {
+        Locale::English if one => "{0} jump",
+        Locale::English => "{0} jumps",
+        Locale::German if one => "{0} Sprung",
+        Locale::German => "{0} Sprünge",
+        Locale::French if one => "{0} saut",
+        Locale::French => "{0} sauts",
+        Locale::Spanish if one => "{0} salto",
+        Locale::Spanish => "{0} saltos",
+        Locale::Russian => "Прыжков: {0}",
+        Locale::Japanese => "{0}ジャンプ",
+        Locale::Chinese => "{0}跳",
+        Locale::Korean => "{0}점프",
+    };
+    lines.push(fill(summary, &[&path.jumps().to_string()]));
+    lines.join("\n")
+}
+
+/// Describes an advisory of a trip plan.
+pub fn advisory(universe: &dyn types::Navigatable, advisory: &Advisory, locale: Locale) -> String {
+    let name = |id: &types::SystemId| {
+        universe
+            .get_system(id)
+            .map(|s| system_name(universe, s, locale).to_string())
+            .unwrap_or_else(|| id.0.to_string())
+    };
+    match advisory {
+        Advisory::LeavesHighsec(system) => {
+            let template = match locale {
+                Locale::English => "Leaves highsec in {0}",
+                Locale::German => "Verlässt den Hochsicherheitsraum in {0}",
+                Locale::French => "Quitte la haute sécurité à {0}",
+                Locale::Spanish => "Sale de alta seguridad en {0}",
+                Locale::Russian => "Выход из хайсека в {0}",
+                Locale::Japanese => "{0}でハイセクを離れます",
+                Locale::Chinese => "在{0}离开高安",
+                Locale::Korean => "{0}에서 하이섹을 벗어납니다",
+            };
+            fill(template, &[&name(system)])
+        }
+        Advisory::EntersNullsec(system) => {
+            let template = match locale {
+                Locale::English => "Enters nullsec in {0}",
+                Locale::German => "Betritt den Nullsicherheitsraum in {0}",
+                Locale::French => "Entre en sécurité nulle à {0}",
+                Locale::Spanish => "Entra en seguridad nula en {0}",
+                Locale::Russian => "Вход в нули в {0}",
+                Locale::Japanese => "{0}でヌルセクに入ります",
+                Locale::Chinese => "在{0}进入00区",
+                Locale::Korean => "{0}에서 널섹에 진입합니다",
+            };
+            fill(template, &[&name(system)])
+        }
+        Advisory::HighRisk(system, risk) => {
+            let template = match locale {
+                Locale::English => "High risk in {0} ({1}%)",
+                Locale::German => "Hohes Risiko in {0} ({1} %)",
+                Locale::French => "Risque élevé à {0} ({1} %)",
+                Locale::Spanish => "Riesgo alto en {0} ({1} %)",
+                Locale::Russian => "Высокий риск в {0} ({1} %)",
+                Locale::Japanese => "{0}は高リスクです ({1}%)",
+                Locale::Chinese => "{0}风险高 ({1}%)",
+                Locale::Korean => "{0} 위험 높음 ({1}%)",
+            };
+            let percent = number(f64::from(*risk) * 100.0, 0, locale);
+            fill(template, &[&name(system), &percent])
+        }
+        Advisory::UsesWormhole { from, to } => {
+            let template = match locale {
+                Locale::English => "Wormhole from {0} to {1}",
+                Locale::German => "Wurmloch von {0} nach {1}",
+                Locale::French => "Trou de ver de {0} à {1}",
+                Locale::Spanish => "Agujero de gusano de {0} a {1}",
+                Locale::Russian => "Червоточина из {0} в {1}",
+                Locale::Japanese => "{0}から{1}へのワームホール",
+                Locale::Chinese => "从{0}到{1}的虫洞",
+                Locale::Korean => "{0}에서 {1}(으)로 가는 웜홀",
+            };
+            fill(template, &[&name(from), &name(to)])
+        }
+        Advisory::UsesBridge { from, to } => {
+            let template = match locale {
+                Locale::English => "Jump bridge from {0} to {1}",
+                Locale::German => "Sprungbrücke von {0} nach {1}",
+                Locale::French => "Pont de saut de {0} à {1}",
+                Locale::Spanish => "Puente de salto de {0} a {1}",
+                Locale::Russian => "Мост из {0} в {1}",
+                Locale::Japanese => "{0}から{1}へのジャンプブリッジ",
+                Locale::Chinese => "从{0}到{1}的跳桥",
+                Locale::Korean => "{0}에서 {1}(으)로 가는 점프 브릿지",
+            };
+            fill(template, &[&name(from), &name(to)])
+        }
+        Advisory::UsesFilament { from, to } => {
+            let template = match locale {
+                Locale::English => "Filament from {0}, it may not land in {1}",
+                Locale::German => "Filament ab {0}, landet eventuell nicht in {1}",
+                Locale::French => "Filament depuis {0}, il peut ne pas mener à {1}",
+                Locale::Spanish => "Filamento desde {0}, puede no llevar a {1}",
+                Locale::Russian => "Филамент из {0}, может не привести в {1}",
+                Locale::Japanese => "{0}からのフィラメントは{1}に着くとは限りません",
+                Locale::Chinese => "从{0}使用的纤维不一定到达{1}",
+                Locale::Korean => "{0}에서 사용하는 필라멘트는 {1}에 도착하지 않을 수 있습니다",
+            };
+            fill(template, &[&name(from), &name(to)])
+        }
+    }
+}
+
+/// Replaces `{0}`, `{1}`, ... in the template. Word order differs between languages,
+/// so the arguments are numbered rather than positional.
+fn fill(template: &str, args: &[&str]) -> String {
+    args.iter()
+        .enumerate()
+        .fold(template.to_string(), |text, (i, arg)| {
+            text.replace(&format!("{{{}}}", i), arg)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::UniverseBuilder;
+    use crate::navigation::PathBuilder;
+    use crate::testing::*;
+    use crate::types::Navigatable;
+
+    #[test]
+    fn test_numbers_and_durations() {
+        assert_eq!("1,234,567.89", number(1234567.891, 2, Locale::English));
+        assert_eq!("1.234.567,89", number(1234567.891, 2, Locale::German));
+        assert_eq!("1\u{a0}234", number(1234.0, 0, Locale::French));
+        assert_eq!("-0,3", number(-0.3, 1, Locale::Russian));
+        assert_eq!("0.0", number(-0.01, 1, Locale::English));
+        assert_eq!("999", number(999.0, 0, Locale::English));
+
+        assert_eq!(Some(Locale::English), Locale::from_code("EN-US"));
+        assert_eq!(None, Locale::from_code("it"));
+
+        let time = Duration::from_secs(3725);
+        assert_eq!("1 h 2 min 5 s", duration(time, Locale::English));
+        assert_eq!("1 Std. 2 Min. 5 Sek.", duration(time, Locale::German));
+        assert_eq!("1時間 2分 5秒", duration(time, Locale::Japanese));
+        assert_eq!("0 s", duration(Duration::ZERO, Locale::French));
+    }
+
+    #[test]
+    fn test_route_and_advisories() {
+        let universe = universe();
+        let mut builder = UniverseBuilder::new();
+        for system in universe.systems.0.values() {
+            builder.add_system(system.clone());
+        }
+        let universe = builder
+            .connection(universe.get_connections(&ALPHA).unwrap()[0].clone())
+            .localized_name(ALPHA, Locale::Russian, "Альфа")
+            .build();
+        let path = PathBuilder::new(&universe)
+            .waypoint(universe.get_system(&ALPHA).unwrap())
+            .waypoint(universe.get_system(&BRAVO).unwrap())
+            .build()
+            .unwrap();
+        assert_eq!(
+            "Альфа (0,9)\nBravo (0,8)\nПрыжков: 1",
+            route(&path, Locale::Russian)
+        );
+        assert_eq!(
+            "Alpha (0.9)\nBravo (0.8)\n1 jump",
+            route(&path, Locale::English)
+        );
+
+        let wormhole = Advisory::UsesWormhole {
+            from: ALPHA,
+            to: types::SystemId(1),
+        };
+        assert_eq!(
+            "Червоточина из Альфа в 1",
+            advisory(&universe, &wormhole, Locale::Russian)
+        );
+        assert_eq!(
+            "Bravoは高リスクです (75%)",
+            advisory(
+                &universe,
+                &Advisory::HighRisk(BRAVO, 0.75),
+                Locale::Japanese
+            )
+        );
+    }
+}
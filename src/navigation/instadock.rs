@@ -0,0 +1,177 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Systems a hauler can dock up in without lingering on a gate grid. When an NPC
+//! station sits off-grid from every stargate, a ship landing at the station docks
+//! right away and is never visible on a gate, which is what unattended overnight
+//! hauls want to end their jumps in when something goes wrong.
+use std::collections::HashSet;
+
+use crate::navigation::{Cost, CostFunction};
+use crate::types;
+
+/// Objects closer than this are assumed to share a grid.
+const GRID_SIZE: types::Meters = types::Meters(8_000_000.0);
+/// Entering a system without an instadock station costs as much as this many extra
+/// jumps by default.
+const DEFAULT_PENALTY: Cost = 5;
+
+impl types::Universe {
+    /// The NPC stations of a system that are off-grid from every stargate. Requires
+    /// celestials; stargates are taken from both the celestials and the stargates
+    /// loaded by the data source.
+    pub fn instadock_stations(&self, system: &types::SystemId) -> Vec<&types::Celestial> {
+        let celestials = self.celestials_in(system);
+        let gates = celestials
+            .iter()
+            .filter(|c| c.kind == types::CelestialKind::Stargate)
+            .map(|c| &c.coordinate)
+            .chain(self.stargates_in(system).iter().map(|g| &g.coordinate))
+            .collect::<Vec<_>>();
+        celestials
+            .iter()
+            .filter(|c| c.kind == types::CelestialKind::Station)
+            .filter(|station| {
+                gates
+                    .iter()
+                    .all(|gate| types::Meters::from(station.distance_to(gate)).0 > GRID_SIZE.0)
+            })
+            .collect()
+    }
+
+    pub fn has_instadock(&self, system: &types::SystemId) -> bool {
+        !self.instadock_stations(system).is_empty()
+    }
+}
+
+/// Wraps a cost function and penalizes entering systems without an instadock station,
+/// so that unattended hauls stay in systems they can safely dock up in. The systems
+/// are determined once, when the preference is created.
+///
+/// # Example
+/// ```
+/// use neweden::builder::UniverseBuilder;
+/// use neweden::navigation::{InstadockPreference, Preference};
+///
+/// let universe = UniverseBuilder::new().build();
+/// // pass to PathBuilder::cost_function
+/// let _cost = InstadockPreference::new(Preference::Highsec, &universe).penalty(20);
+/// ```
+pub struct InstadockPreference<C> {
+    inner: C,
+    systems: HashSet<types::SystemId>,
+    penalty: Cost,
+}
+
+impl<C: CostFunction> InstadockPreference<C> {
+    pub fn new(inner: C, universe: &types::Universe) -> Self {
+        let systems = universe
+            .celestials
+            .keys()
+            .filter(|id| universe.has_instadock(id))
+            .copied()
+            .collect();
+        Self {
+            inner,
+            systems,
+            penalty: DEFAULT_PENALTY,
+        }
+    }
+
+    /// The cost added for entering a system without an instadock station.
+    pub fn penalty(mut self, penalty: Cost) -> Self {
+        self.penalty = penalty;
+        self
+    }
+}
+
+impl<C: CostFunction> CostFunction for InstadockPreference<C> {
+    fn cost(
+        &self,
+        universe: &dyn types::Navigatable,
+        connection: &types::Connection,
+    ) -> Option<Cost> {
+        let base = self.inner.cost(universe, connection)?;
+        if self.systems.contains(&connection.to) {
+            Some(base)
+        } else {
+            Some(base + self.penalty)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::UniverseBuilder;
+    use crate::navigation::{PathBuilder, Preference};
+    use crate::types::Navigatable;
+
+    #[test]
+    fn test_instadock() {
+        let origin = types::Coordinate {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        let system = |id: u32| types::System::new(id.into(), "", origin.clone(), 0.5.into());
+        let gate = |from: u32, to: u32| types::Connection {
+            from: from.into(),
+            to: to.into(),
+            type_: types::ConnectionType::Stargate(types::StargateType::Local),
+            source: types::ConnectionSource::Sde,
+        };
+        let celestial = |id: u32, system: u32, kind, au: f64| types::Celestial {
+            id: id.into(),
+            name: String::new(),
+            system: system.into(),
+            kind,
+            coordinate: types::Coordinate {
+                x: types::Meters::from(types::Au(au)).0,
+                ..origin.clone()
+            },
+        };
+        // 1 - 2 - 3 and 1 - 4 - 3, the station in 2 shares the grid with a gate
+        let universe = UniverseBuilder::new()
+            .system(system(1))
+            .system(system(2))
+            .system(system(3))
+            .system(system(4))
+            .connection_bidirectional(gate(1, 2))
+            .connection_bidirectional(gate(2, 3))
+            .connection_bidirectional(gate(1, 4))
+            .connection_bidirectional(gate(4, 3))
+            .celestial(celestial(50000001, 2, types::CelestialKind::Stargate, 1.0))
+            .celestial(celestial(
+                60000001,
+                2,
+                types::CelestialKind::Station,
+                1.00001,
+            ))
+            .celestial(celestial(50000002, 4, types::CelestialKind::Stargate, 1.0))
+            .celestial(celestial(60000002, 4, types::CelestialKind::Station, 4.0))
+            .build();
+        assert!(!universe.has_instadock(&2.into()));
+        assert_eq!(
+            vec![types::CelestialId(60000002)],
+            universe
+                .instadock_stations(&4.into())
+                .iter()
+                .map(|c| c.id)
+                .collect::<Vec<_>>()
+        );
+
+        let path = PathBuilder::new(&universe)
+            .waypoint(universe.get_system(&1.into()).unwrap())
+            .waypoint(universe.get_system(&3.into()).unwrap())
+            .cost_function(InstadockPreference::new(Preference::Shortest, &universe))
+            .build()
+            .unwrap();
+        assert_eq!(
+            vec![1, 4, 3],
+            path.systems().map(|s| s.id.0).collect::<Vec<_>>()
+        );
+    }
+}
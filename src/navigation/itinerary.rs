@@ -0,0 +1,113 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Gate-to-gate itineraries for routes through a universe with stargates loaded.
+use crate::navigation::Path;
+use crate::types;
+
+/// A system on a route together with the gates it is entered and left through.
+#[derive(Debug, Clone)]
+pub struct GateHop<'a> {
+    pub system: &'a types::System,
+    /// The gate the system is entered through. `None` for the first system, or when
+    /// the system is not entered through a stargate.
+    pub entry: Option<&'a types::Stargate>,
+    /// The gate the system is left through. `None` for the last system, or when the
+    /// system is not left through a stargate.
+    pub exit: Option<&'a types::Stargate>,
+}
+
+impl GateHop<'_> {
+    /// The in-system warp from the entry to the exit gate.
+    pub fn warp_distance(&self) -> Option<types::Au> {
+        Some(self.entry?.distance_to(&self.exit?.coordinate))
+    }
+}
+
+/// Resolves the stargates used along a path. Connections that are not stargates,
+/// or stargates unknown to the universe, leave the corresponding gates empty.
+///
+/// # Example
+/// ```no_run
+/// # #[cfg(feature = "sqlite")]
+/// # fn main() {
+/// use neweden::navigation::{itinerary, PathBuilder};
+/// use neweden::source::sqlite::DatabaseBuilder;
+/// use neweden::Navigatable;
+///
+/// let uri = std::env::var("SQLITE_URI").unwrap();
+/// let universe = DatabaseBuilder::new(&uri).stargates(true).build().unwrap();
+/// let path = PathBuilder::new(&universe)
+///     .waypoint(universe.get_system(&30000142.into()).unwrap()) // Jita
+///     .waypoint(universe.get_system(&30002187.into()).unwrap()) // Amarr
+///     .build()
+///     .unwrap();
+/// for hop in itinerary::gate_itinerary(&universe, &path) {
+///     println!("{}: {:?}", hop.system.name, hop.warp_distance());
+/// }
+/// # }
+/// # #[cfg(not(feature = "sqlite"))]
+/// # fn main() {}
+/// ```
+pub fn gate_itinerary<'a>(universe: &'a types::Universe, path: &Path) -> Vec<GateHop<'a>> {
+    let systems: Vec<_> = path
+        .systems()
+        .filter_map(|s| types::Navigatable::get_system(universe, &s.id))
+        .collect();
+
+    let mut hops: Vec<GateHop<'a>> = Vec::with_capacity(systems.len());
+    for (i, system) in systems.iter().enumerate() {
+        let entry = hops.last().and_then(|previous| {
+            let destination = previous.exit?.destination;
+            universe
+                .stargates_in(&system.id)
+                .iter()
+                .find(|g| g.id == destination)
+        });
+        let exit = systems
+            .get(i + 1)
+            .and_then(|next| universe.stargate_between(&system.id, &next.id));
+        hops.push(GateHop {
+            system,
+            entry,
+            exit,
+        });
+    }
+    hops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation::PathBuilder;
+    use crate::testing::*;
+    use crate::types::Navigatable;
+
+    #[test]
+    fn test_gate_itinerary() {
+        let universe = universe();
+        let path = PathBuilder::new(&universe)
+            .waypoint(universe.get_system(&ALPHA).unwrap())
+            .waypoint(universe.get_system(&CHARLIE).unwrap())
+            .build()
+            .unwrap();
+        let hops = gate_itinerary(&universe, &path);
+        assert_eq!(
+            vec![ALPHA, BRAVO, CHARLIE],
+            hops.iter().map(|h| h.system.id).collect::<Vec<_>>()
+        );
+
+        assert!(hops[0].entry.is_none());
+        assert_eq!(types::CelestialId(50000001), hops[0].exit.unwrap().id);
+        assert!(hops[0].warp_distance().is_none());
+
+        assert_eq!(types::CelestialId(50000002), hops[1].entry.unwrap().id);
+        assert_eq!(types::CelestialId(50000003), hops[1].exit.unwrap().id);
+        assert!((hops[1].warp_distance().unwrap().0 - 5.0).abs() < 1e-9);
+
+        assert_eq!(types::CelestialId(50000004), hops[2].entry.unwrap().id);
+        assert!(hops[2].exit.is_none());
+    }
+}
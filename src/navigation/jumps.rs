@@ -0,0 +1,495 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Jump drive routes for capitals: a chain of cyno systems within jump range.
+use std::collections::{btree_map, BTreeMap, HashSet, VecDeque};
+
+use pathfinding::prelude::dijkstra;
+
+use crate::navigation::ShipProfile;
+use crate::rules;
+use crate::types;
+
+/// Jumps dominate the cost, distance only breaks ties. Distances are in thousandths
+/// of a lightyear.
+const JUMP_COST: u64 = 1_000_000_000;
+/// Randomized midpoints weigh every jump with up to this much extra distance.
+const RANDOM_SPREAD: f64 = 1.0;
+
+/// A jump route from the origin through the midpoints to the destination.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JumpPlan {
+    pub systems: Vec<types::SystemId>,
+}
+
+impl JumpPlan {
+    pub fn jumps(&self) -> usize {
+        self.systems.len().saturating_sub(1)
+    }
+
+    /// The systems between origin and destination, where cynos have to be lit.
+    pub fn midpoints(&self) -> &[types::SystemId] {
+        match self.systems.len() {
+            0..=2 => &[],
+            n => &self.systems[1..n - 1],
+        }
+    }
+
+    /// The systems a cyno has to be lit in, including the destination.
+    pub fn cyno_systems(&self) -> &[types::SystemId] {
+        self.systems.get(1..).unwrap_or_default()
+    }
+
+    /// The straight line distance covered by all jumps.
+    pub fn lightyears(&self, universe: &dyn types::Navigatable) -> types::Lightyears {
+        let meters = self
+            .systems
+            .windows(2)
+            .filter_map(|pair| {
+                let from = universe.get_system(&pair[0])?;
+                let to = universe.get_system(&pair[1])?;
+                Some(from.distance(to).0)
+            })
+            .sum();
+        types::Meters(meters).into()
+    }
+}
+
+/// Conditions the midpoints of a jump plan must meet besides allowing cynos, e.g.
+/// to keep a freighter out of hostile space between jumps. The destination is not
+/// constrained.
+///
+/// Sovereignty comes from `Navigatable::get_sovereignty` and stations from
+/// `Navigatable::get_celestials`, so load them, e.g. with
+/// `DatabaseBuilder::celestials`, before requiring stations.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MidpointConstraints {
+    lowsec_or_npc_nullsec: bool,
+    station: bool,
+    regions: HashSet<types::RegionId>,
+    alliances: HashSet<types::AllianceId>,
+}
+
+impl MidpointConstraints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only lowsec and NPC nullsec, i.e. no player held space.
+    pub fn lowsec_or_npc_nullsec(mut self) -> Self {
+        self.lowsec_or_npc_nullsec = true;
+        self
+    }
+
+    /// Only systems with an NPC station to dock in.
+    pub fn station(mut self) -> Self {
+        self.station = true;
+        self
+    }
+
+    pub fn avoid_region(mut self, region: types::RegionId) -> Self {
+        self.regions.insert(region);
+        self
+    }
+
+    /// No systems held by the alliance.
+    pub fn avoid_alliance(mut self, alliance: types::AllianceId) -> Self {
+        self.alliances.insert(alliance);
+        self
+    }
+
+    /// Whether the system may be a midpoint. Cynos are checked by the planner.
+    pub fn allows(&self, universe: &dyn types::Navigatable, system: &types::System) -> bool {
+        let sovereignty = universe.get_sovereignty(&system.id);
+        if self.lowsec_or_npc_nullsec {
+            let npc = system.faction.is_some() || sovereignty.is_some_and(|s| s.faction.is_some());
+            match types::SecurityClass::from(system.security) {
+                types::SecurityClass::Lowsec => {}
+                types::SecurityClass::Nullsec if npc => {}
+                _ => return false,
+            }
+        }
+        if system.region.is_some_and(|r| self.regions.contains(&r)) {
+            return false;
+        }
+        if sovereignty
+            .and_then(|s| s.alliance)
+            .is_some_and(|a| self.alliances.contains(&a))
+        {
+            return false;
+        }
+        !self.station
+            || universe
+                .get_celestials(&system.id)
+                .iter()
+                .any(|c| c.kind == types::CelestialKind::Station)
+    }
+}
+
+/// Finds jump plans with the fewest jumps, and among those the shortest distance.
+/// Only systems that allow cynos are used as midpoints and destination.
+///
+/// # Example
+/// ```
+/// use neweden::builder::UniverseBuilder;
+/// use neweden::navigation::jumps::JumpPlanner;
+/// use neweden::Lightyears;
+///
+/// # let universe = UniverseBuilder::new().build();
+/// let planner = JumpPlanner::new(&universe, Lightyears(7.0)).randomize_midpoints(42);
+/// assert!(planner.plan(&30000142.into(), &30004759.into()).is_none());
+/// ```
+pub struct JumpPlanner<'a> {
+    universe: &'a dyn types::Navigatable,
+    range: types::Lightyears,
+    seed: Option<u64>,
+    constraints: MidpointConstraints,
+}
+
+impl<'a> JumpPlanner<'a> {
+    pub fn new(universe: &'a dyn types::Navigatable, range: types::Lightyears) -> Self {
+        Self {
+            universe,
+            range,
+            seed: None,
+            constraints: MidpointConstraints::default(),
+        }
+    }
+
+    /// A planner for the jump drive of the ship, or `None` if it has none.
+    pub fn for_ship(universe: &'a dyn types::Navigatable, ship: &ShipProfile) -> Option<Self> {
+        Some(Self::new(universe, ship.jump_range()?))
+    }
+
+    /// Picks different midpoints for different seeds. The number of jumps stays
+    /// minimal, but the distance may grow, trading fuel for unpredictability.
+    pub fn randomize_midpoints(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Only use midpoints meeting the constraints.
+    pub fn midpoints(mut self, constraints: MidpointConstraints) -> Self {
+        self.set_midpoints(constraints);
+        self
+    }
+
+    pub fn set_midpoints(&mut self, constraints: MidpointConstraints) -> &mut Self {
+        self.constraints = constraints;
+        self
+    }
+
+    pub fn plan(&self, from: &types::SystemId, to: &types::SystemId) -> Option<JumpPlan> {
+        let start = self.universe.get_system(from)?;
+        let (systems, _) = dijkstra(
+            &start,
+            |system| {
+                self.in_range(&system.id)
+                    .into_iter()
+                    .filter(|next| next.id == *to || self.is_midpoint(next))
+                    .map(|next| (next, self.cost(system, next)))
+                    .collect::<Vec<_>>()
+            },
+            |system| system.id == *to,
+        )?;
+        Some(JumpPlan {
+            systems: systems.into_iter().map(|s| s.id).collect(),
+        })
+    }
+
+    /// The systems reachable from `origin` in at most `max_jumps` jumps, with the
+    /// fewest jumps needed. The origin is included with zero jumps. Systems that
+    /// are not valid midpoints are reached, but not jumped on from.
+    pub fn reachable(
+        &self,
+        origin: &types::SystemId,
+        max_jumps: usize,
+    ) -> BTreeMap<types::SystemId, usize> {
+        let mut reached = BTreeMap::new();
+        if self.universe.get_system(origin).is_none() {
+            return reached;
+        }
+        reached.insert(*origin, 0);
+        let mut queue = VecDeque::from([(*origin, 0)]);
+        while let Some((id, jumps)) = queue.pop_front() {
+            if jumps == max_jumps {
+                continue;
+            }
+            // systems reached are only jumped on from if they can be midpoints
+            if jumps > 0 && !self.is_midpoint(self.universe.get_system(&id).unwrap()) {
+                continue;
+            }
+            for next in self.in_range(&id) {
+                if let btree_map::Entry::Vacant(entry) = reached.entry(next.id) {
+                    entry.insert(jumps + 1);
+                    queue.push_back((next.id, jumps + 1));
+                }
+            }
+        }
+        reached
+    }
+
+    /// Which of the staging systems reach which targets in a single jump, e.g. to
+    /// pick a staging for an operation. A staging covers itself.
+    pub fn coverage(&self, stagings: &[types::SystemId], targets: &[types::SystemId]) -> Coverage {
+        let range = types::Meters::from(self.range);
+        let covers = |staging: &types::SystemId, target: &types::SystemId| {
+            if staging == target {
+                return true;
+            }
+            let (Some(from), Some(to)) = (
+                self.universe.get_system(staging),
+                self.universe.get_system(target),
+            ) else {
+                return false;
+            };
+            rules::allows_cynos(to) && from.distance(to).0 <= range.0
+        };
+        let targets = targets
+            .iter()
+            .map(|target| {
+                let covering = stagings
+                    .iter()
+                    .filter(|staging| covers(staging, target))
+                    .copied()
+                    .collect();
+                (*target, covering)
+            })
+            .collect();
+        Coverage {
+            stagings: stagings.to_vec(),
+            targets,
+        }
+    }
+
+    fn is_midpoint(&self, system: &types::System) -> bool {
+        self.constraints.allows(self.universe, system)
+    }
+
+    /// The systems one jump away that allow cynos.
+    fn in_range(&self, from: &types::SystemId) -> Vec<&'a types::System> {
+        self.universe
+            .get_systems_by_range(from, types::Meters::from(self.range))
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|next| next.id != *from && rules::allows_cynos(next))
+            .collect()
+    }
+
+    fn cost(&self, from: &types::System, to: &types::System) -> u64 {
+        let ly = types::Lightyears::from(from.distance(to)).0;
+        let factor = match self.seed {
+            Some(seed) => 1.0 + RANDOM_SPREAD * noise(seed, from.id, to.id),
+            None => 1.0,
+        };
+        JUMP_COST + (ly * factor * 1000.0).round() as u64
+    }
+}
+
+/// Which staging systems reach which targets, see `JumpPlanner::coverage`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Coverage {
+    stagings: Vec<types::SystemId>,
+    targets: BTreeMap<types::SystemId, Vec<types::SystemId>>,
+}
+
+impl Coverage {
+    /// The stagings reaching the target in one jump, in the order they were given.
+    pub fn stagings_for(&self, target: &types::SystemId) -> &[types::SystemId] {
+        self.targets
+            .get(target)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// The stagings reaching every target in one jump.
+    pub fn covering_all(&self) -> Vec<types::SystemId> {
+        self.stagings
+            .iter()
+            .filter(|staging| self.targets.values().all(|s| s.contains(staging)))
+            .copied()
+            .collect()
+    }
+
+    /// The targets no staging reaches.
+    pub fn uncovered(&self) -> Vec<types::SystemId> {
+        self.targets
+            .iter()
+            .filter(|(_, stagings)| stagings.is_empty())
+            .map(|(target, _)| *target)
+            .collect()
+    }
+}
+
+/// The systems a ship with a jump drive reaches from `origin` in at most
+/// `max_jumps` jumps, with the fewest jumps needed, or `None` if the hull has no
+/// jump drive. See `JumpPlanner::reachable`.
+pub fn reachable_within(
+    universe: &dyn types::Navigatable,
+    ship: types::HullClass,
+    skills: types::JumpdriveSkills,
+    origin: &types::SystemId,
+    max_jumps: usize,
+) -> Option<BTreeMap<types::SystemId, usize>> {
+    let range = ship.jump_drive(skills)?.into();
+    Some(JumpPlanner::new(universe, range).reachable(origin, max_jumps))
+}
+
+/// A deterministic value in [0, 1) for a seed and a jump.
+fn noise(seed: u64, from: types::SystemId, to: types::SystemId) -> f64 {
+    // splitmix64 over the seed and both ids
+    let mut z = seed ^ ((from.0 as u64) << 32 | to.0 as u64);
+    z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^= z >> 31;
+    (z >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+
+    #[test]
+    fn test_jump_planner() {
+        let universe = universe();
+        let planner = JumpPlanner::new(&universe, types::Lightyears(6.0));
+        let plan = planner.plan(&ALPHA, &ECHO).unwrap();
+        assert_eq!(vec![ALPHA, DELTA, ECHO], plan.systems);
+        assert_eq!(&[DELTA], plan.midpoints());
+        assert_eq!(&[DELTA, ECHO], plan.cyno_systems());
+        assert!((plan.lightyears(&universe).0 - 8.0).abs() < 1e-6);
+
+        // randomized plans keep the number of jumps
+        let midpoints = (0..32)
+            .map(|seed| {
+                let plan = JumpPlanner::new(&universe, types::Lightyears(6.0))
+                    .randomize_midpoints(seed)
+                    .plan(&ALPHA, &ECHO)
+                    .unwrap();
+                assert_eq!(2, plan.jumps());
+                plan.midpoints()[0]
+            })
+            .collect::<Vec<_>>();
+        assert!(midpoints.contains(&DELTA));
+        assert!(midpoints.contains(&HOTEL));
+
+        // Charlie is highsec
+        assert!(planner.plan(&ALPHA, &CHARLIE).is_none());
+
+        assert!(JumpPlanner::for_ship(&universe, &ShipProfile::default()).is_none());
+        // a jump freighter without skills reaches 5 lightyears
+        let freighter = ShipProfile::new(types::HullClass::Jumpfreighter);
+        let planner = JumpPlanner::for_ship(&universe, &freighter).unwrap();
+        assert_eq!(
+            vec![BRAVO, DELTA, ECHO],
+            planner.plan(&BRAVO, &ECHO).unwrap().systems
+        );
+    }
+
+    #[test]
+    fn test_reachable() {
+        let universe = universe();
+        let planner = JumpPlanner::new(&universe, types::Lightyears(6.0));
+        let reached = planner.reachable(&ALPHA, 1);
+        assert_eq!(
+            vec![(ALPHA, 0), (DELTA, 1), (HOTEL, 1)],
+            reached.into_iter().collect::<Vec<_>>()
+        );
+        assert_eq!(Some(&2), planner.reachable(&ALPHA, 2).get(&ECHO));
+        assert_eq!(1, planner.reachable(&ALPHA, 0).len());
+        assert!(planner.reachable(&types::SystemId(1), 3).is_empty());
+
+        // a carrier with jump drive calibration V reaches 7 lightyears
+        let reached = reachable_within(
+            &universe,
+            types::HullClass::Carrier,
+            types::JumpdriveSkills::new(5, 5),
+            &BRAVO,
+            1,
+        )
+        .unwrap();
+        assert_eq!(Some(&1), reached.get(&DELTA));
+        assert!(reachable_within(
+            &universe,
+            types::HullClass::Battleship,
+            types::JumpdriveSkills::new(5, 5),
+            &BRAVO,
+            1
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_coverage() {
+        let universe = universe();
+        let planner = JumpPlanner::new(&universe, types::Lightyears(6.0));
+        let coverage = planner.coverage(&[ALPHA, DELTA, HOTEL], &[DELTA, ECHO, CHARLIE]);
+        assert_eq!(&[ALPHA, DELTA, HOTEL], coverage.stagings_for(&DELTA));
+        assert_eq!(&[DELTA, HOTEL], coverage.stagings_for(&ECHO));
+        // Charlie is highsec
+        assert_eq!(vec![CHARLIE], coverage.uncovered());
+        assert!(coverage.covering_all().is_empty());
+
+        let coverage = planner.coverage(&[ALPHA, DELTA, HOTEL], &[DELTA, ECHO]);
+        assert_eq!(vec![DELTA, HOTEL], coverage.covering_all());
+    }
+
+    #[test]
+    fn test_midpoint_constraints() {
+        let universe = universe();
+        let planner = || JumpPlanner::new(&universe, types::Lightyears(6.0));
+        let plan = |constraints: MidpointConstraints| {
+            planner()
+                .midpoints(constraints)
+                .plan(&ALPHA, &ECHO)
+                .map(|p| p.systems)
+        };
+        assert_eq!(
+            Some(vec![ALPHA, DELTA, ECHO]),
+            plan(MidpointConstraints::new())
+        );
+        // Delta and Echo are in the south, Hotel is not
+        assert_eq!(
+            Some(vec![ALPHA, HOTEL, ECHO]),
+            plan(MidpointConstraints::new().avoid_region(SOUTH))
+        );
+        // both are lowsec
+        assert_eq!(
+            Some(vec![ALPHA, DELTA, ECHO]),
+            plan(MidpointConstraints::new().lowsec_or_npc_nullsec())
+        );
+        // only Alpha has celestials
+        assert_eq!(None, plan(MidpointConstraints::new().station()));
+
+        let alliance = types::AllianceId(99000001);
+        let held = universe.with_sovereignty(
+            vec![(
+                DELTA,
+                types::Sovereignty {
+                    alliance: Some(alliance),
+                    ..Default::default()
+                },
+            )]
+            .into(),
+        );
+        let planner = JumpPlanner::new(&held, types::Lightyears(6.0))
+            .midpoints(MidpointConstraints::new().avoid_alliance(alliance));
+        assert_eq!(
+            vec![ALPHA, HOTEL, ECHO],
+            planner.plan(&ALPHA, &ECHO).unwrap().systems
+        );
+        // the destination is not a midpoint
+        assert_eq!(
+            vec![ALPHA, DELTA],
+            planner.plan(&ALPHA, &DELTA).unwrap().systems
+        );
+        // Delta is reached, but not jumped on from
+        let reached = planner.reachable(&ALPHA, 2);
+        assert_eq!(Some(&1), reached.get(&DELTA));
+        assert_eq!(Some(&2), reached.get(&ECHO));
+    }
+}
@@ -0,0 +1,186 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Learning a pilot's route choices and biasing future routes towards them.
+use std::collections::{HashMap, HashSet};
+
+use crate::navigation::{Cost, CostFunction, Path};
+use crate::types;
+
+/// `PersonalizedPreference` scales costs by this factor so that preferred systems
+/// can be made cheaper than the base cost of 1.
+const COST_SCALE: f32 = 10.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Choices {
+    taken: u32,
+    avoided: u32,
+}
+
+/// Remembers which of several offered routes a pilot actually took.
+///
+/// Only systems that distinguish the routes are learned: a system on the chosen
+/// route but missing from an alternative counts as taken, a system on an alternative
+/// but not on the chosen route counts as avoided. Over time this reveals habits like
+/// always going around a particular pipe. With the `serde` feature the memory can be
+/// stored between sessions.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RouteMemory {
+    systems: HashMap<types::SystemId, Choices>,
+    decisions: u32,
+}
+
+impl RouteMemory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that the pilot took `chosen` when offered `offered`. The offered routes
+    /// may include the chosen one.
+    pub fn record(&mut self, chosen: &Path, offered: &[Path]) {
+        let chosen_systems = chosen.systems().map(|s| s.id).collect::<HashSet<_>>();
+        let mut taken = HashSet::new();
+        let mut avoided = HashSet::new();
+        for alternative in offered {
+            let systems = alternative.systems().map(|s| s.id).collect::<HashSet<_>>();
+            taken.extend(chosen_systems.difference(&systems).copied());
+            avoided.extend(systems.difference(&chosen_systems).copied());
+        }
+        for id in taken {
+            self.systems.entry(id).or_default().taken += 1;
+        }
+        for id in avoided {
+            self.systems.entry(id).or_default().avoided += 1;
+        }
+        self.decisions += 1;
+    }
+
+    /// The number of recorded decisions.
+    pub fn decisions(&self) -> u32 {
+        self.decisions
+    }
+
+    /// How much the pilot likes a system, from -1.0 (always avoided when there was
+    /// a choice) to 1.0 (always taken). Unknown systems are neutral.
+    pub fn affinity(&self, system: &types::SystemId) -> f32 {
+        match self.systems.get(system) {
+            Some(c) if c.taken + c.avoided > 0 => {
+                (c.taken as f32 - c.avoided as f32) / (c.taken + c.avoided) as f32
+            }
+            _ => 0.0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.systems.clear();
+        self.decisions = 0;
+    }
+}
+
+/// Wraps a cost function and biases it towards the pilot's revealed preferences.
+///
+/// Entering a system the pilot always avoided costs up to `1 + strength` times the
+/// base cost, entering a system they always took as little as `1 - strength / 2`
+/// times. All costs are scaled by 10 to make the discount expressible.
+///
+/// # Example
+/// ```
+/// use neweden::navigation::{PersonalizedPreference, Preference, RouteMemory};
+///
+/// let memory = RouteMemory::new();
+/// // pass to PathBuilder::cost_function
+/// let _cost = PersonalizedPreference::new(Preference::Shortest, &memory).strength(2.0);
+/// ```
+pub struct PersonalizedPreference<'m, C> {
+    inner: C,
+    memory: &'m RouteMemory,
+    strength: f32,
+}
+
+impl<'m, C: CostFunction> PersonalizedPreference<'m, C> {
+    pub fn new(inner: C, memory: &'m RouteMemory) -> Self {
+        Self {
+            inner,
+            memory,
+            strength: 1.0,
+        }
+    }
+
+    /// How strongly the memory bends routes. 0.0 disables the personalization.
+    pub fn strength(mut self, strength: f32) -> Self {
+        self.strength = strength.max(0.0);
+        self
+    }
+}
+
+impl<C: CostFunction> CostFunction for PersonalizedPreference<'_, C> {
+    fn cost(
+        &self,
+        universe: &dyn types::Navigatable,
+        connection: &types::Connection,
+    ) -> Option<Cost> {
+        let base = self.inner.cost(universe, connection)? as f32 * COST_SCALE;
+        let affinity = self.memory.affinity(&connection.to);
+        let factor = if affinity < 0.0 {
+            1.0 - affinity * self.strength
+        } else {
+            (1.0 - affinity * self.strength / 2.0).max(0.1)
+        };
+        // dijkstra requires positive costs
+        Some(((base * factor).round() as Cost).max(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation::{PathBuilder, Preference};
+    use crate::testing::*;
+    use crate::types::Navigatable;
+
+    #[test]
+    fn test_personalized_preference() {
+        let universe = universe();
+        let system = |id| universe.get_system(&id).unwrap();
+        let shortest = PathBuilder::new(&universe)
+            .waypoint(system(ALPHA))
+            .waypoint(system(DELTA))
+            .build()
+            .unwrap();
+        let detour = PathBuilder::new(&universe)
+            .waypoints(vec![system(ALPHA), system(FOXTROT), system(DELTA)])
+            .build()
+            .unwrap();
+
+        let offered = [shortest, detour];
+        let mut memory = RouteMemory::new();
+        for _ in 0..3 {
+            memory.record(&offered[1], &offered);
+        }
+        assert_eq!(3, memory.decisions());
+        assert_eq!(-1.0, memory.affinity(&CHARLIE));
+        assert_eq!(1.0, memory.affinity(&GOLF));
+        assert_eq!(0.0, memory.affinity(&BRAVO));
+
+        let path = PathBuilder::new(&universe)
+            .waypoint(system(ALPHA))
+            .waypoint(system(DELTA))
+            .cost_function(PersonalizedPreference::new(Preference::Shortest, &memory))
+            .build()
+            .unwrap();
+        assert_eq!(4, path.jumps());
+        assert!(path.systems().all(|s| s.id != CHARLIE));
+
+        let path = PathBuilder::new(&universe)
+            .waypoint(system(ALPHA))
+            .waypoint(system(DELTA))
+            .cost_function(PersonalizedPreference::new(Preference::Shortest, &memory).strength(0.0))
+            .build()
+            .unwrap();
+        assert_eq!(3, path.jumps());
+    }
+}
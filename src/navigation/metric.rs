@@ -0,0 +1,120 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! A composite distance metric that puts gate and jump drive routes on a common scale.
+use crate::navigation::{Cost, CostFunction, Path};
+use crate::types;
+
+/// Resolution of `EffectiveDistance` when used as a cost function. Costs are integers,
+/// so the metric is scaled to keep fractions of a lightyear significant.
+const COST_SCALE: f64 = 1000.0;
+
+/// Blends the number of jumps and the lightyears travelled into a single value:
+/// `jump_weight * jumps + lightyear_weight * lightyears`.
+///
+/// Gate routes have many jumps over short distances while jump drive routes have few
+/// jumps over long distances. Weighting both lets a planner compare them, and as a
+/// `CostFunction` it lets the router optimize for the blend directly.
+///
+/// # Example
+/// ```
+/// use neweden::navigation::EffectiveDistance;
+///
+/// // a jump is worth as much as travelling two lightyears
+/// let metric = EffectiveDistance::new(2.0, 1.0);
+/// assert_eq!(7.0, metric.value(2, 3.0));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EffectiveDistance {
+    pub jump_weight: f64,
+    pub lightyear_weight: f64,
+}
+
+impl Default for EffectiveDistance {
+    /// Counts jumps only.
+    fn default() -> Self {
+        Self::new(1.0, 0.0)
+    }
+}
+
+impl EffectiveDistance {
+    pub fn new(jump_weight: f64, lightyear_weight: f64) -> Self {
+        Self {
+            jump_weight,
+            lightyear_weight,
+        }
+    }
+
+    pub fn value(&self, jumps: usize, lightyears: f64) -> f64 {
+        self.jump_weight * jumps as f64 + self.lightyear_weight * lightyears
+    }
+}
+
+impl CostFunction for EffectiveDistance {
+    fn cost(
+        &self,
+        universe: &dyn types::Navigatable,
+        connection: &types::Connection,
+    ) -> Option<Cost> {
+        let from = universe.get_system(&connection.from)?;
+        let to = universe.get_system(&connection.to)?;
+        let ly = types::Lightyears::from(from.distance(to)).0;
+        // dijkstra requires positive costs
+        Some(((self.value(1, ly) * COST_SCALE).round() as Cost).max(1))
+    }
+}
+
+impl Path<'_> {
+    /// The straight line distance covered by all jumps of the path.
+    pub fn lightyears(&self) -> types::Lightyears {
+        let systems: Vec<_> = self.systems().collect();
+        let meters = systems
+            .windows(2)
+            .map(|pair| pair[0].distance(pair[1]).0)
+            .sum();
+        types::Meters(meters).into()
+    }
+
+    /// The path's value under the given metric.
+    pub fn metric(&self, metric: &EffectiveDistance) -> f64 {
+        metric.value(self.jumps(), self.lightyears().0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation::PathBuilder;
+    use crate::testing::*;
+    use crate::types::Navigatable;
+
+    #[test]
+    fn test_metric() {
+        let universe = universe();
+        // a wormhole from Alpha to Hotel saves a jump to Golf, but is a long way round
+        let wormhole = types::Connection {
+            from: ALPHA,
+            to: HOTEL,
+            type_: types::ConnectionType::Wormhole(types::WormholeType::Small),
+            source: types::ConnectionSource::UserOverlay,
+        };
+        let extended = universe.extend(vec![wormhole].into());
+        let builder = PathBuilder::new(&extended)
+            .waypoint(universe.get_system(&ALPHA).unwrap())
+            .waypoint(universe.get_system(&GOLF).unwrap());
+
+        let jumps_only = builder.build().unwrap();
+        assert_eq!(2, jumps_only.jumps());
+        assert_eq!(2.0, jumps_only.metric(&EffectiveDistance::default()));
+
+        let metric = EffectiveDistance::new(1.0, 1.0);
+        let path = builder.clone().cost_function(metric).build().unwrap();
+        assert_eq!(3, path.jumps());
+        assert!(path.systems().all(|s| s.id != HOTEL));
+        assert!((path.lightyears().0 - 6.0).abs() < 1e-9);
+        assert!((path.metric(&metric) - 9.0).abs() < 1e-9);
+        assert!(jumps_only.metric(&metric) > path.metric(&metric));
+    }
+}
@@ -0,0 +1,2039 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use pathfinding::prelude::dijkstra_all;
+
+use crate::metrics;
+use crate::types;
+
+pub mod avoid;
+mod cache;
+mod cancel;
+mod config;
+pub mod convoy;
+mod csr;
+pub mod danger;
+mod deepening;
+pub(crate) mod distance;
+mod dwell;
+mod eccentricity;
+#[cfg(feature = "esi")]
+pub mod esi;
+pub mod filaments;
+pub mod format;
+mod instadock;
+pub mod itinerary;
+pub mod jumps;
+mod memory;
+mod metric;
+pub mod monitor;
+mod overlays;
+mod owned;
+pub mod preprocessed;
+#[cfg(test)]
+mod proptests;
+mod router;
+pub mod security;
+pub mod shopping;
+pub mod sovereignty;
+pub mod tour;
+mod travel;
+
+pub use cache::RouteCache;
+pub use cancel::{CancellationToken, Deadline};
+pub use config::RouterConfig;
+pub use csr::CompactAdjacency;
+pub use deepening::SearchStrategy;
+pub use distance::{jump_distance, jump_distances};
+pub use dwell::{Dwell, LowsecWarpPenalty};
+pub use eccentricity::{diameter, eccentricities, eccentricity, estimate_diameter, Diameter};
+#[cfg(feature = "esi")]
+pub use esi::verify_against_esi;
+pub use instadock::InstadockPreference;
+pub use jumps::reachable_within;
+pub use memory::{PersonalizedPreference, RouteMemory};
+pub use metric::EffectiveDistance;
+pub use overlays::OverlaySet;
+pub use owned::{OwnedPath, OwnedPathElement};
+pub use router::Router;
+pub use travel::{ShipProfile, TravelTime};
+
+/// Errors returned by `PathBuilder::try_build`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum RouteError {
+    #[error("no route between the waypoints exists")]
+    NoRoute,
+    #[error("route computation was cancelled")]
+    Cancelled,
+    #[error("route computation exceeded its deadline")]
+    TimedOut,
+    /// The cheapest route under the preference or cost function is longer than
+    /// `PathBuilder::max_jumps` allows. Only with `Preference::Shortest` does this
+    /// mean that no route within the maximum exists, other preferences may have
+    /// passed over a shorter but more expensive one. Legs are not searched once the
+    /// maximum is exceeded, so for several legs `jumps` only counts those searched.
+    #[error("the cheapest route under the preference takes {jumps} jumps, more than the maximum of {max}")]
+    TooManyJumps { jumps: usize, max: usize },
+}
+
+/// The deadline is checked every this many expanded systems, as reading the clock
+/// on every expansion is measurable on long routes.
+const DEADLINE_CHECK_INTERVAL: u32 = 64;
+
+#[derive(Clone, PartialEq)]
+enum PathElementInternal {
+    Waypoint(types::SystemId),
+    System(types::SystemId),
+    Connection(types::ConnectionType, types::ConnectionSource),
+}
+
+impl PathElementInternal {
+    fn system(&self) -> Option<&types::SystemId> {
+        match self {
+            Self::Waypoint(id) | Self::System(id) => Some(id),
+            Self::Connection(..) => None,
+        }
+    }
+}
+
+pub enum PathElement<'a> {
+    Waypoint(&'a types::System),
+    System(&'a types::System),
+    /// A connection and where it came from, e.g. to tell users which hops of a route
+    /// rely on user-supplied wormholes.
+    Connection(types::ConnectionType, types::ConnectionSource),
+}
+
+pub struct Path<'a> {
+    cur: usize,
+    jump_count: usize,
+    path: Vec<PathElementInternal>,
+    universe: &'a dyn types::Navigatable,
+    waypoints: Vec<&'a types::System>,
+}
+
+impl<'a> Path<'a> {
+    pub(self) fn new(
+        universe: &'a dyn types::Navigatable,
+        waypoints: Vec<&'a types::System>,
+        path: Vec<PathElementInternal>,
+        jump_count: usize,
+    ) -> Self {
+        Self {
+            cur: 0,
+            jump_count,
+            path,
+            universe,
+            waypoints,
+        }
+    }
+
+    /// Reassembles a path from its elements, e.g. when reading it back.
+    pub(crate) fn from_elements(
+        universe: &'a dyn types::Navigatable,
+        waypoints: Vec<&'a types::System>,
+        elements: Vec<PathElement<'a>>,
+    ) -> Self {
+        let path = elements
+            .into_iter()
+            .map(|e| match e {
+                PathElement::Waypoint(s) => PathElementInternal::Waypoint(s.id),
+                PathElement::System(s) => PathElementInternal::System(s.id),
+                PathElement::Connection(type_, source) => {
+                    PathElementInternal::Connection(type_, source)
+                }
+            })
+            .collect::<Vec<_>>();
+        let jump_count = path
+            .iter()
+            .filter(|e| matches!(e, PathElementInternal::Connection(..)))
+            .count();
+        Self::new(universe, waypoints, path, jump_count)
+    }
+
+    pub fn jumps(&self) -> usize {
+        self.jump_count
+    }
+
+    /// The waypoints the path was built from, in the order they are visited.
+    pub fn waypoints(&self) -> &[&'a types::System] {
+        &self.waypoints
+    }
+
+    pub fn from(&self) -> Option<&'a types::System> {
+        let id = self.path.first()?;
+        match id {
+            PathElementInternal::Connection(..) => None,
+            PathElementInternal::System(id) => Some(self.universe.get_system(id).unwrap()),
+            PathElementInternal::Waypoint(id) => Some(self.universe.get_system(id).unwrap()),
+        }
+    }
+
+    pub fn to(&self) -> Option<&'a types::System> {
+        let id = self.path.last()?;
+        match id {
+            PathElementInternal::Connection(..) => None,
+            PathElementInternal::System(id) => Some(self.universe.get_system(id).unwrap()),
+            PathElementInternal::Waypoint(id) => Some(self.universe.get_system(id).unwrap()),
+        }
+    }
+
+    pub fn iter(&self) -> PathIterator<'_> {
+        self.into_iter()
+    }
+    pub fn systems(&self) -> impl Iterator<Item = &types::System> {
+        self.iter().filter_map(|f| match f {
+            PathElement::System(s) => Some(s),
+            PathElement::Waypoint(s) => Some(s),
+            _ => None,
+        })
+    }
+
+    /// Every jump of the path as the system it starts in, the connection taken and
+    /// the system it ends in, e.g. to render "A --(regional gate)--> B".
+    pub fn legs(
+        &self,
+    ) -> impl Iterator<Item = (&'a types::System, types::Connection, &'a types::System)> + '_ {
+        self.path.windows(3).filter_map(|window| {
+            let PathElementInternal::Connection(type_, source) = &window[1] else {
+                return None;
+            };
+            let (from, to) = (window[0].system()?, window[2].system()?);
+            let connection = types::Connection {
+                from: *from,
+                to: *to,
+                type_: type_.clone(),
+                source: *source,
+            };
+            Some((
+                self.universe.get_system(from)?,
+                connection,
+                self.universe.get_system(to)?,
+            ))
+        })
+    }
+
+    /// The systems to set as in-game waypoints so the autopilot flies exactly this
+    /// path, in order. The origin is left out as the pilot is already there.
+    pub fn to_esi_waypoints(&self) -> Vec<types::SystemId> {
+        self.systems().skip(1).map(|s| s.id).collect()
+    }
+
+    /// The rest of the path from `system` on, e.g. after the pilot jumped some of it,
+    /// with `system` as the first waypoint. `None` if the path does not pass the
+    /// system. A path passing it several times is split at the first pass.
+    pub fn remaining_from(&self, system: &types::SystemId) -> Option<Path<'a>> {
+        let position = self.path.iter().position(|e| e.system() == Some(system))?;
+        let (passed, rest) = self.path.split_at(position);
+        // the leg waypoints were merged when building and legs between equal waypoints
+        // have no elements, so the nth waypoint element is the nth run of equal
+        // consecutive waypoints
+        let runs = (0..self.waypoints.len())
+            .filter(|&i| i == 0 || self.waypoints[i - 1].id != self.waypoints[i].id)
+            .collect::<Vec<_>>();
+        let visited = passed
+            .iter()
+            .filter(|e| matches!(e, PathElementInternal::Waypoint(_)))
+            .count();
+        let next = match rest[0] {
+            PathElementInternal::Waypoint(_) => visited + 1,
+            _ => visited,
+        };
+        let mut waypoints = vec![self.universe.get_system(system).unwrap()];
+        let start = runs.get(next).copied().unwrap_or(self.waypoints.len());
+        waypoints.extend(&self.waypoints[start..]);
+        let mut path = vec![PathElementInternal::Waypoint(*system)];
+        path.extend_from_slice(&rest[1..]);
+        let jump_count = path
+            .iter()
+            .filter(|e| matches!(e, PathElementInternal::Connection(..)))
+            .count();
+        Some(Path::new(self.universe, waypoints, path, jump_count))
+    }
+
+    /// A hash of the sequence of systems, equal for paths through the same systems
+    /// regardless of the waypoints, connections or universe they were built with. It
+    /// is stable across processes and releases, so services can use it as a cache
+    /// key shared between users.
+    pub fn canonical_hash(&self) -> u64 {
+        // 64 bit FNV-1a
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for system in self.systems() {
+            for byte in system.id.0.to_le_bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        }
+        hash
+    }
+}
+
+pub struct PathIterator<'a> {
+    cur: usize,
+    path: &'a Path<'a>,
+}
+
+impl<'a> Iterator for PathIterator<'a> {
+    type Item = PathElement<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.cur >= self.path.path.len() {
+            return None;
+        }
+        let res = match &self.path.path[self.cur] {
+            PathElementInternal::Waypoint(id) => {
+                PathElement::Waypoint(self.path.universe.get_system(id).unwrap())
+            }
+            PathElementInternal::System(id) => {
+                PathElement::System(self.path.universe.get_system(id).unwrap())
+            }
+            PathElementInternal::Connection(type_, source) => {
+                PathElement::Connection(type_.clone(), *source)
+            }
+        };
+        self.cur += 1;
+        Some(res)
+    }
+}
+
+impl<'a> IntoIterator for &'a Path<'a> {
+    type Item = PathElement<'a>;
+    type IntoIter = PathIterator<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        PathIterator { cur: 0, path: self }
+    }
+}
+
+impl<'a> Iterator for Path<'a> {
+    type Item = PathElement<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.cur >= self.path.len() {
+            return None;
+        }
+        let res = match &self.path[self.cur] {
+            PathElementInternal::Waypoint(id) => {
+                PathElement::Waypoint(self.universe.get_system(id).unwrap())
+            }
+            PathElementInternal::System(id) => {
+                PathElement::System(self.universe.get_system(id).unwrap())
+            }
+            PathElementInternal::Connection(type_, source) => {
+                PathElement::Connection(type_.clone(), *source)
+            }
+        };
+        self.cur += 1;
+        Some(res)
+    }
+}
+
+/// The cost of a route as computed by a `CostFunction`.
+pub type Cost = u32;
+
+/// Wormholes past their end of life cost as much as a system of the wrong security.
+const END_OF_LIFE_PENALTY: Cost = 1000;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Preference {
+    #[default]
+    Shortest,
+    Highsec,
+    LowsecAndNullsec,
+    /// Avoid systems with recent kills reported by the `DangerProvider` set with
+    /// `PathBuilder::danger`. Without a provider this is the same as `Shortest`.
+    AvoidDanger,
+}
+
+impl Preference {
+    fn system_cost(&self, universe: &dyn types::Navigatable, to: types::SystemId) -> Cost {
+        match self {
+            // all are equal distance, danger is added by `DangerCost`
+            Self::Shortest | Self::AvoidDanger => 1,
+            Self::Highsec => {
+                // we must have positive weights
+                // security can go from -1.0 to 1.0
+                match universe.get_system(&to).unwrap().security.into() {
+                    types::SecurityClass::Highsec => 1,
+                    types::SecurityClass::Lowsec | types::SecurityClass::Nullsec => 1000,
+                }
+            }
+            Self::LowsecAndNullsec => match universe.get_system(&to).unwrap().security.into() {
+                types::SecurityClass::Highsec => 1000,
+                types::SecurityClass::Lowsec | types::SecurityClass::Nullsec => 1,
+            },
+        }
+    }
+}
+
+/// Computes the cost of traversing a connection during pathfinding. Costs must be
+/// positive. Returning `None` forbids using the connection altogether.
+///
+/// `Preference` implements this trait, and so does every closure taking a universe
+/// and a connection.
+///
+/// # Example
+/// ```
+/// use neweden::navigation::CostFunction;
+/// use neweden::{ConnectionType, Connection, Navigatable};
+///
+/// // avoid wormholes entirely, all other connections are equal
+/// let no_wormholes = |_: &dyn Navigatable, c: &Connection| match c.type_ {
+///     ConnectionType::Wormhole(_) => None,
+///     _ => Some(1),
+/// };
+/// let _: &dyn CostFunction = &no_wormholes;
+/// ```
+pub trait CostFunction {
+    fn cost(
+        &self,
+        universe: &dyn types::Navigatable,
+        connection: &types::Connection,
+    ) -> Option<Cost>;
+}
+
+impl CostFunction for Preference {
+    fn cost(
+        &self,
+        universe: &dyn types::Navigatable,
+        connection: &types::Connection,
+    ) -> Option<Cost> {
+        let end_of_life = universe
+            .get_connection_info(&connection.from, &connection.to)
+            .is_some_and(|info| info.end_of_life);
+        let penalty = if end_of_life { END_OF_LIFE_PENALTY } else { 0 };
+        Some(self.system_cost(universe, connection.to) + penalty)
+    }
+}
+
+impl<F> CostFunction for F
+where
+    F: Fn(&dyn types::Navigatable, &types::Connection) -> Option<Cost>,
+{
+    fn cost(
+        &self,
+        universe: &dyn types::Navigatable,
+        connection: &types::Connection,
+    ) -> Option<Cost> {
+        self(universe, connection)
+    }
+}
+
+/// Returns the cost of travelling from `origin` to every reachable system under the given
+/// cost function. The origin itself is included with a cost of zero.
+pub(crate) fn costs_from(
+    universe: &dyn types::Navigatable,
+    cost: &dyn CostFunction,
+    origin: types::SystemId,
+) -> HashMap<types::SystemId, Cost> {
+    let mut costs = dijkstra_all(&origin, |id: &types::SystemId| {
+        universe
+            .get_connections(id)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|conn| Some((conn.to, cost.cost(universe, conn)?)))
+            .collect::<Vec<_>>()
+    })
+    .into_iter()
+    .map(|(id, (_, cost))| (id, cost))
+    .collect::<HashMap<_, _>>();
+    costs.insert(origin, 0);
+    costs
+}
+
+/// The distance of `p` from the line segment between `a` and `b`.
+fn distance_to_segment(
+    p: &types::Coordinate,
+    a: &types::Coordinate,
+    b: &types::Coordinate,
+) -> types::Meters {
+    let ab = [b.x - a.x, b.y - a.y, b.z - a.z];
+    let ap = [p.x - a.x, p.y - a.y, p.z - a.z];
+    let length = ab.iter().map(|v| v * v).sum::<f64>();
+    let t = if length > 0.0 {
+        (ab.iter().zip(&ap).map(|(u, v)| u * v).sum::<f64>() / length).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let d = [ap[0] - t * ab[0], ap[1] - t * ab[1], ap[2] - t * ab[2]];
+    types::Meters(d.iter().map(|v| v * v).sum::<f64>().sqrt())
+}
+
+#[derive(Clone)]
+pub struct PathBuilder<'a> {
+    universe: &'a dyn types::Navigatable,
+    waypoints: Vec<&'a types::System>,
+    preference: Preference,
+    cost_function: Option<Rc<dyn CostFunction + 'a>>,
+    danger: Option<Rc<dyn danger::DangerProvider + 'a>>,
+    cancellation: Option<CancellationToken>,
+    deadline: Option<Deadline>,
+    ship_mass: Option<types::Kilotons>,
+    ship: Option<ShipProfile>,
+    max_detour: Option<types::Lightyears>,
+    strategy: SearchStrategy,
+    optimize_order: bool,
+    round_trip: bool,
+    max_jumps: Option<usize>,
+    min_security: Option<types::Security>,
+    max_security: Option<types::Security>,
+    overlays: Option<&'a OverlaySet>,
+    overlay_labels: Option<Vec<String>>,
+}
+
+impl<'a> PathBuilder<'a> {
+    pub fn new(universe: &'a dyn types::Navigatable) -> Self {
+        Self {
+            universe,
+            waypoints: vec![],
+            preference: Preference::Shortest,
+            cost_function: None,
+            danger: None,
+            cancellation: None,
+            deadline: None,
+            ship_mass: None,
+            ship: None,
+            max_detour: None,
+            strategy: SearchStrategy::Dijkstra,
+            optimize_order: false,
+            round_trip: false,
+            max_jumps: None,
+            min_security: None,
+            max_security: None,
+            overlays: None,
+            overlay_labels: None,
+        }
+    }
+
+    pub fn waypoint(mut self, system: &'a types::System) -> Self {
+        self.add_waypoint(system);
+        self
+    }
+
+    pub fn waypoints(mut self, systems: Vec<&'a types::System>) -> Self {
+        self.add_waypoints(systems);
+        self
+    }
+
+    pub fn prefer(mut self, preference: Preference) -> Self {
+        self.set_preference(preference);
+        self
+    }
+
+    /// Use a custom cost function instead of the preference.
+    pub fn cost_function(mut self, cost_function: impl CostFunction + 'a) -> Self {
+        self.set_cost_function(cost_function);
+        self
+    }
+
+    /// Where `Preference::AvoidDanger` gets the recent kills of systems from.
+    pub fn danger(mut self, provider: impl danger::DangerProvider + 'a) -> Self {
+        self.set_danger(provider);
+        self
+    }
+
+    /// Abandon the computation once the token is cancelled. Only `try_build` reports
+    /// the cancellation, `build` returns `None`.
+    pub fn cancel_on(mut self, token: CancellationToken) -> Self {
+        self.set_cancellation(Some(token));
+        self
+    }
+
+    /// Abandon the computation once the deadline has passed. Only `try_build` reports
+    /// the timeout, `build` returns `None`.
+    pub fn deadline(mut self, deadline: Deadline) -> Self {
+        self.set_deadline(Some(deadline));
+        self
+    }
+
+    /// Avoid wormholes too small for a ship of this mass, or without enough mass left
+    /// according to their `ConnectionInfo`. Wormholes of unknown size are still used.
+    /// Without a mass every wormhole is used.
+    pub fn ship_mass(mut self, mass: types::Kilotons) -> Self {
+        self.set_ship_mass(Some(mass));
+        self
+    }
+
+    /// Route for this ship: avoid wormholes it does not fit through, like `ship_mass`,
+    /// and systems it may not enter, e.g. highsec for capitals.
+    pub fn ship(mut self, ship: &ShipProfile) -> Self {
+        self.set_ship(Some(ship));
+        self
+    }
+
+    /// Only enter systems within this straight line distance of the line between the
+    /// two waypoints of a leg. This prunes geographic detours, e.g. when a custom cost
+    /// function makes far away systems look cheap, and shrinks the search.
+    pub fn max_detour(mut self, detour: types::Lightyears) -> Self {
+        self.set_max_detour(Some(detour));
+        self
+    }
+
+    /// Search with this strategy, e.g. to bound memory on constrained devices.
+    pub fn strategy(mut self, strategy: SearchStrategy) -> Self {
+        self.set_strategy(strategy);
+        self
+    }
+
+    /// Like `waypoint`, but for use when the builder is not consumed, e.g. when adding
+    /// waypoints in a loop.
+    ///
+    /// # Example
+    /// ```
+    /// use neweden::builder::UniverseBuilder;
+    /// use neweden::navigation::PathBuilder;
+    /// use neweden::{Coordinate, Navigatable, System};
+    ///
+    /// let universe = UniverseBuilder::new()
+    ///     .system(System::new(
+    ///         1.into(),
+    ///         "Lonely",
+    ///         Coordinate { x: 0.0, y: 0.0, z: 0.0 },
+    ///         0.5.into(),
+    ///     ))
+    ///     .build();
+    /// let mut builder = PathBuilder::new(&universe);
+    /// for id in [1, 1] {
+    ///     builder.add_waypoint(universe.get_system(&id.into()).unwrap());
+    /// }
+    /// assert_eq!(0, builder.build().unwrap().jumps());
+    /// ```
+    /// Visits the waypoints between the first and the last in the order taking the
+    /// fewest jumps instead of the given one, e.g. for the stops of courier
+    /// contracts. This is exact for up to 12 stops and a nearest neighbour heuristic
+    /// beyond.
+    pub fn optimize_waypoint_order(mut self) -> Self {
+        self.optimize_order = true;
+        self
+    }
+
+    /// Returns to the first waypoint after the last one, e.g. for patrol or mining
+    /// loops. With `optimize_waypoint_order` all other waypoints are reordered.
+    pub fn return_to_start(mut self) -> Self {
+        self.round_trip = true;
+        self
+    }
+
+    /// Fails with `RouteError::TooManyJumps` if the cheapest route under the
+    /// preference is longer. The maximum does not steer the search, so a longer
+    /// but cheaper route is rejected rather than traded for a shorter one.
+    pub fn max_jumps(mut self, jumps: usize) -> Self {
+        self.max_jumps = Some(jumps);
+        self
+    }
+
+    /// Only enters systems of at least this security, e.g. `Security(0.5)` for
+    /// highsec only. Securities are compared rounded, as in the game.
+    pub fn min_security(mut self, security: types::Security) -> Self {
+        self.min_security = Some(security);
+        self
+    }
+
+    /// Only enters systems of at most this security.
+    pub fn max_security(mut self, security: types::Security) -> Self {
+        self.max_security = Some(security);
+        self
+    }
+
+    /// Also route over the enabled overlays of `overlays`.
+    pub fn overlays(mut self, overlays: &'a OverlaySet) -> Self {
+        self.set_overlays(Some(overlays));
+        self
+    }
+
+    /// Route over only these overlays of the set passed to `overlays`, whether they
+    /// are enabled or not, e.g. `&["ansiblex"]` for a pilot without a titan.
+    pub fn using_overlays(mut self, labels: &[&str]) -> Self {
+        self.set_using_overlays(Some(labels));
+        self
+    }
+
+    pub fn add_waypoint(&mut self, system: &'a types::System) -> &mut Self {
+        self.waypoints.push(system);
+        self
+    }
+
+    pub fn add_waypoints(&mut self, systems: Vec<&'a types::System>) -> &mut Self {
+        self.waypoints.extend(systems);
+        self
+    }
+
+    pub fn clear_waypoints(&mut self) -> &mut Self {
+        self.waypoints.clear();
+        self
+    }
+
+    pub fn set_preference(&mut self, preference: Preference) -> &mut Self {
+        self.preference = preference;
+        self
+    }
+
+    pub fn set_cost_function(&mut self, cost_function: impl CostFunction + 'a) -> &mut Self {
+        self.cost_function = Some(Rc::new(cost_function));
+        self
+    }
+
+    /// Go back to computing costs from the preference.
+    pub fn clear_cost_function(&mut self) -> &mut Self {
+        self.cost_function = None;
+        self
+    }
+
+    pub fn set_danger(&mut self, provider: impl danger::DangerProvider + 'a) -> &mut Self {
+        self.danger = Some(Rc::new(provider));
+        self
+    }
+
+    pub fn clear_danger(&mut self) -> &mut Self {
+        self.danger = None;
+        self
+    }
+
+    pub fn set_cancellation(&mut self, token: Option<CancellationToken>) -> &mut Self {
+        self.cancellation = token;
+        self
+    }
+
+    pub fn set_deadline(&mut self, deadline: Option<Deadline>) -> &mut Self {
+        self.deadline = deadline;
+        self
+    }
+
+    pub fn set_ship_mass(&mut self, mass: Option<types::Kilotons>) -> &mut Self {
+        self.ship_mass = mass;
+        self
+    }
+
+    pub fn set_ship(&mut self, ship: Option<&ShipProfile>) -> &mut Self {
+        self.ship_mass = ship.map(|s| s.mass);
+        self.ship = ship.cloned();
+        self
+    }
+
+    pub fn set_max_detour(&mut self, detour: Option<types::Lightyears>) -> &mut Self {
+        self.max_detour = detour;
+        self
+    }
+
+    pub fn set_strategy(&mut self, strategy: SearchStrategy) -> &mut Self {
+        self.strategy = strategy;
+        self
+    }
+
+    pub fn set_optimize_waypoint_order(&mut self, optimize: bool) -> &mut Self {
+        self.optimize_order = optimize;
+        self
+    }
+
+    pub fn set_return_to_start(&mut self, round_trip: bool) -> &mut Self {
+        self.round_trip = round_trip;
+        self
+    }
+
+    pub fn set_max_jumps(&mut self, jumps: Option<usize>) -> &mut Self {
+        self.max_jumps = jumps;
+        self
+    }
+
+    pub fn set_min_security(&mut self, security: Option<types::Security>) -> &mut Self {
+        self.min_security = security;
+        self
+    }
+
+    pub fn set_max_security(&mut self, security: Option<types::Security>) -> &mut Self {
+        self.max_security = security;
+        self
+    }
+
+    pub fn set_overlays(&mut self, overlays: Option<&'a OverlaySet>) -> &mut Self {
+        self.overlays = overlays;
+        self
+    }
+
+    /// `None` uses the overlays enabled in the set.
+    pub fn set_using_overlays(&mut self, labels: Option<&[&str]>) -> &mut Self {
+        self.overlay_labels = labels.map(|labels| labels.iter().map(|l| l.to_string()).collect());
+        self
+    }
+
+    /// Computes the path. The builder is not consumed and can be tweaked and built again.
+    pub fn build(&self) -> Option<Path<'a>> {
+        self.try_build().ok()
+    }
+
+    // TODO: We need to include the Connection itself, otherwise connections can be
+    // ambiguous in the rare case that a wormhole leads to the same system next door.
+    // In practise it likely doesn't matter.
+    pub fn try_build(&self) -> Result<Path<'a>, RouteError> {
+        self.try_build_with(&mut Router::new())
+    }
+
+    /// Like `build`, but searches with the buffers of `router` instead of allocating.
+    pub fn build_with(&self, router: &mut Router) -> Option<Path<'a>> {
+        self.try_build_with(router).ok()
+    }
+
+    /// Like `try_build`, but searches with the buffers of `router` instead of
+    /// allocating. Reuse a router for many queries on the same universe.
+    pub fn try_build_with(&self, router: &mut Router) -> Result<Path<'a>, RouteError> {
+        let expansions = Cell::new(0u32);
+        let result = self.search(router, &expansions);
+        metrics::observe(metrics::ROUTE_SEARCH_SIZE, expansions.get() as f64);
+        metrics::increment(match &result {
+            Ok(_) => metrics::ROUTES_FOUND,
+            Err(RouteError::NoRoute | RouteError::TooManyJumps { .. }) => metrics::ROUTES_NOT_FOUND,
+            Err(_) => metrics::ROUTES_INTERRUPTED,
+        });
+        result
+    }
+
+    /// Computes the path, counting the expanded systems in `expansions`.
+    fn search(&self, router: &mut Router, expansions: &Cell<u32>) -> Result<Path<'a>, RouteError> {
+        let interrupted = Cell::new(None);
+        // starving the search of successors lets it terminate right away
+        let interrupt = || self.interrupt(expansions, &interrupted);
+        let mut danger_cost = None;
+        let cost_function = self.effective_cost(&mut danger_cost);
+        let leg: Cell<Option<(&types::System, &types::System)>> = Cell::new(None);
+        let admissible = |conn: &types::Connection| -> Option<Cost> {
+            if !self.fits(conn) || !self.within_detour(leg.get(), conn) {
+                return None;
+            }
+            cost_function.cost(self.universe, conn)
+        };
+        let successor = |id: &types::SystemId| -> Vec<(types::SystemId, router::Via, Cost)> {
+            if interrupt() {
+                return Vec::new();
+            }
+            self.successors(id, admissible)
+        };
+        // the compact adjacency knows nothing of the overlays
+        let compact = self
+            .universe
+            .compact_adjacency()
+            .filter(|_| self.overlays.is_none());
+
+        let mut waypoints = self.waypoints.clone();
+        if self.round_trip {
+            waypoints.extend(self.waypoints.first());
+        }
+        if self.optimize_order {
+            waypoints =
+                tour::order_waypoints(self.universe, &waypoints).ok_or(RouteError::NoRoute)?;
+        }
+
+        let mut jump_count = 0;
+        let mut result = Vec::new();
+        for systems_slice in waypoints.windows(2) {
+            let a = &systems_slice[0];
+            let b = &systems_slice[1];
+            leg.set(Some((a, b)));
+            // we operate only on system ids, or on the nodes of the compact adjacency
+            // if the universe has one
+            let nodes = compact
+                .filter(|_| self.strategy == SearchStrategy::Dijkstra)
+                .and_then(|graph| Some((graph, graph.node(&a.id)?, graph.node(&b.id)?)));
+            let found =
+                match nodes {
+                    Some((graph, from, to)) => router
+                        .route_dense(graph.len(), from, to, |node, successors| {
+                            if interrupt() {
+                                return;
+                            }
+                            successors.extend(graph.edges(node).filter_map(|(via, next, conn)| {
+                                Some((next, via, admissible(conn)?))
+                            }));
+                        })
+                        .map(|route| {
+                            route
+                                .into_iter()
+                                .map(|(node, via)| (graph.id(node), via))
+                                .collect::<Vec<_>>()
+                        }),
+                    None if self.strategy == SearchStrategy::IterativeDeepening => {
+                        deepening::route(a.id, b.id, successor)
+                    }
+                    None => router.route(a.id, b.id, successor),
+                };
+            if let Some(err) = interrupted.take() {
+                return Err(err);
+            }
+            if let Some(route) = found {
+                let mut previous = a.id;
+                // every leg but the first starts where the previous one ended
+                let skip = usize::from(!result.is_empty());
+                for (id, via) in route.into_iter().skip(skip) {
+                    if let Some(index) = via {
+                        let conn = self.resolve(&previous, index);
+                        result.push(PathElementInternal::Connection(conn.type_, conn.source));
+                        jump_count += 1;
+                    }
+                    previous = id;
+                    if id == a.id || id == b.id {
+                        result.push(PathElementInternal::Waypoint(id));
+                    } else {
+                        result.push(PathElementInternal::System(id));
+                    }
+                }
+            } else {
+                return Err(RouteError::NoRoute);
+            }
+            if let Some(max) = self.max_jumps.filter(|max| jump_count > *max) {
+                return Err(RouteError::TooManyJumps {
+                    jumps: jump_count,
+                    max,
+                });
+            }
+        }
+
+        Ok(Path::new(self.universe, waypoints, result, jump_count))
+    }
+
+    /// The route from `current` to the end of `path`, which was built earlier, e.g.
+    /// with this builder. While the pilot is on the path the rest of it is returned
+    /// without searching. Otherwise the route is computed with the settings of the
+    /// builder through the waypoints of `path` after its origin, as it is unknown
+    /// which of them were visited.
+    pub fn reroute(
+        &self,
+        path: &Path<'a>,
+        current: &'a types::System,
+    ) -> Result<Path<'a>, RouteError> {
+        if let Some(remaining) = path.remaining_from(&current.id) {
+            return Ok(remaining);
+        }
+        let mut builder = self.clone();
+        builder
+            .clear_waypoints()
+            .add_waypoint(current)
+            .add_waypoints(path.waypoints().iter().skip(1).copied().collect());
+        builder.try_build()
+    }
+
+    /// Routes from the first waypoint to the cheapest of `destinations` under the
+    /// settings of the builder. Further waypoints are ignored. With `max_jumps`, this
+    /// fails if the route to the cheapest destination is too long, even if another
+    /// destination is within the maximum.
+    pub fn to_any(&self, destinations: &[types::SystemId]) -> Result<Path<'a>, RouteError> {
+        let path = self
+            .fan_out(&mut Router::new(), destinations, true)?
+            .pop()
+            .ok_or(RouteError::NoRoute)?;
+        match self.max_jumps {
+            Some(max) if path.jumps() > max => Err(RouteError::TooManyJumps {
+                jumps: path.jumps(),
+                max,
+            }),
+            _ => Ok(path),
+        }
+    }
+
+    /// Routes from the first waypoint to each of `destinations` in a single search,
+    /// instead of building a path per destination. Unreachable destinations and
+    /// those further than `max_jumps` are left out. Further waypoints are ignored.
+    pub fn paths_to_all(
+        &self,
+        destinations: &[types::SystemId],
+    ) -> Result<HashMap<types::SystemId, Path<'a>>, RouteError> {
+        Ok(self
+            .fan_out(&mut Router::new(), destinations, false)?
+            .into_iter()
+            .filter(|path| self.max_jumps.is_none_or(|max| path.jumps() <= max))
+            .filter_map(|path| Some((path.to()?.id, path)))
+            .collect())
+    }
+
+    /// The paths from the first waypoint to the destinations in order of their
+    /// cost, only to the cheapest one if `first` is set.
+    fn fan_out(
+        &self,
+        router: &mut Router,
+        destinations: &[types::SystemId],
+        first: bool,
+    ) -> Result<Vec<Path<'a>>, RouteError> {
+        let origin = *self.waypoints.first().ok_or(RouteError::NoRoute)?;
+        let expansions = Cell::new(0u32);
+        let interrupted = Cell::new(None);
+        let mut danger_cost = None;
+        let cost_function = self.effective_cost(&mut danger_cost);
+        let routes = router.routes(origin.id, destinations, first, |id| {
+            if self.interrupt(&expansions, &interrupted) {
+                return Vec::new();
+            }
+            self.successors(id, |conn| {
+                if !self.fits(conn) {
+                    return None;
+                }
+                cost_function.cost(self.universe, conn)
+            })
+        });
+        if let Some(err) = interrupted.take() {
+            return Err(err);
+        }
+        routes
+            .into_iter()
+            .map(|route| {
+                let to = self.universe.get_system(&route.last().unwrap().0).unwrap();
+                let mut path = Vec::new();
+                let mut jump_count = 0;
+                let mut previous = origin.id;
+                for (id, via) in route {
+                    if let Some(index) = via {
+                        let conn = self.resolve(&previous, index);
+                        path.push(PathElementInternal::Connection(conn.type_, conn.source));
+                        jump_count += 1;
+                    }
+                    previous = id;
+                    if id == origin.id || id == to.id {
+                        path.push(PathElementInternal::Waypoint(id));
+                    } else {
+                        path.push(PathElementInternal::System(id));
+                    }
+                }
+                Ok(Path::new(self.universe, vec![origin, to], path, jump_count))
+            })
+            .collect()
+    }
+
+    /// Whether a search has to stop as it was cancelled or timed out, which is then
+    /// kept in `interrupted`. Counts the expansion.
+    fn interrupt(&self, expansions: &Cell<u32>, interrupted: &Cell<Option<RouteError>>) -> bool {
+        if let Some(token) = &self.cancellation {
+            if token.is_cancelled() {
+                interrupted.set(Some(RouteError::Cancelled));
+            }
+        }
+        let n = expansions.get();
+        expansions.set(n.wrapping_add(1));
+        if let Some(deadline) = &self.deadline {
+            if n.is_multiple_of(DEADLINE_CHECK_INTERVAL) && deadline.is_expired() {
+                interrupted.set(Some(RouteError::TimedOut));
+            }
+        }
+        interrupted.get().is_some()
+    }
+
+    /// The cost function searches use. A cost function built from the danger
+    /// provider is kept in `danger_cost`.
+    fn effective_cost<'s>(
+        &'s self,
+        danger_cost: &'s mut Option<danger::DangerCost<'s>>,
+    ) -> &'s dyn CostFunction {
+        match (&self.cost_function, &self.danger) {
+            (Some(f), _) => f.as_ref(),
+            (None, Some(danger)) if self.preference == Preference::AvoidDanger => {
+                danger_cost.insert(danger::DangerCost::new(danger.as_ref()))
+            }
+            (None, _) => &self.preference,
+        }
+    }
+
+    /// Calls `f` with the connections out of `id`, including those of the overlays
+    /// in use. The index of a connection in this order identifies it in a route.
+    fn for_each_connection(&self, id: &types::SystemId, f: &mut dyn FnMut(&types::Connection)) {
+        self.universe.for_each_connection(id, f);
+        if let Some(overlays) = self.overlays {
+            overlays.for_each_connection(id, self.overlay_labels.as_deref(), f);
+        }
+    }
+
+    /// The successors of `id` with the index of their connection, for connections
+    /// that `cost` admits.
+    fn successors(
+        &self,
+        id: &types::SystemId,
+        mut cost: impl FnMut(&types::Connection) -> Option<Cost>,
+    ) -> Vec<(types::SystemId, router::Via, Cost)> {
+        let mut successors = Vec::new();
+        let mut index = 0;
+        self.for_each_connection(id, &mut |conn| {
+            if let Some(cost) = cost(conn) {
+                successors.push((conn.to, index as router::Via, cost));
+            }
+            index += 1;
+        });
+        successors
+    }
+
+    /// The connection a route took out of `from`.
+    fn resolve(&self, from: &types::SystemId, index: router::Via) -> types::Connection {
+        let mut resolved = None;
+        let mut i = 0;
+        self.for_each_connection(from, &mut |conn| {
+            if i == index as usize {
+                resolved = Some(conn.clone());
+            }
+            i += 1;
+        });
+        resolved.expect("connection of a route")
+    }
+
+    fn within_detour(
+        &self,
+        leg: Option<(&types::System, &types::System)>,
+        connection: &types::Connection,
+    ) -> bool {
+        let (Some(detour), Some((a, b))) = (self.max_detour, leg) else {
+            return true;
+        };
+        let Some(to) = self.universe.get_system(&connection.to) else {
+            return true;
+        };
+        let distance = distance_to_segment(&to.coordinate, &a.coordinate, &b.coordinate);
+        distance.0 <= types::Meters::from(detour).0
+    }
+
+    fn fits(&self, connection: &types::Connection) -> bool {
+        if let Some(to) = self.universe.get_system(&connection.to) {
+            if self.ship.as_ref().is_some_and(|ship| !ship.may_enter(to)) {
+                return false;
+            }
+            let security = to.security.rounded();
+            if self
+                .min_security
+                .is_some_and(|min| security < min.rounded())
+                || self
+                    .max_security
+                    .is_some_and(|max| security > max.rounded())
+            {
+                return false;
+            }
+        }
+        match (&connection.type_, self.ship_mass) {
+            (types::ConnectionType::Wormhole(wormhole), Some(mass)) => {
+                let remaining = self
+                    .universe
+                    .get_connection_info(&connection.from, &connection.to)
+                    .and_then(|info| info.mass_remaining);
+                wormhole.fits(mass) && remaining.is_none_or(|remaining| mass <= remaining)
+            }
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+    use crate::types::Navigatable;
+
+    fn builder(universe: &types::Universe) -> PathBuilder<'_> {
+        PathBuilder::new(universe)
+            .waypoint(universe.get_system(&ALPHA).unwrap())
+            .waypoint(universe.get_system(&ECHO).unwrap())
+    }
+
+    #[test]
+    fn test_try_build() {
+        let universe = universe();
+        assert_eq!(4, builder(&universe).try_build().unwrap().jumps());
+    }
+
+    #[test]
+    fn test_remaining_from() {
+        let universe = universe();
+        let system = |id| universe.get_system(&id).unwrap();
+        let path = PathBuilder::new(&universe)
+            .waypoints(vec![system(ALPHA), system(CHARLIE), system(ECHO)])
+            .build()
+            .unwrap();
+
+        let remaining = path.remaining_from(&BRAVO).unwrap();
+        assert_eq!(3, remaining.jumps());
+        assert_eq!(BRAVO, remaining.from().unwrap().id);
+        assert_eq!(
+            vec![BRAVO, CHARLIE, ECHO],
+            remaining
+                .waypoints()
+                .iter()
+                .map(|s| s.id)
+                .collect::<Vec<_>>()
+        );
+        let remaining = path.remaining_from(&CHARLIE).unwrap();
+        assert_eq!(2, remaining.jumps());
+        assert_eq!(
+            vec![CHARLIE, ECHO],
+            remaining
+                .waypoints()
+                .iter()
+                .map(|s| s.id)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(0, path.remaining_from(&ECHO).unwrap().jumps());
+        assert!(path.remaining_from(&HOTEL).is_none());
+
+        // the legs between equal waypoints have no elements of their own
+        let path = PathBuilder::new(&universe)
+            .waypoints(vec![system(ALPHA), system(ALPHA), system(ECHO)])
+            .build()
+            .unwrap();
+        let waypoints = |remaining: Path| {
+            remaining
+                .waypoints()
+                .iter()
+                .map(|s| s.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            vec![CHARLIE, ECHO],
+            waypoints(path.remaining_from(&CHARLIE).unwrap())
+        );
+        assert_eq!(
+            vec![ALPHA, ECHO],
+            waypoints(path.remaining_from(&ALPHA).unwrap())
+        );
+        assert_eq!(vec![ECHO], waypoints(path.remaining_from(&ECHO).unwrap()));
+
+        let path = PathBuilder::new(&universe)
+            .waypoint(system(ALPHA))
+            .return_to_start()
+            .build()
+            .unwrap();
+        assert_eq!(vec![ALPHA], waypoints(path.remaining_from(&ALPHA).unwrap()));
+    }
+
+    #[test]
+    fn test_reroute() {
+        let universe = universe();
+        let builder = builder(&universe);
+        let path = builder.build().unwrap();
+        let on_path = builder
+            .reroute(&path, universe.get_system(&CHARLIE).unwrap())
+            .unwrap();
+        assert_eq!(2, on_path.jumps());
+        // Hotel is off the path, three jumps from Echo via Golf and Delta
+        let off_path = builder
+            .reroute(&path, universe.get_system(&HOTEL).unwrap())
+            .unwrap();
+        assert_eq!(
+            vec![HOTEL, GOLF, DELTA, ECHO],
+            off_path.systems().map(|s| s.id).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_optimize_waypoint_order() {
+        let universe = universe();
+        let system = |id| universe.get_system(&id).unwrap();
+        let builder = PathBuilder::new(&universe).waypoints(vec![
+            system(ALPHA),
+            system(DELTA),
+            system(FOXTROT),
+            system(ECHO),
+        ]);
+        assert_eq!(8, builder.build().unwrap().jumps());
+        let path = builder.optimize_waypoint_order().build().unwrap();
+        assert_eq!(5, path.jumps());
+        assert_eq!(
+            vec![ALPHA, FOXTROT, DELTA, ECHO],
+            path.waypoints().iter().map(|s| s.id).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_return_to_start() {
+        let universe = universe();
+        let system = |id| universe.get_system(&id).unwrap();
+        let path = PathBuilder::new(&universe)
+            .waypoints(vec![system(ALPHA), system(CHARLIE)])
+            .return_to_start()
+            .build()
+            .unwrap();
+        assert_eq!(4, path.jumps());
+        assert_eq!(
+            vec![ALPHA, BRAVO, CHARLIE, BRAVO, ALPHA],
+            path.systems().map(|s| s.id).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![ALPHA, CHARLIE, ALPHA],
+            path.waypoints().iter().map(|s| s.id).collect::<Vec<_>>()
+        );
+        assert_eq!(2, path.remaining_from(&CHARLIE).unwrap().jumps());
+
+        let builder = PathBuilder::new(&universe)
+            .waypoints(vec![
+                system(ALPHA),
+                system(DELTA),
+                system(FOXTROT),
+                system(CHARLIE),
+            ])
+            .return_to_start();
+        assert_eq!(9, builder.build().unwrap().jumps());
+        let path = builder.optimize_waypoint_order().build().unwrap();
+        assert_eq!(7, path.jumps());
+        assert_eq!(ALPHA, path.to().unwrap().id);
+
+        // a loop without other waypoints stays in place
+        let path = PathBuilder::new(&universe)
+            .waypoint(system(ALPHA))
+            .return_to_start()
+            .build()
+            .unwrap();
+        assert_eq!(0, path.jumps());
+        assert_eq!(1, path.systems().count());
+    }
+
+    #[test]
+    fn test_max_jumps() {
+        let universe = universe();
+        assert_eq!(4, builder(&universe).max_jumps(4).build().unwrap().jumps());
+        assert_eq!(
+            Some(RouteError::TooManyJumps { jumps: 4, max: 3 }),
+            builder(&universe).max_jumps(3).try_build().err()
+        );
+    }
+
+    #[test]
+    fn test_security_bounds() {
+        let universe = universe();
+        let system = |id| universe.get_system(&id).unwrap();
+        let systems = |builder: PathBuilder| {
+            builder
+                .build()
+                .map(|path| path.systems().map(|s| s.id).collect::<Vec<_>>())
+        };
+        // Golf is 0.5
+        let builder = PathBuilder::new(&universe)
+            .waypoint(system(FOXTROT))
+            .waypoint(system(CHARLIE))
+            .min_security(types::Security(0.6));
+        assert_eq!(Some(vec![FOXTROT, BRAVO, CHARLIE]), systems(builder));
+        let builder = PathBuilder::new(&universe)
+            .waypoint(system(ALPHA))
+            .waypoint(system(ECHO))
+            .min_security(types::Security(0.5));
+        assert_eq!(Some(RouteError::NoRoute), builder.try_build().err());
+
+        // the origin may be outside of the bounds
+        let builder = PathBuilder::new(&universe)
+            .waypoint(system(ECHO))
+            .waypoint(system(HOTEL))
+            .max_security(types::Security(0.5));
+        assert_eq!(
+            Some(vec![ECHO, DELTA, GOLF, HOTEL]),
+            systems(builder.clone())
+        );
+        assert_eq!(None, systems(builder.max_security(types::Security(0.4))));
+    }
+
+    #[test]
+    fn test_overlays() {
+        let universe = universe();
+        let bridge = |to| types::Connection {
+            from: ALPHA,
+            to,
+            type_: types::ConnectionType::Stargate(types::StargateType::Local),
+            source: types::ConnectionSource::UserOverlay,
+        };
+        let mut overlays = OverlaySet::new()
+            .overlay("ansiblex", vec![bridge(DELTA)])
+            .overlay("titan-bridge", vec![bridge(ECHO)]);
+        overlays.disable("titan-bridge");
+        let overlays = overlays;
+
+        let path = builder(&universe).overlays(&overlays).build().unwrap();
+        assert_eq!(
+            vec![ALPHA, DELTA, ECHO],
+            path.systems().map(|s| s.id).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            types::ConnectionSource::UserOverlay,
+            path.legs().next().unwrap().1.source
+        );
+        let jumps = |labels: &[&str]| {
+            builder(&universe)
+                .overlays(&overlays)
+                .using_overlays(labels)
+                .build()
+                .unwrap()
+                .jumps()
+        };
+        assert_eq!(1, jumps(&["titan-bridge"]));
+        assert_eq!(4, jumps(&[]));
+        assert_eq!(4, jumps(&["thera"]));
+    }
+
+    #[test]
+    fn test_to_any() {
+        let universe = universe();
+        let builder = PathBuilder::new(&universe).waypoint(universe.get_system(&ALPHA).unwrap());
+        let path = builder.to_any(&[ECHO, HOTEL, GOLF]).unwrap();
+        assert_eq!(GOLF, path.to().unwrap().id);
+        assert_eq!(3, path.jumps());
+        assert_eq!(
+            Some(RouteError::TooManyJumps { jumps: 3, max: 2 }),
+            builder.clone().max_jumps(2).to_any(&[ECHO, GOLF]).err()
+        );
+        assert_eq!(Some(RouteError::NoRoute), builder.to_any(&[]).err());
+    }
+
+    #[test]
+    fn test_paths_to_all() {
+        let universe = universe();
+        let builder = PathBuilder::new(&universe).waypoint(universe.get_system(&ALPHA).unwrap());
+        let paths = builder
+            .paths_to_all(&[ECHO, HOTEL, ALPHA, types::SystemId(1)])
+            .unwrap();
+        assert_eq!(3, paths.len());
+        assert_eq!(4, paths[&ECHO].jumps());
+        assert_eq!(4, paths[&HOTEL].jumps());
+        assert_eq!(0, paths[&ALPHA].jumps());
+        for (id, path) in &paths {
+            assert_eq!(
+                builder
+                    .clone()
+                    .waypoint(universe.get_system(id).unwrap())
+                    .build()
+                    .unwrap()
+                    .jumps(),
+                path.jumps()
+            );
+        }
+        let paths = builder.max_jumps(3).paths_to_all(&[ECHO, CHARLIE]).unwrap();
+        assert_eq!(vec![&CHARLIE], paths.keys().collect::<Vec<_>>());
+    }
+
+    /// The names of the systems of a route through `cluster`.
+    fn cluster_route(
+        universe: &types::Universe,
+        from: types::SystemId,
+        to: types::SystemId,
+        preference: Preference,
+    ) -> Vec<String> {
+        PathBuilder::new(universe)
+            .waypoint(universe.get_system(&from).unwrap())
+            .waypoint(universe.get_system(&to).unwrap())
+            .prefer(preference)
+            .build()
+            .unwrap()
+            .systems()
+            .map(|s| s.name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_shortest_route() {
+        let universe = cluster();
+        let from = cluster_system(0, 0, 0);
+        let to = cluster_system(2, 0, 4);
+        let names = cluster_route(&universe, from, to, Preference::Shortest);
+        assert_eq!(17, names.len());
+        assert_eq!("Core 1", names[0]);
+        // the only way into Deep
+        assert_eq!("Rim 10", names[10]);
+        assert_eq!("Deep 6", names[11]);
+        assert_eq!("Deep 5", names[16]);
+    }
+
+    #[test]
+    fn test_preference_highsec() {
+        let universe = cluster();
+        // through Core 10 and the second row of Rim rather than the first row
+        assert_eq!(
+            vec!["Core 5", "Core 10", "Rim 6", "Rim 7", "Rim 8", "Rim 9", "Rim 10"],
+            cluster_route(
+                &universe,
+                cluster_system(0, 0, 4),
+                cluster_system(1, 1, 4),
+                Preference::Highsec
+            )
+        );
+        assert_eq!(
+            vec!["Core 10", "Core 5", "Rim 1"],
+            cluster_route(
+                &universe,
+                cluster_system(0, 1, 4),
+                cluster_system(1, 0, 0),
+                Preference::Highsec
+            )
+        );
+    }
+
+    #[test]
+    fn test_preference_lowsec_and_nullsec() {
+        let universe = cluster();
+        assert_eq!(
+            vec!["Core 10", "Rim 6", "Rim 1"],
+            cluster_route(
+                &universe,
+                cluster_system(0, 1, 4),
+                cluster_system(1, 0, 0),
+                Preference::LowsecAndNullsec
+            )
+        );
+        // leaves Core as early as possible, through its first row
+        assert_eq!(
+            vec!["Core 1", "Core 2", "Core 3", "Core 4", "Core 5", "Rim 1", "Rim 6"],
+            cluster_route(
+                &universe,
+                cluster_system(0, 0, 0),
+                cluster_system(1, 1, 0),
+                Preference::LowsecAndNullsec
+            )
+        );
+    }
+
+    #[test]
+    fn test_wormhole_shortcut() {
+        let universe = cluster();
+        let west = universe.get_system(&cluster_system(0, 0, 0)).unwrap();
+        let east = universe.get_system(&cluster_system(2, 0, 4)).unwrap();
+        // 16 jumps through stargates, see testing::cluster
+        let wormhole = types::Connection {
+            from: west.id,
+            to: cluster_system(2, 0, 0),
+            type_: types::ConnectionType::Wormhole(types::WormholeType::VeryLarge),
+            source: types::ConnectionSource::UserOverlay,
+        };
+        let extended = universe.extend(vec![wormhole].into());
+        let path = PathBuilder::new(&extended)
+            .waypoint(west)
+            .waypoint(east)
+            .build()
+            .unwrap();
+        assert_eq!(5, path.jumps());
+        assert_eq!(
+            "Deep 1",
+            path.systems().nth(1).map(|s| s.name.as_str()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_legs() {
+        let universe = universe();
+        let path = builder(&universe).build().unwrap();
+        let legs = path.legs().collect::<Vec<_>>();
+        assert_eq!(path.jumps(), legs.len());
+        assert_eq!(
+            vec![
+                (ALPHA, BRAVO),
+                (BRAVO, CHARLIE),
+                (CHARLIE, DELTA),
+                (DELTA, ECHO)
+            ],
+            legs.iter()
+                .map(|(from, _, to)| (from.id, to.id))
+                .collect::<Vec<_>>()
+        );
+        let (_, connection, _) = &legs[1];
+        assert_eq!((BRAVO, CHARLIE), (connection.from, connection.to));
+        assert_eq!(
+            types::ConnectionType::Stargate(types::StargateType::Regional),
+            connection.type_
+        );
+    }
+
+    #[test]
+    fn test_connection_source() {
+        let universe = universe();
+        let wormhole = types::Connection {
+            from: ALPHA,
+            to: ECHO,
+            type_: types::ConnectionType::Wormhole(types::WormholeType::Small),
+            source: types::ConnectionSource::EveScout,
+        };
+        let extended = universe.extend(vec![wormhole].into());
+        let path = PathBuilder::new(&extended)
+            .waypoint(universe.get_system(&ALPHA).unwrap())
+            .waypoint(universe.get_system(&ECHO).unwrap())
+            .build()
+            .unwrap();
+        let sources = path
+            .iter()
+            .filter_map(|e| match e {
+                PathElement::Connection(_, source) => Some(source),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(vec![types::ConnectionSource::EveScout], sources);
+    }
+
+    #[test]
+    fn test_ship_mass() {
+        let universe = universe();
+        let hole = |type_| types::Connection {
+            from: ALPHA,
+            to: ECHO,
+            type_: types::ConnectionType::Wormhole(type_),
+            source: types::ConnectionSource::UserOverlay,
+        };
+        let extended = universe.extend(vec![hole(types::WormholeType::Medium)].into());
+        let mut builder = PathBuilder::new(&extended)
+            .waypoint(universe.get_system(&ALPHA).unwrap())
+            .waypoint(universe.get_system(&ECHO).unwrap());
+        assert_eq!(1, builder.build().unwrap().jumps());
+
+        // a battleship does not fit through a medium hole
+        builder.set_ship_mass(Some(types::Kilotons(100.0)));
+        assert_eq!(4, builder.build().unwrap().jumps());
+        builder.set_ship_mass(Some(types::Kilotons(13.0)));
+        assert_eq!(1, builder.build().unwrap().jumps());
+
+        let extended = universe.extend(vec![hole(types::WormholeType::Unknown)].into());
+        let path = PathBuilder::new(&extended)
+            .waypoint(universe.get_system(&ALPHA).unwrap())
+            .waypoint(universe.get_system(&ECHO).unwrap())
+            .ship_mass(types::Kilotons(1_000.0))
+            .build()
+            .unwrap();
+        assert_eq!(1, path.jumps());
+    }
+
+    #[test]
+    fn test_ship() {
+        let universe = universe();
+        let carrier = ShipProfile::new(types::HullClass::Carrier);
+        // capitals may not enter highsec, Delta and Echo are the only low and nullsec
+        // systems next to each other
+        let path = PathBuilder::new(&universe)
+            .waypoint(universe.get_system(&DELTA).unwrap())
+            .waypoint(universe.get_system(&ECHO).unwrap())
+            .ship(&carrier)
+            .build()
+            .unwrap();
+        assert_eq!(1, path.jumps());
+        assert!(builder(&universe).ship(&carrier).build().is_none());
+
+        let hole = types::Connection {
+            from: DELTA,
+            to: HOTEL,
+            type_: types::ConnectionType::Wormhole(types::WormholeType::Large),
+            source: types::ConnectionSource::UserOverlay,
+        };
+        let extended = universe.extend(vec![hole].into());
+        let mut builder = PathBuilder::new(&extended);
+        builder
+            .add_waypoint(extended.get_system(&DELTA).unwrap())
+            .add_waypoint(extended.get_system(&HOTEL).unwrap())
+            .set_ship(Some(&ShipProfile::new(types::HullClass::Battleship)));
+        assert_eq!(1, builder.build().unwrap().jumps());
+        // too heavy for the wormhole, and Golf is highsec
+        builder.set_ship(Some(&carrier));
+        assert!(builder.build().is_none());
+    }
+
+    #[test]
+    fn test_canonical_hash() {
+        let universe = universe();
+        let sys = |id| universe.get_system(&id).unwrap();
+        let direct = builder(&universe).build().unwrap();
+        let via_charlie = PathBuilder::new(&universe)
+            .waypoints(vec![sys(ALPHA), sys(CHARLIE), sys(ECHO)])
+            .build()
+            .unwrap();
+        let extended = universe.extend(types::AdjacentMap::empty());
+        let elsewhere = PathBuilder::new(&extended)
+            .waypoints(vec![sys(ALPHA), sys(ECHO)])
+            .build()
+            .unwrap();
+        assert_eq!(direct.canonical_hash(), via_charlie.canonical_hash());
+        assert_eq!(direct.canonical_hash(), elsewhere.canonical_hash());
+        let back = PathBuilder::new(&universe)
+            .waypoints(vec![sys(ECHO), sys(ALPHA)])
+            .build()
+            .unwrap();
+        assert_ne!(direct.canonical_hash(), back.canonical_hash());
+        // pinned, changing it invalidates every cache keyed by it
+        let alone = PathBuilder::new(&universe)
+            .waypoints(vec![sys(ALPHA), sys(ALPHA)])
+            .build()
+            .unwrap();
+        assert_eq!(0x9926_6aa1_365c_2c27, alone.canonical_hash());
+    }
+
+    #[test]
+    fn test_max_detour() {
+        let universe = universe();
+        let sys = |id| universe.get_system(&id).unwrap();
+        // Golf avoided, the only other way from Charlie to Delta is through Foxtrot,
+        // two lightyears off the line between Alpha and Echo
+        let avoid_charlie_delta = |_: &dyn types::Navigatable, c: &types::Connection| {
+            Some(if c.from == CHARLIE && c.to == DELTA {
+                10
+            } else {
+                1
+            })
+        };
+        let builder = PathBuilder::new(&universe)
+            .waypoint(sys(ALPHA))
+            .waypoint(sys(ECHO))
+            .cost_function(avoid_charlie_delta);
+        let path = builder.build().unwrap();
+        assert!(path.systems().any(|s| s.id == GOLF));
+
+        let mut builder = builder.max_detour(types::Lightyears(1.0));
+        assert_eq!(
+            vec![ALPHA, BRAVO, CHARLIE, DELTA, ECHO],
+            builder
+                .build()
+                .unwrap()
+                .systems()
+                .map(|s| s.id)
+                .collect::<Vec<_>>()
+        );
+        builder.set_max_detour(None);
+        assert!(builder.build().unwrap().systems().any(|s| s.id == GOLF));
+
+        let a = types::Coordinate {
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        };
+        let b = types::Coordinate { x: 4.0, ..a };
+        assert_eq!(
+            types::Meters(3.0),
+            distance_to_segment(
+                &types::Coordinate {
+                    x: 2.0,
+                    y: 3.0,
+                    z: 0.0
+                },
+                &a,
+                &b
+            )
+        );
+        assert_eq!(
+            types::Meters(1.0),
+            distance_to_segment(&types::Coordinate { x: 5.0, ..a }, &a, &b)
+        );
+        assert_eq!(
+            types::Meters(1.0),
+            distance_to_segment(&types::Coordinate { x: 1.0, ..a }, &a, &a)
+        );
+    }
+
+    #[test]
+    fn test_connection_info() {
+        let universe = universe();
+        let hole = types::Connection {
+            from: ALPHA,
+            to: ECHO,
+            type_: types::ConnectionType::Wormhole(types::WormholeType::Large),
+            source: types::ConnectionSource::UserOverlay,
+        };
+        let mut extended = universe.extend(vec![hole].into());
+        extended.set_connection_info(
+            ALPHA,
+            ECHO,
+            types::ConnectionInfo {
+                mass_remaining: Some(types::Kilotons(50.0)),
+                ..Default::default()
+            },
+        );
+        let build = |extended: &types::ExtendedUniverse<&types::Universe>, mass| {
+            let mut builder = PathBuilder::new(extended)
+                .waypoint(universe.get_system(&ALPHA).unwrap())
+                .waypoint(universe.get_system(&ECHO).unwrap());
+            builder.set_ship_mass(mass);
+            builder.build().unwrap().jumps()
+        };
+        assert_eq!(1, build(&extended, Some(types::Kilotons(13.0))));
+        // not enough mass left for a battleship
+        assert_eq!(4, build(&extended, Some(types::Kilotons(100.0))));
+
+        // end of life holes are avoided unless they save a lot of jumps
+        extended.set_connection_info(
+            ALPHA,
+            ECHO,
+            types::ConnectionInfo {
+                end_of_life: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(4, build(&extended, None));
+    }
+
+    #[test]
+    fn test_cost_function() {
+        let universe = universe();
+        let avoid_charlie = |_: &dyn types::Navigatable, c: &types::Connection| {
+            if c.to == CHARLIE {
+                None
+            } else {
+                Some(1)
+            }
+        };
+        let mut builder = builder(&universe).cost_function(avoid_charlie);
+        let path = builder.build().unwrap();
+        assert_eq!(5, path.jumps());
+        assert!(path.systems().all(|s| s.id != CHARLIE));
+
+        builder.clear_cost_function();
+        assert_eq!(4, builder.build().unwrap().jumps());
+    }
+
+    #[test]
+    fn test_builder_reuse() {
+        let universe = universe();
+        let mut builder = builder(&universe);
+        assert_eq!(4, builder.build().unwrap().jumps());
+        builder.add_waypoint(universe.get_system(&HOTEL).unwrap());
+        assert_eq!(7, builder.build().unwrap().jumps());
+        builder.set_preference(Preference::Highsec);
+        let path = builder.build().unwrap();
+        assert_eq!(Some(universe.get_system(&HOTEL).unwrap()), path.to());
+        builder.clear_waypoints();
+        assert_eq!(0, builder.build().unwrap().jumps());
+    }
+
+    #[test]
+    fn test_cancelled() {
+        let universe = universe();
+        let token = CancellationToken::new();
+        token.cancel();
+        let result = builder(&universe).cancel_on(token).try_build();
+        assert_eq!(Some(RouteError::Cancelled), result.err());
+    }
+
+    #[test]
+    fn test_timed_out() {
+        let universe = universe();
+        let deadline = Deadline(std::time::Instant::now());
+        let result = builder(&universe).deadline(deadline).try_build();
+        assert_eq!(Some(RouteError::TimedOut), result.err());
+
+        let deadline = Deadline::after(std::time::Duration::from_secs(60));
+        assert!(builder(&universe).deadline(deadline).try_build().is_ok());
+    }
+
+    #[test]
+    fn test_no_route() {
+        let universe = crate::builder::UniverseBuilder::new()
+            .system(system(ALPHA, "Alpha", 0.9, 0.0, 0.0))
+            .system(system(ECHO, "Echo", -0.2, 1.0, 0.0))
+            .build();
+        let result = builder(&universe).try_build();
+        assert_eq!(Some(RouteError::NoRoute), result.err());
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod dbtests {
+    use std::env;
+
+    use crate::source::sqlite::DatabaseBuilder;
+    use crate::types::Navigatable;
+
+    use super::*;
+
+    extern crate test;
+
+    fn sde() -> types::Universe {
+        let uri = env::var("SQLITE_URI").expect("expected env variable SQLITE_URI set");
+        DatabaseBuilder::new(&uri).build().unwrap()
+    }
+
+    fn name<'a>(e: &PathElement<'a>) -> &'a str {
+        match e {
+            PathElement::System(sys) => &sys.name,
+            _ => panic!("should not be reached"),
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a static data export in SQLITE_URI"]
+    fn test_dijkstra() {
+        let universe = sde();
+        let path = PathBuilder::new(&universe)
+            .waypoint(universe.get_system(&30000142.into()).unwrap()) // jita
+            .waypoint(universe.get_system(&30000049.into()).unwrap()) // camal
+            .build()
+            .unwrap();
+        assert_eq!(27, path.jumps());
+
+        let sys = path.systems().collect::<Vec<_>>();
+        assert_eq!("Jita", sys[0].name);
+        assert_eq!("Iyen-Oursta", sys[2].name);
+        assert_eq!("Hek", sys[9].name);
+        assert_eq!("Camal", sys[27].name);
+    }
+
+    #[test]
+    #[ignore = "needs a static data export in SQLITE_URI"]
+    fn test_dijkstra_preference_safer() {
+        let universe = sde();
+        let path = PathBuilder::new(&universe)
+            .waypoint(universe.get_system(&30000142.into()).unwrap()) // jita
+            .waypoint(universe.get_system(&30000049.into()).unwrap()) // camal
+            .prefer(Preference::Highsec)
+            .build()
+            .unwrap();
+        assert_eq!(37, path.jumps());
+
+        let sys = path.systems().collect::<Vec<_>>();
+        assert_eq!("Jita", sys[0].name);
+        assert_eq!("Urlen", sys[2].name);
+        assert_eq!("Trytedald", sys[25].name);
+        assert_eq!("Camal", sys[37].name);
+    }
+
+    #[test]
+    #[ignore = "needs a static data export in SQLITE_URI"]
+    fn test_dijkstra_preference_unsafer() {
+        let universe = sde();
+        let path = PathBuilder::new(&universe)
+            .waypoint(universe.get_system(&30000142.into()).unwrap()) // jita
+            .waypoint(universe.get_system(&30000049.into()).unwrap()) // camal
+            .prefer(Preference::LowsecAndNullsec)
+            .build()
+            .unwrap();
+        assert_eq!(69, path.jumps());
+
+        let sys = path.systems().collect::<Vec<_>>();
+        assert_eq!("Jita", sys[0].name);
+        assert_eq!("LXQ2-T", sys[39].name);
+        assert_eq!("6-EQYE", sys[48].name);
+        assert_eq!("Camal", sys[69].name);
+    }
+
+    #[bench]
+    #[ignore = "needs a static data export in SQLITE_URI"]
+    fn bench_dijkstra(b: &mut test::Bencher) {
+        let universe = sde();
+        b.iter(|| {
+            test::black_box(
+                PathBuilder::new(&universe)
+                    .waypoint(universe.get_system(&30000142.into()).unwrap()) // jita
+                    .waypoint(universe.get_system(&30000049.into()).unwrap()) // camal
+                    .build()
+                    .unwrap(),
+            );
+        });
+    }
+
+    #[bench]
+    #[ignore = "needs a static data export in SQLITE_URI"]
+    fn bnech_dijkstra_and_collection(b: &mut test::Bencher) {
+        let universe = sde();
+        b.iter(|| {
+            test::black_box(
+                PathBuilder::new(&universe)
+                    .waypoint(universe.get_system(&30000142.into()).unwrap()) // jita
+                    .waypoint(universe.get_system(&30000049.into()).unwrap()) // camal
+                    .build()
+                    .unwrap()
+                    .collect::<Vec<_>>(),
+            );
+        });
+    }
+
+    #[bench]
+    #[ignore = "needs a static data export in SQLITE_URI"]
+    fn bnech_dijkstra_preference_highsec(b: &mut test::Bencher) {
+        let universe = sde();
+        b.iter(|| {
+            test::black_box(
+                PathBuilder::new(&universe)
+                    .waypoint(universe.get_system(&30000142.into()).unwrap()) // jita
+                    .waypoint(universe.get_system(&30000049.into()).unwrap()) // camal
+                    .prefer(Preference::Highsec)
+                    .build()
+                    .unwrap()
+                    .collect::<Vec<_>>(),
+            );
+        });
+    }
+
+    #[bench]
+    #[ignore = "needs a static data export in SQLITE_URI"]
+    fn bnech_dijkstra_preference_lowsec(b: &mut test::Bencher) {
+        let universe = sde();
+        b.iter(|| {
+            test::black_box(
+                PathBuilder::new(&universe)
+                    .waypoint(universe.get_system(&30000142.into()).unwrap()) // jita
+                    .waypoint(universe.get_system(&30000049.into()).unwrap()) // camal
+                    .prefer(Preference::LowsecAndNullsec)
+                    .build()
+                    .unwrap()
+                    .collect::<Vec<_>>(),
+            );
+        });
+    }
+
+    #[bench]
+    #[ignore = "needs a static data export in SQLITE_URI"]
+    fn bnech_dijkstra_longest(b: &mut test::Bencher) {
+        let universe = sde();
+        b.iter(|| {
+            test::black_box(
+                PathBuilder::new(&universe)
+                    // the diameter of new eden, 99 jumps, see test_diameter
+                    .waypoint(universe.get_system(&30001947.into()).unwrap()) // 373Z-7
+                    .waypoint(universe.get_system(&30004377.into()).unwrap()) // SVB-RE
+                    .build()
+                    .unwrap()
+                    .collect::<Vec<_>>(),
+            );
+        });
+    }
+
+    #[test]
+    #[ignore = "needs a static data export in SQLITE_URI"]
+    fn test_diameter() {
+        let universe = sde();
+        let diameter = diameter(&universe).unwrap();
+        assert_eq!(99, diameter.jumps);
+        assert_eq!(Some(99), eccentricity(&universe, &30001947.into())); // 373Z-7
+    }
+
+    #[test]
+    #[ignore = "needs a static data export in SQLITE_URI"]
+    fn test_dijkstra_extended() {
+        let universe = sde();
+        let adj = vec![types::Connection {
+            from: 30002718.into(), // Rancer
+            to: 30000004.into(),   // Jark
+            type_: types::ConnectionType::Wormhole(types::WormholeType::VeryLarge),
+            source: types::ConnectionSource::UserOverlay,
+        }]
+        .into();
+        let extended = types::ExtendedUniverse::new(&universe, adj);
+
+        let path = PathBuilder::new(&extended)
+            .waypoint(universe.get_system(&30000142.into()).unwrap()) // jita
+            .waypoint(universe.get_system(&30000049.into()).unwrap()) // camal
+            .build()
+            .unwrap();
+
+        assert_eq!(17, path.jumps());
+
+        let sys = path.systems().collect::<Vec<_>>();
+        assert_eq!("Jita", sys[0].name);
+        assert_eq!("Iyen-Oursta", sys[2].name);
+        assert_eq!("Camal", sys[17].name);
+    }
+
+    #[bench]
+    #[ignore = "needs a static data export in SQLITE_URI"]
+    fn bnech_dijkstra_extended(b: &mut test::Bencher) {
+        let universe = sde();
+        let adj = vec![types::Connection {
+            from: 30002718.into(), // Rancer
+            to: 30000004.into(),   // Jark
+            type_: types::ConnectionType::Wormhole(types::WormholeType::VeryLarge),
+            source: types::ConnectionSource::UserOverlay,
+        }]
+        .into();
+        let extended = types::ExtendedUniverse::new(&universe, adj);
+        b.iter(|| {
+            test::black_box(
+                PathBuilder::new(&extended)
+                    .waypoint(universe.get_system(&30000142.into()).unwrap()) // jita
+                    .waypoint(universe.get_system(&30000049.into()).unwrap()) // camal
+                    .build()
+                    .unwrap()
+                    .collect::<Vec<_>>(),
+            );
+        });
+    }
+}
@@ -0,0 +1,300 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Re-route suggestions while travelling, fed by intel reports.
+//!
+//! A `RouteMonitor` follows the pilot along an active route. When intel reports a
+//! hostile presence in a system still ahead, it suggests a way around it, both from
+//! where the pilot is right now and from the last system before the danger where the
+//! route can still branch off, so the pilot can decide whether to turn now or keep
+//! going for a while.
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+use crate::types;
+
+use super::{Cost, CostFunction, Path, PathBuilder, Preference};
+
+/// How long an intel report is considered current by default.
+const DEFAULT_EXPIRY: Duration = Duration::from_secs(10 * 60);
+
+/// How dangerous an intel report is, e.g. a neutral passing through versus a gate
+/// camp.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+}
+
+/// A report from an intel channel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntelEvent {
+    pub system: types::SystemId,
+    pub severity: Severity,
+    pub time: SystemTime,
+}
+
+/// A route to the destination that avoids all systems currently reported dangerous.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diversion {
+    /// Where the route leaves the active route.
+    pub branch: types::SystemId,
+    /// All systems from the current one to the destination.
+    pub systems: Vec<types::SystemId>,
+    pub cost: Cost,
+}
+
+/// Emitted by `RouteMonitor::observe` when a system ahead became dangerous.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RerouteSuggestion {
+    /// The first dangerous system ahead.
+    pub danger: types::SystemId,
+    pub severity: Severity,
+    /// Turning around right away, `None` if there is no way around.
+    pub divert_now: Option<Diversion>,
+    /// Following the route up to the last system before the danger from which there
+    /// is a way around, `None` if there is none.
+    pub divert_at_branch: Option<Diversion>,
+}
+
+impl RerouteSuggestion {
+    /// How much more diverting now costs than diverting at the branch point. Negative
+    /// if diverting now is cheaper.
+    pub fn cost_delta(&self) -> Option<i64> {
+        let now = self.divert_now.as_ref()?;
+        let branch = self.divert_at_branch.as_ref()?;
+        Some(now.cost as i64 - branch.cost as i64)
+    }
+}
+
+/// Follows a route and suggests diversions when intel reports danger ahead.
+///
+/// # Example
+/// ```
+/// use std::time::SystemTime;
+/// use neweden::builder::UniverseBuilder;
+/// use neweden::navigation::monitor::{IntelEvent, RouteMonitor, Severity};
+/// use neweden::navigation::PathBuilder;
+/// use neweden::{Connection, ConnectionSource, ConnectionType, Coordinate, Navigatable, StargateType, System};
+///
+/// let system = |id: u32| System::new(id.into(), "", Coordinate { x: 0.0, y: 0.0, z: 0.0 }, 0.3.into());
+/// let gate = |from: u32, to: u32| Connection {
+///     from: from.into(),
+///     to: to.into(),
+///     type_: ConnectionType::Stargate(StargateType::Local),
+///     source: ConnectionSource::Sde,
+/// };
+/// let universe = UniverseBuilder::new()
+///     .system(system(1))
+///     .system(system(2))
+///     .connection(gate(1, 2))
+///     .build();
+/// let path = PathBuilder::new(&universe)
+///     .waypoint(universe.get_system(&1.into()).unwrap())
+///     .waypoint(universe.get_system(&2.into()).unwrap())
+///     .build()
+///     .unwrap();
+///
+/// let mut monitor = RouteMonitor::new(&universe, &path);
+/// let camp = IntelEvent { system: 2.into(), severity: Severity::High, time: SystemTime::now() };
+/// let suggestion = monitor.observe(camp).unwrap();
+/// assert!(suggestion.divert_now.is_none()); // the destination itself is camped
+/// ```
+pub struct RouteMonitor<'a> {
+    universe: &'a dyn types::Navigatable,
+    route: Vec<types::SystemId>,
+    /// The index of the current system in `route`.
+    position: usize,
+    threshold: Severity,
+    expiry: Duration,
+    preference: Preference,
+    dangerous: HashMap<types::SystemId, (Severity, SystemTime)>,
+}
+
+impl<'a> RouteMonitor<'a> {
+    /// Starts monitoring at the first system of the path.
+    pub fn new(universe: &'a dyn types::Navigatable, path: &Path) -> Self {
+        Self {
+            universe,
+            route: path.systems().map(|s| s.id).collect(),
+            position: 0,
+            threshold: Severity::Medium,
+            expiry: DEFAULT_EXPIRY,
+            preference: Preference::Shortest,
+            dangerous: HashMap::new(),
+        }
+    }
+
+    /// Reports below this severity are ignored. Defaults to `Severity::Medium`.
+    pub fn threshold(mut self, severity: Severity) -> Self {
+        self.threshold = severity;
+        self
+    }
+
+    /// How long a report is considered current. Defaults to ten minutes.
+    pub fn expiry(mut self, expiry: Duration) -> Self {
+        self.expiry = expiry;
+        self
+    }
+
+    /// The preference diversions are computed with. Should match the one the route
+    /// was built with.
+    pub fn prefer(mut self, preference: Preference) -> Self {
+        self.preference = preference;
+        self
+    }
+
+    /// The system the pilot is in.
+    pub fn current(&self) -> Option<types::SystemId> {
+        self.route.get(self.position).copied()
+    }
+
+    /// Moves the pilot to a system further along the route. Returns false if the
+    /// system is not ahead on the route, e.g. because the pilot diverted.
+    pub fn advance(&mut self, system: types::SystemId) -> bool {
+        match self.route[self.position..]
+            .iter()
+            .position(|s| *s == system)
+        {
+            Some(offset) => {
+                self.position += offset;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Records the report and returns a suggestion if it concerns a system ahead on
+    /// the route. Reports older than the expiry, measured from this report, are
+    /// forgotten.
+    pub fn observe(&mut self, event: IntelEvent) -> Option<RerouteSuggestion> {
+        let expiry = self.expiry;
+        self.dangerous.retain(|_, (_, time)| {
+            event
+                .time
+                .duration_since(*time)
+                .map_or(true, |age| age <= expiry)
+        });
+        if event.severity < self.threshold {
+            return None;
+        }
+        self.dangerous
+            .insert(event.system, (event.severity, event.time));
+
+        let ahead = &self.route[self.position + 1..];
+        if !ahead.contains(&event.system) {
+            return None;
+        }
+        let index =
+            self.position + 1 + ahead.iter().position(|s| self.dangerous.contains_key(s))?;
+        let danger = self.route[index];
+
+        let divert_now = self.divert_from(self.position);
+        let divert_at_branch = (self.position..index)
+            .rev()
+            .find_map(|branch| self.divert_from(branch));
+        Some(RerouteSuggestion {
+            danger,
+            severity: self.dangerous[&danger].0,
+            divert_now,
+            divert_at_branch,
+        })
+    }
+
+    /// Follows the route from the current system up to `branch` and takes the
+    /// cheapest way around all dangerous systems from there.
+    fn divert_from(&self, branch: usize) -> Option<Diversion> {
+        let destination = self.universe.get_system(self.route.last()?)?;
+        let from = self.universe.get_system(&self.route[branch])?;
+        let avoid = |universe: &dyn types::Navigatable, connection: &types::Connection| {
+            if self.dangerous.contains_key(&connection.to) {
+                None
+            } else {
+                self.preference.cost(universe, connection)
+            }
+        };
+        let detour = PathBuilder::new(self.universe)
+            .waypoint(from)
+            .waypoint(destination)
+            .cost_function(avoid)
+            .build()?;
+
+        let mut systems = self.route[self.position..branch].to_vec();
+        systems.extend(detour.systems().map(|s| s.id));
+        Some(Diversion {
+            branch: from.id,
+            cost: self.cost(&systems)?,
+            systems,
+        })
+    }
+
+    /// The cost of travelling the systems in order.
+    fn cost(&self, systems: &[types::SystemId]) -> Option<Cost> {
+        systems
+            .windows(2)
+            .map(|hop| {
+                let connections = self.universe.get_connections(&hop[0])?;
+                let connection = connections.iter().find(|c| c.to == hop[1])?;
+                self.preference.cost(self.universe, connection)
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+    use crate::types::Navigatable;
+
+    #[test]
+    fn test_route_monitor() {
+        let universe = universe();
+        let sys = |id| universe.get_system(&id).unwrap();
+        let path = PathBuilder::new(&universe)
+            .waypoints(vec![sys(GOLF), sys(DELTA), sys(BRAVO)])
+            .build()
+            .unwrap();
+        let mut monitor = RouteMonitor::new(&universe, &path);
+        let start = SystemTime::UNIX_EPOCH;
+        let report = |system, severity, minutes: u64| IntelEvent {
+            system,
+            severity,
+            time: start + Duration::from_secs(minutes * 60),
+        };
+
+        // not on the route, or not dangerous enough
+        assert!(monitor.observe(report(HOTEL, Severity::High, 0)).is_none());
+        assert!(monitor.observe(report(CHARLIE, Severity::Low, 0)).is_none());
+
+        // Golf - Delta - Charlie - Bravo, turning to Foxtrot right away is cheaper than
+        // going around from Delta
+        let suggestion = monitor.observe(report(CHARLIE, Severity::High, 1)).unwrap();
+        assert_eq!(CHARLIE, suggestion.danger);
+        let now = suggestion.divert_now.as_ref().unwrap();
+        assert_eq!(vec![GOLF, FOXTROT, BRAVO], now.systems);
+        let branch = suggestion.divert_at_branch.as_ref().unwrap();
+        assert_eq!(DELTA, branch.branch);
+        assert_eq!(vec![GOLF, DELTA, GOLF, FOXTROT, BRAVO], branch.systems);
+        assert_eq!(Some(-2), suggestion.cost_delta());
+
+        // Golf is behind the pilot by now, but it blocks the way around
+        assert!(monitor.advance(DELTA));
+        assert!(!monitor.advance(GOLF));
+        assert!(monitor.observe(report(GOLF, Severity::High, 2)).is_none());
+        let suggestion = monitor
+            .observe(report(CHARLIE, Severity::Medium, 3))
+            .unwrap();
+        assert_eq!(CHARLIE, suggestion.danger);
+        assert!(suggestion.divert_now.is_none());
+        assert!(suggestion.cost_delta().is_none());
+
+        // the camps expired, Bravo is camped
+        let suggestion = monitor.observe(report(BRAVO, Severity::High, 30)).unwrap();
+        assert_eq!(BRAVO, suggestion.danger);
+        assert!(suggestion.divert_now.is_none());
+    }
+}
@@ -0,0 +1,179 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Named groups of extra connections that routes may or may not use.
+//!
+//! An `OverlaySet` keeps connections such as Ansiblex jump bridges, the current
+//! Thera wormholes or titan bridges under a label each. Overlays are enabled by
+//! default. `PathBuilder::overlays` routes over the enabled overlays of a set and
+//! `PathBuilder::using_overlays` picks the overlays for a single query instead,
+//! so one set can serve users with different access.
+use crate::types;
+
+/// Labelled groups of connections, each of which can be toggled.
+#[derive(Debug, Default)]
+pub struct OverlaySet {
+    overlays: Vec<Overlay>,
+}
+
+#[derive(Debug)]
+struct Overlay {
+    label: String,
+    connections: types::AdjacentMap,
+    enabled: bool,
+}
+
+impl OverlaySet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn overlay(mut self, label: &str, connections: Vec<types::Connection>) -> Self {
+        self.add_overlay(label, connections);
+        self
+    }
+
+    /// Adds an enabled overlay. Connections added under an existing label are
+    /// merged into that overlay.
+    pub fn add_overlay(&mut self, label: &str, connections: Vec<types::Connection>) -> &mut Self {
+        let connections = types::AdjacentMap::from(connections);
+        match self.overlays.iter_mut().find(|o| o.label == label) {
+            Some(overlay) => overlay.connections.merge(connections),
+            None => self.overlays.push(Overlay {
+                label: label.to_string(),
+                connections,
+                enabled: true,
+            }),
+        }
+        self
+    }
+
+    /// Removes an overlay, returning whether it existed.
+    pub fn remove_overlay(&mut self, label: &str) -> bool {
+        let before = self.overlays.len();
+        self.overlays.retain(|o| o.label != label);
+        self.overlays.len() != before
+    }
+
+    /// Enables an overlay, returning whether it exists.
+    pub fn enable(&mut self, label: &str) -> bool {
+        self.set_enabled(label, true)
+    }
+
+    /// Disables an overlay, returning whether it exists.
+    pub fn disable(&mut self, label: &str) -> bool {
+        self.set_enabled(label, false)
+    }
+
+    pub fn set_enabled(&mut self, label: &str, enabled: bool) -> bool {
+        match self.overlays.iter_mut().find(|o| o.label == label) {
+            Some(overlay) => {
+                overlay.enabled = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether the overlay exists and is enabled.
+    pub fn is_enabled(&self, label: &str) -> bool {
+        self.overlays.iter().any(|o| o.label == label && o.enabled)
+    }
+
+    /// The labels of all overlays, in the order they were added.
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.overlays.iter().map(|o| o.label.as_str())
+    }
+
+    /// The connections out of `from` in the enabled overlays.
+    pub fn connections(&self, from: &types::SystemId) -> Vec<types::Connection> {
+        self.collect(from, |o| o.enabled)
+    }
+
+    /// The connections out of `from` in the given overlays, whether they are
+    /// enabled or not. Unknown labels are ignored.
+    pub fn connections_in(
+        &self,
+        from: &types::SystemId,
+        labels: &[&str],
+    ) -> Vec<types::Connection> {
+        self.collect(from, |o| labels.contains(&o.label.as_str()))
+    }
+
+    fn collect(
+        &self,
+        from: &types::SystemId,
+        use_overlay: impl Fn(&Overlay) -> bool,
+    ) -> Vec<types::Connection> {
+        self.overlays
+            .iter()
+            .filter(|o| use_overlay(o))
+            .filter_map(|o| o.connections.0.get(from))
+            .flatten()
+            .cloned()
+            .collect()
+    }
+
+    /// Calls `f` with the connections out of `from` in the overlays, either the
+    /// enabled ones or the given `labels`.
+    pub(crate) fn for_each_connection(
+        &self,
+        from: &types::SystemId,
+        labels: Option<&[String]>,
+        f: &mut dyn FnMut(&types::Connection),
+    ) {
+        let connections = self
+            .overlays
+            .iter()
+            .filter(|o| match labels {
+                Some(labels) => labels.contains(&o.label),
+                None => o.enabled,
+            })
+            .filter_map(|o| o.connections.0.get(from))
+            .flatten();
+        for connection in connections {
+            f(connection);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+
+    fn bridge(from: types::SystemId, to: types::SystemId) -> types::Connection {
+        types::Connection {
+            from,
+            to,
+            type_: types::ConnectionType::Stargate(types::StargateType::Local),
+            source: types::ConnectionSource::UserOverlay,
+        }
+    }
+
+    #[test]
+    fn test_toggles() {
+        let mut set = OverlaySet::new()
+            .overlay("ansiblex", vec![bridge(ALPHA, DELTA)])
+            .overlay("titan-bridge", vec![bridge(ALPHA, HOTEL)]);
+        assert_eq!(
+            vec!["ansiblex", "titan-bridge"],
+            set.labels().collect::<Vec<_>>()
+        );
+        assert_eq!(2, set.connections(&ALPHA).len());
+
+        assert!(set.disable("titan-bridge"));
+        assert!(!set.is_enabled("titan-bridge"));
+        assert_eq!(DELTA, set.connections(&ALPHA)[0].to);
+        assert_eq!(1, set.connections(&ALPHA).len());
+        assert_eq!(HOTEL, set.connections_in(&ALPHA, &["titan-bridge"])[0].to);
+
+        set.add_overlay("ansiblex", vec![bridge(ALPHA, ECHO)]);
+        assert_eq!(2, set.connections(&ALPHA).len());
+        assert!(!set.enable("thera"));
+        assert!(set.remove_overlay("ansiblex"));
+        assert!(set.connections(&ALPHA).is_empty());
+    }
+}
@@ -0,0 +1,151 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Paths that own their systems instead of borrowing the universe.
+use crate::types;
+
+use super::{Path, PathElement};
+
+/// An element of an `OwnedPath`, see `PathElement`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OwnedPathElement {
+    Waypoint(types::System),
+    System(types::System),
+    Connection(types::ConnectionType, types::ConnectionSource),
+}
+
+impl OwnedPathElement {
+    pub fn system(&self) -> Option<&types::System> {
+        match self {
+            Self::Waypoint(system) | Self::System(system) => Some(system),
+            Self::Connection(..) => None,
+        }
+    }
+}
+
+/// A `Path` with copies of its systems, so it outlives the universe it was built
+/// from, e.g. to compute a route while holding a lock and return it afterwards.
+/// Create one with `Path::to_owned_path` or `into`.
+///
+/// # Example
+/// ```
+/// use neweden::navigation::{OwnedPath, PathBuilder};
+/// use neweden::testing::{self, ALPHA, ECHO};
+/// use neweden::Navigatable;
+///
+/// fn route() -> OwnedPath {
+///     let universe = testing::universe();
+///     let path = PathBuilder::new(&universe)
+///         .waypoint(universe.get_system(&ALPHA).unwrap())
+///         .waypoint(universe.get_system(&ECHO).unwrap())
+///         .build()
+///         .unwrap();
+///     path.to_owned_path()
+/// }
+/// assert_eq!(4, route().jumps());
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedPath {
+    jumps: usize,
+    waypoints: Vec<types::System>,
+    elements: Vec<OwnedPathElement>,
+}
+
+impl OwnedPath {
+    pub fn jumps(&self) -> usize {
+        self.jumps
+    }
+
+    /// The waypoints the path was built from, in the order they are visited.
+    pub fn waypoints(&self) -> &[types::System] {
+        &self.waypoints
+    }
+
+    pub fn elements(&self) -> &[OwnedPathElement] {
+        &self.elements
+    }
+
+    pub fn from(&self) -> Option<&types::System> {
+        self.elements.first()?.system()
+    }
+
+    pub fn to(&self) -> Option<&types::System> {
+        self.elements.last()?.system()
+    }
+
+    pub fn systems(&self) -> impl Iterator<Item = &types::System> {
+        self.elements.iter().filter_map(OwnedPathElement::system)
+    }
+
+    /// See `Path::to_esi_waypoints`.
+    pub fn to_esi_waypoints(&self) -> Vec<types::SystemId> {
+        self.systems().skip(1).map(|s| s.id).collect()
+    }
+}
+
+impl From<&Path<'_>> for OwnedPath {
+    fn from(path: &Path<'_>) -> Self {
+        Self {
+            jumps: path.jumps(),
+            waypoints: path.waypoints().iter().map(|s| (*s).clone()).collect(),
+            elements: path
+                .iter()
+                .map(|e| match e {
+                    PathElement::Waypoint(s) => OwnedPathElement::Waypoint(s.clone()),
+                    PathElement::System(s) => OwnedPathElement::System(s.clone()),
+                    PathElement::Connection(type_, source) => {
+                        OwnedPathElement::Connection(type_, source)
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<Path<'_>> for OwnedPath {
+    fn from(path: Path<'_>) -> Self {
+        path.to_owned_path()
+    }
+}
+
+impl Path<'_> {
+    /// Copies the systems of the path into an `OwnedPath`.
+    pub fn to_owned_path(&self) -> OwnedPath {
+        self.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::navigation::PathBuilder;
+    use crate::testing::*;
+    use crate::types::Navigatable;
+
+    #[test]
+    fn test_owned_path() {
+        let owned = {
+            let universe = universe();
+            let path = PathBuilder::new(&universe)
+                .waypoint(universe.get_system(&ALPHA).unwrap())
+                .waypoint(universe.get_system(&HOTEL).unwrap())
+                .build()
+                .unwrap();
+            let owned = path.to_owned_path();
+            assert_eq!(
+                path.systems().map(|s| s.id).collect::<Vec<_>>(),
+                owned.systems().map(|s| s.id).collect::<Vec<_>>()
+            );
+            assert_eq!(path.to_esi_waypoints(), owned.to_esi_waypoints());
+            owned
+        };
+        assert_eq!(4, owned.jumps());
+        assert_eq!("Alpha", owned.from().unwrap().name);
+        assert_eq!("Hotel", owned.to().unwrap().name);
+        assert_eq!(2, owned.waypoints().len());
+        assert_eq!(9, owned.elements().len());
+    }
+}
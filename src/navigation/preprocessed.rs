@@ -0,0 +1,403 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Preprocessed routing for services answering large numbers of route queries.
+//!
+//! `RoutingGraph` flattens a universe into dense arrays and precomputes distances
+//! from and to a small set of landmark systems (the ALT technique: A*, landmarks and
+//! the triangle inequality). Queries run A* guided by the landmark lower bounds and
+//! only explore a small fraction of the graph.
+//!
+//! Additional connections of an `ExtendedUniverse` are patched in at query time. The
+//! lower bounds are relaxed accordingly, so routes through wormholes or bridges remain
+//! optimal without recomputing the landmark tables. Connections the extended universe
+//! removes are skipped; removing edges only makes routes longer, so the lower bounds
+//! stay valid.
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use crate::types;
+
+use super::{Cost, Path, PathElementInternal, Preference};
+
+/// Number of landmarks selected when building a `RoutingGraph`.
+const LANDMARKS: usize = 16;
+
+const UNREACHABLE: Cost = Cost::MAX;
+
+#[derive(Clone, Copy)]
+enum Edge {
+    Base(usize),
+    Overlay(usize),
+}
+
+/// A routing graph optimised for repeated queries against the same universe.
+///
+/// # Example
+/// ```
+/// use neweden::builder::UniverseBuilder;
+/// use neweden::navigation::preprocessed::RoutingGraph;
+/// use neweden::navigation::Preference;
+/// use neweden::{Connection, ConnectionSource, ConnectionType, Coordinate, StargateType, System};
+///
+/// let system = |id: u32| {
+///     System::new(id.into(), &id.to_string(), Coordinate { x: 0.0, y: 0.0, z: 0.0 }, 0.9.into())
+/// };
+/// let gate = |from: u32, to: u32| Connection {
+///     from: from.into(),
+///     to: to.into(),
+///     type_: ConnectionType::Stargate(StargateType::Local),
+///     source: ConnectionSource::Sde,
+/// };
+/// let universe = UniverseBuilder::new()
+///     .system(system(1))
+///     .system(system(2))
+///     .system(system(3))
+///     .connection(gate(1, 2))
+///     .connection(gate(2, 3))
+///     .build();
+///
+/// let graph = RoutingGraph::new(&universe, Preference::Shortest);
+/// let path = graph.route(&1.into(), &3.into()).unwrap();
+/// assert_eq!(2, path.jumps());
+/// ```
+pub struct RoutingGraph<'a> {
+    universe: &'a dyn types::Navigatable,
+    ids: Vec<types::SystemId>,
+    index: HashMap<types::SystemId, usize>,
+    // forward adjacency in compressed sparse row layout
+    offsets: Vec<usize>,
+    targets: Vec<usize>,
+    connection_types: Vec<types::ConnectionType>,
+    connection_sources: Vec<types::ConnectionSource>,
+    // cost of entering a system under the graph's preference
+    node_cost: Vec<Cost>,
+    from_landmark: Vec<Vec<Cost>>,
+    to_landmark: Vec<Vec<Cost>>,
+}
+
+impl<'a> RoutingGraph<'a> {
+    /// Preprocesses the universe for the given preference. Routes returned by the
+    /// graph are optimal with respect to that preference.
+    pub fn new<U: types::Galaxy + types::Navigatable>(
+        universe: &'a U,
+        preference: Preference,
+    ) -> Self {
+        let mut ids = universe.iter_systems().map(|s| s.id).collect::<Vec<_>>();
+        ids.sort();
+        let index = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (*id, i))
+            .collect::<HashMap<_, _>>();
+
+        let mut offsets = Vec::with_capacity(ids.len() + 1);
+        let mut targets = Vec::new();
+        let mut connection_types = Vec::new();
+        let mut connection_sources = Vec::new();
+        let mut reverse = vec![Vec::new(); ids.len()];
+        offsets.push(0);
+        for (from, id) in ids.iter().enumerate() {
+            for conn in universe.get_connections(id).unwrap_or_default().iter() {
+                if let Some(to) = index.get(&conn.to) {
+                    targets.push(*to);
+                    connection_types.push(conn.type_.clone());
+                    connection_sources.push(conn.source);
+                    reverse[*to].push(from);
+                }
+            }
+            offsets.push(targets.len());
+        }
+        let node_cost = ids
+            .iter()
+            .map(|id| preference.system_cost(universe, *id))
+            .collect::<Vec<_>>();
+
+        let mut graph = Self {
+            universe,
+            ids,
+            index,
+            offsets,
+            targets,
+            connection_types,
+            connection_sources,
+            node_cost,
+            from_landmark: vec![],
+            to_landmark: vec![],
+        };
+        graph.select_landmarks(&reverse);
+        graph
+    }
+
+    /// Picks landmarks by repeatedly choosing the system farthest away from all
+    /// landmarks selected so far.
+    fn select_landmarks(&mut self, reverse: &[Vec<usize>]) {
+        if self.ids.is_empty() {
+            return;
+        }
+        let mut closest = vec![UNREACHABLE; self.ids.len()];
+        let mut next = 0;
+        for _ in 0..LANDMARKS.min(self.ids.len()) {
+            let forward = self.sweep(next, |v| {
+                (self.offsets[v]..self.offsets[v + 1])
+                    .map(|e| (self.targets[e], self.node_cost[self.targets[e]]))
+                    .collect()
+            });
+            let backward = self.sweep(next, |v| {
+                reverse[v].iter().map(|u| (*u, self.node_cost[v])).collect()
+            });
+            for (c, d) in closest.iter_mut().zip(forward.iter()) {
+                *c = (*c).min(*d);
+            }
+            self.from_landmark.push(forward);
+            self.to_landmark.push(backward);
+
+            // systems no landmark reaches yet come first, so every component gets one
+            next = match closest
+                .iter()
+                .enumerate()
+                .filter(|(_, d)| **d != 0)
+                .max_by_key(|(i, d)| (**d, Reverse(*i)))
+            {
+                Some((i, _)) => i,
+                None => break,
+            };
+        }
+    }
+
+    fn sweep<F>(&self, start: usize, neighbours: F) -> Vec<Cost>
+    where
+        F: Fn(usize) -> Vec<(usize, Cost)>,
+    {
+        let mut dist = vec![UNREACHABLE; self.ids.len()];
+        let mut heap = BinaryHeap::new();
+        dist[start] = 0;
+        heap.push(Reverse((0, start)));
+        while let Some(Reverse((d, v))) = heap.pop() {
+            if d > dist[v] {
+                continue;
+            }
+            for (w, c) in neighbours(v) {
+                let nd = d + c;
+                if nd < dist[w] {
+                    dist[w] = nd;
+                    heap.push(Reverse((nd, w)));
+                }
+            }
+        }
+        dist
+    }
+
+    /// Lower bound of the cost from `v` to `t` derived from the landmark tables.
+    fn lower_bound(&self, v: usize, t: usize) -> Cost {
+        let mut bound = 0;
+        for (from, to) in self.from_landmark.iter().zip(self.to_landmark.iter()) {
+            if from[v] != UNREACHABLE && from[t] != UNREACHABLE {
+                bound = bound.max(from[t].saturating_sub(from[v]));
+            }
+            if to[v] != UNREACHABLE && to[t] != UNREACHABLE {
+                bound = bound.max(to[v].saturating_sub(to[t]));
+            }
+        }
+        bound
+    }
+
+    /// Finds the cheapest route between two systems of the preprocessed universe.
+    pub fn route(&self, from: &types::SystemId, to: &types::SystemId) -> Option<Path<'a>> {
+        self.search(self.universe, &[], &HashSet::new(), from, to)
+    }
+
+    /// Finds the cheapest route between two systems, additionally considering the
+    /// connections of the given extended universe. The extended universe is expected
+    /// to extend the universe this graph was built from.
+    pub fn route_extended<'b, U>(
+        &self,
+        extended: &'b types::ExtendedUniverse<U>,
+        from: &types::SystemId,
+        to: &types::SystemId,
+    ) -> Option<Path<'b>>
+    where
+        U: types::Navigatable,
+    {
+        let overlay = extended
+            .connections
+            .0
+            .values()
+            .flatten()
+            .filter(|c| !extended.removed.contains(&(c.from, c.to)))
+            .collect::<Vec<_>>();
+        self.search(extended, &overlay, &extended.removed, from, to)
+    }
+
+    fn search<'b>(
+        &self,
+        universe: &'b dyn types::Navigatable,
+        overlay: &[&types::Connection],
+        removed: &HashSet<(types::SystemId, types::SystemId)>,
+        from: &types::SystemId,
+        to: &types::SystemId,
+    ) -> Option<Path<'b>> {
+        let s = *self.index.get(from)?;
+        let t = *self.index.get(to)?;
+
+        // overlay edges as (tail, head, connection index) on the dense indices
+        let mut overlay_out: HashMap<usize, Vec<(usize, usize)>> = HashMap::new();
+        for (i, conn) in overlay.iter().enumerate() {
+            if let (Some(u), Some(w)) = (self.index.get(&conn.from), self.index.get(&conn.to)) {
+                overlay_out.entry(*u).or_default().push((*w, i));
+            }
+        }
+        // cheapest way to finish the route after taking any overlay edge
+        let overlay_exit = overlay_out
+            .values()
+            .flatten()
+            .map(|(w, _)| self.node_cost[*w].saturating_add(self.lower_bound(*w, t)))
+            .min();
+        let heuristic = |v: usize| -> Cost {
+            let direct = self.lower_bound(v, t);
+            match overlay_exit {
+                Some(exit) => overlay_out
+                    .keys()
+                    .map(|u| self.lower_bound(v, *u).saturating_add(exit))
+                    .min()
+                    .map_or(direct, |via| direct.min(via)),
+                None => direct,
+            }
+        };
+
+        let mut dist = vec![UNREACHABLE; self.ids.len()];
+        let mut parent: Vec<Option<(usize, Edge)>> = vec![None; self.ids.len()];
+        let mut heap = BinaryHeap::new();
+        dist[s] = 0;
+        heap.push(Reverse((heuristic(s), s)));
+        while let Some(Reverse((_, v))) = heap.pop() {
+            if v == t {
+                break;
+            }
+            let d = dist[v];
+            let base = (self.offsets[v]..self.offsets[v + 1])
+                .filter(|e| !removed.contains(&(self.ids[v], self.ids[self.targets[*e]])))
+                .map(|e| (self.targets[e], Edge::Base(e)));
+            let extra = overlay_out
+                .get(&v)
+                .into_iter()
+                .flatten()
+                .map(|(w, i)| (*w, Edge::Overlay(*i)));
+            for (w, edge) in base.chain(extra) {
+                let nd = d + self.node_cost[w];
+                if nd < dist[w] {
+                    dist[w] = nd;
+                    parent[w] = Some((v, edge));
+                    heap.push(Reverse((nd.saturating_add(heuristic(w)), w)));
+                }
+            }
+        }
+        if dist[t] == UNREACHABLE {
+            return None;
+        }
+
+        let mut result = vec![PathElementInternal::Waypoint(self.ids[t])];
+        let mut v = t;
+        while let Some((u, edge)) = parent[v] {
+            let (type_, source) = match edge {
+                Edge::Base(e) => (self.connection_types[e].clone(), self.connection_sources[e]),
+                Edge::Overlay(i) => (overlay[i].type_.clone(), overlay[i].source),
+            };
+            result.push(PathElementInternal::Connection(type_, source));
+            result.push(if u == s {
+                PathElementInternal::Waypoint(self.ids[u])
+            } else {
+                PathElementInternal::System(self.ids[u])
+            });
+            v = u;
+        }
+        result.reverse();
+        let jumps = (result.len() - 1) / 2;
+        let waypoints = vec![universe.get_system(from)?, universe.get_system(to)?];
+        Some(Path::new(universe, waypoints, result, jumps))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation::{costs_from, PathBuilder};
+    use crate::testing::*;
+    use crate::types::{Galaxy, Navigatable};
+
+    #[test]
+    fn test_matches_dijkstra() {
+        let universe = universe();
+        for preference in [
+            Preference::Shortest,
+            Preference::Highsec,
+            Preference::LowsecAndNullsec,
+        ] {
+            let graph = RoutingGraph::new(&universe, preference);
+            for a in universe.systems() {
+                let costs = costs_from(&universe, &preference, a.id);
+                for b in universe.systems() {
+                    let path = graph.route(&a.id, &b.id).unwrap();
+                    let cost = path
+                        .systems()
+                        .skip(1)
+                        .map(|s| preference.system_cost(&universe, s.id))
+                        .sum::<Cost>();
+                    assert_eq!(costs[&b.id], cost);
+                    assert_eq!(Some(a), path.from());
+                    assert_eq!(Some(b), path.to());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_route_extended() {
+        let universe = universe();
+        let graph = RoutingGraph::new(&universe, Preference::Shortest);
+        let extended = universe.extend(
+            vec![types::Connection {
+                from: ALPHA,
+                to: DELTA,
+                type_: types::ConnectionType::Wormhole(types::WormholeType::Small),
+                source: types::ConnectionSource::UserOverlay,
+            }]
+            .into(),
+        );
+        let expected = PathBuilder::new(&extended)
+            .waypoint(universe.get_system(&ALPHA).unwrap())
+            .waypoint(universe.get_system(&ECHO).unwrap())
+            .build()
+            .unwrap();
+        let path = graph.route_extended(&extended, &ALPHA, &ECHO).unwrap();
+        assert_eq!(expected.jumps(), path.jumps());
+        assert_eq!(2, path.jumps());
+        assert_eq!(4, graph.route(&ALPHA, &ECHO).unwrap().jumps());
+    }
+
+    #[test]
+    fn test_route_extended_removed() {
+        let universe = universe();
+        let graph = RoutingGraph::new(&universe, Preference::Shortest);
+        let mut extended = universe.extend(types::AdjacentMap::empty());
+        extended.close_gate(CHARLIE, DELTA);
+        let path = graph.route_extended(&extended, &ALPHA, &ECHO).unwrap();
+        assert_eq!(5, path.jumps());
+        assert_eq!(
+            vec![ALPHA, BRAVO, CHARLIE, GOLF, DELTA, ECHO],
+            path.systems().map(|s| s.id).collect::<Vec<_>>()
+        );
+
+        extended.close_gate(DELTA, ECHO);
+        assert!(graph.route_extended(&extended, &ALPHA, &ECHO).is_none());
+    }
+
+    #[test]
+    fn test_unknown_system() {
+        let universe = universe();
+        let graph = RoutingGraph::new(&universe, Preference::Shortest);
+        assert!(graph.route(&ALPHA, &types::SystemId(1)).is_none());
+    }
+}
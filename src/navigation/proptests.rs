@@ -0,0 +1,108 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Router invariants checked against pseudo-random universes.
+//!
+//! Failing cases are reduced by proptest to the smallest universe and route that
+//! still break an invariant. Reproduce one with `pseudo_universe` and the printed
+//! arguments.
+use proptest::prelude::*;
+
+use crate::navigation::{Path, PathBuilder};
+use crate::testing::pseudo_universe;
+use crate::types::{self, Navigatable};
+
+const FIRST: u32 = 30000001;
+
+/// A universe with up to 30 systems and a route request within it.
+fn universe_and_route() -> impl Strategy<Value = (u64, usize, usize, u32, u32)> {
+    (any::<u64>(), 2..30usize, 0..60usize).prop_flat_map(|(seed, systems, connections)| {
+        (
+            Just(seed),
+            Just(systems),
+            Just(connections),
+            0..systems as u32,
+            0..systems as u32,
+        )
+    })
+}
+
+fn route<'a>(
+    universe: &'a dyn Navigatable,
+    from: types::SystemId,
+    to: types::SystemId,
+) -> Option<Path<'a>> {
+    PathBuilder::new(universe)
+        .waypoint(universe.get_system(&from).unwrap())
+        .waypoint(universe.get_system(&to).unwrap())
+        .build()
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(128))]
+
+    #[test]
+    fn path_matches_waypoints_and_is_connected(
+        (seed, systems, connections, from, to) in universe_and_route()
+    ) {
+        let universe = pseudo_universe(seed, systems, connections);
+        let (from, to) = (types::SystemId(FIRST + from), types::SystemId(FIRST + to));
+        if let Some(path) = route(&universe, from, to) {
+            prop_assert_eq!(Some(from), path.from().map(|s| s.id));
+            prop_assert_eq!(Some(to), path.to().map(|s| s.id));
+            let waypoints = path.waypoints().iter().map(|s| s.id).collect::<Vec<_>>();
+            prop_assert_eq!(vec![from, to], waypoints);
+
+            let systems = path.systems().map(|s| s.id).collect::<Vec<_>>();
+            prop_assert_eq!(path.jumps() + 1, systems.len());
+            for pair in systems.windows(2) {
+                let connected = universe
+                    .get_connections(&pair[0])
+                    .unwrap_or_default()
+                    .iter()
+                    .any(|c| c.to == pair[1]);
+                prop_assert!(connected, "no connection from {:?} to {:?}", pair[0], pair[1]);
+            }
+        }
+    }
+
+    #[test]
+    fn path_agrees_with_core_router(
+        (seed, systems, connections, from, to) in universe_and_route()
+    ) {
+        let universe = pseudo_universe(seed, systems, connections);
+        let (from, to) = (types::SystemId(FIRST + from), types::SystemId(FIRST + to));
+        let jumps = route(&universe, from, to).map(|p| p.jumps());
+        let core = if from == to {
+            Some(0)
+        } else {
+            universe.to_graph().shortest_path(from, to).map(|(_, cost)| cost as usize)
+        };
+        prop_assert_eq!(core, jumps);
+    }
+
+    #[test]
+    fn removing_a_connection_never_shortens_a_path(
+        (seed, systems, connections, from, to) in universe_and_route(),
+        pick in any::<prop::sample::Index>(),
+    ) {
+        let universe = pseudo_universe(seed, systems, connections);
+        let (from, to) = (types::SystemId(FIRST + from), types::SystemId(FIRST + to));
+        let before = route(&universe, from, to).map(|p| p.jumps());
+
+        let pairs = types::Galaxy::connections(&universe);
+        prop_assume!(!pairs.is_empty());
+        let (a, b) = pairs[pick.index(pairs.len())];
+        let mut extended = universe.extend(types::AdjacentMap::empty());
+        extended.close_gate(a, b);
+        let after = route(&extended, from, to).map(|p| p.jumps());
+
+        match (before, after) {
+            (Some(before), Some(after)) => prop_assert!(after >= before),
+            (None, after) => prop_assert_eq!(None, after),
+            (Some(_), None) => {}
+        }
+    }
+}
@@ -0,0 +1,380 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Dijkstra over buffers that are reused across searches.
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::navigation::Cost;
+use crate::types;
+
+/// How a system was reached: the index of the last hop in the connections of the
+/// previous system, as returned by `Navigatable::get_connections`. Connection types
+/// are only resolved for the final route instead of cloned for every expanded edge.
+pub(crate) type Via = u32;
+
+/// The distance and came-from tables of one kind of search, indexed by slot.
+#[derive(Debug, Default)]
+struct Tables {
+    costs: Vec<Cost>,
+    previous: Vec<Option<(u32, Via)>>,
+    /// The search in which a slot was last reached. Slots of earlier searches count
+    /// as unreached, so the tables never have to be cleared.
+    reached: Vec<u32>,
+}
+
+impl Tables {
+    fn len(&self) -> usize {
+        self.costs.len()
+    }
+
+    fn grow(&mut self, len: usize) {
+        if len > self.len() {
+            self.costs.resize(len, 0);
+            self.previous.resize(len, None);
+            self.reached.resize(len, 0);
+        }
+    }
+}
+
+/// Owns the distance table, the came-from table and the priority queue of route
+/// searches. Short routes spend most of their time allocating these, so services
+/// answering many queries should keep a router around and pass it to
+/// `PathBuilder::try_build_with`. The buffers grow to the number of systems seen
+/// and are never shrunk.
+///
+/// A router is not tied to a universe, but sticking to one keeps the buffers small.
+///
+/// # Example
+/// ```
+/// use neweden::builder::UniverseBuilder;
+/// use neweden::navigation::{PathBuilder, Router};
+/// use neweden::{Coordinate, Navigatable, System};
+///
+/// let universe = UniverseBuilder::new()
+///     .system(System::new(1.into(), "Lonely", Coordinate { x: 0.0, y: 0.0, z: 0.0 }, 0.5.into()))
+///     .build();
+/// let lonely = universe.get_system(&1.into()).unwrap();
+/// let mut router = Router::new();
+/// for _ in 0..3 {
+///     let path = PathBuilder::new(&universe)
+///         .waypoint(lonely)
+///         .waypoint(lonely)
+///         .try_build_with(&mut router)
+///         .unwrap();
+///     assert_eq!(0, path.jumps());
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct Router {
+    slots: HashMap<types::SystemId, u32>,
+    ids: Vec<types::SystemId>,
+    /// Tables of searches by system id, slots are handed out by `slots`.
+    sparse: Tables,
+    /// Tables of searches on a `CompactAdjacency`, slots are its node indices.
+    dense: Tables,
+    search: u32,
+    queue: BinaryHeap<Reverse<(Cost, u32)>>,
+    /// Successors of the system being expanded.
+    scratch: Vec<(u32, Via, Cost)>,
+}
+
+impl Router {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of systems the buffers have room for.
+    pub fn capacity(&self) -> usize {
+        self.sparse.len().max(self.dense.len())
+    }
+
+    fn next_search(&mut self) -> u32 {
+        self.search = self.search.wrapping_add(1);
+        if self.search == 0 {
+            self.sparse.reached.fill(0);
+            self.dense.reached.fill(0);
+            self.search = 1;
+        }
+        self.search
+    }
+
+    /// Finds the cheapest route from `from` to `to`, returning every system with the
+    /// hop it was reached by, starting with `from`.
+    pub(crate) fn route<F>(
+        &mut self,
+        from: types::SystemId,
+        to: types::SystemId,
+        mut successors: F,
+    ) -> Option<Vec<(types::SystemId, Option<Via>)>>
+    where
+        F: FnMut(&types::SystemId) -> Vec<(types::SystemId, Via, Cost)>,
+    {
+        let search = self.next_search();
+        let Self {
+            slots,
+            ids,
+            sparse,
+            queue,
+            scratch,
+            ..
+        } = self;
+        let start = slot(slots, ids, from);
+        let goal = slot(slots, ids, to);
+        let route = dijkstra(sparse, queue, scratch, search, start, goal, |i, out| {
+            let id = ids[i as usize];
+            for (next, via, cost) in successors(&id) {
+                out.push((slot(slots, ids, next), via, cost));
+            }
+        })?;
+        Some(
+            route
+                .into_iter()
+                .map(|(i, via)| (self.ids[i as usize], via))
+                .collect(),
+        )
+    }
+
+    /// Finds the cheapest routes from `from` to each of `to` in one search, in the
+    /// order of their cost. With `first` only the cheapest is returned. Unreachable
+    /// systems are left out.
+    pub(crate) fn routes<F>(
+        &mut self,
+        from: types::SystemId,
+        to: &[types::SystemId],
+        first: bool,
+        mut successors: F,
+    ) -> Vec<Vec<(types::SystemId, Option<Via>)>>
+    where
+        F: FnMut(&types::SystemId) -> Vec<(types::SystemId, Via, Cost)>,
+    {
+        let search = self.next_search();
+        let Self {
+            slots,
+            ids,
+            sparse,
+            queue,
+            scratch,
+            ..
+        } = self;
+        let start = slot(slots, ids, from);
+        let mut goals = to
+            .iter()
+            .map(|id| slot(slots, ids, *id))
+            .collect::<Vec<_>>();
+        goals.sort_unstable();
+        goals.dedup();
+        let mut settled = Vec::new();
+        sweep(
+            sparse,
+            queue,
+            scratch,
+            search,
+            start,
+            |slot| {
+                if goals.binary_search(&slot).is_ok() {
+                    settled.push(slot);
+                }
+                (first && !settled.is_empty()) || settled.len() == goals.len()
+            },
+            |i, out| {
+                let id = ids[i as usize];
+                for (next, via, cost) in successors(&id) {
+                    out.push((slot(slots, ids, next), via, cost));
+                }
+            },
+        );
+        settled
+            .into_iter()
+            .map(|goal| {
+                unwind(sparse, goal)
+                    .into_iter()
+                    .map(|(i, via)| (ids[i as usize], via))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Like `route`, but on the node indices of a `CompactAdjacency`, which saves
+    /// looking up slots. `successors` appends the successors of a node to the buffer.
+    pub(crate) fn route_dense<F>(
+        &mut self,
+        nodes: usize,
+        from: u32,
+        to: u32,
+        successors: F,
+    ) -> Option<Vec<(u32, Option<Via>)>>
+    where
+        F: FnMut(u32, &mut Vec<(u32, Via, Cost)>),
+    {
+        let search = self.next_search();
+        self.dense.grow(nodes);
+        dijkstra(
+            &mut self.dense,
+            &mut self.queue,
+            &mut self.scratch,
+            search,
+            from,
+            to,
+            successors,
+        )
+    }
+}
+
+/// The slot of a system in the sparse tables, handing out a new one if needed.
+fn slot(
+    slots: &mut HashMap<types::SystemId, u32>,
+    ids: &mut Vec<types::SystemId>,
+    id: types::SystemId,
+) -> u32 {
+    *slots.entry(id).or_insert_with(|| {
+        ids.push(id);
+        (ids.len() - 1) as u32
+    })
+}
+
+fn dijkstra<F>(
+    tables: &mut Tables,
+    queue: &mut BinaryHeap<Reverse<(Cost, u32)>>,
+    scratch: &mut Vec<(u32, Via, Cost)>,
+    search: u32,
+    start: u32,
+    goal: u32,
+    successors: F,
+) -> Option<Vec<(u32, Option<Via>)>>
+where
+    F: FnMut(u32, &mut Vec<(u32, Via, Cost)>),
+{
+    let mut found = false;
+    sweep(
+        tables,
+        queue,
+        scratch,
+        search,
+        start,
+        |slot| {
+            found = slot == goal;
+            found
+        },
+        successors,
+    );
+    found.then(|| unwind(tables, goal))
+}
+
+/// Settles systems in the order of their cost until `settled` returns true for one
+/// or all reachable systems are settled.
+fn sweep<S, F>(
+    tables: &mut Tables,
+    queue: &mut BinaryHeap<Reverse<(Cost, u32)>>,
+    scratch: &mut Vec<(u32, Via, Cost)>,
+    search: u32,
+    start: u32,
+    mut settled: S,
+    mut successors: F,
+) where
+    S: FnMut(u32) -> bool,
+    F: FnMut(u32, &mut Vec<(u32, Via, Cost)>),
+{
+    queue.clear();
+    reach(tables, queue, search, start, 0, None);
+    while let Some(Reverse((cost, slot))) = queue.pop() {
+        if cost > tables.costs[slot as usize] {
+            continue;
+        }
+        if settled(slot) {
+            return;
+        }
+        scratch.clear();
+        successors(slot, scratch);
+        for (next, via, step) in scratch.drain(..) {
+            let total = cost + step;
+            let n = next as usize;
+            if n >= tables.len() || tables.reached[n] != search || total < tables.costs[n] {
+                reach(tables, queue, search, next, total, Some((slot, via)));
+            }
+        }
+    }
+}
+
+fn reach(
+    tables: &mut Tables,
+    queue: &mut BinaryHeap<Reverse<(Cost, u32)>>,
+    search: u32,
+    slot: u32,
+    cost: Cost,
+    previous: Option<(u32, Via)>,
+) {
+    let i = slot as usize;
+    tables.grow(i + 1);
+    tables.reached[i] = search;
+    tables.costs[i] = cost;
+    tables.previous[i] = previous;
+    queue.push(Reverse((cost, slot)));
+}
+
+fn unwind(tables: &Tables, mut slot: u32) -> Vec<(u32, Option<Via>)> {
+    let mut route = Vec::new();
+    loop {
+        match tables.previous[slot as usize] {
+            Some((previous, via)) => {
+                route.push((slot, Some(via)));
+                slot = previous;
+            }
+            None => {
+                route.push((slot, None));
+                break;
+            }
+        }
+    }
+    route.reverse();
+    route
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation::{CostFunction, Preference};
+    use crate::testing::*;
+    use crate::types::Navigatable;
+
+    #[test]
+    fn test_router_reuse() {
+        let universe = universe();
+        let successors = |id: &types::SystemId| {
+            universe
+                .get_connections(id)
+                .unwrap_or_default()
+                .iter()
+                .enumerate()
+                .map(|(i, c)| {
+                    let cost = Preference::Shortest.cost(&universe, c).unwrap();
+                    (c.to, i as Via, cost)
+                })
+                .collect::<Vec<_>>()
+        };
+        let mut router = Router::new();
+        let route = router.route(ALPHA, ECHO, successors).unwrap();
+        assert_eq!(5, route.len());
+        assert!(route[0].1.is_none());
+        let capacity = router.capacity();
+
+        // a later search does not see the costs of the earlier one
+        let route = router.route(ECHO, DELTA, successors).unwrap();
+        assert_eq!(
+            vec![ECHO, DELTA],
+            route.iter().map(|r| r.0).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![HOTEL],
+            router
+                .route(HOTEL, HOTEL, successors)
+                .unwrap()
+                .iter()
+                .map(|r| r.0)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(capacity, router.capacity());
+    }
+}
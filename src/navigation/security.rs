@@ -0,0 +1,133 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! The stretches of a path in high, low and nullsec and where it crosses between
+//! them, e.g. for hauling tools to warn "you enter lowsec at Rancer (jump 14)".
+use crate::types;
+
+use super::Path;
+
+/// Consecutive systems of a path in the same security class.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SecurityLeg<'a> {
+    pub class: types::SecurityClass,
+    pub systems: Vec<&'a types::System>,
+    /// The jumps into the path of the first system of the leg, zero for the origin.
+    pub first_jump: usize,
+}
+
+/// A jump from a system of one security class into one of another.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SecurityCrossing<'a> {
+    /// The last system before the crossing.
+    pub from: &'a types::System,
+    /// The first system after the crossing.
+    pub to: &'a types::System,
+    /// The jumps into the path `to` is reached at.
+    pub jump: usize,
+}
+
+impl SecurityCrossing<'_> {
+    pub fn from_class(&self) -> types::SecurityClass {
+        self.from.security.into()
+    }
+
+    pub fn to_class(&self) -> types::SecurityClass {
+        self.to.security.into()
+    }
+}
+
+/// The systems of the path, borrowed from its universe rather than the path.
+fn systems<'p, 'a>(path: &'p Path<'a>) -> impl Iterator<Item = &'a types::System> + 'p {
+    path.path
+        .iter()
+        .filter_map(|e| path.universe.get_system(e.system()?))
+}
+
+impl<'a> Path<'a> {
+    /// The path split into stretches of the same security class, in order.
+    pub fn security_legs(&self) -> Vec<SecurityLeg<'a>> {
+        let mut legs: Vec<SecurityLeg<'a>> = Vec::new();
+        for (jump, system) in systems(self).enumerate() {
+            let class = types::SecurityClass::from(system.security);
+            match legs.last_mut() {
+                Some(leg) if leg.class == class => leg.systems.push(system),
+                _ => legs.push(SecurityLeg {
+                    class,
+                    systems: vec![system],
+                    first_jump: jump,
+                }),
+            }
+        }
+        legs
+    }
+
+    /// Every jump between systems of different security classes, in order.
+    pub fn security_crossings(&self) -> Vec<SecurityCrossing<'a>> {
+        self.security_legs()
+            .windows(2)
+            .map(|legs| SecurityCrossing {
+                from: legs[0].systems.last().unwrap(),
+                to: legs[1].systems[0],
+                jump: legs[1].first_jump,
+            })
+            .collect()
+    }
+
+    /// The first jump into a system of `class` from one of another class, e.g. the
+    /// first lowsec system of a route from highsec. `None` if the path never enters
+    /// the class or starts in it and never leaves.
+    pub fn first_entry(&self, class: types::SecurityClass) -> Option<SecurityCrossing<'a>> {
+        self.security_crossings()
+            .into_iter()
+            .find(|c| c.to_class() == class)
+    }
+
+    /// The last jump out of a system of `class`, e.g. from the last highsec system
+    /// before nullsec.
+    pub fn last_exit(&self, class: types::SecurityClass) -> Option<SecurityCrossing<'a>> {
+        self.security_crossings()
+            .into_iter()
+            .rev()
+            .find(|c| c.from_class() == class)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::navigation::PathBuilder;
+    use crate::testing::*;
+    use crate::types::{Navigatable, SecurityClass};
+
+    #[test]
+    fn test_security_legs() {
+        let universe = universe();
+        let path = PathBuilder::new(&universe)
+            .waypoint(universe.get_system(&ALPHA).unwrap())
+            .waypoint(universe.get_system(&ECHO).unwrap())
+            .build()
+            .unwrap();
+        let legs = path.security_legs();
+        assert_eq!(
+            vec![
+                (SecurityClass::Highsec, 0, 3),
+                (SecurityClass::Lowsec, 3, 1),
+                (SecurityClass::Nullsec, 4, 1)
+            ],
+            legs.iter()
+                .map(|l| (l.class.clone(), l.first_jump, l.systems.len()))
+                .collect::<Vec<_>>()
+        );
+
+        let crossings = path.security_crossings();
+        assert_eq!(2, crossings.len());
+        let lowsec = path.first_entry(SecurityClass::Lowsec).unwrap();
+        assert_eq!((DELTA, 3), (lowsec.to.id, lowsec.jump));
+        let highsec = path.last_exit(SecurityClass::Highsec).unwrap();
+        assert_eq!(CHARLIE, highsec.from.id);
+        assert_eq!(SecurityClass::Nullsec, crossings[1].to_class());
+        assert!(path.first_entry(SecurityClass::Highsec).is_none());
+    }
+}
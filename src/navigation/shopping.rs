@@ -0,0 +1,308 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Consolidated shopping for several characters, e.g. for corp buyback programs.
+//!
+//! The shopping lists of all characters are pooled, items on several lists are
+//! bought once, and every item is assigned to the character and market that adds
+//! the fewest jumps. All purchases are delivered to a single system. The
+//! assignment uses cheapest insertion, which is fast but not guaranteed optimal.
+use std::collections::HashMap;
+
+use crate::types;
+
+use super::{costs_from, Cost, Path, PathBuilder, Preference};
+
+/// An item and the systems it can be bought in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    pub name: String,
+    pub markets: Vec<types::SystemId>,
+}
+
+impl Item {
+    pub fn new(name: &str, markets: Vec<types::SystemId>) -> Self {
+        Self {
+            name: name.to_string(),
+            markets,
+        }
+    }
+}
+
+/// A market a character stops at and the items bought there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Purchase {
+    pub system: types::SystemId,
+    pub items: Vec<String>,
+}
+
+/// The purchases of one character and the route from its location through the
+/// markets to the delivery system.
+pub struct Assignment<'a> {
+    pub character: String,
+    pub purchases: Vec<Purchase>,
+    pub path: Path<'a>,
+}
+
+/// The result of `ShoppingPlanner::plan`. Characters without purchases stay where
+/// they are and have no assignment.
+pub struct ShoppingPlan<'a> {
+    pub assignments: Vec<Assignment<'a>>,
+}
+
+impl ShoppingPlan<'_> {
+    /// The jumps of all routes together.
+    pub fn jumps(&self) -> usize {
+        self.assignments.iter().map(|a| a.path.jumps()).sum()
+    }
+}
+
+/// Splits the pooled shopping lists of several characters among them.
+///
+/// # Example
+/// ```
+/// use neweden::builder::UniverseBuilder;
+/// use neweden::navigation::shopping::{Item, ShoppingPlanner};
+/// use neweden::{Connection, ConnectionSource, ConnectionType, Coordinate, Navigatable, StargateType, System};
+///
+/// let system = |id: u32, name: &str| {
+///     System::new(id.into(), name, Coordinate { x: 0.0, y: 0.0, z: 0.0 }, 0.9.into())
+/// };
+/// let gate = |from: u32, to: u32| Connection {
+///     from: from.into(),
+///     to: to.into(),
+///     type_: ConnectionType::Stargate(StargateType::Local),
+///     source: ConnectionSource::Sde,
+/// };
+/// let universe = UniverseBuilder::new()
+///     .system(system(1, "Office"))
+///     .system(system(2, "Market"))
+///     .connection_bidirectional(gate(1, 2))
+///     .build();
+/// let sys = |id: u32| universe.get_system(&id.into()).unwrap();
+///
+/// let plan = ShoppingPlanner::new(&universe, sys(1))
+///     .character("Buyer", sys(1), vec![Item::new("Tritanium", vec![2.into()])])
+///     .plan()
+///     .unwrap();
+/// assert_eq!(2, plan.jumps()); // to the market and back
+/// ```
+pub struct ShoppingPlanner<'a> {
+    universe: &'a dyn types::Navigatable,
+    delivery: &'a types::System,
+    characters: Vec<(String, &'a types::System, Vec<Item>)>,
+    preference: Preference,
+}
+
+impl<'a> ShoppingPlanner<'a> {
+    /// `delivery` is where all routes end, e.g. the corp buyback office.
+    pub fn new(universe: &'a dyn types::Navigatable, delivery: &'a types::System) -> Self {
+        Self {
+            universe,
+            delivery,
+            characters: Vec::new(),
+            preference: Preference::Shortest,
+        }
+    }
+
+    /// A character, where it currently is and its shopping list.
+    pub fn character(mut self, name: &str, location: &'a types::System, list: Vec<Item>) -> Self {
+        self.add_character(name, location, list);
+        self
+    }
+
+    pub fn prefer(mut self, preference: Preference) -> Self {
+        self.preference = preference;
+        self
+    }
+
+    /// Like `character`, but for use when the planner is not consumed, e.g. in loops.
+    pub fn add_character(
+        &mut self,
+        name: &str,
+        location: &'a types::System,
+        list: Vec<Item>,
+    ) -> &mut Self {
+        self.characters.push((name.to_string(), location, list));
+        self
+    }
+
+    /// Computes the plan. Returns `None` if an item has no market any character can
+    /// reach and deliver from.
+    pub fn plan(self) -> Option<ShoppingPlan<'a>> {
+        let items = self.pooled_items();
+
+        let mut costs = HashMap::new();
+        let sources = self
+            .characters
+            .iter()
+            .map(|(_, location, _)| location.id)
+            .chain(items.iter().flat_map(|i| i.markets.iter().copied()));
+        for id in sources {
+            costs
+                .entry(id)
+                .or_insert_with(|| costs_from(self.universe, &self.preference, id));
+        }
+        let cost = |a: types::SystemId, b: types::SystemId| -> Option<Cost> {
+            costs.get(&a)?.get(&b).copied()
+        };
+
+        // the most constrained items first, they leave the least choice
+        let mut order = (0..items.len()).collect::<Vec<_>>();
+        order.sort_by_key(|i| items[*i].markets.len());
+
+        let mut stops: Vec<Vec<Purchase>> = vec![Vec::new(); self.characters.len()];
+        for i in order {
+            let item = &items[i];
+            // buying at a stop that is already planned costs nothing
+            let planned = stops
+                .iter_mut()
+                .flatten()
+                .find(|p| item.markets.contains(&p.system));
+            if let Some(purchase) = planned {
+                purchase.items.push(item.name.clone());
+                continue;
+            }
+
+            // ties go to the character with the shorter route, spreading the work
+            let mut best: Option<((i64, i64), usize, usize, types::SystemId)> = None;
+            for (c, (_, location, _)) in self.characters.iter().enumerate() {
+                let route = &stops[c];
+                let load = match route.is_empty() {
+                    true => 0,
+                    false => std::iter::once(location.id)
+                        .chain(route.iter().map(|p| p.system))
+                        .chain(std::iter::once(self.delivery.id))
+                        .collect::<Vec<_>>()
+                        .windows(2)
+                        .map(|leg| cost(leg[0], leg[1]).map(i64::from))
+                        .sum::<Option<i64>>()?,
+                };
+                for position in 0..=route.len() {
+                    let previous = match position {
+                        0 => location.id,
+                        _ => route[position - 1].system,
+                    };
+                    let next = route.get(position).map_or(self.delivery.id, |p| p.system);
+                    // idle characters do not have to move at all
+                    let base = match route.is_empty() {
+                        true => 0,
+                        false => i64::from(cost(previous, next)?),
+                    };
+                    for market in &item.markets {
+                        let (Some(to), Some(from)) = (cost(previous, *market), cost(*market, next))
+                        else {
+                            continue;
+                        };
+                        let key = (i64::from(to) + i64::from(from) - base, load);
+                        if best.is_none_or(|(b, ..)| key < b) {
+                            best = Some((key, c, position, *market));
+                        }
+                    }
+                }
+            }
+            let (_, c, position, market) = best?;
+            stops[c].insert(
+                position,
+                Purchase {
+                    system: market,
+                    items: vec![item.name.clone()],
+                },
+            );
+        }
+
+        let mut assignments = Vec::new();
+        for ((name, location, _), purchases) in self.characters.iter().zip(stops) {
+            if purchases.is_empty() {
+                continue;
+            }
+            let mut waypoints = vec![*location];
+            for purchase in &purchases {
+                waypoints.push(self.universe.get_system(&purchase.system)?);
+            }
+            waypoints.push(self.delivery);
+            let path = PathBuilder::new(self.universe)
+                .waypoints(waypoints)
+                .prefer(self.preference)
+                .build()?;
+            assignments.push(Assignment {
+                character: name.clone(),
+                purchases,
+                path,
+            });
+        }
+        Some(ShoppingPlan { assignments })
+    }
+
+    /// All items of all lists, where an item on several lists is bought once from any
+    /// of the markets given for it.
+    fn pooled_items(&self) -> Vec<Item> {
+        let mut items: Vec<Item> = Vec::new();
+        for item in self.characters.iter().flat_map(|(_, _, list)| list) {
+            match items.iter_mut().find(|i| i.name == item.name) {
+                Some(pooled) => {
+                    for market in &item.markets {
+                        if !pooled.markets.contains(market) {
+                            pooled.markets.push(*market);
+                        }
+                    }
+                }
+                None => items.push(item.clone()),
+            }
+        }
+        items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+    use crate::types::Navigatable;
+
+    #[test]
+    fn test_shopping_plan() {
+        let universe = universe();
+        let sys = |id| universe.get_system(&id).unwrap();
+        let plan = ShoppingPlanner::new(&universe, sys(CHARLIE))
+            .character(
+                "Alpha",
+                sys(ALPHA),
+                vec![Item::new("ice", vec![BRAVO]), Item::new("ore", vec![DELTA])],
+            )
+            .character(
+                "Echo",
+                sys(ECHO),
+                vec![
+                    Item::new("ice", vec![BRAVO]),
+                    Item::new("gas", vec![ECHO, HOTEL]),
+                ],
+            )
+            .plan()
+            .unwrap();
+
+        let mut bought = plan
+            .assignments
+            .iter()
+            .flat_map(|a| a.purchases.iter().flat_map(|p| p.items.clone()))
+            .collect::<Vec<_>>();
+        bought.sort();
+        assert_eq!(vec!["gas", "ice", "ore"], bought);
+        for assignment in &plan.assignments {
+            assert_eq!(Some(sys(CHARLIE)), assignment.path.to());
+        }
+        assert_eq!(4, plan.jumps());
+    }
+
+    #[test]
+    fn test_shopping_plan_unknown_market() {
+        let universe = universe();
+        let sys = |id| universe.get_system(&id).unwrap();
+        assert!(ShoppingPlanner::new(&universe, sys(CHARLIE))
+            .character("Alpha", sys(ALPHA), vec![Item::new("ice", vec![1.into()])])
+            .plan()
+            .is_none());
+    }
+}
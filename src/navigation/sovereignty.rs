@@ -0,0 +1,148 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Routing around space held by hostile alliances and militias.
+//!
+//! # Example
+//! ```
+//! use neweden::builder::UniverseBuilder;
+//! use neweden::navigation::sovereignty::HostileSovereignty;
+//! use neweden::navigation::{PathBuilder, Preference};
+//!
+//! let universe = UniverseBuilder::new().build();
+//! let hostile = HostileSovereignty::new(Preference::Shortest)
+//!     .alliance(99005338.into())
+//!     .faction(500001.into());
+//! let builder = PathBuilder::new(&universe).cost_function(hostile);
+//! ```
+use std::collections::HashSet;
+
+use crate::navigation::{Cost, CostFunction, Preference};
+use crate::types;
+
+/// Entering hostile space costs as much as a system of the wrong security.
+pub const HOSTILE_PENALTY: Cost = 1000;
+
+/// A cost function avoiding systems held by hostile alliances or occupied by hostile
+/// faction warfare militias, according to `Navigatable::get_sovereignty`. Hostile
+/// space is still entered if there is no way around.
+#[derive(Debug, Clone)]
+pub struct HostileSovereignty {
+    preference: Preference,
+    alliances: HashSet<types::AllianceId>,
+    factions: HashSet<types::FactionId>,
+}
+
+impl HostileSovereignty {
+    /// Routes by the preference outside of hostile space.
+    pub fn new(preference: Preference) -> Self {
+        Self {
+            preference,
+            alliances: HashSet::new(),
+            factions: HashSet::new(),
+        }
+    }
+
+    pub fn alliance(mut self, alliance: types::AllianceId) -> Self {
+        self.add_alliance(alliance);
+        self
+    }
+
+    /// A faction whose own and occupied systems are hostile.
+    pub fn faction(mut self, faction: types::FactionId) -> Self {
+        self.add_faction(faction);
+        self
+    }
+
+    pub fn add_alliance(&mut self, alliance: types::AllianceId) -> &mut Self {
+        self.alliances.insert(alliance);
+        self
+    }
+
+    pub fn add_faction(&mut self, faction: types::FactionId) -> &mut Self {
+        self.factions.insert(faction);
+        self
+    }
+
+    pub fn is_hostile(&self, sovereignty: &types::Sovereignty) -> bool {
+        sovereignty
+            .alliance
+            .is_some_and(|a| self.alliances.contains(&a))
+            || [sovereignty.faction, sovereignty.occupier]
+                .iter()
+                .flatten()
+                .any(|f| self.factions.contains(f))
+    }
+}
+
+impl CostFunction for HostileSovereignty {
+    fn cost(
+        &self,
+        universe: &dyn types::Navigatable,
+        connection: &types::Connection,
+    ) -> Option<Cost> {
+        let cost = self.preference.cost(universe, connection)?;
+        let hostile = universe
+            .get_sovereignty(&connection.to)
+            .is_some_and(|sov| self.is_hostile(sov));
+        Some(if hostile {
+            cost + HOSTILE_PENALTY
+        } else {
+            cost
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation::PathBuilder;
+    use crate::testing::*;
+    use crate::types::Navigatable;
+
+    #[test]
+    fn test_hostile_sovereignty() {
+        let goons = types::AllianceId(1354830081);
+        let amarr = types::FactionId(500003);
+        let held = |alliance, occupier| types::Sovereignty {
+            alliance,
+            occupier,
+            ..Default::default()
+        };
+        let universe = universe().with_sovereignty(
+            vec![
+                (CHARLIE, held(Some(goons), None)),
+                (GOLF, held(None, Some(amarr))),
+                (FOXTROT, held(Some(types::AllianceId(1)), None)),
+            ]
+            .into(),
+        );
+        assert_eq!(
+            vec![CHARLIE],
+            universe
+                .systems_owned_by(goons)
+                .iter()
+                .map(|s| s.id)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(GOLF, universe.systems_occupied_by(amarr)[0].id);
+
+        let route = |hostile: HostileSovereignty| {
+            PathBuilder::new(&universe)
+                .waypoint(universe.get_system(&BRAVO).unwrap())
+                .waypoint(universe.get_system(&DELTA).unwrap())
+                .cost_function(hostile)
+                .build()
+                .unwrap()
+                .systems()
+                .map(|s| s.id)
+                .collect::<Vec<_>>()
+        };
+        let hostile = HostileSovereignty::new(Preference::Shortest).alliance(goons);
+        assert_eq!(vec![BRAVO, FOXTROT, GOLF, DELTA], route(hostile.clone()));
+        // no way around, both routes cross hostile space
+        assert_eq!(vec![BRAVO, CHARLIE, DELTA], route(hostile.faction(amarr)));
+    }
+}
@@ -0,0 +1,389 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Routes that have to visit one system out of each of several groups, e.g.
+//! "any ice belt system, then any L4 agent hub, then any market hub". This is
+//! commonly known as the generalized (or set) traveling salesman problem.
+use std::collections::HashMap;
+
+use crate::types;
+
+use super::{costs_from, Cost, Path, PathBuilder, Preference};
+
+/// Up to this many groups the tour is solved exactly. Beyond that a nearest
+/// neighbour heuristic is used, as the exact solver is exponential in the number
+/// of groups.
+const EXACT_GROUP_LIMIT: usize = 12;
+
+/// Builds a route from a start system that visits at least one system of every group.
+///
+/// # Example
+/// ```
+/// use neweden::builder::UniverseBuilder;
+/// use neweden::navigation::tour::TourBuilder;
+/// use neweden::{Connection, ConnectionSource, ConnectionType, Coordinate, Navigatable, StargateType, System};
+///
+/// let system = |id: u32, name: &str| {
+///     System::new(id.into(), name, Coordinate { x: 0.0, y: 0.0, z: 0.0 }, 0.9.into())
+/// };
+/// let gate = |from: u32, to: u32| Connection {
+///     from: from.into(),
+///     to: to.into(),
+///     type_: ConnectionType::Stargate(StargateType::Local),
+///     source: ConnectionSource::Sde,
+/// };
+/// let universe = UniverseBuilder::new()
+///     .system(system(1, "Start"))
+///     .system(system(2, "Near"))
+///     .system(system(3, "Far"))
+///     .connection(gate(1, 2))
+///     .connection(gate(2, 3))
+///     .build();
+///
+/// let path = TourBuilder::new(&universe)
+///     .start(universe.get_system(&1.into()).unwrap())
+///     .group(vec![
+///         universe.get_system(&2.into()).unwrap(),
+///         universe.get_system(&3.into()).unwrap(),
+///     ])
+///     .build()
+///     .unwrap();
+/// assert_eq!(1, path.jumps()); // "Near" satisfies the group
+/// ```
+pub struct TourBuilder<'a> {
+    universe: &'a dyn types::Navigatable,
+    start: Option<&'a types::System>,
+    groups: Vec<Vec<&'a types::System>>,
+    preference: Preference,
+}
+
+impl<'a> TourBuilder<'a> {
+    pub fn new(universe: &'a dyn types::Navigatable) -> Self {
+        Self {
+            universe,
+            start: None,
+            groups: vec![],
+            preference: Preference::Shortest,
+        }
+    }
+
+    pub fn start(mut self, system: &'a types::System) -> Self {
+        self.start = Some(system);
+        self
+    }
+
+    /// Adds a group of candidate systems. The route will visit at least one of them.
+    pub fn group(mut self, systems: Vec<&'a types::System>) -> Self {
+        self.groups.push(systems);
+        self
+    }
+
+    pub fn prefer(mut self, preference: Preference) -> Self {
+        self.preference = preference;
+        self
+    }
+
+    /// Computes the tour. Returns `None` if no start was given, a group is empty or
+    /// a group cannot be reached from the start. Without groups the tour stays at
+    /// the start.
+    pub fn build(self) -> Option<Path<'a>> {
+        let start = self.start?;
+        if self.groups.is_empty() {
+            return PathBuilder::new(self.universe)
+                .waypoint(start)
+                .return_to_start()
+                .build();
+        }
+        let groups = self
+            .groups
+            .iter()
+            .map(|g| g.iter().map(|s| s.id).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        let mut costs = HashMap::new();
+        costs.insert(
+            start.id,
+            costs_from(self.universe, &self.preference, start.id),
+        );
+        for id in groups.iter().flatten() {
+            costs
+                .entry(*id)
+                .or_insert_with(|| costs_from(self.universe, &self.preference, *id));
+        }
+        let cost = |a: types::SystemId, b: types::SystemId| costs.get(&a)?.get(&b).copied();
+
+        let order = if groups.len() <= EXACT_GROUP_LIMIT {
+            solve_exact(start.id, &groups, None, cost)?
+        } else {
+            solve_greedy(start.id, &groups, None, cost)?
+        };
+
+        let mut waypoints = vec![start];
+        for id in order {
+            waypoints.push(self.universe.get_system(&id)?);
+        }
+        PathBuilder::new(self.universe)
+            .waypoints(waypoints)
+            .prefer(self.preference)
+            .build()
+    }
+}
+
+/// Orders the waypoints between the first and the last to visit them in the fewest
+/// jumps. See `PathBuilder::optimize_waypoint_order`. `None` if a waypoint cannot
+/// be reached.
+pub(super) fn order_waypoints<'a>(
+    universe: &'a dyn types::Navigatable,
+    waypoints: &[&'a types::System],
+) -> Option<Vec<&'a types::System>> {
+    let [start, stops @ .., end] = waypoints else {
+        return Some(waypoints.to_vec());
+    };
+    if stops.len() < 2 {
+        return Some(waypoints.to_vec());
+    }
+    let mut costs = HashMap::new();
+    for system in waypoints {
+        costs
+            .entry(system.id)
+            .or_insert_with(|| costs_from(universe, &Preference::Shortest, system.id));
+    }
+    let cost = |a: types::SystemId, b: types::SystemId| costs.get(&a)?.get(&b).copied();
+
+    // every stop is a group of its own
+    let groups = stops.iter().map(|s| vec![s.id]).collect::<Vec<_>>();
+    let order = if groups.len() <= EXACT_GROUP_LIMIT {
+        solve_exact(start.id, &groups, Some(end.id), cost)?
+    } else {
+        solve_greedy(start.id, &groups, Some(end.id), cost)?
+    };
+    let mut result = vec![*start];
+    for id in order {
+        result.push(universe.get_system(&id)?);
+    }
+    result.push(*end);
+    Some(result)
+}
+
+/// Flattens the groups into a list of unique candidates, each with a bitmask of the
+/// groups it satisfies.
+fn candidates(groups: &[Vec<types::SystemId>]) -> Vec<(types::SystemId, u64)> {
+    let mut result: Vec<(types::SystemId, u64)> = Vec::new();
+    for (i, group) in groups.iter().enumerate() {
+        for id in group {
+            match result.iter_mut().find(|(c, _)| c == id) {
+                Some((_, mask)) => *mask |= 1 << i,
+                None => result.push((*id, 1 << i)),
+            }
+        }
+    }
+    result
+}
+
+/// Dynamic programming over (visited groups, last candidate). Exponential in the
+/// number of groups, quadratic in the number of candidates. With an `end` the tour
+/// has to continue there, which is not part of the returned order.
+fn solve_exact<F>(
+    start: types::SystemId,
+    groups: &[Vec<types::SystemId>],
+    end: Option<types::SystemId>,
+    cost: F,
+) -> Option<Vec<types::SystemId>>
+where
+    F: Fn(types::SystemId, types::SystemId) -> Option<Cost>,
+{
+    if groups.iter().any(|g| g.is_empty()) {
+        return None;
+    }
+    let full = (1usize << groups.len()) - 1;
+    let candidates = candidates(groups);
+    let n = candidates.len();
+
+    // dp[mask * n + c] = (cost, previous state) of the cheapest tour covering `mask`
+    // and ending at candidate `c`.
+    let mut dp: Vec<Option<(Cost, Option<usize>)>> = vec![None; (full + 1) * n.max(1)];
+    for (c, (id, mask)) in candidates.iter().enumerate() {
+        if let Some(k) = cost(start, *id) {
+            dp[*mask as usize * n + c] = Some((k, None));
+        }
+    }
+
+    for mask in 1..=full {
+        for c in 0..n {
+            let Some((k, _)) = dp[mask * n + c] else {
+                continue;
+            };
+            for (next, (id, covers)) in candidates.iter().enumerate() {
+                let covers = *covers as usize;
+                if covers & !mask == 0 {
+                    continue;
+                }
+                let Some(step) = cost(candidates[c].0, *id) else {
+                    continue;
+                };
+                let state = (mask | covers) * n + next;
+                if dp[state].is_none_or(|(best, _)| k + step < best) {
+                    dp[state] = Some((k + step, Some(mask * n + c)));
+                }
+            }
+        }
+    }
+
+    let mut state = (0..n)
+        .filter_map(|c| {
+            let (k, _) = dp[full * n + c]?;
+            let rest = match end {
+                Some(end) => cost(candidates[c].0, end)?,
+                None => 0,
+            };
+            Some((k + rest, full * n + c))
+        })
+        .min_by_key(|(k, _)| *k)?
+        .1;
+    let mut order = vec![candidates[state % n].0];
+    while let Some((_, Some(prev))) = dp[state] {
+        order.push(candidates[prev % n].0);
+        state = prev;
+    }
+    order.reverse();
+    Some(order)
+}
+
+/// Repeatedly travels to the cheapest candidate that satisfies a group not yet visited.
+/// The `end` is only checked to be reachable from the last candidate.
+fn solve_greedy<F>(
+    start: types::SystemId,
+    groups: &[Vec<types::SystemId>],
+    end: Option<types::SystemId>,
+    cost: F,
+) -> Option<Vec<types::SystemId>>
+where
+    F: Fn(types::SystemId, types::SystemId) -> Option<Cost>,
+{
+    let mut visited = vec![false; groups.len()];
+    let mut current = start;
+    let mut order = vec![];
+    while visited.iter().any(|v| !v) {
+        let next = groups
+            .iter()
+            .zip(visited.iter())
+            .filter(|(_, visited)| !**visited)
+            .flat_map(|(group, _)| group.iter())
+            .filter_map(|id| Some((cost(current, *id)?, *id)))
+            .min_by_key(|(k, _)| *k)?
+            .1;
+        for (group, visited) in groups.iter().zip(visited.iter_mut()) {
+            if group.contains(&next) {
+                *visited = true;
+            }
+        }
+        order.push(next);
+        current = next;
+    }
+    if let Some(end) = end {
+        cost(current, end)?;
+    }
+    Some(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+    use crate::types::Navigatable;
+
+    #[test]
+    fn test_tour_picks_cheapest_combination() {
+        let universe = universe();
+        let sys = |id| universe.get_system(&id).unwrap();
+        let path = TourBuilder::new(&universe)
+            .start(sys(ALPHA))
+            .group(vec![sys(ECHO), sys(HOTEL)])
+            .group(vec![sys(FOXTROT)])
+            .build()
+            .unwrap();
+        assert_eq!(4, path.jumps());
+        assert_eq!(Some(sys(HOTEL)), path.to());
+    }
+
+    #[test]
+    fn test_tour_shared_candidate() {
+        let universe = universe();
+        let sys = |id| universe.get_system(&id).unwrap();
+        let path = TourBuilder::new(&universe)
+            .start(sys(ALPHA))
+            .group(vec![sys(GOLF)])
+            .group(vec![sys(ECHO), sys(GOLF)])
+            .build()
+            .unwrap();
+        assert_eq!(3, path.jumps());
+    }
+
+    #[test]
+    fn test_tour_empty_group() {
+        let universe = universe();
+        let sys = |id| universe.get_system(&id).unwrap();
+        assert!(TourBuilder::new(&universe)
+            .start(sys(ALPHA))
+            .group(vec![])
+            .build()
+            .is_none());
+    }
+
+    #[test]
+    fn test_tour_without_groups() {
+        let universe = universe();
+        let path = TourBuilder::new(&universe)
+            .start(universe.get_system(&ALPHA).unwrap())
+            .build()
+            .unwrap();
+        assert_eq!(0, path.jumps());
+        assert_eq!(
+            vec![ALPHA],
+            path.systems().map(|s| s.id).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_greedy() {
+        let universe = universe();
+        let costs = [ALPHA, FOXTROT, HOTEL, ECHO]
+            .iter()
+            .map(|id| (*id, costs_from(&universe, &Preference::Shortest, *id)))
+            .collect::<HashMap<_, _>>();
+        let cost = |a, b| costs.get(&a)?.get(&b).copied();
+        let groups = vec![vec![ECHO, HOTEL], vec![FOXTROT]];
+        assert_eq!(
+            Some(vec![FOXTROT, HOTEL]),
+            solve_greedy(ALPHA, &groups, None, cost)
+        );
+    }
+
+    #[test]
+    fn test_order_waypoints() {
+        let universe = universe();
+        let sys = |id| universe.get_system(&id).unwrap();
+        let ordered = order_waypoints(
+            &universe,
+            &[sys(ALPHA), sys(HOTEL), sys(BRAVO), sys(GOLF), sys(ECHO)],
+        )
+        .unwrap();
+        assert_eq!(
+            vec![ALPHA, BRAVO, GOLF, HOTEL, ECHO],
+            ordered.iter().map(|s| s.id).collect::<Vec<_>>()
+        );
+
+        let costs = [ALPHA, HOTEL, BRAVO]
+            .iter()
+            .map(|id| (*id, costs_from(&universe, &Preference::Shortest, *id)))
+            .collect::<HashMap<_, _>>();
+        let cost = |a, b| costs.get(&a)?.get(&b).copied();
+        let groups = vec![vec![HOTEL], vec![BRAVO]];
+        assert_eq!(
+            Some(vec![BRAVO, HOTEL]),
+            solve_greedy(ALPHA, &groups, Some(ECHO), cost)
+        );
+    }
+}
@@ -0,0 +1,261 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Travel time estimates for paths.
+use std::time::Duration;
+
+use crate::navigation::{Path, PathElement};
+use crate::rules;
+use crate::types;
+
+/// In-system warp distance assumed when the gates of a system are not known, e.g.
+/// because the data source did not load stargates or the connection is a wormhole.
+const DEFAULT_WARP_DISTANCE: types::Au = types::Au(15.0);
+/// The speed in m/s at which a ship drops out of warp.
+const WARP_DROPOUT_SPEED: f64 = 100.0;
+
+/// The characteristics of a ship that determine how fast it travels, how much fuel it
+/// needs and where it may go. Jump range, bridges, wormhole fit and cyno rules are all
+/// derived from the same profile.
+///
+/// # Example
+/// ```
+/// use neweden::navigation::ShipProfile;
+/// use neweden::{HullClass, JumpdriveSkills, WormholeType};
+///
+/// let carrier = ShipProfile::new(HullClass::Carrier).skills(JumpdriveSkills::new(5, 5));
+/// assert!((carrier.jump_range().unwrap().0 - 7.0).abs() < 1e-9);
+/// assert!(carrier.fits(&WormholeType::VeryLarge));
+/// assert!(!carrier.fits(&WormholeType::Large));
+/// assert!(carrier.bridge().is_none());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShipProfile {
+    pub hull: types::HullClass,
+    pub mass: types::Kilotons,
+    /// Only matters for hulls with a jump drive.
+    pub skills: types::JumpdriveSkills,
+    pub align_time: Duration,
+    /// Warp speed in AU per second.
+    pub warp_speed: f64,
+    /// Time per jump, including the session change.
+    pub jump_time: Duration,
+    /// Isotopes burnt per lightyear on bridges.
+    pub fuel_per_lightyear: u32,
+}
+
+impl ShipProfile {
+    /// A typical ship of the hull class with untrained jump skills. The travel
+    /// characteristics are those of the default profile.
+    pub fn new(hull: types::HullClass) -> Self {
+        Self {
+            hull,
+            mass: hull.typical_mass(),
+            ..Self::default()
+        }
+    }
+
+    pub fn skills(mut self, skills: types::JumpdriveSkills) -> Self {
+        self.skills = skills;
+        self
+    }
+
+    pub fn jump_drive(&self) -> Option<types::JumpdriveShip> {
+        self.hull.jump_drive(self.skills)
+    }
+
+    /// The jump range, or `None` without a jump drive.
+    pub fn jump_range(&self) -> Option<types::Lightyears> {
+        self.jump_drive().map(Into::into)
+    }
+
+    /// The bridge the ship can open for others, if any.
+    pub fn bridge(&self) -> Option<types::BridgeType> {
+        self.hull.bridge(self.skills)
+    }
+
+    /// Whether the ship fits through a wormhole of this size.
+    pub fn fits(&self, wormhole: &types::WormholeType) -> bool {
+        wormhole.fits(self.mass)
+    }
+
+    /// Whether the ship may enter the system at all, e.g. capitals may not enter
+    /// highsec.
+    pub fn may_enter(&self, system: &types::System) -> bool {
+        self.hull.may_enter_highsec()
+            || types::SecurityClass::from(system.security) != types::SecurityClass::Highsec
+    }
+
+    /// Whether the ship can jump to a cyno in the system.
+    pub fn may_jump_to(&self, system: &types::System) -> bool {
+        self.jump_drive().is_some() && self.may_enter(system) && rules::allows_cynos(system)
+    }
+}
+
+impl Default for ShipProfile {
+    /// A cruiser with average align time and warp speed.
+    fn default() -> Self {
+        let hull = types::HullClass::Cruiser;
+        Self {
+            hull,
+            mass: hull.typical_mass(),
+            skills: types::JumpdriveSkills::new(0, 0),
+            align_time: Duration::from_secs(6),
+            warp_speed: 3.0,
+            jump_time: Duration::from_secs(10),
+            fuel_per_lightyear: 0,
+        }
+    }
+}
+
+/// Estimates how long it takes a ship to travel a path.
+///
+/// Every system except the last is crossed by aligning, warping from the gate the
+/// ship arrived at to the gate it leaves through and jumping. Where either gate is
+/// unknown an average warp distance is assumed.
+///
+/// # Example
+/// ```
+/// use std::time::Duration;
+/// use neweden::navigation::{ShipProfile, TravelTime};
+/// use neweden::Au;
+///
+/// let ship = ShipProfile::default();
+/// let estimator = TravelTime::new(&ship);
+/// assert!(estimator.warp_time(Au(5.0)) < estimator.warp_time(Au(50.0)));
+/// assert!(estimator.warp_time(Au(5.0)) > Duration::from_secs(5));
+/// ```
+pub struct TravelTime<'s> {
+    ship: &'s ShipProfile,
+}
+
+impl<'s> TravelTime<'s> {
+    pub fn new(ship: &'s ShipProfile) -> Self {
+        Self { ship }
+    }
+
+    /// The time a warp over `distance` takes, including acceleration and deceleration.
+    pub fn warp_time(&self, distance: types::Au) -> Duration {
+        let meters = types::Meters::from(distance).0;
+        let accel = self.ship.warp_speed;
+        let decel = (self.ship.warp_speed / 3.0).min(2.0);
+        let max_speed = types::Meters::from(types::Au(self.ship.warp_speed)).0;
+
+        // the ship accelerates and decelerates exponentially, short warps never reach
+        // the maximum speed
+        let (peak, cruise) = if meters > max_speed / accel + max_speed / decel {
+            let cruise_distance = meters - max_speed / accel - max_speed / decel;
+            (max_speed, cruise_distance / max_speed)
+        } else {
+            (meters * accel * decel / (accel + decel), 0.0)
+        };
+        let accelerating = (peak / accel).ln().max(0.0) / accel;
+        let decelerating = (peak / WARP_DROPOUT_SPEED).ln().max(0.0) / decel;
+        Duration::from_secs_f64(accelerating + cruise + decelerating)
+    }
+
+    pub fn estimate(&self, path: &Path) -> Duration {
+        self.legs(path).into_iter().sum()
+    }
+
+    /// The time spent in every system of the path except the last, from entering
+    /// the system to jumping out of it.
+    pub fn legs(&self, path: &Path) -> Vec<Duration> {
+        self.warps(path)
+            .into_iter()
+            .map(|(_, distance)| {
+                let distance = distance.unwrap_or(DEFAULT_WARP_DISTANCE);
+                self.ship.align_time + self.warp_time(distance) + self.ship.jump_time
+            })
+            .collect()
+    }
+
+    /// The in-system warp distance of every system of the path except the last, from
+    /// the gate the ship arrived at to the gate it leaves through. `None` where either
+    /// gate is unknown.
+    pub(crate) fn warps(&self, path: &Path) -> Vec<(types::SystemId, Option<types::Au>)> {
+        let mut systems = Vec::new();
+        let mut via = Vec::new();
+        for element in path.iter() {
+            match element {
+                PathElement::System(s) | PathElement::Waypoint(s) => systems.push(s),
+                PathElement::Connection(type_, _) => via.push(type_),
+            }
+        }
+
+        let mut warps = Vec::with_capacity(via.len());
+        let mut entry: Option<&types::Stargate> = None;
+        for (i, pair) in systems.windows(2).enumerate() {
+            let exit = match via.get(i) {
+                Some(types::ConnectionType::Stargate(_)) => path
+                    .universe
+                    .get_stargates(&pair[0].id)
+                    .iter()
+                    .find(|g| g.destination_system == pair[1].id),
+                _ => None,
+            };
+            let distance = match (entry, exit) {
+                (Some(entry), Some(exit)) => Some(entry.distance_to(&exit.coordinate)),
+                _ => None,
+            };
+            warps.push((pair[0].id, distance));
+
+            entry = exit.and_then(|exit| {
+                path.universe
+                    .get_stargates(&pair[1].id)
+                    .iter()
+                    .find(|g| g.id == exit.destination)
+            });
+        }
+        warps
+    }
+}
+
+impl Path<'_> {
+    /// Estimates the time a ship needs to travel the path. See `TravelTime`.
+    pub fn estimated_time(&self, ship: &ShipProfile) -> Duration {
+        TravelTime::new(ship).estimate(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation::PathBuilder;
+    use crate::testing::*;
+    use crate::types::Navigatable;
+
+    #[test]
+    fn test_warp_time() {
+        let ship = ShipProfile::default();
+        let estimator = TravelTime::new(&ship);
+        let short = estimator.warp_time(types::Au(1.0));
+        let long = estimator.warp_time(types::Au(30.0));
+        assert!(short < long);
+        // cruising adds distance divided by warp speed
+        let longer = estimator.warp_time(types::Au(60.0));
+        assert!(((longer - long).as_secs_f64() - 10.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_estimated_time() {
+        let universe = universe();
+        let ship = ShipProfile::default();
+        let estimator = TravelTime::new(&ship);
+        let path = PathBuilder::new(&universe)
+            .waypoint(universe.get_system(&ALPHA).unwrap())
+            .waypoint(universe.get_system(&CHARLIE).unwrap())
+            .build()
+            .unwrap();
+        // Alpha is entered from nowhere, Bravo's gates are 5 AU apart
+        let per_jump = ship.align_time + ship.jump_time;
+        assert_eq!(
+            per_jump * 2
+                + estimator.warp_time(DEFAULT_WARP_DISTANCE)
+                + estimator.warp_time(types::Au(5.0)),
+            path.estimated_time(&ship)
+        );
+    }
+}
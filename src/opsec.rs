@@ -0,0 +1,185 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Operational security of capital movements.
+//!
+//! Hostiles watch the systems cynos were lit in before and the midpoints a
+//! shortest route would take. `score` rates a jump plan by how much of it could be
+//! anticipated, combine it with `JumpPlanner::randomize_midpoints` to pick the plan
+//! that gives the least away.
+//!
+//! # Example
+//! ```
+//! use neweden::builder::UniverseBuilder;
+//! use neweden::navigation::jumps::JumpPlan;
+//! use neweden::opsec::{score, OpsecContext};
+//! use neweden::Lightyears;
+//!
+//! # let universe = UniverseBuilder::new().build();
+//! let plan = JumpPlan { systems: vec![1.into(), 2.into(), 3.into()] };
+//! let context = OpsecContext::new(Lightyears(7.0)).previous_cyno(2.into());
+//! let report = score(&universe, &plan, &context);
+//! assert_eq!(1, report.reused_cynos);
+//! ```
+use std::collections::HashSet;
+
+use crate::navigation::jumps::{JumpPlan, JumpPlanner};
+use crate::types::{self, Navigatable};
+
+/// Systems within this range of a hostile staging count as close to it.
+const DEFAULT_HOSTILE_RANGE: types::Lightyears = types::Lightyears(5.0);
+
+/// What is known about previous operations and the hostiles.
+#[derive(Debug, Clone)]
+pub struct OpsecContext {
+    range: types::Lightyears,
+    previous_cynos: HashSet<types::SystemId>,
+    hostile_staging: Vec<types::SystemId>,
+    hostile_range: types::Lightyears,
+}
+
+impl OpsecContext {
+    /// `range` is the jump range of the fleet, used to find the predictable plan.
+    pub fn new(range: types::Lightyears) -> Self {
+        Self {
+            range,
+            previous_cynos: HashSet::new(),
+            hostile_staging: Vec::new(),
+            hostile_range: DEFAULT_HOSTILE_RANGE,
+        }
+    }
+
+    /// A system a cyno was lit in during a previous operation.
+    pub fn previous_cyno(mut self, system: types::SystemId) -> Self {
+        self.add_previous_cyno(system);
+        self
+    }
+
+    /// A system hostiles stage in.
+    pub fn hostile_staging(mut self, system: types::SystemId) -> Self {
+        self.add_hostile_staging(system);
+        self
+    }
+
+    /// How close to a hostile staging a cyno counts as exposed.
+    pub fn hostile_range(mut self, range: types::Lightyears) -> Self {
+        self.hostile_range = range;
+        self
+    }
+
+    /// Like `previous_cyno`, but for use when the context is not consumed, e.g. in loops.
+    pub fn add_previous_cyno(&mut self, system: types::SystemId) -> &mut Self {
+        self.previous_cynos.insert(system);
+        self
+    }
+
+    /// Like `hostile_staging`, but for use when the context is not consumed, e.g. in
+    /// loops.
+    pub fn add_hostile_staging(&mut self, system: types::SystemId) -> &mut Self {
+        self.hostile_staging.push(system);
+        self
+    }
+}
+
+/// The result of `score`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpsecReport {
+    /// Cyno systems already used in previous operations.
+    pub reused_cynos: usize,
+    /// Cyno systems within the hostile range of a staging, with the distance to the
+    /// closest staging.
+    pub near_hostiles: Vec<(types::SystemId, types::Lightyears)>,
+    /// The share of midpoints the shortest plan would use as well, from 0.0 to 1.0.
+    /// Plans without midpoints are fully predictable.
+    pub predictability: f32,
+    /// The average of the three criteria, from 0.0 (nothing to anticipate) to 1.0.
+    pub score: f32,
+}
+
+/// Rates a jump plan by its operational security.
+pub fn score<N: Navigatable>(universe: &N, plan: &JumpPlan, context: &OpsecContext) -> OpsecReport {
+    let cynos = plan.cyno_systems();
+    let reused_cynos = cynos
+        .iter()
+        .filter(|s| context.previous_cynos.contains(s))
+        .count();
+
+    let near_hostiles = cynos
+        .iter()
+        .filter_map(|id| {
+            let system = universe.get_system(id)?;
+            let closest = context
+                .hostile_staging
+                .iter()
+                .filter_map(|staging| universe.get_system(staging))
+                .map(|staging| types::Lightyears::from(system.distance(staging)))
+                .min_by(|a, b| a.0.total_cmp(&b.0))?;
+            (closest.0 <= context.hostile_range.0).then_some((*id, closest))
+        })
+        .collect::<Vec<_>>();
+
+    let midpoints = plan.midpoints();
+    let predictability = if midpoints.is_empty() {
+        1.0
+    } else {
+        let (from, to) = (&plan.systems[0], &plan.systems[plan.systems.len() - 1]);
+        let shortest = JumpPlanner::new(universe, context.range)
+            .plan(from, to)
+            .map(|p| p.midpoints().iter().copied().collect::<HashSet<_>>())
+            .unwrap_or_default();
+        let shared = midpoints.iter().filter(|s| shortest.contains(s)).count();
+        shared as f32 / midpoints.len() as f32
+    };
+
+    let share = |n: usize| {
+        if cynos.is_empty() {
+            0.0
+        } else {
+            n as f32 / cynos.len() as f32
+        }
+    };
+    let score = (share(reused_cynos) + share(near_hostiles.len()) + predictability) / 3.0;
+    OpsecReport {
+        reused_cynos,
+        near_hostiles,
+        predictability,
+        score,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+
+    #[test]
+    fn test_score() {
+        let universe = universe();
+        let context = OpsecContext::new(types::Lightyears(6.0))
+            .previous_cyno(DELTA)
+            .hostile_staging(CHARLIE)
+            .hostile_range(types::Lightyears(3.0));
+
+        let shortest = JumpPlan {
+            systems: vec![ALPHA, DELTA, ECHO],
+        };
+        let report = score(&universe, &shortest, &context);
+        assert_eq!(1, report.reused_cynos);
+        assert_eq!(
+            vec![DELTA],
+            report.near_hostiles.iter().map(|h| h.0).collect::<Vec<_>>()
+        );
+        assert_eq!(1.0, report.predictability);
+
+        let detour = JumpPlan {
+            systems: vec![ALPHA, HOTEL, ECHO],
+        };
+        let report = score(&universe, &detour, &context);
+        assert_eq!(0, report.reused_cynos);
+        assert!(report.near_hostiles.is_empty());
+        assert_eq!(0.0, report.predictability);
+        assert_eq!(0.0, report.score);
+    }
+}
@@ -0,0 +1,384 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Declarative corrections to a universe.
+//!
+//! A `Patch` is an ordered list of operations, such as adding a missing stargate or
+//! changing the security of a system. Patches are independent of the data source,
+//! so corrections maintained by the community can be applied to the output of any
+//! source with `Universe::apply_patch`. `Universe::diff` generates the patch that
+//! turns one universe into another. With the `json` feature, patches can be shared
+//! as files using `export::json::PatchDocument`.
+//!
+//! # Example
+//! ```
+//! use neweden::builder::UniverseBuilder;
+//! use neweden::patch::Patch;
+//! use neweden::{Connection, ConnectionSource, ConnectionType, Coordinate, Galaxy};
+//! use neweden::{StargateType, System};
+//!
+//! let system = |id: u32| {
+//!     System::new(id.into(), "", Coordinate { x: 0.0, y: 0.0, z: 0.0 }, 0.5.into())
+//! };
+//! let mut universe = UniverseBuilder::new().system(system(1)).system(system(2)).build();
+//!
+//! let patch = Patch::new().add_connection(Connection {
+//!     from: 1.into(),
+//!     to: 2.into(),
+//!     type_: ConnectionType::Stargate(StargateType::Local),
+//!     source: ConnectionSource::UserOverlay,
+//! });
+//! universe.apply_patch(&patch).unwrap();
+//! assert_eq!(1, universe.connections().len());
+//! ```
+use std::collections::HashSet;
+
+use crate::types::{Connection, Galaxy, Security, System, SystemId, Universe};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum PatchError {
+    #[error("patch refers to unknown system {0:?}")]
+    UnknownSystem(SystemId),
+}
+
+/// A single change to a universe.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "op", rename_all = "snake_case"))]
+pub enum PatchOp {
+    /// Adds a system, or replaces the system with the same id. Connections are kept.
+    AddSystem {
+        system: System,
+    },
+    /// Removes a system together with all connections from and to it.
+    RemoveSystem {
+        system: SystemId,
+    },
+    AddConnection {
+        connection: Connection,
+    },
+    /// Removes all connections from `from` to `to`. Removing a connection that
+    /// does not exist is not an error.
+    RemoveConnection {
+        from: SystemId,
+        to: SystemId,
+    },
+    SetSecurity {
+        system: SystemId,
+        security: Security,
+    },
+}
+
+/// An ordered list of changes to a universe.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Patch {
+    pub ops: Vec<PatchOp>,
+}
+
+impl Patch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_system(mut self, system: System) -> Self {
+        self.ops.push(PatchOp::AddSystem { system });
+        self
+    }
+
+    pub fn remove_system(mut self, system: SystemId) -> Self {
+        self.ops.push(PatchOp::RemoveSystem { system });
+        self
+    }
+
+    pub fn add_connection(mut self, connection: Connection) -> Self {
+        self.ops.push(PatchOp::AddConnection { connection });
+        self
+    }
+
+    /// Adds the connection in both directions, as most stargates are.
+    pub fn add_connection_bidirectional(self, connection: Connection) -> Self {
+        let reversed = connection.reversed();
+        self.add_connection(connection).add_connection(reversed)
+    }
+
+    pub fn remove_connection(mut self, from: SystemId, to: SystemId) -> Self {
+        self.ops.push(PatchOp::RemoveConnection { from, to });
+        self
+    }
+
+    pub fn set_security(mut self, system: SystemId, security: Security) -> Self {
+        self.ops.push(PatchOp::SetSecurity { system, security });
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &PatchOp> {
+        self.ops.iter()
+    }
+
+    /// Checks that every operation only refers to systems that exist at the time
+    /// it is applied, without changing the universe.
+    fn validate(&self, universe: &Universe) -> Result<(), PatchError> {
+        let mut known = universe.systems.0.keys().copied().collect::<HashSet<_>>();
+        let check = |known: &HashSet<SystemId>, id: &SystemId| {
+            if known.contains(id) {
+                Ok(())
+            } else {
+                Err(PatchError::UnknownSystem(*id))
+            }
+        };
+        for op in &self.ops {
+            match op {
+                PatchOp::AddSystem { system } => {
+                    known.insert(system.id);
+                }
+                PatchOp::RemoveSystem { system } => {
+                    check(&known, system)?;
+                    known.remove(system);
+                }
+                PatchOp::AddConnection { connection } => {
+                    check(&known, &connection.from)?;
+                    check(&known, &connection.to)?;
+                }
+                PatchOp::RemoveConnection { .. } => {}
+                PatchOp::SetSecurity { system, .. } => check(&known, system)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Universe {
+    /// Applies the operations of a patch in order. The patch is validated first, so
+    /// the universe is left unchanged if an operation refers to an unknown system.
+    pub fn apply_patch(&mut self, patch: &Patch) -> Result<(), PatchError> {
+        patch.validate(self)?;
+        for op in &patch.ops {
+            match op {
+                PatchOp::AddSystem { system } => {
+                    self.insert_system(system.clone());
+                }
+                PatchOp::RemoveSystem { system } => {
+                    self.remove_system(system);
+                }
+                PatchOp::AddConnection { connection } => {
+                    self.connections_mut()
+                        .0
+                        .entry(connection.from)
+                        .or_default()
+                        .push(connection.clone());
+                }
+                PatchOp::RemoveConnection { from, to } => {
+                    if let Some(connections) = self.connections_mut().0.get_mut(from) {
+                        connections.retain(|c| c.to != *to);
+                        if connections.is_empty() {
+                            self.connections.0.remove(from);
+                        }
+                    }
+                }
+                PatchOp::SetSecurity { system, security } => {
+                    let mut updated = self.systems.0[system].clone();
+                    updated.security = *security;
+                    self.insert_system(updated);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Generates the patch that turns this universe into `other`. Systems that only
+    /// differ in security become `SetSecurity` operations, any other difference
+    /// replaces the system. Celestials and stargate positions are not compared.
+    pub fn diff(&self, other: &Universe) -> Patch {
+        let mut patch = Patch::new();
+
+        let mut systems = other.systems().into_iter().collect::<Vec<_>>();
+        systems.sort_by_key(|s| s.id);
+        for system in systems {
+            match self.systems.0.get(&system.id) {
+                None => patch = patch.add_system(system.clone()),
+                Some(old) if !same_except_security(old, system) => {
+                    patch = patch.add_system(system.clone())
+                }
+                Some(old) if old.security != system.security => {
+                    patch = patch.set_security(system.id, system.security)
+                }
+                Some(_) => {}
+            }
+        }
+
+        let mut removed = self
+            .systems
+            .0
+            .keys()
+            .filter(|id| !other.systems.0.contains_key(id))
+            .copied()
+            .collect::<Vec<_>>();
+        removed.sort();
+
+        let mut pairs = self
+            .connections
+            .0
+            .keys()
+            .chain(other.connections.0.keys())
+            .copied()
+            .collect::<Vec<_>>();
+        pairs.sort();
+        pairs.dedup();
+        for from in pairs {
+            let old = self.connections.0.get(&from).map_or(&[][..], |c| &c[..]);
+            let new = other.connections.0.get(&from).map_or(&[][..], |c| &c[..]);
+            let mut targets = old.iter().chain(new).map(|c| c.to).collect::<Vec<_>>();
+            targets.sort();
+            targets.dedup();
+            for to in targets {
+                if removed.contains(&from) || removed.contains(&to) {
+                    continue;
+                }
+                let before = old.iter().filter(|c| c.to == to).collect::<Vec<_>>();
+                let after = new.iter().filter(|c| c.to == to).collect::<Vec<_>>();
+                if same_connections(&before, &after) {
+                    continue;
+                }
+                if !before.is_empty() {
+                    patch = patch.remove_connection(from, to);
+                }
+                for connection in after {
+                    patch = patch.add_connection(connection.clone());
+                }
+            }
+        }
+
+        for id in removed {
+            patch = patch.remove_system(id);
+        }
+        patch
+    }
+}
+
+fn same_except_security(a: &System, b: &System) -> bool {
+    a.name == b.name
+        && a.coordinate == b.coordinate
+        && a.region == b.region
+        && a.constellation == b.constellation
+        && a.invasion == b.invasion
+        && a.faction == b.faction
+        && a.effect == b.effect
+}
+
+fn same_connections(a: &[&Connection], b: &[&Connection]) -> bool {
+    a.len() == b.len()
+        && a.iter()
+            .zip(b)
+            .all(|(a, b)| a.type_ == b.type_ && a.source == b.source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+    use crate::types::{self, Navigatable};
+
+    fn gate(from: SystemId, to: SystemId) -> Connection {
+        Connection {
+            from,
+            to,
+            type_: types::ConnectionType::Stargate(types::StargateType::Regional),
+            source: types::ConnectionSource::UserOverlay,
+        }
+    }
+
+    #[test]
+    fn test_apply_patch() {
+        let mut universe = universe();
+        let patch = Patch::new()
+            .add_system(system(types::SystemId(30000009), "India", -0.5, 8.0, 2.0))
+            .add_connection_bidirectional(gate(ECHO, types::SystemId(30000009)))
+            .remove_connection(DELTA, ECHO)
+            .set_security(ECHO, types::Security(0.1))
+            .remove_system(HOTEL);
+        universe.apply_patch(&patch).unwrap();
+
+        assert!(universe.get_system(&HOTEL).is_none());
+        assert!(universe
+            .get_connections(&GOLF)
+            .unwrap()
+            .iter()
+            .all(|c| c.to != HOTEL));
+        assert_eq!(
+            types::Security(0.1),
+            universe.get_system(&ECHO).unwrap().security
+        );
+        assert!(universe
+            .get_connections(&DELTA)
+            .unwrap()
+            .iter()
+            .all(|c| c.to != ECHO));
+        assert_eq!(
+            vec![DELTA, types::SystemId(30000009)],
+            universe
+                .get_connections(&ECHO)
+                .unwrap()
+                .iter()
+                .map(|c| c.to)
+                .collect::<Vec<_>>()
+        );
+        let india = universe.get_system(&types::SystemId(30000009)).unwrap();
+        assert_eq!(
+            india.id,
+            universe.nearest_systems(&india.coordinate, 1)[0].id
+        );
+    }
+
+    #[test]
+    fn test_apply_patch_unknown_system() {
+        let mut universe = universe();
+        let patch = Patch::new()
+            .set_security(ECHO, types::Security(0.1))
+            .remove_system(HOTEL)
+            .add_connection(gate(GOLF, HOTEL));
+        assert_eq!(
+            Err(PatchError::UnknownSystem(HOTEL)),
+            universe.apply_patch(&patch)
+        );
+        assert!(universe.get_system(&HOTEL).is_some());
+        assert_eq!(
+            types::Security(-0.2),
+            universe.get_system(&ECHO).unwrap().security
+        );
+    }
+
+    #[test]
+    fn test_diff_roundtrip() {
+        let base = universe();
+        let mut target = universe();
+        target
+            .apply_patch(
+                &Patch::new()
+                    .remove_system(FOXTROT)
+                    .add_connection(gate(ALPHA, ECHO))
+                    .set_security(DELTA, types::Security(0.5)),
+            )
+            .unwrap();
+
+        assert!(base.diff(&base).is_empty());
+        let patch = base.diff(&target);
+        assert_eq!(3, patch.len());
+
+        let mut patched = universe();
+        patched.apply_patch(&patch).unwrap();
+        assert!(patched.diff(&target).is_empty());
+        assert!(target.diff(&patched).is_empty());
+    }
+}
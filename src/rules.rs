@@ -3,15 +3,424 @@
  * All rights reserved.
  */
 
+use crate::navigation::{self, CostFunction};
 use crate::types;
 
 pub fn allows_cynos(system: &types::System) -> bool {
-    let sec_class = types::SecurityClass::from(system.security.clone());
+    let sec_class = types::SecurityClass::from(system.security);
     let sys_class = types::SystemClass::from(system);
     match (sys_class, sec_class) {
         (types::SystemClass::KSpace, types::SecurityClass::Highsec) => false,
         (types::SystemClass::KSpace, types::SecurityClass::Lowsec) => true,
         (types::SystemClass::KSpace, types::SecurityClass::Nullsec) => true,
         (types::SystemClass::WSpace, _) => false,
+        (types::SystemClass::Pochven, _) => false,
+    }
+}
+
+/// The gates between Pochven systems, derived from the stargates of the universe.
+/// The static data lacks them in the connection table, see
+/// `DatabaseBuilder::pochven`. This covers the gates inside each Krai as well as
+/// the triangle joining the home systems of the three Krai, which are constellations.
+/// Gates that are already known as connections are skipped.
+pub fn pochven_gates<'a, N, I>(universe: &N, stargates: I) -> Vec<types::Connection>
+where
+    N: types::Navigatable + ?Sized,
+    I: IntoIterator<Item = &'a types::Stargate>,
+{
+    let pochven = |id: &types::SystemId| {
+        universe
+            .get_system(id)
+            .filter(|s| types::SystemClass::from(*s) == types::SystemClass::Pochven)
+    };
+    stargates
+        .into_iter()
+        .filter_map(|gate| {
+            let from = pochven(&gate.system)?;
+            let to = pochven(&gate.destination_system)?;
+            let known = universe
+                .get_connections(&from.id)
+                .unwrap_or_default()
+                .iter()
+                .any(|c| c.to == to.id);
+            if known {
+                return None;
+            }
+            let class = if from.constellation != to.constellation {
+                types::StargateType::Constellation
+            } else {
+                types::StargateType::Local
+            };
+            Some(types::Connection {
+                from: from.id,
+                to: to.id,
+                type_: types::ConnectionType::Stargate(class),
+                source: types::ConnectionSource::Sde,
+            })
+        })
+        .collect()
+}
+
+/// Whether a Pochven filament can be activated in the system. Filaments work
+/// anywhere in known space outside of Pochven.
+pub fn allows_pochven_filament(system: &types::System) -> bool {
+    types::SystemClass::from(system) == types::SystemClass::KSpace
+}
+
+/// The filament connections from a system into Pochven, one to every Pochven
+/// system. Filaments land in a random system, so routing over these connections
+/// yields the best case. Add them to an `ExtendedUniverse` to route into Pochven.
+///
+/// # Example
+/// ```
+/// use neweden::builder::UniverseBuilder;
+/// use neweden::{rules, AdjacentMap, Navigatable};
+///
+/// let universe = UniverseBuilder::new().build();
+/// let filaments = universe
+///     .get_system(&30000142.into())
+///     .map(|jita| rules::pochven_filaments(&universe, jita))
+///     .unwrap_or_default();
+/// let extended = universe.extend(AdjacentMap::from(filaments));
+/// ```
+pub fn pochven_filaments<G: types::Galaxy>(
+    galaxy: &G,
+    from: &types::System,
+) -> Vec<types::Connection> {
+    if !allows_pochven_filament(from) {
+        return Vec::new();
+    }
+    galaxy
+        .systems()
+        .into_iter()
+        .filter(|s| types::SystemClass::from(*s) == types::SystemClass::Pochven)
+        .map(|s| types::Connection {
+            from: from.id,
+            to: s.id,
+            type_: types::ConnectionType::Filament(types::FilamentKind::Krai),
+            source: types::ConnectionSource::UserOverlay,
+        })
+        .collect()
+}
+
+/// The NPC navies that hold systems after the Triglavian invasion.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Navy {
+    Triglavian,
+    Edencom,
+}
+
+/// Standings of a pilot towards the navies involved in the Triglavian invasion.
+/// Navies attack pilots with negative standings in the systems they hold.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PilotProfile {
+    pub triglavian_standing: f32,
+    pub edencom_standing: f32,
+}
+
+impl PilotProfile {
+    fn standing(&self, navy: Navy) -> f32 {
+        match navy {
+            Navy::Triglavian => self.triglavian_standing,
+            Navy::Edencom => self.edencom_standing,
+        }
+    }
+}
+
+/// The navy holding a system, if the invasion left one in control.
+pub fn holding_navy(system: &types::System) -> Option<Navy> {
+    match system.invasion? {
+        types::InvasionStatus::TriglavianMinorVictory | types::InvasionStatus::FinalLiminality => {
+            Some(Navy::Triglavian)
+        }
+        types::InvasionStatus::EdencomMinorVictory | types::InvasionStatus::EdencomFortress => {
+            Some(Navy::Edencom)
+        }
+    }
+}
+
+/// Whether the navy holding the system attacks the pilot.
+pub fn is_attacked(system: &types::System, pilot: &PilotProfile) -> bool {
+    holding_navy(system).is_some_and(|navy| pilot.standing(navy) < 0.0)
+}
+
+/// Routes by preference, but never through systems whose navy attacks the pilot.
+///
+/// # Example
+/// ```
+/// use neweden::navigation::{PathBuilder, Preference};
+/// use neweden::rules::{PilotConstraint, PilotProfile};
+/// # use neweden::builder::UniverseBuilder;
+/// # let universe = UniverseBuilder::new().build();
+///
+/// let pilot = PilotProfile {
+///     triglavian_standing: -5.0,
+///     edencom_standing: 2.0,
+/// };
+/// let builder = PathBuilder::new(&universe)
+///     .cost_function(PilotConstraint::new(pilot, Preference::Highsec));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct PilotConstraint {
+    pub pilot: PilotProfile,
+    pub preference: navigation::Preference,
+}
+
+impl PilotConstraint {
+    pub fn new(pilot: PilotProfile, preference: navigation::Preference) -> Self {
+        Self { pilot, preference }
+    }
+}
+
+impl CostFunction for PilotConstraint {
+    fn cost(
+        &self,
+        universe: &dyn types::Navigatable,
+        connection: &types::Connection,
+    ) -> Option<navigation::Cost> {
+        let system = universe.get_system(&connection.to)?;
+        if is_attacked(system, &self.pilot) {
+            return None;
+        }
+        self.preference.cost(universe, connection)
+    }
+}
+
+impl navigation::Path<'_> {
+    /// Checks that no system on the path is held by a navy that attacks the pilot.
+    /// Returns the offending systems otherwise.
+    pub fn validate_for(&self, pilot: &PilotProfile) -> Result<(), Vec<types::SystemId>> {
+        let attacked: Vec<_> = self
+            .systems()
+            .filter(|s| is_attacked(s, pilot))
+            .map(|s| s.id)
+            .collect();
+        if attacked.is_empty() {
+            Ok(())
+        } else {
+            Err(attacked)
+        }
+    }
+}
+
+/// Whether a connection is a frigate hole: a small wormhole, or any wormhole into a
+/// C13, which only spawns those.
+pub fn is_frigate_hole(universe: &types::Universe, connection: &types::Connection) -> bool {
+    match &connection.type_ {
+        types::ConnectionType::Wormhole(types::WormholeType::Small) => true,
+        types::ConnectionType::Wormhole(_) => {
+            universe.max_ship_mass_into(&connection.to)
+                == types::WormholeType::Small.max_ship_mass()
+        }
+        _ => false,
+    }
+}
+
+/// Routes with another cost function, but never into systems a ship of this mass
+/// cannot enter, see `Universe::max_ship_mass_into`. Unlike `PathBuilder::ship_mass`
+/// this catches C13s and Drifter systems reached through wormholes of unknown size.
+///
+/// # Example
+/// ```
+/// use neweden::navigation::{PathBuilder, Preference};
+/// use neweden::rules::ShipSizeConstraint;
+/// use neweden::Kilotons;
+/// # use neweden::builder::UniverseBuilder;
+/// # let universe = UniverseBuilder::new().build();
+///
+/// // a cruiser
+/// let builder = PathBuilder::new(&universe)
+///     .cost_function(ShipSizeConstraint::new(Preference::Shortest, &universe, Kilotons(11.0)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ShipSizeConstraint<'a, C> {
+    inner: C,
+    universe: &'a types::Universe,
+    mass: types::Kilotons,
+}
+
+impl<'a, C: CostFunction> ShipSizeConstraint<'a, C> {
+    pub fn new(inner: C, universe: &'a types::Universe, mass: types::Kilotons) -> Self {
+        Self {
+            inner,
+            universe,
+            mass,
+        }
+    }
+}
+
+impl<C: CostFunction> CostFunction for ShipSizeConstraint<'_, C> {
+    fn cost(
+        &self,
+        universe: &dyn types::Navigatable,
+        connection: &types::Connection,
+    ) -> Option<navigation::Cost> {
+        if let Some(max) = self.universe.max_ship_mass_into(&connection.to) {
+            if self.mass > max {
+                return None;
+            }
+        }
+        if let types::ConnectionType::Wormhole(wormhole) = &connection.type_ {
+            if !wormhole.fits(self.mass) {
+                return None;
+            }
+        }
+        self.inner.cost(universe, connection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation::{PathBuilder, Preference};
+    use crate::testing::*;
+    use crate::types::Navigatable;
+
+    #[test]
+    fn test_pilot_constraint() {
+        let mut universe = types::DynamicUniverse::from(universe());
+        let mut charlie = universe.get_system(&CHARLIE).unwrap().clone();
+        charlie.invasion = Some(types::InvasionStatus::TriglavianMinorVictory);
+        universe.insert_system(charlie);
+
+        let loyalist = PilotProfile {
+            triglavian_standing: 1.0,
+            edencom_standing: -1.0,
+        };
+        let enemy = PilotProfile {
+            triglavian_standing: -1.0,
+            edencom_standing: 1.0,
+        };
+
+        let builder = PathBuilder::new(&universe)
+            .waypoint(universe.get_system(&ALPHA).unwrap())
+            .waypoint(universe.get_system(&DELTA).unwrap());
+        let shortest = builder.build().unwrap();
+        assert!(shortest.validate_for(&loyalist).is_ok());
+        assert_eq!(Err(vec![CHARLIE]), shortest.validate_for(&enemy));
+
+        let constrained = builder
+            .cost_function(PilotConstraint::new(enemy, Preference::Shortest))
+            .build()
+            .unwrap();
+        assert!(constrained.validate_for(&enemy).is_ok());
+        assert_eq!(4, constrained.jumps());
+    }
+
+    #[test]
+    fn test_ship_size_constraint() {
+        let hole = |id: u32, name: &str| {
+            types::System::new(
+                id.into(),
+                name,
+                types::Coordinate {
+                    x: 0.0,
+                    y: 0.0,
+                    z: 0.0,
+                },
+                (-1.0).into(),
+            )
+        };
+        let wormhole = |from: u32, to: u32| types::Connection {
+            from: from.into(),
+            to: to.into(),
+            type_: types::ConnectionType::Wormhole(types::WormholeType::Unknown),
+            source: types::ConnectionSource::UserOverlay,
+        };
+        // a C13, Sentinel and a C3 behind both, and a direct very large hole
+        let universe = crate::builder::UniverseBuilder::new()
+            .system(hole(31000010, "J000010"))
+            .system(hole(31000001, "J055520"))
+            .system(hole(31000011, "J000011"))
+            .system(hole(31000012, "J000012"))
+            .wormhole_class(31000010.into(), types::WormholeClass::C13)
+            .connection(wormhole(31000012, 31000010))
+            .connection(wormhole(31000010, 31000011))
+            .connection(wormhole(31000012, 31000001))
+            .connection(wormhole(31000001, 31000011))
+            .build();
+
+        assert!(universe.is_shattered(&31000010.into()));
+        assert!(!universe.is_shattered(&31000011.into()));
+        let info = universe.wormhole_info(&31000010.into()).unwrap();
+        assert!(info.shattered);
+        assert_eq!(
+            Some(types::DrifterSystem::Sentinel),
+            universe.drifter_system(&31000001.into())
+        );
+        assert!(is_frigate_hole(&universe, &wormhole(31000012, 31000010)));
+        assert!(!is_frigate_hole(&universe, &wormhole(31000012, 31000001)));
+
+        let route = |mass: f64| {
+            PathBuilder::new(&universe)
+                .waypoint(universe.get_system(&31000012.into()).unwrap())
+                .waypoint(universe.get_system(&31000011.into()).unwrap())
+                .cost_function(ShipSizeConstraint::new(
+                    Preference::Shortest,
+                    &universe,
+                    types::Kilotons(mass),
+                ))
+                .build()
+                .map(|p| p.systems().map(|s| s.id).collect::<Vec<_>>())
+        };
+        // a frigate may take either, a battleship only fits into Sentinel and a
+        // capital fits into neither
+        assert_eq!(2, route(1.0).unwrap().len() - 1);
+        assert_eq!(
+            Some(vec![31000012.into(), 31000001.into(), 31000011.into()]),
+            route(100.0)
+        );
+        assert_eq!(None, route(1_000.0));
+    }
+
+    #[test]
+    fn test_pochven() {
+        let system = |id: u32, constellation: u32| {
+            let mut system = types::System::new(
+                id.into(),
+                "",
+                types::Coordinate {
+                    x: 0.0,
+                    y: 0.0,
+                    z: 0.0,
+                },
+                (-1.0).into(),
+            );
+            system.region = Some(types::RegionId::POCHVEN);
+            system.constellation = Some(types::ConstellationId(constellation));
+            system
+        };
+        let gate = |from: u32, to: u32| types::Stargate {
+            id: (from * 10).into(),
+            system: from.into(),
+            coordinate: types::Coordinate {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            destination: (to * 10).into(),
+            destination_system: to.into(),
+        };
+        let mut builder = crate::builder::UniverseBuilder::new();
+        builder
+            .add_system(system(30000001, 20000001))
+            .add_system(system(30000002, 20000001))
+            .add_system(system(30000003, 20000002));
+        let pochven = builder.build();
+        let gates = [gate(30000001, 30000002), gate(30000002, 30000003)];
+        let connections = pochven_gates(&pochven, &gates);
+        assert_eq!(2, connections.len());
+        assert_eq!(
+            types::ConnectionType::Stargate(types::StargateType::Constellation),
+            connections[1].type_
+        );
+
+        let universe = universe();
+        let alpha = universe.get_system(&ALPHA).unwrap();
+        assert!(!allows_cynos(&system(30000001, 20000001)));
+        assert!(allows_pochven_filament(alpha));
+        assert!(!allows_pochven_filament(&system(30000001, 20000001)));
+        assert_eq!(3, pochven_filaments(&pochven, alpha).len());
+        assert!(pochven_filaments(&pochven, &system(30000001, 20000001)).is_empty());
     }
 }
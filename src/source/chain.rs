@@ -0,0 +1,328 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Wormhole chains exported from the Tripwire and Pathfinder mappers.
+//!
+//! Both mappers record the state of a wormhole besides the systems it connects:
+//! whether it reached its end of life and how much mass went through it. The
+//! importers keep that state next to the connection, so routes can avoid holes
+//! that are about to collapse.
+//!
+//! # Example
+//! ```no_run
+//! use neweden::source::chain::Chain;
+//! # use neweden::builder::UniverseBuilder;
+//! # let universe = UniverseBuilder::new().build();
+//!
+//! let json = std::fs::read_to_string("chain.json").unwrap();
+//! let chain = Chain::from_pathfinder(&json).unwrap();
+//! let extended = universe.extend(chain.without_critical().into_adjacent_map());
+//! ```
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{self, Context};
+use serde_json::Value;
+
+use crate::types;
+
+/// A wormhole past its end of life collapses within this time.
+const END_OF_LIFE: Duration = Duration::from_secs(4 * 60 * 60);
+
+/// Frigate holes are the only mass class Tripwire's wormhole codes reveal reliably.
+const FRIGATE_HOLES: [&str; 8] = [
+    "A009", "C008", "E004", "G008", "L005", "M001", "Q003", "Z006",
+];
+
+/// How much mass went through a wormhole.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MassStatus {
+    #[default]
+    Stable,
+    /// Less than half of the mass is left.
+    Reduced,
+    /// Less than a tenth of the mass is left.
+    Critical,
+}
+
+/// A wormhole of a chain. The connection leads from the side it was scanned in.
+#[derive(Debug, Clone)]
+pub struct ChainHole {
+    pub connection: types::Connection,
+    /// The wormhole type, e.g. `H296`, if the mapper knows it.
+    pub code: Option<String>,
+    pub end_of_life: bool,
+    pub mass: MassStatus,
+    /// When the hole collapses at the latest, if the mapper knows it.
+    pub expires: Option<SystemTime>,
+}
+
+impl ChainHole {
+    /// The life left at `now`. Zero for holes that already expired.
+    pub fn remaining(&self, now: SystemTime) -> Option<Duration> {
+        let expires = self.expires?;
+        Some(expires.duration_since(now).unwrap_or_default())
+    }
+
+    /// The state of the hole, ready for `ExtendedUniverse::set_connection_info`.
+    pub fn info(&self) -> types::ConnectionInfo {
+        types::ConnectionInfo {
+            expires: self.expires,
+            mass_remaining: None,
+            end_of_life: self.end_of_life,
+        }
+    }
+}
+
+/// The wormholes of a chain.
+#[derive(Debug, Clone, Default)]
+pub struct Chain {
+    pub holes: Vec<ChainHole>,
+}
+
+impl Chain {
+    /// Imports the JSON returned by Tripwire's refresh endpoint, containing
+    /// `signatures` and `wormholes`.
+    pub fn from_tripwire(json: &str) -> anyhow::Result<Self> {
+        let value: Value = serde_json::from_str(json)?;
+        let signatures = entries(&value["signatures"]);
+        let systems = signatures
+            .iter()
+            .filter_map(|s| Some((id(&s["id"])?, id(&s["systemID"])?)))
+            .collect::<HashMap<_, _>>();
+        let expiry = signatures
+            .iter()
+            .filter_map(|s| Some((id(&s["id"])?, parse_utc(s["lifeLeft"].as_str()?)?)))
+            .collect::<HashMap<_, _>>();
+
+        let mut holes = Vec::new();
+        for wormhole in entries(&value["wormholes"]) {
+            let initial = id(&wormhole["initialID"]).context("wormhole without initialID")?;
+            let secondary = id(&wormhole["secondaryID"]).context("wormhole without secondaryID")?;
+            // unscanned exits are not in the chain yet
+            let (Some(from), Some(to)) = (systems.get(&initial), systems.get(&secondary)) else {
+                continue;
+            };
+            let code = wormhole["type"]
+                .as_str()
+                .filter(|c| !c.is_empty() && *c != "????")
+                .map(str::to_string);
+            let type_ = match &code {
+                Some(c) if FRIGATE_HOLES.contains(&c.as_str()) => types::WormholeType::Small,
+                _ => types::WormholeType::Unknown,
+            };
+            let end_of_life = wormhole["life"].as_str() == Some("critical");
+            holes.push(ChainHole {
+                connection: hole(*from, *to, type_),
+                code,
+                end_of_life,
+                mass: match wormhole["mass"].as_str() {
+                    Some("destab") => MassStatus::Reduced,
+                    Some("critical") => MassStatus::Critical,
+                    _ => MassStatus::Stable,
+                },
+                expires: expiry.get(&initial).copied(),
+            });
+        }
+        Ok(Self { holes })
+    }
+
+    /// Imports a Pathfinder map export. Only wormhole connections are imported,
+    /// stargates and jump bridges are left out.
+    pub fn from_pathfinder(json: &str) -> anyhow::Result<Self> {
+        let value: Value = serde_json::from_str(json)?;
+        let map = value.get("mapData").unwrap_or(&value);
+        let systems = entries(&map["systems"])
+            .iter()
+            .filter_map(|s| Some((id(&s["id"])?, id(&s["systemId"])?)))
+            .collect::<HashMap<_, _>>();
+
+        let mut holes = Vec::new();
+        for connection in entries(&map["connections"]) {
+            if connection["scope"].as_str() != Some("wh") {
+                continue;
+            }
+            let source = id(&connection["source"]).context("connection without source")?;
+            let target = id(&connection["target"]).context("connection without target")?;
+            let (Some(from), Some(to)) = (systems.get(&source), systems.get(&target)) else {
+                continue;
+            };
+            let flags = connection["type"]
+                .as_array()
+                .map(|t| t.iter().filter_map(Value::as_str).collect::<Vec<_>>())
+                .unwrap_or_default();
+            let has = |flag: &str| flags.contains(&flag);
+
+            let type_ = if has("frigate") || has("wh_jump_mass_s") {
+                types::WormholeType::Small
+            } else if has("wh_jump_mass_m") {
+                types::WormholeType::Medium
+            } else if has("wh_jump_mass_l") {
+                types::WormholeType::Large
+            } else if has("wh_jump_mass_xl") {
+                types::WormholeType::VeryLarge
+            } else {
+                types::WormholeType::Unknown
+            };
+            let end_of_life = has("wh_eol");
+            let expires = connection["eolUpdated"]
+                .as_u64()
+                .filter(|_| end_of_life)
+                .map(|t| SystemTime::UNIX_EPOCH + Duration::from_secs(t) + END_OF_LIFE);
+            holes.push(ChainHole {
+                connection: hole(*from, *to, type_),
+                code: None,
+                end_of_life,
+                mass: if has("wh_critical") {
+                    MassStatus::Critical
+                } else if has("wh_reduced") {
+                    MassStatus::Reduced
+                } else {
+                    MassStatus::Stable
+                },
+                expires,
+            });
+        }
+        Ok(Self { holes })
+    }
+
+    /// Drops holes that are critical on mass or past their end of life.
+    pub fn without_critical(mut self) -> Self {
+        self.holes
+            .retain(|h| !h.end_of_life && h.mass != MassStatus::Critical);
+        self
+    }
+
+    /// The holes as connections in both directions, ready for `Universe::extend`.
+    pub fn into_adjacent_map(self) -> types::AdjacentMap {
+        self.holes
+            .into_iter()
+            .flat_map(|h| [h.connection.reversed(), h.connection])
+            .collect::<Vec<_>>()
+            .into()
+    }
+}
+
+fn hole(from: u64, to: u64, type_: types::WormholeType) -> types::Connection {
+    types::Connection {
+        from: (from as u32).into(),
+        to: (to as u32).into(),
+        type_: types::ConnectionType::Wormhole(type_),
+        source: types::ConnectionSource::UserOverlay,
+    }
+}
+
+/// The values of a JSON object or the items of an array.
+fn entries(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Object(map) => map.values().collect(),
+        Value::Array(items) => items.iter().collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Ids are numbers in Pathfinder and strings in Tripwire.
+fn id(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(n) => n.as_u64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Parses a `YYYY-MM-DD HH:MM:SS` timestamp in UTC.
+fn parse_utc(s: &str) -> Option<SystemTime> {
+    let number = |range: std::ops::Range<usize>| s.get(range)?.parse::<i64>().ok();
+    let (year, month, day) = (number(0..4)?, number(5..7)?, number(8..10)?);
+    let (hour, minute, second) = (number(11..13)?, number(14..16)?, number(17..19)?);
+
+    // days since the epoch of a proleptic gregorian date
+    let (y, m) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = y.div_euclid(400);
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * m + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    let seconds = days * 86400 + hour * 3600 + minute * 60 + second;
+    Some(SystemTime::UNIX_EPOCH + Duration::from_secs(u64::try_from(seconds).ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tripwire() {
+        let json = r#"{
+            "signatures": {
+                "1": { "id": "1", "systemID": "31000005", "type": "wormhole",
+                       "lifeLeft": "2024-01-02 03:04:05" },
+                "2": { "id": "2", "systemID": "30000142", "type": "wormhole" },
+                "3": { "id": "3", "systemID": "31000005", "type": "wormhole" }
+            },
+            "wormholes": {
+                "10": { "id": "10", "initialID": "1", "secondaryID": "2",
+                        "type": "Q003", "life": "critical", "mass": "destab" },
+                "11": { "id": "11", "initialID": "3", "secondaryID": "99",
+                        "type": "????", "life": "stable", "mass": "stable" }
+            }
+        }"#;
+        let chain = Chain::from_tripwire(json).unwrap();
+        assert_eq!(1, chain.holes.len());
+        let hole = &chain.holes[0];
+        assert_eq!(types::SystemId::THERA, hole.connection.from);
+        assert_eq!(
+            types::ConnectionType::Wormhole(types::WormholeType::Small),
+            hole.connection.type_
+        );
+        assert!(hole.end_of_life);
+        assert!(hole.info().end_of_life);
+        assert_eq!(MassStatus::Reduced, hole.mass);
+        assert_eq!(
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1704164645)),
+            hole.expires
+        );
+        assert!(chain.without_critical().holes.is_empty());
+    }
+
+    #[test]
+    fn test_pathfinder() {
+        let json = r#"{
+            "mapData": {
+                "systems": [
+                    { "id": 1, "systemId": 30000142 },
+                    { "id": 2, "systemId": 31000005 },
+                    { "id": 3, "systemId": 30002187 }
+                ],
+                "connections": [
+                    { "source": 1, "target": 2, "scope": "wh",
+                      "type": ["wh_fresh", "wh_jump_mass_xl", "wh_eol"], "eolUpdated": 1000 },
+                    { "source": 2, "target": 3, "scope": "wh",
+                      "type": ["wh_critical", "wh_jump_mass_l"] },
+                    { "source": 1, "target": 3, "scope": "stargate", "type": ["stargate"] }
+                ]
+            }
+        }"#;
+        let chain = Chain::from_pathfinder(json).unwrap();
+        assert_eq!(2, chain.holes.len());
+        assert_eq!(
+            types::ConnectionType::Wormhole(types::WormholeType::VeryLarge),
+            chain.holes[0].connection.type_
+        );
+        assert_eq!(
+            Some(END_OF_LIFE - Duration::from_secs(500)),
+            chain.holes[0].remaining(SystemTime::UNIX_EPOCH + Duration::from_secs(1500))
+        );
+        assert_eq!(MassStatus::Critical, chain.holes[1].mass);
+
+        let map = chain.into_adjacent_map();
+        assert_eq!(2, map.0[&types::SystemId::THERA].len());
+    }
+}
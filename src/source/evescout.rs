@@ -0,0 +1,168 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! The current Thera and Turnur wormholes from the public EVE-Scout API.
+//!
+//! # Example
+//! ```no_run
+//! use neweden::source::evescout::EveScout;
+//! # use neweden::builder::UniverseBuilder;
+//! # let universe = UniverseBuilder::new().build();
+//!
+//! let holes = EveScout::new().connections().unwrap();
+//! let extended = universe.extend(holes);
+//! ```
+use anyhow;
+use serde::Deserialize;
+
+use crate::types;
+
+/// The public endpoint listing all signatures scanned down by EVE-Scout.
+pub const SIGNATURES_URL: &str = "https://api.eve-scout.com/v2/public/signatures";
+
+/// A wormhole signature as reported by EVE-Scout. Only the fields needed for
+/// routing are kept.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Signature {
+    /// Thera or Turnur.
+    pub out_system_id: u32,
+    /// The system the wormhole leads to.
+    pub in_system_id: u32,
+    /// `small`, `medium`, `large`, `xlarge` or `capital`.
+    #[serde(default)]
+    pub max_ship_size: Option<String>,
+    #[serde(default)]
+    pub remaining_hours: Option<f32>,
+    #[serde(default)]
+    pub signature_type: Option<String>,
+}
+
+impl Signature {
+    pub fn wormhole_type(&self) -> types::WormholeType {
+        match self.max_ship_size.as_deref() {
+            Some("small") => types::WormholeType::Small,
+            Some("medium") => types::WormholeType::Medium,
+            Some("large") => types::WormholeType::Large,
+            Some("xlarge") | Some("capital") => types::WormholeType::VeryLarge,
+            _ => types::WormholeType::Unknown,
+        }
+    }
+}
+
+/// Fetches the current hole list from EVE-Scout.
+pub struct EveScout {
+    url: String,
+}
+
+impl Default for EveScout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EveScout {
+    pub fn new() -> Self {
+        Self {
+            url: SIGNATURES_URL.to_string(),
+        }
+    }
+
+    /// Uses a different endpoint, e.g. a caching proxy.
+    pub fn url(mut self, url: &str) -> Self {
+        self.url = url.to_string();
+        self
+    }
+
+    /// Fetches all wormhole signatures.
+    pub fn signatures(&self) -> anyhow::Result<Vec<Signature>> {
+        let body = ureq::get(&self.url).call()?.into_string()?;
+        parse(&body)
+    }
+
+    /// Fetches the wormholes as connections, ready for `Universe::extend`.
+    pub fn connections(&self) -> anyhow::Result<types::AdjacentMap> {
+        Ok(to_adjacent_map(&self.signatures()?))
+    }
+}
+
+/// Parses a response of the signatures endpoint. Signatures that are not wormholes
+/// are skipped.
+pub fn parse(json: &str) -> anyhow::Result<Vec<Signature>> {
+    let signatures: Vec<Signature> = serde_json::from_str(json)?;
+    Ok(signatures
+        .into_iter()
+        .filter(|s| s.signature_type.as_deref().is_none_or(|t| t == "wormhole"))
+        .collect())
+}
+
+/// Converts signatures into wormholes in both directions.
+pub fn to_adjacent_map(signatures: &[Signature]) -> types::AdjacentMap {
+    signatures
+        .iter()
+        .flat_map(|s| {
+            let hole = types::Connection {
+                from: s.out_system_id.into(),
+                to: s.in_system_id.into(),
+                type_: types::ConnectionType::Wormhole(s.wormhole_type()),
+                source: types::ConnectionSource::EveScout,
+            };
+            [hole.reversed(), hole]
+        })
+        .collect::<Vec<_>>()
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESPONSE: &str = r#"[
+        {
+            "id": "1",
+            "signature_type": "wormhole",
+            "out_system_id": 31000005,
+            "out_system_name": "Thera",
+            "in_system_id": 30000142,
+            "in_system_name": "Jita",
+            "max_ship_size": "xlarge",
+            "remaining_hours": 12.5
+        },
+        {
+            "id": "2",
+            "signature_type": "wormhole",
+            "out_system_id": 30002086,
+            "out_system_name": "Turnur",
+            "in_system_id": 30002187,
+            "in_system_name": "Amarr",
+            "max_ship_size": "medium"
+        },
+        {
+            "id": "3",
+            "signature_type": "combat",
+            "out_system_id": 31000005,
+            "in_system_id": 30002659
+        }
+    ]"#;
+
+    #[test]
+    fn test_parse() {
+        let signatures = parse(RESPONSE).unwrap();
+        assert_eq!(2, signatures.len());
+        assert_eq!(
+            types::WormholeType::VeryLarge,
+            signatures[0].wormhole_type()
+        );
+        assert_eq!(Some(12.5), signatures[0].remaining_hours);
+
+        let map = to_adjacent_map(&signatures);
+        let jita = map.0.get(&30000142.into()).unwrap();
+        assert_eq!(types::SystemId::THERA, jita[0].to);
+        assert_eq!(types::ConnectionSource::EveScout, jita[0].source);
+        assert_eq!(
+            types::ConnectionType::Wormhole(types::WormholeType::Medium),
+            map.0.get(&types::SystemId::TURNUR).unwrap()[0].type_
+        );
+    }
+}
@@ -0,0 +1,151 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Universes from a static JSON bundle, for in-browser route planners.
+//!
+//! The SQL sources need a database and the file system, neither of which exists on
+//! `wasm32-unknown-unknown`. A bundle is the `export::json::UniverseDocument` of a
+//! universe, optionally with its stargates and celestials, produced once on a
+//! server and shipped as a static file. The browser fetches it and hands the bytes
+//! to `load_slice`, after which routes are computed by the same code as everywhere
+//! else.
+//!
+//! # Example
+//! ```
+//! use neweden::export::json::UniverseDocument;
+//! use neweden::navigation::PathBuilder;
+//! use neweden::{source, testing, Navigatable};
+//!
+//! // on the server
+//! let bundle = UniverseDocument::new(&testing::universe()).to_json().unwrap();
+//!
+//! // in the browser
+//! let universe = source::json::load(&bundle).unwrap();
+//! let path = PathBuilder::new(&universe)
+//!     .waypoint(universe.get_system(&testing::ALPHA).unwrap())
+//!     .waypoint(universe.get_system(&testing::ECHO).unwrap())
+//!     .build();
+//! assert_eq!(4, path.unwrap().jumps());
+//! ```
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::export::json::{from_versioned_value, FormatError};
+use crate::metrics;
+use crate::types;
+
+/// A `UniverseDocument` with the optional tables of a bundle.
+#[derive(Debug, Deserialize)]
+struct Bundle {
+    systems: Vec<types::System>,
+    connections: Vec<types::Connection>,
+    #[serde(default)]
+    stargates: Vec<types::Stargate>,
+    #[serde(default)]
+    celestials: Vec<types::Celestial>,
+}
+
+/// Loads a universe from the text of a bundle. Fails with `FormatError::Integrity`
+/// if a connection refers to a system the bundle does not contain.
+pub fn load(json: &str) -> Result<types::Universe, FormatError> {
+    count(
+        serde_json::from_str(json)
+            .map_err(FormatError::from)
+            .and_then(build),
+    )
+}
+
+/// Loads a universe from the bytes of a bundle, e.g. the body of a fetch response.
+pub fn load_slice(json: &[u8]) -> Result<types::Universe, FormatError> {
+    count(
+        serde_json::from_slice(json)
+            .map_err(FormatError::from)
+            .and_then(build),
+    )
+}
+
+/// Bundles come from outside the program, so issues that break routing are
+/// rejected, as `UniverseBuilder::try_build` does.
+fn build(value: Value) -> Result<types::Universe, FormatError> {
+    let bundle: Bundle = from_versioned_value(value)?;
+    let universe = types::Universe::new(
+        types::SystemMap::from(bundle.systems),
+        types::AdjacentMap::from(bundle.connections),
+    )
+    .with_stargates(bundle.stargates)
+    .with_celestials(bundle.celestials);
+    if let Err(issues) = universe.validate() {
+        let fatal = issues
+            .into_iter()
+            .filter(|i| i.is_fatal())
+            .collect::<Vec<_>>();
+        if !fatal.is_empty() {
+            return Err(FormatError::Integrity(fatal));
+        }
+    }
+    Ok(universe)
+}
+
+/// Counts the load, but does not time it: there is no clock on wasm32-unknown-unknown.
+fn count(result: Result<types::Universe, FormatError>) -> Result<types::Universe, FormatError> {
+    metrics::increment(match result {
+        Ok(_) => metrics::UNIVERSE_LOADS,
+        Err(_) => metrics::UNIVERSE_LOAD_FAILURES,
+    });
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::json::UniverseDocument;
+    use crate::testing::*;
+    use crate::types::{Galaxy, Navigatable};
+    use crate::validate::IntegrityIssue;
+
+    #[test]
+    fn test_load_bundle() {
+        let original = universe();
+        let bundle = UniverseDocument::new(&original).to_json().unwrap();
+        let loaded = load_slice(bundle.as_bytes()).unwrap();
+        assert_eq!(original.connections().len(), loaded.connections().len());
+        assert_eq!(
+            original.get_system(&ALPHA).unwrap().name,
+            loaded.get_system(&ALPHA).unwrap().name
+        );
+        assert!(loaded.get_system_by_name("alpha").is_some());
+
+        let mut value: Value = serde_json::from_str(&bundle).unwrap();
+        value["stargates"] = serde_json::to_value(original.get_stargates(&ALPHA)).unwrap();
+        let loaded = load(&value.to_string()).unwrap();
+        assert_eq!(1, loaded.get_stargates(&ALPHA).len());
+
+        value["version"] = 0.into();
+        assert!(matches!(
+            load(&value.to_string()),
+            Err(FormatError::UnsupportedVersion { found: 0, .. })
+        ));
+        assert!(matches!(load("{"), Err(FormatError::Json(_))));
+    }
+
+    #[test]
+    fn test_load_dangling_connection() {
+        let mut document = UniverseDocument::new(&universe());
+        let mut dangling = document.connections[0].clone();
+        dangling.to = 30009999.into();
+        document.connections.push(dangling.clone());
+        let bundle = document.to_json().unwrap();
+        match load(&bundle) {
+            Err(FormatError::Integrity(issues)) => assert_eq!(
+                vec![IntegrityIssue::DanglingConnection {
+                    from: dangling.from,
+                    to: dangling.to,
+                }],
+                issues
+            ),
+            other => panic!("expected an integrity error, got {:?}", other.map(|_| ())),
+        }
+    }
+}
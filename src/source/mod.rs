@@ -3,11 +3,34 @@
  * All rights reserved.
  */
 
+#[cfg(feature = "json")]
+pub mod chain;
+
+#[cfg(feature = "evescout")]
+pub mod evescout;
+
+#[cfg(feature = "json")]
+pub mod json;
+
+#[cfg(feature = "mysql")]
+pub mod mysql;
+
 #[cfg(feature = "postgres")]
 pub mod postgres;
 
+#[cfg(feature = "esi")]
+pub mod sovereignty;
+
 #[cfg(feature = "sqlite")]
 pub mod sqlite;
 
+#[cfg(any(feature = "sqlite", feature = "postgres", feature = "mysql"))]
+pub mod sql;
+
+#[cfg(feature = "zkillboard")]
+pub mod zkillboard;
+
+pub mod verify;
+
 #[cfg(feature = "rpc")]
 pub mod rpc;
@@ -0,0 +1,358 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+#[allow(non_snake_case)]
+mod schema;
+
+use std::collections::HashMap;
+
+use anyhow;
+use diesel::mysql::MysqlConnection;
+use diesel::prelude::*;
+
+use crate::source::sql::{self, CelestialRow, JumpRow, LoadOptions, SdeSource, SystemRow};
+use crate::types;
+use schema::mapSolarSystemJumps::dsl::*;
+use schema::mapSolarSystems::dsl::*;
+
+type DB = diesel::mysql::Mysql;
+
+/// Loads a universe from a MySQL or MariaDB database, e.g. the Fuzzwork MySQL dump.
+/// Mirrors `source::postgres::DatabaseBuilder`.
+///
+/// `Universe` implements `Navigatable` and can be used in pathfinding.
+///
+/// `Universe` is intended to be used immutable and can only be instantiated
+/// from a data source such as a database. If you need to add additional connections,
+/// such as dynamic wormhole connections during pathfinding, construct an `ExtendedUniverse`
+/// from a universe by calling `.extend()` or `ExtendedUniverse::new()`.
+///
+/// # Example
+/// ```no_run
+/// use std::env;
+/// use neweden::source::mysql::DatabaseBuilder;
+/// use neweden::Navigatable;
+///
+/// let uri = std::env::var("MYSQL_URL").unwrap();
+/// let universe = DatabaseBuilder::new(&uri).build().unwrap();
+/// let system_id = 30000142.into(); // returns a SystemId
+/// println!("{:?}", universe.get_system(&system_id).unwrap().name); // Jita
+/// ```
+#[derive(Debug, Clone)]
+pub struct DatabaseBuilder {
+    uri: String,
+    options: LoadOptions,
+}
+
+impl DatabaseBuilder {
+    pub fn new(uri: &str) -> Self {
+        Self {
+            uri: uri.to_string(),
+            options: LoadOptions::default(),
+        }
+    }
+
+    /// Also load planets, moons, asteroid belts, stargates and NPC stations from
+    /// mapDenormalize.
+    /// This is off by default as it considerably increases loading time and memory use.
+    pub fn celestials(mut self, load: bool) -> Self {
+        self.options.celestials = load;
+        self
+    }
+
+    /// Also load stargates with their positions and destinations from mapJumps.
+    pub fn stargates(mut self, load: bool) -> Self {
+        self.options.stargates = load;
+        self
+    }
+
+    /// Also load the wormhole system effects from the secondary suns in mapDenormalize.
+    pub fn effects(mut self, load: bool) -> Self {
+        self.options.effects = load;
+        self
+    }
+
+    /// Also load the Pochven gates, which are missing from mapSolarSystemJumps, from
+    /// the stargates in mapJumps. See `rules::pochven_gates`.
+    pub fn pochven(mut self, load: bool) -> Self {
+        self.options.pochven = load;
+        self
+    }
+
+    /// Also load the classes of wormhole systems from mapLocationWormholeClasses. See
+    /// `Universe::wormhole_info`.
+    pub fn wormholes(mut self, load: bool) -> Self {
+        self.options.wormholes = load;
+        self
+    }
+
+    /// Also load the translated system names from trnTranslations. See
+    /// `navigation::format`.
+    pub fn translations(mut self, load: bool) -> Self {
+        self.options.translations = load;
+        self
+    }
+
+    pub fn build(self) -> anyhow::Result<types::Universe> {
+        let conn = MysqlConnection::establish(&self.uri)?;
+        sql::load(&conn, &self.options)
+    }
+}
+
+impl SdeSource for MysqlConnection {
+    fn systems(&self) -> anyhow::Result<Vec<SystemRow>> {
+        Ok(mapSolarSystems
+            // this is k-space and w-space
+            .filter(solarSystemID.lt(32000000))
+            .load::<SystemRow>(self)?)
+    }
+
+    fn jumps(&self) -> anyhow::Result<Vec<JumpRow>> {
+        Ok(mapSolarSystemJumps
+            .filter(
+                // only query k-space since w-space has no connections
+                fromSolarSystemID
+                    .lt(31000000)
+                    .and(toSolarSystemID.lt(31000000)),
+            )
+            .load::<JumpRow>(self)?)
+    }
+
+    fn celestials(&self, groups: &[u32]) -> anyhow::Result<Vec<CelestialRow>> {
+        use schema::mapDenormalize::dsl as denormalize;
+
+        let groups = groups.iter().map(|g| *g as i32).collect::<Vec<_>>();
+        Ok(denormalize::mapDenormalize
+            .filter(denormalize::groupID.eq_any(groups))
+            .load::<CelestialRow>(self)?)
+    }
+
+    fn stargate_jumps(&self) -> anyhow::Result<Vec<(u32, u32)>> {
+        use schema::mapJumps::dsl as jumps;
+
+        Ok(jumps::mapJumps
+            .select((jumps::stargateID, jumps::destinationID))
+            .load::<(i32, Option<i32>)>(self)?
+            .into_iter()
+            .filter_map(|(gate, destination)| Some((gate as u32, destination? as u32)))
+            .collect())
+    }
+
+    fn secondary_suns(&self) -> anyhow::Result<Vec<(u32, i32)>> {
+        use schema::mapDenormalize::dsl as denormalize;
+
+        Ok(denormalize::mapDenormalize
+            .filter(denormalize::groupID.eq(995))
+            .select((denormalize::solarSystemID, denormalize::typeID))
+            .load::<(Option<i32>, Option<i32>)>(self)?
+            .into_iter()
+            .filter_map(|(system, type_id)| Some((system? as u32, type_id?)))
+            .collect())
+    }
+
+    fn wormhole_classes(&self) -> anyhow::Result<HashMap<u32, i32>> {
+        use schema::mapLocationWormholeClasses::dsl as classes;
+
+        Ok(classes::mapLocationWormholeClasses
+            .select((classes::locationID, classes::wormholeClassID))
+            .load::<(i32, Option<i32>)>(self)?
+            .into_iter()
+            .filter_map(|(location, class)| Some((location as u32, class?)))
+            .collect())
+    }
+
+    fn system_name_translations(&self) -> anyhow::Result<Vec<(u32, String, String)>> {
+        use schema::trnTranslationColumns::dsl as columns;
+        use schema::trnTranslations::dsl as translations;
+
+        let column = columns::trnTranslationColumns
+            .filter(columns::tableName.eq("dbo.mapSolarSystems"))
+            .filter(columns::columnName.eq("solarSystemName"))
+            .select(columns::tcID)
+            .first::<i32>(self)?;
+        Ok(translations::trnTranslations
+            .filter(translations::tcID.eq(column))
+            .select((
+                translations::keyID,
+                translations::languageID,
+                translations::text,
+            ))
+            .load::<(i32, String, String)>(self)?
+            .into_iter()
+            .map(|(system, language, text)| (system as u32, language, text))
+            .collect())
+    }
+}
+
+impl Queryable<schema::mapSolarSystems::SqlType, DB> for SystemRow {
+    type Row = (
+        Option<i32>,    // regionID
+        Option<i32>,    // constellationID
+        i32,            // solarSystemID
+        Option<String>, // solarSystemName
+        Option<f64>,    // x
+        Option<f64>,    // y
+        Option<f64>,    // z
+        Option<f64>,    // luminosity
+        Option<f64>,    // security
+        Option<i32>,    // factionID
+    );
+
+    fn build(row: Self::Row) -> Self {
+        SystemRow {
+            id: row.2 as u32,
+            name: row.3.unwrap(),
+            x: row.4.unwrap(),
+            y: row.5.unwrap(),
+            z: row.6.unwrap(),
+            security: row.8.unwrap(),
+            region: row.0.map(|id| id as u32),
+            constellation: row.1.map(|id| id as u32),
+            faction: row.9.map(|id| id as u32),
+        }
+    }
+}
+
+impl Queryable<schema::mapDenormalize::SqlType, DB> for CelestialRow {
+    type Row = (
+        i32,            // itemID
+        Option<i32>,    // typeID
+        Option<i32>,    // groupID
+        Option<i32>,    // solarSystemID
+        Option<i32>,    // constellationID
+        Option<i32>,    // regionID
+        Option<i32>,    // orbitID
+        Option<f64>,    // x
+        Option<f64>,    // y
+        Option<f64>,    // z
+        Option<f64>,    // radius
+        Option<String>, // itemName
+        Option<f64>,    // security
+        Option<i32>,    // celestialIndex
+        Option<i32>,    // orbitIndex
+    );
+
+    fn build(row: Self::Row) -> Self {
+        CelestialRow {
+            id: row.0 as u32,
+            name: row.11.unwrap(),
+            system: row.3.unwrap() as u32,
+            group: row.2.unwrap() as u32,
+            x: row.7.unwrap(),
+            y: row.8.unwrap(),
+            z: row.9.unwrap(),
+        }
+    }
+}
+
+impl Queryable<schema::mapSolarSystemJumps::SqlType, DB> for JumpRow {
+    type Row = (
+        Option<i32>, // fromRegionID
+        Option<i32>, // fromConstellationID,
+        i32,         // fromSolarSystemID
+        i32,         // toSolarSystemID
+        Option<i32>, // toConstellationID,
+        Option<i32>, // toRegionID,
+    );
+
+    fn build(row: Self::Row) -> Self {
+        JumpRow {
+            from_region: row.0.map(|id| id as u32),
+            from_constellation: row.1.map(|id| id as u32),
+            from: row.2 as u32,
+            to: row.3 as u32,
+            to_constellation: row.4.map(|id| id as u32),
+            to_region: row.5.map(|id| id as u32),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+    use crate::source::sql::SdeRowMapper;
+
+    #[test]
+    fn test_simple_system_query() {
+        let uri = env::var("MYSQL_URL").expect("expected env variable MYSQL_URL set");
+        let conn =
+            MysqlConnection::establish(&uri).expect("expected mysql connection to be established");
+        let system = mapSolarSystems
+            .filter(solarSystemID.eq(30000049))
+            .limit(1)
+            .load::<SystemRow>(&conn)
+            .expect("first row to be returned from mysql");
+        assert_eq!("Camal", SdeRowMapper::system(system[0].clone()).name);
+    }
+
+    #[test]
+    fn test_simple_connection_query() {
+        let uri = env::var("MYSQL_URL").expect("expected env variable MYSQL_URL set");
+        let conn =
+            MysqlConnection::establish(&uri).expect("expected mysql connection to be established");
+        let res = mapSolarSystemJumps
+            .filter(
+                fromSolarSystemID
+                    .eq(30000049)
+                    .and(toSolarSystemID.eq(30000045))
+                    .or(fromSolarSystemID
+                        .eq(30000015)
+                        .and(toSolarSystemID.eq(30001047))),
+            )
+            .limit(2)
+            .order_by(fromSolarSystemID)
+            .load::<JumpRow>(&conn)
+            .expect("expect connection")
+            .into_iter()
+            .map(SdeRowMapper::connection)
+            .collect::<Vec<_>>();
+        let (sg1, sg2) = (&res[0], &res[1]);
+        assert_eq!(sg1.from, types::SystemId(30000015));
+        assert_eq!(sg1.to, types::SystemId(30001047));
+        assert_eq!(sg2.from, types::SystemId(30000049));
+        assert_eq!(sg2.to, types::SystemId(30000045));
+        if let types::ConnectionType::Stargate(jt) = &sg1.type_ {
+            assert_eq!(jt, &types::StargateType::Regional);
+        }
+        if let types::ConnectionType::Stargate(jt) = &sg2.type_ {
+            assert_eq!(jt, &types::StargateType::Local);
+        }
+    }
+}
+
+#[cfg(test)]
+mod benches {
+    extern crate test;
+
+    use std::env;
+
+    use super::*;
+
+    #[bench]
+    fn bench_simple_system_query(b: &mut test::Bencher) {
+        let uri = env::var("MYSQL_URL").expect("expected env variable MYSQL_URL set");
+        let conn = MysqlConnection::establish(&uri).expect("establish connection");
+        b.iter(|| {
+            test::black_box(
+                mapSolarSystems
+                    .filter(solarSystemID.eq(30000049))
+                    .limit(1)
+                    .load::<SystemRow>(&conn)
+                    .expect("first row to be returned from mysql"),
+            );
+        });
+    }
+
+    #[bench]
+    fn bench_build_universe(b: &mut test::Bencher) {
+        let uri = env::var("MYSQL_URL").expect("expected env variable MYSQL_URL set");
+        let conn = MysqlConnection::establish(&uri).expect("establish connection");
+        b.iter(|| {
+            test::black_box(sql::load(&conn, &LoadOptions::default()).unwrap());
+        });
+    }
+}
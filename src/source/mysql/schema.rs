@@ -0,0 +1,109 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+table! {
+    mapDenormalize (itemID) {
+        itemID -> Integer,
+        typeID -> Nullable<Integer>,
+        groupID -> Nullable<Integer>,
+        solarSystemID -> Nullable<Integer>,
+        constellationID -> Nullable<Integer>,
+        regionID -> Nullable<Integer>,
+        orbitID -> Nullable<Integer>,
+        x -> Nullable<Double>,
+        y -> Nullable<Double>,
+        z -> Nullable<Double>,
+        radius -> Nullable<Double>,
+        itemName -> Nullable<Varchar>,
+        security -> Nullable<Double>,
+        celestialIndex -> Nullable<Integer>,
+        orbitIndex -> Nullable<Integer>,
+    }
+}
+
+table! {
+    mapJumps (stargateID) {
+        stargateID -> Integer,
+        destinationID -> Nullable<Integer>,
+    }
+}
+
+table! {
+    mapLocationWormholeClasses (locationID) {
+        locationID -> Integer,
+        wormholeClassID -> Nullable<Integer>,
+    }
+}
+
+table! {
+    mapSolarSystemJumps (fromSolarSystemID, toSolarSystemID) {
+        fromRegionID -> Nullable<Integer>,
+        fromConstellationID -> Nullable<Integer>,
+        fromSolarSystemID -> Integer,
+        toSolarSystemID -> Integer,
+        toConstellationID -> Nullable<Integer>,
+        toRegionID -> Nullable<Integer>,
+    }
+}
+
+table! {
+    mapSolarSystems (solarSystemID) {
+        regionID -> Nullable<Integer>,
+        constellationID -> Nullable<Integer>,
+        solarSystemID -> Integer,
+        solarSystemName -> Nullable<Varchar>,
+        x -> Nullable<Double>,
+        y -> Nullable<Double>,
+        z -> Nullable<Double>,
+        // xMin -> Nullable<Double>,
+        // xMax -> Nullable<Double>,
+        // yMin -> Nullable<Double>,
+        // yMax -> Nullable<Double>,
+        // zMin -> Nullable<Double>,
+        // zMax -> Nullable<Double>,
+        luminosity -> Nullable<Double>,
+        // border -> Nullable<Bool>,
+        // fringe -> Nullable<Bool>,
+        // corridor -> Nullable<Bool>,
+        // hub -> Nullable<Bool>,
+        // international -> Nullable<Bool>,
+        // regional -> Nullable<Bool>,
+        // constellation -> Nullable<Bool>,
+        security -> Nullable<Double>,
+        factionID -> Nullable<Integer>,
+        // radius -> Nullable<Double>,
+        // sunTypeID -> Nullable<Integer>,
+        // securityClass -> Nullable<Varchar>,
+    }
+}
+
+table! {
+    trnTranslationColumns (tcID) {
+        tcGroupID -> Nullable<Integer>,
+        tcID -> Integer,
+        tableName -> Varchar,
+        columnName -> Varchar,
+        masterID -> Nullable<Varchar>,
+    }
+}
+
+table! {
+    trnTranslations (tcID, keyID, languageID) {
+        tcID -> Integer,
+        keyID -> Integer,
+        languageID -> Varchar,
+        text -> Text,
+    }
+}
+
+allow_tables_to_appear_in_same_query!(
+    mapDenormalize,
+    mapJumps,
+    mapLocationWormholeClasses,
+    mapSolarSystemJumps,
+    mapSolarSystems,
+    trnTranslationColumns,
+    trnTranslations,
+);
@@ -6,10 +6,13 @@
 #[allow(non_snake_case)]
 mod schema;
 
+use std::collections::HashMap;
+
 use anyhow;
 use diesel::pg::PgConnection;
 use diesel::prelude::*;
 
+use crate::source::sql::{self, CelestialRow, JumpRow, LoadOptions, SdeSource, SystemRow};
 use crate::types;
 use schema::mapSolarSystemJumps::dsl::*;
 use schema::mapSolarSystems::dsl::*;
@@ -36,44 +39,156 @@ type DB = diesel::pg::Pg;
 /// let system_id = 30000142.into(); // returns a SystemId
 /// println!("{:?}", universe.get_system(system_id).unwrap().name); // Jita
 /// ```
+#[derive(Debug, Clone)]
 pub struct DatabaseBuilder {
     uri: String,
+    options: LoadOptions,
 }
 
 impl DatabaseBuilder {
     pub fn new(uri: &str) -> Self {
         Self {
             uri: uri.to_string(),
+            options: LoadOptions::default(),
         }
     }
 
+    /// Also load planets, moons, asteroid belts, stargates and NPC stations from
+    /// mapDenormalize.
+    /// This is off by default as it considerably increases loading time and memory use.
+    pub fn celestials(mut self, load: bool) -> Self {
+        self.options.celestials = load;
+        self
+    }
+
+    /// Also load stargates with their positions and destinations from mapJumps.
+    pub fn stargates(mut self, load: bool) -> Self {
+        self.options.stargates = load;
+        self
+    }
+
+    /// Also load the wormhole system effects from the secondary suns in mapDenormalize.
+    pub fn effects(mut self, load: bool) -> Self {
+        self.options.effects = load;
+        self
+    }
+
+    /// Also load the Pochven gates, which are missing from mapSolarSystemJumps, from
+    /// the stargates in mapJumps. See `rules::pochven_gates`.
+    pub fn pochven(mut self, load: bool) -> Self {
+        self.options.pochven = load;
+        self
+    }
+
+    /// Also load the classes of wormhole systems from mapLocationWormholeClasses. See
+    /// `Universe::wormhole_info`.
+    pub fn wormholes(mut self, load: bool) -> Self {
+        self.options.wormholes = load;
+        self
+    }
+
+    /// Also load the translated system names from trnTranslations. See
+    /// `navigation::format`.
+    pub fn translations(mut self, load: bool) -> Self {
+        self.options.translations = load;
+        self
+    }
+
     pub fn build(self) -> anyhow::Result<types::Universe> {
         let conn = PgConnection::establish(&self.uri)?;
-        Self::from_connection(&conn)
+        sql::load(&conn, &self.options)
     }
+}
 
-    pub(self) fn from_connection(conn: &PgConnection) -> anyhow::Result<types::Universe> {
-        let systems = mapSolarSystems
+impl SdeSource for PgConnection {
+    fn systems(&self) -> anyhow::Result<Vec<SystemRow>> {
+        Ok(mapSolarSystems
             // this is k-space and w-space
             .filter(solarSystemID.lt(32000000))
-            .load::<types::System>(conn)?;
+            .load::<SystemRow>(self)?)
+    }
 
-        let jumps = mapSolarSystemJumps
+    fn jumps(&self) -> anyhow::Result<Vec<JumpRow>> {
+        Ok(mapSolarSystemJumps
             .filter(
                 // only query k-space since w-space has no connections
                 fromSolarSystemID
                     .lt(31000000)
                     .and(toSolarSystemID.lt(31000000)),
             )
-            .load::<types::Connection>(conn)?;
+            .load::<JumpRow>(self)?)
+    }
+
+    fn celestials(&self, groups: &[u32]) -> anyhow::Result<Vec<CelestialRow>> {
+        use schema::mapDenormalize::dsl as denormalize;
+
+        let groups = groups.iter().map(|g| *g as i32).collect::<Vec<_>>();
+        Ok(denormalize::mapDenormalize
+            .filter(denormalize::groupID.eq_any(groups))
+            .load::<CelestialRow>(self)?)
+    }
+
+    fn stargate_jumps(&self) -> anyhow::Result<Vec<(u32, u32)>> {
+        use schema::mapJumps::dsl as jumps;
+
+        Ok(jumps::mapJumps
+            .select((jumps::stargateID, jumps::destinationID))
+            .load::<(i32, Option<i32>)>(self)?
+            .into_iter()
+            .filter_map(|(gate, destination)| Some((gate as u32, destination? as u32)))
+            .collect())
+    }
+
+    fn secondary_suns(&self) -> anyhow::Result<Vec<(u32, i32)>> {
+        use schema::mapDenormalize::dsl as denormalize;
+
+        Ok(denormalize::mapDenormalize
+            .filter(denormalize::groupID.eq(995))
+            .select((denormalize::solarSystemID, denormalize::typeID))
+            .load::<(Option<i32>, Option<i32>)>(self)?
+            .into_iter()
+            .filter_map(|(system, type_id)| Some((system? as u32, type_id?)))
+            .collect())
+    }
+
+    fn wormhole_classes(&self) -> anyhow::Result<HashMap<u32, i32>> {
+        use schema::mapLocationWormholeClasses::dsl as classes;
 
-        Ok(types::Universe::new(systems.into(), jumps.into()))
+        Ok(classes::mapLocationWormholeClasses
+            .select((classes::locationID, classes::wormholeClassID))
+            .load::<(i32, Option<i32>)>(self)?
+            .into_iter()
+            .filter_map(|(location, class)| Some((location as u32, class?)))
+            .collect())
+    }
+
+    fn system_name_translations(&self) -> anyhow::Result<Vec<(u32, String, String)>> {
+        use schema::trnTranslationColumns::dsl as columns;
+        use schema::trnTranslations::dsl as translations;
+
+        let column = columns::trnTranslationColumns
+            .filter(columns::tableName.eq("dbo.mapSolarSystems"))
+            .filter(columns::columnName.eq("solarSystemName"))
+            .select(columns::tcID)
+            .first::<i32>(self)?;
+        Ok(translations::trnTranslations
+            .filter(translations::tcID.eq(column))
+            .select((
+                translations::keyID,
+                translations::languageID,
+                translations::text,
+            ))
+            .load::<(i32, String, String)>(self)?
+            .into_iter()
+            .map(|(system, language, text)| (system as u32, language, text))
+            .collect())
     }
 }
 
-impl Queryable<schema::mapSolarSystems::SqlType, DB> for types::System {
+impl Queryable<schema::mapSolarSystems::SqlType, DB> for SystemRow {
     type Row = (
         Option<i32>,    // regionID
+        Option<i32>,    // constellationID
         i32,            // solarSystemID
         Option<String>, // solarSystemName
         Option<f64>,    // x
@@ -81,23 +196,57 @@ impl Queryable<schema::mapSolarSystems::SqlType, DB> for types::System {
         Option<f64>,    // z
         Option<f64>,    // luminosity
         Option<f64>,    // security
+        Option<i32>,    // factionID
+    );
+
+    fn build(row: Self::Row) -> Self {
+        SystemRow {
+            id: row.2 as u32,
+            name: row.3.unwrap(),
+            x: row.4.unwrap(),
+            y: row.5.unwrap(),
+            z: row.6.unwrap(),
+            security: row.8.unwrap(),
+            region: row.0.map(|id| id as u32),
+            constellation: row.1.map(|id| id as u32),
+            faction: row.9.map(|id| id as u32),
+        }
+    }
+}
+
+impl Queryable<schema::mapDenormalize::SqlType, DB> for CelestialRow {
+    type Row = (
+        i32,            // itemID
+        Option<i32>,    // typeID
+        Option<i32>,    // groupID
+        Option<i32>,    // solarSystemID
+        Option<i32>,    // constellationID
+        Option<i32>,    // regionID
+        Option<i32>,    // orbitID
+        Option<f64>,    // x
+        Option<f64>,    // y
+        Option<f64>,    // z
+        Option<f64>,    // radius
+        Option<String>, // itemName
+        Option<f64>,    // security
+        Option<i32>,    // celestialIndex
+        Option<i32>,    // orbitIndex
     );
 
     fn build(row: Self::Row) -> Self {
-        types::System {
-            id: types::SystemId(row.1 as u32),
-            name: row.2.unwrap(),
-            coordinate: types::Coordinate {
-                x: row.3.unwrap(),
-                y: row.4.unwrap(),
-                z: row.5.unwrap(),
-            },
-            security: types::Security(row.7.unwrap() as f32),
+        CelestialRow {
+            id: row.0 as u32,
+            name: row.11.unwrap(),
+            system: row.3.unwrap() as u32,
+            group: row.2.unwrap() as u32,
+            x: row.7.unwrap(),
+            y: row.8.unwrap(),
+            z: row.9.unwrap(),
         }
     }
 }
 
-impl Queryable<schema::mapSolarSystemJumps::SqlType, DB> for types::Connection {
+impl Queryable<schema::mapSolarSystemJumps::SqlType, DB> for JumpRow {
     type Row = (
         Option<i32>, // fromRegionID
         Option<i32>, // fromConstellationID,
@@ -108,16 +257,13 @@ impl Queryable<schema::mapSolarSystemJumps::SqlType, DB> for types::Connection {
     );
 
     fn build(row: Self::Row) -> Self {
-        let stargate_type = match (row.0, row.1, row.4, row.5) {
-            (a, _, _, b) if a != b => types::StargateType::Regional,
-            (_, a, b, _) if a != b => types::StargateType::Constellation,
-            _ => types::StargateType::Local,
-        };
-
-        types::Connection {
-            from: types::SystemId(row.2 as u32),
-            to: types::SystemId(row.3 as u32),
-            type_: types::ConnectionType::Stargate(stargate_type),
+        JumpRow {
+            from_region: row.0.map(|id| id as u32),
+            from_constellation: row.1.map(|id| id as u32),
+            from: row.2 as u32,
+            to: row.3 as u32,
+            to_constellation: row.4.map(|id| id as u32),
+            to_region: row.5.map(|id| id as u32),
         }
     }
 }
@@ -127,6 +273,7 @@ mod tests {
     use std::env;
 
     use super::*;
+    use crate::source::sql::SdeRowMapper;
 
     #[test]
     fn test_simple_system_query() {
@@ -136,9 +283,9 @@ mod tests {
         let system = mapSolarSystems
             .filter(solarSystemID.eq(30000049))
             .limit(1)
-            .load::<types::System>(&conn)
+            .load::<SystemRow>(&conn)
             .expect("first row to be returned from postgres");
-        assert_eq!("Camal", system[0].name);
+        assert_eq!("Camal", SdeRowMapper::system(system[0].clone()).name);
     }
 
     #[test]
@@ -157,8 +304,11 @@ mod tests {
             )
             .limit(2)
             .order_by(fromSolarSystemID)
-            .load::<types::Connection>(&conn)
-            .expect("expect connection");
+            .load::<JumpRow>(&conn)
+            .expect("expect connection")
+            .into_iter()
+            .map(SdeRowMapper::connection)
+            .collect::<Vec<_>>();
         let (sg1, sg2) = (&res[0], &res[1]);
         assert_eq!(sg1.from, types::SystemId(30000015));
         assert_eq!(sg1.to, types::SystemId(30001047));
@@ -190,7 +340,7 @@ mod benches {
                 mapSolarSystems
                     .filter(solarSystemID.eq(30000049))
                     .limit(1)
-                    .load::<types::System>(&conn)
+                    .load::<SystemRow>(&conn)
                     .expect("first row to be returned from postgres"),
             );
         });
@@ -201,7 +351,7 @@ mod benches {
         let uri = env::var("DATABASE_URL").expect("expected env variable DATABASE_URL set");
         let conn = PgConnection::establish(&uri).expect("establish connection");
         b.iter(|| {
-            test::black_box(DatabaseBuilder::from_connection(&conn).unwrap());
+            test::black_box(sql::load(&conn, &LoadOptions::default()).unwrap());
         });
     }
 }
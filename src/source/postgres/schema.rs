@@ -28,6 +28,33 @@ table! {
     }
 }
 
+table! {
+    mapDenormalize (itemID) {
+        itemID -> Int4,
+        typeID -> Nullable<Int4>,
+        groupID -> Nullable<Int4>,
+        solarSystemID -> Nullable<Int4>,
+        constellationID -> Nullable<Int4>,
+        regionID -> Nullable<Int4>,
+        orbitID -> Nullable<Int4>,
+        x -> Nullable<Float8>,
+        y -> Nullable<Float8>,
+        z -> Nullable<Float8>,
+        radius -> Nullable<Float8>,
+        itemName -> Nullable<Varchar>,
+        security -> Nullable<Float8>,
+        celestialIndex -> Nullable<Int4>,
+        orbitIndex -> Nullable<Int4>,
+    }
+}
+
+table! {
+    mapJumps (stargateID) {
+        stargateID -> Int4,
+        destinationID -> Nullable<Int4>,
+    }
+}
+
 table! {
     mapLandmarks (landmarkID) {
         landmarkID -> Int4,
@@ -41,6 +68,13 @@ table! {
     }
 }
 
+table! {
+    mapLocationWormholeClasses (locationID) {
+        locationID -> Int4,
+        wormholeClassID -> Nullable<Int4>,
+    }
+}
+
 table! {
     mapRegions (regionID) {
         regionID -> Int4,
@@ -73,7 +107,7 @@ table! {
 table! {
     mapSolarSystems (solarSystemID) {
         regionID -> Nullable<Int4>,
-        // constellationID -> Nullable<Int4>,
+        constellationID -> Nullable<Int4>,
         solarSystemID -> Int4,
         solarSystemName -> Nullable<Varchar>,
         x -> Nullable<Float8>,
@@ -94,7 +128,7 @@ table! {
         // regional -> Nullable<Bool>,
         // constellation -> Nullable<Bool>,
         security -> Nullable<Float8>,
-        // factionID -> Nullable<Int4>,
+        factionID -> Nullable<Int4>,
         // radius -> Nullable<Float8>,
         // sunTypeID -> Nullable<Int4>,
         // securityClass -> Nullable<Varchar>,
@@ -118,13 +152,37 @@ table! {
     }
 }
 
+table! {
+    trnTranslationColumns (tcID) {
+        tcGroupID -> Nullable<Int4>,
+        tcID -> Int4,
+        tableName -> Varchar,
+        columnName -> Varchar,
+        masterID -> Nullable<Varchar>,
+    }
+}
+
+table! {
+    trnTranslations (tcID, keyID, languageID) {
+        tcID -> Int4,
+        keyID -> Int4,
+        languageID -> Varchar,
+        text -> Text,
+    }
+}
+
 allow_tables_to_appear_in_same_query!(
     mapCelestialStatistics,
+    mapDenormalize,
+    mapJumps,
     mapLandmarks,
+    mapLocationWormholeClasses,
     mapRegions,
     mapSolarSystemJumps,
     mapSolarSystems,
     mapUniverse,
+    trnTranslationColumns,
+    trnTranslations,
 );
 
 joinable!(
@@ -25,6 +25,11 @@ impl From<rpctypes::System> for types::System {
             name: other.name,
             coordinate: other.coordinate.into(),
             security: other.security.into(),
+            region: None,
+            constellation: None,
+            invasion: None,
+            faction: None,
+            effect: None,
         }
     }
 }
@@ -38,11 +43,13 @@ fn conv_undirected_to_directed(other: rpctypes::UndirectedConnection) -> Vec<typ
         from: id1.clone(),
         to: id2.clone(),
         type_: stargate_type.clone(),
+        source: types::ConnectionSource::Sde,
     };
     let b = types::Connection {
         from: id2.clone(),
         to: id1.clone(),
         type_: stargate_type.clone(),
+        source: types::ConnectionSource::Sde,
     };
     vec![a, b]
 }
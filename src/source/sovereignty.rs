@@ -0,0 +1,151 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! The current sovereignty and faction warfare occupancy from ESI.
+//!
+//! # Example
+//! ```no_run
+//! use neweden::source::sovereignty::EsiSovereignty;
+//! # use neweden::builder::UniverseBuilder;
+//! # let universe = UniverseBuilder::new().build();
+//!
+//! let universe = universe.with_sovereignty(EsiSovereignty::new().fetch().unwrap());
+//! ```
+use anyhow;
+use serde::Deserialize;
+
+use crate::types;
+
+/// The ESI endpoint listing the sovereignty of every system.
+pub const SOVEREIGNTY_URL: &str = "https://esi.evetech.net/latest/sovereignty/map/";
+/// The ESI endpoint listing the faction warfare systems.
+pub const FW_SYSTEMS_URL: &str = "https://esi.evetech.net/latest/fw/systems/";
+
+#[derive(Deserialize)]
+struct SovereigntyEntry {
+    system_id: u32,
+    alliance_id: Option<u32>,
+    corporation_id: Option<u32>,
+    faction_id: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct FwSystem {
+    solar_system_id: u32,
+    occupier_faction_id: u32,
+}
+
+/// Fetches sovereignty from ESI. Both endpoints are public and cached for an
+/// hour, so there is no point in fetching more often.
+pub struct EsiSovereignty {
+    sovereignty_url: String,
+    fw_url: String,
+}
+
+impl Default for EsiSovereignty {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EsiSovereignty {
+    pub fn new() -> Self {
+        Self {
+            sovereignty_url: SOVEREIGNTY_URL.to_string(),
+            fw_url: FW_SYSTEMS_URL.to_string(),
+        }
+    }
+
+    /// Uses different endpoints, e.g. a caching proxy.
+    pub fn urls(mut self, sovereignty: &str, fw_systems: &str) -> Self {
+        self.sovereignty_url = sovereignty.to_string();
+        self.fw_url = fw_systems.to_string();
+        self
+    }
+
+    /// Fetches the sovereignty map and the faction warfare occupancy, ready for
+    /// `Universe::with_sovereignty`.
+    pub fn fetch(&self) -> anyhow::Result<types::SovMap> {
+        let mut map = parse_sovereignty(&ureq::get(&self.sovereignty_url).call()?.into_string()?)?;
+        map.merge(parse_fw_systems(
+            &ureq::get(&self.fw_url).call()?.into_string()?,
+        )?);
+        Ok(map)
+    }
+}
+
+/// Parses a response of the sovereignty map endpoint. Systems nobody holds are
+/// skipped.
+pub fn parse_sovereignty(json: &str) -> anyhow::Result<types::SovMap> {
+    let entries: Vec<SovereigntyEntry> = serde_json::from_str(json)?;
+    Ok(entries
+        .into_iter()
+        .filter(|e| e.alliance_id.is_some() || e.corporation_id.is_some() || e.faction_id.is_some())
+        .map(|e| {
+            let sovereignty = types::Sovereignty {
+                alliance: e.alliance_id.map(types::AllianceId),
+                corporation: e.corporation_id.map(types::CorporationId),
+                faction: e.faction_id.map(types::FactionId),
+                occupier: None,
+            };
+            (types::SystemId(e.system_id), sovereignty)
+        })
+        .collect::<Vec<_>>()
+        .into())
+}
+
+/// Parses a response of the faction warfare systems endpoint into occupiers.
+pub fn parse_fw_systems(json: &str) -> anyhow::Result<types::SovMap> {
+    let systems: Vec<FwSystem> = serde_json::from_str(json)?;
+    Ok(systems
+        .into_iter()
+        .map(|s| {
+            let sovereignty = types::Sovereignty {
+                occupier: Some(types::FactionId(s.occupier_faction_id)),
+                ..Default::default()
+            };
+            (types::SystemId(s.solar_system_id), sovereignty)
+        })
+        .collect::<Vec<_>>()
+        .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let mut map = parse_sovereignty(
+            r#"[
+                {"system_id": 30004759, "alliance_id": 1354830081, "corporation_id": 1344654522},
+                {"system_id": 30002813, "faction_id": 500001},
+                {"system_id": 30000142}
+            ]"#,
+        )
+        .unwrap();
+        map.merge(
+            parse_fw_systems(
+                r#"[{
+                    "solar_system_id": 30002813,
+                    "owner_faction_id": 500001,
+                    "occupier_faction_id": 500002,
+                    "contested": "uncontested",
+                    "victory_points": 0,
+                    "victory_points_threshold": 3000
+                }]"#,
+            )
+            .unwrap(),
+        );
+        assert_eq!(2, map.0.len());
+        assert_eq!(
+            Some(types::AllianceId(1354830081)),
+            map.0[&30004759.into()].alliance
+        );
+        let tama = &map.0[&30002813.into()];
+        assert_eq!(Some(types::FactionId(500001)), tama.faction);
+        assert_eq!(Some(types::FactionId(500002)), tama.occupier);
+    }
+}
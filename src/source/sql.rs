@@ -0,0 +1,375 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! What the SQL sources have in common: the rows they read from the static data
+//! export, how those rows become universe types and which tables are loaded.
+//!
+//! A backend only implements `SdeSource` for its connection type, i.e. runs the
+//! queries, and passes the connection to `load`. The SQLite, Postgres and MySQL
+//! sources are built this way.
+use std::collections::HashMap;
+
+use anyhow;
+
+use crate::metrics;
+use crate::types;
+
+/// A row of mapSolarSystems.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SystemRow {
+    pub id: u32,
+    pub name: String,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub security: f64,
+    pub region: Option<u32>,
+    pub constellation: Option<u32>,
+    pub faction: Option<u32>,
+}
+
+/// A row of mapSolarSystemJumps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JumpRow {
+    pub from_region: Option<u32>,
+    pub from_constellation: Option<u32>,
+    pub from: u32,
+    pub to: u32,
+    pub to_constellation: Option<u32>,
+    pub to_region: Option<u32>,
+}
+
+/// A row of mapDenormalize.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CelestialRow {
+    pub id: u32,
+    pub name: String,
+    pub system: u32,
+    pub group: u32,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+/// The queries a SQL backend runs against the static data export.
+pub trait SdeSource {
+    /// All of mapSolarSystems the backend supports.
+    fn systems(&self) -> anyhow::Result<Vec<SystemRow>>;
+    /// All of mapSolarSystemJumps the backend supports.
+    fn jumps(&self) -> anyhow::Result<Vec<JumpRow>>;
+    /// The rows of mapDenormalize in any of the given groups.
+    fn celestials(&self, groups: &[u32]) -> anyhow::Result<Vec<CelestialRow>>;
+    /// The stargates of mapJumps and their destination gates.
+    fn stargate_jumps(&self) -> anyhow::Result<Vec<(u32, u32)>>;
+    /// The systems and types of the secondary suns (group 995) of mapDenormalize.
+    fn secondary_suns(&self) -> anyhow::Result<Vec<(u32, i32)>>;
+    /// mapLocationWormholeClasses, skipping locations without a class.
+    fn wormhole_classes(&self) -> anyhow::Result<HashMap<u32, i32>>;
+    /// The system ids, language ids and texts of the translated system names in
+    /// trnTranslations.
+    fn system_name_translations(&self) -> anyhow::Result<Vec<(u32, String, String)>>;
+}
+
+/// Turns rows of the static data export into universe types.
+pub struct SdeRowMapper;
+
+impl SdeRowMapper {
+    pub fn system(row: SystemRow) -> types::System {
+        types::System {
+            id: types::SystemId(row.id),
+            name: row.name,
+            coordinate: types::Coordinate {
+                x: row.x,
+                y: row.y,
+                z: row.z,
+            },
+            security: types::Security(row.security as f32),
+            region: row.region.map(types::RegionId),
+            constellation: row.constellation.map(types::ConstellationId),
+            invasion: None,
+            faction: row.faction.map(types::FactionId),
+            effect: None,
+        }
+    }
+
+    pub fn connection(row: JumpRow) -> types::Connection {
+        let stargate_type = match (
+            row.from_region,
+            row.from_constellation,
+            row.to_constellation,
+            row.to_region,
+        ) {
+            (a, _, _, b) if a != b => types::StargateType::Regional,
+            (_, a, b, _) if a != b => types::StargateType::Constellation,
+            _ => types::StargateType::Local,
+        };
+        types::Connection {
+            from: types::SystemId(row.from),
+            to: types::SystemId(row.to),
+            type_: types::ConnectionType::Stargate(stargate_type),
+            source: types::ConnectionSource::Sde,
+        }
+    }
+
+    /// `None` for groups without a `CelestialKind`.
+    pub fn celestial(row: CelestialRow) -> Option<types::Celestial> {
+        Some(types::Celestial {
+            id: types::CelestialId(row.id),
+            name: row.name,
+            system: types::SystemId(row.system),
+            kind: types::CelestialKind::from_group_id(row.group)?,
+            coordinate: types::Coordinate {
+                x: row.x,
+                y: row.y,
+                z: row.z,
+            },
+        })
+    }
+
+    /// Joins the stargates of mapJumps with their gates and destination gates.
+    /// Jumps whose gates are unknown are skipped.
+    pub fn stargates(gates: Vec<CelestialRow>, jumps: Vec<(u32, u32)>) -> Vec<types::Stargate> {
+        let gates: HashMap<_, _> = gates.into_iter().map(|g| (g.id, g)).collect();
+        jumps
+            .into_iter()
+            .filter_map(|(gate, destination)| {
+                let gate = gates.get(&gate)?;
+                let destination = gates.get(&destination)?;
+                Some(types::Stargate {
+                    id: types::CelestialId(gate.id),
+                    system: types::SystemId(gate.system),
+                    coordinate: types::Coordinate {
+                        x: gate.x,
+                        y: gate.y,
+                        z: gate.z,
+                    },
+                    destination: types::CelestialId(destination.id),
+                    destination_system: types::SystemId(destination.system),
+                })
+            })
+            .collect()
+    }
+
+    /// Secondary suns without an effect, e.g. in known space, are skipped.
+    pub fn effects(suns: Vec<(u32, i32)>) -> Vec<(types::SystemId, types::SystemEffect)> {
+        suns.into_iter()
+            .filter_map(|(system, type_id)| {
+                Some((system.into(), types::SystemEffect::from_type_id(type_id)?))
+            })
+            .collect()
+    }
+
+    /// Languages without a `Locale` are skipped.
+    pub fn localized_names(
+        names: Vec<(u32, String, String)>,
+    ) -> Vec<(types::SystemId, types::Locale, String)> {
+        names
+            .into_iter()
+            .filter_map(|(system, language, text)| {
+                Some((system.into(), types::Locale::from_code(&language)?, text))
+            })
+            .collect()
+    }
+}
+
+/// Which tables to load besides systems and jumps. The `DatabaseBuilder`s of the
+/// sources set these.
+#[derive(Debug, Clone, Default)]
+pub struct LoadOptions {
+    pub celestials: bool,
+    pub stargates: bool,
+    pub effects: bool,
+    pub pochven: bool,
+    pub wormholes: bool,
+    pub translations: bool,
+}
+
+/// The groups of mapDenormalize loaded as celestials.
+const CELESTIAL_GROUPS: [u32; 5] = [7, 8, 9, 10, 15];
+const STARGATE_GROUP: u32 = 10;
+
+/// Loads a universe from any SQL backend.
+pub fn load<S: SdeSource + ?Sized>(
+    source: &S,
+    options: &LoadOptions,
+) -> anyhow::Result<types::Universe> {
+    let start = std::time::Instant::now();
+    let result = load_tables(source, options);
+    match &result {
+        Ok(_) => {
+            metrics::increment(metrics::UNIVERSE_LOADS);
+            metrics::observe(
+                metrics::UNIVERSE_LOAD_SECONDS,
+                start.elapsed().as_secs_f64(),
+            );
+        }
+        Err(_) => metrics::increment(metrics::UNIVERSE_LOAD_FAILURES),
+    }
+    result
+}
+
+fn load_tables<S: SdeSource + ?Sized>(
+    source: &S,
+    options: &LoadOptions,
+) -> anyhow::Result<types::Universe> {
+    let systems = source
+        .systems()?
+        .into_iter()
+        .map(SdeRowMapper::system)
+        .collect::<Vec<_>>();
+    let connections = source
+        .jumps()?
+        .into_iter()
+        .map(SdeRowMapper::connection)
+        .collect::<Vec<_>>();
+    let mut universe = types::Universe::new(
+        types::SystemMap::from(systems),
+        types::AdjacentMap::from(connections),
+    );
+
+    if options.celestials {
+        let celestials = source
+            .celestials(&CELESTIAL_GROUPS)?
+            .into_iter()
+            .filter_map(SdeRowMapper::celestial)
+            .collect();
+        universe = universe.with_celestials(celestials);
+    }
+    if options.stargates || options.pochven {
+        let stargates = SdeRowMapper::stargates(
+            source.celestials(&[STARGATE_GROUP])?,
+            source.stargate_jumps()?,
+        );
+        if options.pochven {
+            let gates = crate::rules::pochven_gates(&universe, &stargates);
+            universe = universe.with_connections(gates);
+        }
+        if options.stargates {
+            universe = universe.with_stargates(stargates);
+        }
+    }
+    if options.effects {
+        universe = universe.with_effects(SdeRowMapper::effects(source.secondary_suns()?));
+    }
+    if options.wormholes {
+        universe = universe.with_location_wormhole_classes(source.wormhole_classes()?);
+    }
+    if options.translations {
+        universe = universe.with_localized_names(SdeRowMapper::localized_names(
+            source.system_name_translations()?,
+        ));
+    }
+    Ok(universe)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Galaxy, Navigatable};
+
+    /// A static data export with two systems in different regions.
+    struct Rows;
+
+    impl SdeSource for Rows {
+        fn systems(&self) -> anyhow::Result<Vec<SystemRow>> {
+            let system = |id: u32, name: &str, region: u32| SystemRow {
+                id,
+                name: name.to_string(),
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+                security: 0.5,
+                region: Some(region),
+                constellation: Some(region + 10000000),
+                faction: None,
+            };
+            Ok(vec![
+                system(30000001, "Alpha", 10000001),
+                system(30000002, "Bravo", 10000002),
+            ])
+        }
+
+        fn jumps(&self) -> anyhow::Result<Vec<JumpRow>> {
+            Ok(vec![JumpRow {
+                from_region: Some(10000001),
+                from_constellation: Some(20000001),
+                from: 30000001,
+                to: 30000002,
+                to_constellation: Some(20000002),
+                to_region: Some(10000002),
+            }])
+        }
+
+        fn celestials(&self, groups: &[u32]) -> anyhow::Result<Vec<CelestialRow>> {
+            let celestial = |id: u32, system: u32, group: u32| CelestialRow {
+                id,
+                name: format!("{}", id),
+                system,
+                group,
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            };
+            Ok(vec![
+                celestial(40000001, 30000001, 7),
+                celestial(50000001, 30000001, 10),
+                celestial(50000002, 30000002, 10),
+            ]
+            .into_iter()
+            .filter(|c| groups.contains(&c.group))
+            .collect())
+        }
+
+        fn stargate_jumps(&self) -> anyhow::Result<Vec<(u32, u32)>> {
+            Ok(vec![(50000001, 50000002), (50000002, 50000001)])
+        }
+
+        fn secondary_suns(&self) -> anyhow::Result<Vec<(u32, i32)>> {
+            Ok(vec![(30000001, 30577), (30000002, 1)])
+        }
+
+        fn wormhole_classes(&self) -> anyhow::Result<HashMap<u32, i32>> {
+            Ok(HashMap::new())
+        }
+
+        fn system_name_translations(&self) -> anyhow::Result<Vec<(u32, String, String)>> {
+            Ok(vec![
+                (30000001, "de".to_string(), "Alfa".to_string()),
+                (30000001, "xx".to_string(), "?".to_string()),
+            ])
+        }
+    }
+
+    #[test]
+    fn test_load() {
+        let universe = load(&Rows, &LoadOptions::default()).unwrap();
+        assert_eq!(2, universe.systems().len());
+        let connections = universe.get_connections(&30000001.into()).unwrap();
+        assert_eq!(
+            types::ConnectionType::Stargate(types::StargateType::Regional),
+            connections[0].type_
+        );
+        assert!(universe.celestials_in(&30000001.into()).is_empty());
+
+        let options = LoadOptions {
+            celestials: true,
+            stargates: true,
+            effects: true,
+            translations: true,
+            ..Default::default()
+        };
+        let universe = load(&Rows, &options).unwrap();
+        assert_eq!(2, universe.celestials_in(&30000001.into()).len());
+        assert_eq!(
+            types::SystemId(30000002),
+            universe.get_stargates(&30000001.into())[0].destination_system
+        );
+        assert_eq!(
+            &[types::SystemEffect::Pulsar],
+            universe.system_effects(&30000001.into())
+        );
+        assert!(universe.system_effects(&30000002.into()).is_empty());
+    }
+}
@@ -1,10 +1,15 @@
+use std::collections::HashMap;
+
 use anyhow;
 use rusqlite;
 
+use crate::source::sql::{self, CelestialRow, JumpRow, LoadOptions, SdeSource, SystemRow};
 use crate::types;
 
+#[derive(Debug, Clone)]
 pub struct DatabaseBuilder {
     uri: String,
+    options: LoadOptions,
 }
 
 /// Loads a universe from a database.
@@ -17,7 +22,7 @@ pub struct DatabaseBuilder {
 /// from a universe by calling `.extend()` or `ExtendedUniverse::new()`.
 ///
 /// # Example
-/// ```
+/// ```no_run
 /// use std::env;
 /// use neweden::source::sqlite::DatabaseBuilder;
 /// use neweden::Navigatable;
@@ -31,87 +36,202 @@ impl DatabaseBuilder {
     pub fn new(uri: &str) -> Self {
         Self {
             uri: uri.to_string(),
+            options: LoadOptions::default(),
         }
     }
 
+    /// Also load planets, moons, asteroid belts, stargates and NPC stations from
+    /// mapDenormalize.
+    /// This is off by default as it considerably increases loading time and memory use.
+    pub fn celestials(mut self, load: bool) -> Self {
+        self.options.celestials = load;
+        self
+    }
+
+    /// Also load stargates with their positions and destinations from mapJumps.
+    pub fn stargates(mut self, load: bool) -> Self {
+        self.options.stargates = load;
+        self
+    }
+
+    /// Also load the wormhole system effects from the secondary suns in mapDenormalize.
+    pub fn effects(mut self, load: bool) -> Self {
+        self.options.effects = load;
+        self
+    }
+
+    /// Also load the Pochven gates, which are missing from mapSolarSystemJumps, from
+    /// the stargates in mapJumps. See `rules::pochven_gates`.
+    pub fn pochven(mut self, load: bool) -> Self {
+        self.options.pochven = load;
+        self
+    }
+
+    /// Also load the classes of wormhole systems from mapLocationWormholeClasses. See
+    /// `Universe::wormhole_info`.
+    pub fn wormholes(mut self, load: bool) -> Self {
+        self.options.wormholes = load;
+        self
+    }
+
+    /// Also load the translated system names from trnTranslations. See
+    /// `navigation::format`.
+    pub fn translations(mut self, load: bool) -> Self {
+        self.options.translations = load;
+        self
+    }
+
     pub fn build(self) -> anyhow::Result<types::Universe> {
-        Self::from_connection(rusqlite::Connection::open_with_flags(
+        let conn = rusqlite::Connection::open_with_flags(
             self.uri,
             rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_URI,
-        )?)
+        )?;
+        sql::load(&conn, &self.options)
     }
+}
 
-    pub(self) fn from_connection(conn: rusqlite::Connection) -> anyhow::Result<types::Universe> {
-        let systems = {
-            let mut stm = conn.prepare(
-                "
-    		    SELECT solarSystemID, solarSystemName, x, y, z, security
+/// Collects the rows of a query, mapping each with `f`.
+fn query<T, F>(conn: &rusqlite::Connection, sql: &str, f: F) -> anyhow::Result<Vec<T>>
+where
+    F: FnMut(&rusqlite::Row<'_>) -> rusqlite::Result<T>,
+{
+    let mut stm = conn.prepare(sql)?;
+    let result = stm.query([])?.mapped(f).collect::<Result<Vec<_>, _>>()?;
+    Ok(result)
+}
+
+impl SdeSource for rusqlite::Connection {
+    fn systems(&self) -> anyhow::Result<Vec<SystemRow>> {
+        query(
+            self,
+            "
+    		    SELECT solarSystemID, solarSystemName, x, y, z, security, regionID, constellationID,
+    		        factionID
     			FROM mapSolarSystems
+    			-- this is k-space and w-space
+    			WHERE solarSystemID < 32000000
     		",
-            )?;
-
-            let result = stm
-                .query([])?
-                .mapped(|row| {
-                    Ok(types::System {
-                        id: types::SystemId::from(row.get::<_, u32>(0)?),
-                        name: row.get(1)?,
-                        coordinate: types::Coordinate {
-                            x: row.get(2)?,
-                            y: row.get(3)?,
-                            z: row.get(4)?,
-                        },
-                        security: types::Security::from(row.get::<_, f32>(5)?),
-                    })
+            |row| {
+                Ok(SystemRow {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    x: row.get(2)?,
+                    y: row.get(3)?,
+                    z: row.get(4)?,
+                    security: row.get(5)?,
+                    region: row.get(6)?,
+                    constellation: row.get(7)?,
+                    faction: row.get(8)?,
                 })
-                .collect::<Result<Vec<_>, _>>()?;
-            // apparently we can't directly retrun due to borrow rules of stm
-            // so we gather everything into result and return it.
-            result
-        };
-
-        let connections = {
-            let mut stm = conn.prepare(
-                "
+            },
+        )
+    }
+
+    fn jumps(&self) -> anyhow::Result<Vec<JumpRow>> {
+        query(
+            self,
+            "
     		    SELECT
                     fromRegionID,
                     fromConstellationID,
                     fromSolarSystemID,
-                    toSolarSystemID
+                    toSolarSystemID,
                     toConstellationID,
                     toRegionID
     			FROM mapSolarSystemJumps
+    			-- only query k-space since w-space has no connections
+    			WHERE fromSolarSystemID < 31000000 AND toSolarSystemID < 31000000
+    		",
+            |row| {
+                Ok(JumpRow {
+                    from_region: row.get(0).ok(),
+                    from_constellation: row.get(1).ok(),
+                    from: row.get(2)?,
+                    to: row.get(3)?,
+                    to_constellation: row.get(4).ok(),
+                    to_region: row.get(5).ok(),
+                })
+            },
+        )
+    }
+
+    fn celestials(&self, groups: &[u32]) -> anyhow::Result<Vec<CelestialRow>> {
+        let groups = groups
+            .iter()
+            .map(|g| g.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        query(
+            self,
+            &format!(
+                "
+    		    SELECT itemID, itemName, solarSystemID, groupID, x, y, z
+    			FROM mapDenormalize
+    			WHERE groupID IN ({})
     		",
-            )?;
-
-            let result = stm
-                .query([])?
-                .mapped(|row| {
-                    let from: i32 = row.get(2)?;
-                    let to: i32 = row.get(3)?;
-                    let stargate_type = match (
-                        row.get::<_, i32>(0),
-                        row.get::<_, i32>(1),
-                        row.get::<_, i32>(4),
-                        row.get::<_, i32>(5),
-                    ) {
-                        (a, _, _, b) if a != b => types::StargateType::Regional,
-                        (_, a, b, _) if a != b => types::StargateType::Constellation,
-                        _ => types::StargateType::Local,
-                    };
-                    Ok(types::Connection {
-                        from: from.into(),
-                        to: to.into(),
-                        type_: types::ConnectionType::Stargate(stargate_type),
-                    })
+                groups
+            ),
+            |row| {
+                Ok(CelestialRow {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    system: row.get(2)?,
+                    group: row.get(3)?,
+                    x: row.get(4)?,
+                    y: row.get(5)?,
+                    z: row.get(6)?,
                 })
-                .collect::<Result<Vec<_>, _>>()?;
-            result
-        };
-
-        Ok(types::Universe::new(
-            types::SystemMap::from(systems),
-            types::AdjacentMap::from(connections),
-        ))
+            },
+        )
+    }
+
+    fn stargate_jumps(&self) -> anyhow::Result<Vec<(u32, u32)>> {
+        query(
+            self,
+            "
+    		    SELECT stargateID, destinationID
+    			FROM mapJumps
+    			WHERE destinationID IS NOT NULL
+    		",
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+    }
+
+    fn secondary_suns(&self) -> anyhow::Result<Vec<(u32, i32)>> {
+        query(
+            self,
+            "
+    		    SELECT solarSystemID, typeID
+    			FROM mapDenormalize
+    			WHERE groupID = 995
+    		",
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+    }
+
+    fn wormhole_classes(&self) -> anyhow::Result<HashMap<u32, i32>> {
+        let classes = query(
+            self,
+            "
+    		    SELECT locationID, wormholeClassID
+    			FROM mapLocationWormholeClasses
+    			WHERE wormholeClassID IS NOT NULL
+    		",
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        Ok(classes.into_iter().collect())
+    }
+
+    fn system_name_translations(&self) -> anyhow::Result<Vec<(u32, String, String)>> {
+        query(
+            self,
+            "
+    		    SELECT t.keyID, t.languageID, t.text
+    			FROM trnTranslations t
+    			JOIN trnTranslationColumns c ON c.tcID = t.tcID
+    			WHERE c.tableName = 'dbo.mapSolarSystems' AND c.columnName = 'solarSystemName'
+    		",
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
     }
 }
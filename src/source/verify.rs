@@ -0,0 +1,218 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Cross-checks universes loaded through different sources.
+//!
+//! The SQL sources run their own queries against the same static data export, so a
+//! typo in one of them silently produces a different graph. Load the same export
+//! through two backends and compare the results:
+//!
+//! ```no_run
+//! use neweden::source::verify::compare;
+//! # fn load_sqlite() -> anyhow::Result<neweden::Universe> { unimplemented!() }
+//! # fn load_postgres() -> anyhow::Result<neweden::Universe> { unimplemented!() }
+//!
+//! // source::sqlite::DatabaseBuilder::new("sqlite-latest.sqlite").build()
+//! let sqlite = load_sqlite().unwrap();
+//! // source::postgres::DatabaseBuilder::new(&database_url).build()
+//! let postgres = load_postgres().unwrap();
+//! let report = compare(&sqlite, &postgres);
+//! assert!(report.is_empty(), "{}", report);
+//! ```
+//!
+//! With both the `sqlite` and `postgres` features, `cargo test` does this when
+//! `SQLITE_URI` and `DATABASE_URL` point at the same export.
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::types::{self, Galaxy, Navigatable};
+
+/// A system present in both universes whose fields differ.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SystemMismatch {
+    pub id: types::SystemId,
+    /// The names of the differing fields of `System`.
+    pub fields: Vec<&'static str>,
+}
+
+/// The differences between two universes, see `compare`. Everything is sorted by
+/// system id.
+#[derive(Debug, Clone, Default)]
+pub struct SourceMismatchReport {
+    pub systems_only_in_a: Vec<types::SystemId>,
+    pub systems_only_in_b: Vec<types::SystemId>,
+    pub differing_systems: Vec<SystemMismatch>,
+    pub connections_only_in_a: Vec<types::Connection>,
+    pub connections_only_in_b: Vec<types::Connection>,
+}
+
+impl SourceMismatchReport {
+    /// Whether the universes have the same systems and connections.
+    pub fn is_empty(&self) -> bool {
+        self.systems_only_in_a.is_empty()
+            && self.systems_only_in_b.is_empty()
+            && self.differing_systems.is_empty()
+            && self.connections_only_in_a.is_empty()
+            && self.connections_only_in_b.is_empty()
+    }
+}
+
+impl fmt::Display for SourceMismatchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "universes are identical");
+        }
+        writeln!(
+            f,
+            "{} systems only in a, {} only in b, {} differ",
+            self.systems_only_in_a.len(),
+            self.systems_only_in_b.len(),
+            self.differing_systems.len()
+        )?;
+        write!(
+            f,
+            "{} connections only in a, {} only in b",
+            self.connections_only_in_a.len(),
+            self.connections_only_in_b.len()
+        )?;
+        // a few examples are enough to find the offending query
+        for mismatch in self.differing_systems.iter().take(5) {
+            write!(
+                f,
+                "\n  system {}: {}",
+                mismatch.id.0,
+                mismatch.fields.join(", ")
+            )?;
+        }
+        for (side, connections) in [
+            ("a", &self.connections_only_in_a),
+            ("b", &self.connections_only_in_b),
+        ] {
+            for c in connections.iter().take(5) {
+                write!(
+                    f,
+                    "\n  only in {}: {} -> {} ({:?})",
+                    side, c.from.0, c.to.0, c.type_
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compares the systems and connections of two universes. Connections are compared
+/// with their type, but not their source.
+pub fn compare(a: &types::Universe, b: &types::Universe) -> SourceMismatchReport {
+    let mut report = SourceMismatchReport::default();
+    let ids = |u: &types::Universe| u.systems().iter().map(|s| s.id).collect::<BTreeSet<_>>();
+    let (ids_a, ids_b) = (ids(a), ids(b));
+    report.systems_only_in_a = ids_a.difference(&ids_b).copied().collect();
+    report.systems_only_in_b = ids_b.difference(&ids_a).copied().collect();
+
+    for id in ids_a.intersection(&ids_b) {
+        let (x, y) = (a.get_system(id).unwrap(), b.get_system(id).unwrap());
+        let fields = [
+            ("name", x.name == y.name),
+            ("coordinate", x.coordinate == y.coordinate),
+            ("security", x.security == y.security),
+            ("region", x.region == y.region),
+            ("constellation", x.constellation == y.constellation),
+            ("faction", x.faction == y.faction),
+        ]
+        .into_iter()
+        .filter(|(_, equal)| !equal)
+        .map(|(field, _)| field)
+        .collect::<Vec<_>>();
+        if !fields.is_empty() {
+            report
+                .differing_systems
+                .push(SystemMismatch { id: *id, fields });
+        }
+    }
+
+    for id in ids_a.union(&ids_b) {
+        let (from_a, from_b) = (
+            a.get_connections(id).unwrap_or_default(),
+            b.get_connections(id).unwrap_or_default(),
+        );
+        let missing = |x: &[types::Connection], y: &[types::Connection]| {
+            x.iter()
+                .filter(|c| !y.iter().any(|d| c.to == d.to && c.type_ == d.type_))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        report
+            .connections_only_in_a
+            .extend(missing(&from_a, &from_b));
+        report
+            .connections_only_in_b
+            .extend(missing(&from_b, &from_a));
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::*;
+
+    #[test]
+    fn test_compare() {
+        let a = universe();
+        assert!(compare(&a, &universe()).is_empty());
+
+        let mut systems = a.systems().into_iter().cloned().collect::<Vec<_>>();
+        systems.retain(|s| s.id != HOTEL);
+        systems.iter_mut().find(|s| s.id == ALPHA).unwrap().name = "Alfa".to_string();
+        let mut connections = a
+            .systems()
+            .iter()
+            .flat_map(|s| a.get_connections(&s.id).unwrap_or_default().into_owned())
+            .filter(|c| c.from != HOTEL && c.to != HOTEL)
+            .collect::<Vec<_>>();
+        for c in connections.iter_mut().filter(|c| c.from == ALPHA) {
+            c.type_ = types::ConnectionType::Stargate(types::StargateType::Regional);
+        }
+        let b = types::Universe::new(systems.into(), connections.into());
+
+        let report = compare(&a, &b);
+        assert_eq!(vec![HOTEL], report.systems_only_in_a);
+        assert!(report.systems_only_in_b.is_empty());
+        assert_eq!(
+            vec![SystemMismatch {
+                id: ALPHA,
+                fields: vec!["name"]
+            }],
+            report.differing_systems
+        );
+        // Golf - Hotel both ways and Alpha - Bravo with its old type
+        assert_eq!(3, report.connections_only_in_a.len());
+        assert_eq!(1, report.connections_only_in_b.len());
+        assert_eq!(ALPHA, report.connections_only_in_b[0].from);
+        assert!(report.to_string().contains("system 30000001: name"));
+    }
+}
+
+#[cfg(all(test, feature = "sqlite", feature = "postgres"))]
+mod dbtests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    #[ignore = "needs static data exports in SQLITE_URI and DATABASE_URL"]
+    fn test_sqlite_matches_postgres() {
+        let sqlite = env::var("SQLITE_URI").expect("expected env variable SQLITE_URI set");
+        let postgres = env::var("DATABASE_URL").expect("expected env variable DATABASE_URL set");
+        let a = crate::source::sqlite::DatabaseBuilder::new(&sqlite)
+            .build()
+            .unwrap();
+        let b = crate::source::postgres::DatabaseBuilder::new(&postgres)
+            .build()
+            .unwrap();
+        let report = compare(&a, &b);
+        assert!(report.is_empty(), "{}", report);
+    }
+}
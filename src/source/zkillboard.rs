@@ -0,0 +1,223 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Recent kills from the zKillboard RedisQ feed, as a `DangerProvider`.
+//!
+//! RedisQ hands out every new killmail once per queue. `ZKillboard::poll` drains
+//! the queue and remembers where ships and pods died; kills older than the window
+//! no longer count.
+//!
+//! # Example
+//! ```no_run
+//! use neweden::navigation::{PathBuilder, Preference};
+//! use neweden::source::zkillboard::ZKillboard;
+//! # use neweden::builder::UniverseBuilder;
+//! # let universe = UniverseBuilder::new().build();
+//!
+//! let kills = ZKillboard::new("my-route-planner");
+//! // e.g. every few seconds in a background thread
+//! kills.poll().unwrap();
+//! let builder = PathBuilder::new(&universe)
+//!     .prefer(Preference::AvoidDanger)
+//!     .danger(&kills);
+//! ```
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow;
+use serde::Deserialize;
+
+use crate::navigation::danger::{Activity, DangerProvider};
+use crate::types;
+
+/// The RedisQ endpoint of zKillboard.
+pub const REDISQ_URL: &str = "https://zkillredisq.stream/listen.php";
+
+/// How long kills count by default.
+pub const DEFAULT_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// The ship types of capsules, whose loss is a pod kill.
+const CAPSULE_TYPES: [u32; 2] = [670, 33328];
+
+/// A kill as reported by RedisQ. Only the fields needed for routing are kept.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Kill {
+    pub system: types::SystemId,
+    pub ship_type: u32,
+}
+
+impl Kill {
+    pub fn is_pod(&self) -> bool {
+        CAPSULE_TYPES.contains(&self.ship_type)
+    }
+}
+
+#[derive(Deserialize)]
+struct Response {
+    package: Option<Package>,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    killmail: Killmail,
+}
+
+#[derive(Deserialize)]
+struct Killmail {
+    solar_system_id: u32,
+    victim: Victim,
+}
+
+#[derive(Deserialize)]
+struct Victim {
+    ship_type_id: u32,
+}
+
+/// Parses a RedisQ response, `None` if the queue was empty.
+pub fn parse(json: &str) -> anyhow::Result<Option<Kill>> {
+    let response: Response = serde_json::from_str(json)?;
+    Ok(response.package.map(|p| Kill {
+        system: types::SystemId(p.killmail.solar_system_id),
+        ship_type: p.killmail.victim.ship_type_id,
+    }))
+}
+
+/// The kills seen on a RedisQ queue within a time window.
+pub struct ZKillboard {
+    url: String,
+    window: Duration,
+    kills: Mutex<VecDeque<(Instant, Kill)>>,
+}
+
+impl ZKillboard {
+    /// Listens on the queue with this id. Each queue sees every kill, so use one id
+    /// per service.
+    pub fn new(queue_id: &str) -> Self {
+        Self {
+            url: format!("{}?queueID={}&ttw=1", REDISQ_URL, queue_id),
+            window: DEFAULT_WINDOW,
+            kills: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Uses a different endpoint, including the query string.
+    pub fn url(mut self, url: &str) -> Self {
+        self.url = url.to_string();
+        self
+    }
+
+    /// Count kills for this long after they were seen.
+    pub fn window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Fetches kills until the queue is empty and returns how many were new.
+    pub fn poll(&self) -> anyhow::Result<usize> {
+        let mut count = 0;
+        loop {
+            let body = ureq::get(&self.url).call()?.into_string()?;
+            match parse(&body)? {
+                Some(kill) => {
+                    self.record(kill);
+                    count += 1;
+                }
+                None => return Ok(count),
+            }
+        }
+    }
+
+    /// Remembers a kill seen now, e.g. from another feed.
+    pub fn record(&self, kill: Kill) {
+        let now = Instant::now();
+        let mut kills = self.lock();
+        kills.push_back((now, kill));
+        while kills
+            .front()
+            .is_some_and(|(seen, _)| now.duration_since(*seen) > self.window)
+        {
+            kills.pop_front();
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<(Instant, Kill)>> {
+        self.kills.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl DangerProvider for ZKillboard {
+    fn activity(&self, system: &types::SystemId) -> Activity {
+        let now = Instant::now();
+        let mut activity = Activity::default();
+        for (_, kill) in self.lock().iter().filter(|(seen, kill)| {
+            kill.system == *system && now.duration_since(*seen) <= self.window
+        }) {
+            if kill.is_pod() {
+                activity.pod_kills += 1;
+            } else {
+                activity.ship_kills += 1;
+            }
+        }
+        activity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RESPONSE: &str = r#"{
+        "package": {
+            "killID": 123456789,
+            "killmail": {
+                "killmail_id": 123456789,
+                "killmail_time": "2024-01-01T12:00:00Z",
+                "solar_system_id": 30002813,
+                "victim": {"ship_type_id": 670, "character_id": 1},
+                "attackers": []
+            },
+            "zkb": {"totalValue": 10000.0}
+        }
+    }"#;
+
+    #[test]
+    fn test_parse() {
+        let kill = parse(RESPONSE).unwrap().unwrap();
+        assert_eq!(types::SystemId(30002813), kill.system);
+        assert!(kill.is_pod());
+        assert_eq!(None, parse(r#"{"package": null}"#).unwrap());
+    }
+
+    #[test]
+    fn test_activity() {
+        let tama = types::SystemId(30002813);
+        let kills = ZKillboard::new("test");
+        kills.record(Kill {
+            system: tama,
+            ship_type: 670,
+        });
+        kills.record(Kill {
+            system: tama,
+            ship_type: 587,
+        });
+        assert_eq!(
+            Activity {
+                ship_kills: 1,
+                pod_kills: 1
+            },
+            kills.activity(&tama)
+        );
+        assert_eq!(Activity::default(), kills.activity(&30000142.into()));
+
+        let expired = ZKillboard::new("test").window(Duration::ZERO);
+        expired.record(Kill {
+            system: tama,
+            ship_type: 587,
+        });
+        std::thread::sleep(Duration::from_millis(2));
+        assert_eq!(Activity::default(), expired.activity(&tama));
+    }
+}
@@ -0,0 +1,319 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Key value storage for caches, so they can live in whatever the embedding service
+//! already runs instead of the memory of a single process.
+//!
+//! `MemoryStore` keeps everything in memory. With the `redis` and `sled` features
+//! `RedisStore` and `SledStore` persist into those. Caches such as
+//! `navigation::RouteCache` take any `KvStore`.
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, thiserror::Error)]
+pub enum StoreError {
+    #[error("storage backend failed: {0}")]
+    Backend(#[source] Box<dyn std::error::Error + Send + Sync>),
+    #[error("stored value is malformed")]
+    Malformed,
+}
+
+/// Storage mapping byte keys to byte values that may expire. Implementations are
+/// shared between threads and synchronise internally.
+pub trait KvStore {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StoreError>;
+
+    /// Stores a value, replacing the previous one. Values with a time to live are
+    /// gone once it passed.
+    fn put(&self, key: &[u8], value: &[u8], ttl: Option<Duration>) -> Result<(), StoreError>;
+
+    /// Sets the time to live of a stored value. Returns whether the key exists.
+    fn expire(&self, key: &[u8], ttl: Duration) -> Result<bool, StoreError>;
+}
+
+impl<S: KvStore + ?Sized> KvStore for &S {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StoreError> {
+        (**self).get(key)
+    }
+
+    fn put(&self, key: &[u8], value: &[u8], ttl: Option<Duration>) -> Result<(), StoreError> {
+        (**self).put(key, value, ttl)
+    }
+
+    fn expire(&self, key: &[u8], ttl: Duration) -> Result<bool, StoreError> {
+        (**self).expire(key, ttl)
+    }
+}
+
+impl<S: KvStore + ?Sized> KvStore for std::sync::Arc<S> {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StoreError> {
+        (**self).get(key)
+    }
+
+    fn put(&self, key: &[u8], value: &[u8], ttl: Option<Duration>) -> Result<(), StoreError> {
+        (**self).put(key, value, ttl)
+    }
+
+    fn expire(&self, key: &[u8], ttl: Duration) -> Result<bool, StoreError> {
+        (**self).expire(key, ttl)
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    value: Vec<u8>,
+    expires: Option<Instant>,
+}
+
+impl Entry {
+    /// Only reads the clock for values that expire, as there is none on
+    /// wasm32-unknown-unknown.
+    fn is_live(&self) -> bool {
+        self.expires.is_none_or(|expires| Instant::now() < expires)
+    }
+}
+
+/// A store in the memory of the process. Expired values are dropped when they are
+/// next accessed.
+///
+/// Values without a TTL never read the clock. `put` with a TTL and `expire` do, and
+/// panic on wasm32-unknown-unknown, which has none.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    entries: Mutex<HashMap<Vec<u8>, Entry>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of values stored, including expired ones not accessed since.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<Vec<u8>, Entry>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl KvStore for MemoryStore {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StoreError> {
+        let mut entries = self.lock();
+        match entries.get(key) {
+            Some(entry) if entry.is_live() => Ok(Some(entry.value.clone())),
+            Some(_) => {
+                entries.remove(key);
+                Ok(None)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn put(&self, key: &[u8], value: &[u8], ttl: Option<Duration>) -> Result<(), StoreError> {
+        let entry = Entry {
+            value: value.to_vec(),
+            expires: ttl.map(|ttl| Instant::now() + ttl),
+        };
+        self.lock().insert(key.to_vec(), entry);
+        Ok(())
+    }
+
+    fn expire(&self, key: &[u8], ttl: Duration) -> Result<bool, StoreError> {
+        let mut entries = self.lock();
+        match entries.get_mut(key) {
+            Some(entry) if entry.is_live() => {
+                entry.expires = Some(Instant::now() + ttl);
+                Ok(true)
+            }
+            Some(_) => {
+                entries.remove(key);
+                Ok(false)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+/// A store in redis. Every operation takes a connection from the client.
+#[cfg(feature = "redis")]
+#[derive(Debug, Clone)]
+pub struct RedisStore {
+    client: redis::Client,
+}
+
+#[cfg(feature = "redis")]
+impl RedisStore {
+    pub fn new(client: redis::Client) -> Self {
+        Self { client }
+    }
+
+    /// Connects to a redis URL like `redis://127.0.0.1/`.
+    pub fn open(url: &str) -> Result<Self, StoreError> {
+        Ok(Self::new(redis::Client::open(url).map_err(backend)?))
+    }
+
+    fn connection(&self) -> Result<redis::Connection, StoreError> {
+        self.client.get_connection().map_err(backend)
+    }
+}
+
+#[cfg(feature = "redis")]
+fn backend<E: std::error::Error + Send + Sync + 'static>(error: E) -> StoreError {
+    StoreError::Backend(Box::new(error))
+}
+
+#[cfg(feature = "redis")]
+impl KvStore for RedisStore {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StoreError> {
+        redis::cmd("GET")
+            .arg(key)
+            .query(&mut self.connection()?)
+            .map_err(backend)
+    }
+
+    fn put(&self, key: &[u8], value: &[u8], ttl: Option<Duration>) -> Result<(), StoreError> {
+        let mut command = redis::cmd("SET");
+        command.arg(key).arg(value);
+        if let Some(ttl) = ttl {
+            // redis rejects a time to live of zero
+            command.arg("PX").arg(ttl.as_millis().max(1) as u64);
+        }
+        command.query(&mut self.connection()?).map_err(backend)
+    }
+
+    fn expire(&self, key: &[u8], ttl: Duration) -> Result<bool, StoreError> {
+        redis::cmd("PEXPIRE")
+            .arg(key)
+            .arg(ttl.as_millis() as u64)
+            .query(&mut self.connection()?)
+            .map_err(backend)
+    }
+}
+
+/// A store in a sled database. Sled has no expiry, so values are prefixed with the
+/// unix time in milliseconds they expire at, or zero, and dropped when read after.
+#[cfg(feature = "sled")]
+#[derive(Debug, Clone)]
+pub struct SledStore {
+    tree: sled::Tree,
+}
+
+#[cfg(feature = "sled")]
+impl SledStore {
+    pub fn new(tree: sled::Tree) -> Self {
+        Self { tree }
+    }
+
+    /// Opens a database at a path, using its default tree.
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> Result<Self, StoreError> {
+        let db = sled::open(path).map_err(sled_error)?;
+        Ok(Self::new((*db).clone()))
+    }
+
+    fn now() -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64)
+    }
+
+    fn deadline(ttl: Duration) -> u64 {
+        Self::now().saturating_add(ttl.as_millis() as u64).max(1)
+    }
+
+    /// The value and its deadline, `None` if there is none or it expired.
+    fn read(&self, key: &[u8]) -> Result<Option<(Vec<u8>, u64)>, StoreError> {
+        let Some(stored) = self.tree.get(key).map_err(sled_error)? else {
+            return Ok(None);
+        };
+        if stored.len() < 8 {
+            return Err(StoreError::Malformed);
+        }
+        let (deadline, value) = stored.split_at(8);
+        let deadline = u64::from_le_bytes(deadline.try_into().unwrap());
+        if deadline != 0 && deadline <= Self::now() {
+            self.tree.remove(key).map_err(sled_error)?;
+            return Ok(None);
+        }
+        Ok(Some((value.to_vec(), deadline)))
+    }
+
+    fn write(&self, key: &[u8], value: &[u8], deadline: u64) -> Result<(), StoreError> {
+        let mut stored = Vec::with_capacity(8 + value.len());
+        stored.extend_from_slice(&deadline.to_le_bytes());
+        stored.extend_from_slice(value);
+        self.tree.insert(key, stored).map_err(sled_error)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sled")]
+fn sled_error(error: sled::Error) -> StoreError {
+    StoreError::Backend(Box::new(error))
+}
+
+#[cfg(feature = "sled")]
+impl KvStore for SledStore {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StoreError> {
+        Ok(self.read(key)?.map(|(value, _)| value))
+    }
+
+    fn put(&self, key: &[u8], value: &[u8], ttl: Option<Duration>) -> Result<(), StoreError> {
+        self.write(key, value, ttl.map_or(0, Self::deadline))
+    }
+
+    fn expire(&self, key: &[u8], ttl: Duration) -> Result<bool, StoreError> {
+        match self.read(key)? {
+            Some((value, _)) => {
+                self.write(key, &value, Self::deadline(ttl))?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The behaviour every store must have.
+    fn check_store(store: &dyn KvStore) {
+        assert_eq!(None, store.get(b"missing").unwrap());
+        store.put(b"key", b"value", None).unwrap();
+        assert_eq!(Some(b"value".to_vec()), store.get(b"key").unwrap());
+        store.put(b"key", b"other", None).unwrap();
+        assert_eq!(Some(b"other".to_vec()), store.get(b"key").unwrap());
+
+        assert!(store.expire(b"key", Duration::from_secs(60)).unwrap());
+        assert_eq!(Some(b"other".to_vec()), store.get(b"key").unwrap());
+        assert!(!store.expire(b"missing", Duration::from_secs(60)).unwrap());
+
+        store.put(b"short", b"lived", Some(Duration::ZERO)).unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(None, store.get(b"short").unwrap());
+        assert!(!store.expire(b"short", Duration::from_secs(60)).unwrap());
+    }
+
+    #[test]
+    fn test_memory_store() {
+        let store = MemoryStore::new();
+        check_store(&store);
+        assert_eq!(1, store.len());
+    }
+
+    #[cfg(feature = "sled")]
+    #[test]
+    fn test_sled_store() {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        check_store(&SledStore::new(db.open_tree("cache").unwrap()));
+    }
+}
@@ -0,0 +1,398 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Universes for unit tests that should not depend on a static data export being
+//! available, e.g. routing logic in downstream crates.
+//!
+//! `universe` is a small hand-built universe, `cluster` a larger fixture of three
+//! regions, and `connected_universe` and `pseudo_universe` generate random
+//! universes from a seed for property tests.
+//!
+//! # Example
+//! ```
+//! use neweden::navigation::PathBuilder;
+//! use neweden::testing::{self, ALPHA, ECHO};
+//! use neweden::Navigatable;
+//!
+//! let universe = testing::universe();
+//! let path = PathBuilder::new(&universe)
+//!     .waypoint(universe.get_system(&ALPHA).unwrap())
+//!     .waypoint(universe.get_system(&ECHO).unwrap())
+//!     .build()
+//!     .unwrap();
+//! assert_eq!(4, path.jumps());
+//! ```
+//!
+//! The systems of `universe`:
+//!
+//! ```text
+//! Alpha(0.9) - Bravo(0.8) - Charlie(0.6) - Delta(0.3) - Echo(-0.2)
+//!                  |             |            |
+//!             Foxtrot(0.7) - Golf(0.5) -------+
+//!                                |
+//!                            Hotel(0.1)
+//! ```
+//!
+//! Alpha, Bravo and Foxtrot form region `NORTH`, Charlie, Golf and Hotel region
+//! `MIDDLE` and Delta and Echo region `SOUTH`. Every region consists of a single
+//! constellation, except `NORTH` where Foxtrot has its own.
+//!
+//! Only Alpha has celestials: a planet at 1 AU from the sun with a moon, an asteroid
+//! belt at 3 AU and the stargate to Bravo at 10 AU, all along the x axis. Stargates
+//! are only known for Alpha - Bravo - Charlie; Bravo's gate to Alpha sits at the sun
+//! and its gate to Charlie 5 AU away.
+use crate::builder::UniverseBuilder;
+use crate::types;
+
+pub const ALPHA: types::SystemId = types::SystemId(30000001);
+pub const BRAVO: types::SystemId = types::SystemId(30000002);
+pub const CHARLIE: types::SystemId = types::SystemId(30000003);
+pub const DELTA: types::SystemId = types::SystemId(30000004);
+pub const ECHO: types::SystemId = types::SystemId(30000005);
+pub const FOXTROT: types::SystemId = types::SystemId(30000006);
+pub const GOLF: types::SystemId = types::SystemId(30000007);
+pub const HOTEL: types::SystemId = types::SystemId(30000008);
+
+pub const NORTH: types::RegionId = types::RegionId(10000001);
+pub const MIDDLE: types::RegionId = types::RegionId(10000002);
+pub const SOUTH: types::RegionId = types::RegionId(10000003);
+
+/// A system in the plane of the fixtures, `x` and `y` in lightyears.
+pub fn system(id: types::SystemId, name: &str, security: f32, x: f64, y: f64) -> types::System {
+    let ly = types::Meters::from(types::Lightyears(1.0)).0;
+    types::System::new(
+        id,
+        name,
+        types::Coordinate {
+            x: x * ly,
+            y: 0.0,
+            z: y * ly,
+        },
+        security.into(),
+    )
+}
+
+fn located(
+    mut system: types::System,
+    region: types::RegionId,
+    constellation: u32,
+) -> types::System {
+    system.region = Some(region);
+    system.constellation = Some(types::ConstellationId(constellation));
+    system
+}
+
+fn celestial(id: u32, name: &str, kind: types::CelestialKind, au: f64) -> types::Celestial {
+    types::Celestial {
+        id: types::CelestialId(id),
+        name: name.to_string(),
+        system: ALPHA,
+        kind,
+        coordinate: types::Coordinate {
+            x: types::Meters::from(types::Au(au)).0,
+            y: 0.0,
+            z: 0.0,
+        },
+    }
+}
+
+fn stargate_at(
+    id: u32,
+    system: types::SystemId,
+    au: f64,
+    destination: u32,
+    destination_system: types::SystemId,
+) -> types::Stargate {
+    types::Stargate {
+        id: types::CelestialId(id),
+        system,
+        coordinate: types::Coordinate {
+            x: types::Meters::from(types::Au(au)).0,
+            y: 0.0,
+            z: 0.0,
+        },
+        destination: types::CelestialId(destination),
+        destination_system,
+    }
+}
+
+/// The type of the stargate between two located systems.
+fn gate_type(a: &types::System, b: &types::System) -> types::StargateType {
+    if a.region != b.region {
+        types::StargateType::Regional
+    } else if a.constellation != b.constellation {
+        types::StargateType::Constellation
+    } else {
+        types::StargateType::Local
+    }
+}
+
+/// Adds the systems and bidirectional stargates between them.
+fn connect(
+    builder: &mut UniverseBuilder,
+    systems: Vec<types::System>,
+    edges: &[(types::SystemId, types::SystemId)],
+) {
+    for (a, b) in edges {
+        let sa = systems.iter().find(|s| s.id == *a).unwrap();
+        let sb = systems.iter().find(|s| s.id == *b).unwrap();
+        builder.add_connection_bidirectional(types::Connection {
+            from: sa.id,
+            to: sb.id,
+            type_: types::ConnectionType::Stargate(gate_type(sa, sb)),
+            source: types::ConnectionSource::Sde,
+        });
+    }
+    for system in systems {
+        builder.add_system(system);
+    }
+}
+
+/// The small universe described in the module documentation.
+pub fn universe() -> types::Universe {
+    let systems = vec![
+        located(system(ALPHA, "Alpha", 0.9, 0.0, 0.0), NORTH, 20000001),
+        located(system(BRAVO, "Bravo", 0.8, 2.0, 0.0), NORTH, 20000001),
+        located(system(CHARLIE, "Charlie", 0.6, 4.0, 0.0), MIDDLE, 20000003),
+        located(system(DELTA, "Delta", 0.3, 6.0, 0.0), SOUTH, 20000004),
+        located(system(ECHO, "Echo", -0.2, 8.0, 0.0), SOUTH, 20000004),
+        located(system(FOXTROT, "Foxtrot", 0.7, 2.0, 2.0), NORTH, 20000002),
+        located(system(GOLF, "Golf", 0.5, 4.0, 2.0), MIDDLE, 20000003),
+        located(system(HOTEL, "Hotel", 0.1, 4.0, 4.0), MIDDLE, 20000003),
+    ];
+    let edges = [
+        (ALPHA, BRAVO),
+        (BRAVO, CHARLIE),
+        (CHARLIE, DELTA),
+        (DELTA, ECHO),
+        (BRAVO, FOXTROT),
+        (FOXTROT, GOLF),
+        (CHARLIE, GOLF),
+        (GOLF, DELTA),
+        (GOLF, HOTEL),
+    ];
+
+    let mut builder = UniverseBuilder::new();
+    connect(&mut builder, systems, &edges);
+    builder
+        .add_celestial(celestial(
+            40000001,
+            "Alpha I",
+            types::CelestialKind::Planet,
+            1.0,
+        ))
+        .add_celestial(celestial(
+            40000002,
+            "Alpha I - Moon 1",
+            types::CelestialKind::Moon,
+            1.01,
+        ))
+        .add_celestial(celestial(
+            40000003,
+            "Alpha I - Asteroid Belt 1",
+            types::CelestialKind::AsteroidBelt,
+            3.0,
+        ))
+        .add_celestial(celestial(
+            50000001,
+            "Stargate (Bravo)",
+            types::CelestialKind::Stargate,
+            10.0,
+        ))
+        .add_stargate(stargate_at(50000001, ALPHA, 10.0, 50000002, BRAVO))
+        .add_stargate(stargate_at(50000002, BRAVO, 0.0, 50000001, ALPHA))
+        .add_stargate(stargate_at(50000003, BRAVO, 5.0, 50000004, CHARLIE))
+        .add_stargate(stargate_at(50000004, CHARLIE, 0.0, 50000003, BRAVO));
+    builder.build()
+}
+
+/// The regions of `cluster`, in descending security.
+pub const CORE: types::RegionId = types::RegionId(10000011);
+pub const RIM: types::RegionId = types::RegionId(10000012);
+pub const DEEP: types::RegionId = types::RegionId(10000013);
+
+/// The systems of a region of `cluster` per row.
+const CLUSTER_COLUMNS: u32 = 5;
+
+/// The id of the system of `cluster` in a region (0 to 2), row (0 or 1) and column
+/// (0 to 4). Systems are named after their region and numbered row by row, e.g.
+/// `cluster_system(1, 1, 0)` is "Rim 6".
+pub fn cluster_system(region: u32, row: u32, column: u32) -> types::SystemId {
+    types::SystemId(30010001 + region * 2 * CLUSTER_COLUMNS + row * CLUSTER_COLUMNS + column)
+}
+
+/// A fixture of 30 systems in three regions of ten, highsec `CORE`, lowsec `RIM` and
+/// nullsec `DEEP`, lined up from west to east. Every region is a ladder of two rows
+/// of five systems, each row its own constellation, with rungs at the first, third
+/// and last column. Security falls towards the east and the second row.
+///
+/// ```text
+/// Core 1 - ... - Core 5 - Rim 1 - ... - Rim 5
+///   |              |        |             |
+/// Core 6 - ... - Core 10 - Rim 6 - ... - Rim 10 - Deep 6 - ... - Deep 10
+///                                                    |              |
+///                                                  Deep 1 - ... - Deep 5
+/// ```
+///
+/// Both rows of `CORE` lead into `RIM`, but only `Rim 10` leads into `DEEP`.
+pub fn cluster() -> types::Universe {
+    let regions = [
+        (CORE, "Core", 1.0, 0.1, 0.05),
+        (RIM, "Rim", 0.4, 0.07, 0.02),
+        (DEEP, "Deep", -0.1, 0.2, 0.1),
+    ];
+    let mut systems = Vec::new();
+    let mut edges = Vec::new();
+    for (r, (region, name, top, per_column, per_row)) in regions.iter().enumerate() {
+        let r = r as u32;
+        for row in 0..2 {
+            for column in 0..CLUSTER_COLUMNS {
+                let id = cluster_system(r, row, column);
+                let security = top - per_column * column as f32 - per_row * row as f32;
+                let x = (r * (2 * CLUSTER_COLUMNS + 2) + 2 * column) as f64;
+                let name = format!("{} {}", name, row * CLUSTER_COLUMNS + column + 1);
+                let constellation = 20000011 + r * 2 + row;
+                systems.push(located(
+                    system(id, &name, security, x, 3.0 * row as f64),
+                    *region,
+                    constellation,
+                ));
+                if column > 0 {
+                    edges.push((cluster_system(r, row, column - 1), id));
+                }
+            }
+        }
+        for column in [0, 2, CLUSTER_COLUMNS - 1] {
+            edges.push((cluster_system(r, 0, column), cluster_system(r, 1, column)));
+        }
+    }
+    let last = CLUSTER_COLUMNS - 1;
+    edges.push((cluster_system(0, 0, last), cluster_system(1, 0, 0)));
+    edges.push((cluster_system(0, 1, last), cluster_system(1, 1, 0)));
+    edges.push((cluster_system(1, 1, last), cluster_system(2, 1, 0)));
+
+    let mut builder = UniverseBuilder::new();
+    connect(&mut builder, systems, &edges);
+    builder.build()
+}
+
+/// Like `pseudo_universe`, but connected: every system is joined to one of the
+/// systems before it, and `extra_connections` random stargates are added on top.
+pub fn connected_universe(seed: u64, systems: usize, extra_connections: usize) -> types::Universe {
+    let mut next = splitmix(seed);
+    let mut builder = scatter(&mut next, systems);
+    for i in 1..systems {
+        let j = (next() % i as u64) as usize;
+        builder.add_connection_bidirectional(gate(i, j));
+    }
+    random_gates(&mut builder, &mut next, systems, extra_connections);
+    builder.build()
+}
+
+/// A pseudo-random universe for property tests. The same arguments always produce
+/// the same universe. Systems are scattered over a 20 by 20 lightyear area and
+/// joined by `connections` random bidirectional stargates, so the universe is not
+/// necessarily connected.
+pub fn pseudo_universe(seed: u64, systems: usize, connections: usize) -> types::Universe {
+    let mut next = splitmix(seed);
+    let mut builder = scatter(&mut next, systems);
+    random_gates(&mut builder, &mut next, systems, connections);
+    builder.build()
+}
+
+/// A splitmix64 generator.
+fn splitmix(seed: u64) -> impl FnMut() -> u64 {
+    let mut state = seed;
+    move || {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// A builder with systems of random security scattered over 20 by 20 lightyears.
+fn scatter(next: &mut impl FnMut() -> u64, systems: usize) -> UniverseBuilder {
+    let mut unit = || next() as f64 / u64::MAX as f64;
+    let mut builder = UniverseBuilder::new();
+    for i in 0..systems {
+        let security = (unit() * 2.0 - 1.0) as f32;
+        let (x, y) = (unit() * 20.0, unit() * 20.0);
+        builder.add_system(system(
+            types::SystemId(30000001 + i as u32),
+            &format!("System {}", i),
+            security,
+            x,
+            y,
+        ));
+    }
+    builder
+}
+
+fn random_gates(
+    builder: &mut UniverseBuilder,
+    next: &mut impl FnMut() -> u64,
+    systems: usize,
+    connections: usize,
+) {
+    let mut unit = || next() as f64 / u64::MAX as f64;
+    if systems > 1 {
+        for _ in 0..connections {
+            let a = (unit() * systems as f64) as usize % systems;
+            let b = (a + 1 + (unit() * (systems - 1) as f64) as usize % (systems - 1)) % systems;
+            builder.add_connection_bidirectional(gate(a, b));
+        }
+    }
+}
+
+/// A stargate between the `a`th and `b`th generated system.
+fn gate(a: usize, b: usize) -> types::Connection {
+    types::Connection {
+        from: types::SystemId(30000001 + a as u32),
+        to: types::SystemId(30000001 + b as u32),
+        type_: types::ConnectionType::Stargate(types::StargateType::Local),
+        source: types::ConnectionSource::Sde,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::navigation::{jump_distance, jump_distances};
+    use crate::types::{Galaxy, Navigatable};
+
+    #[test]
+    fn test_cluster() {
+        let universe = cluster();
+        assert_eq!(30, universe.systems().len());
+        for (r, region) in [CORE, RIM, DEEP].into_iter().enumerate() {
+            for row in 0..2 {
+                for column in 0..CLUSTER_COLUMNS {
+                    let id = cluster_system(r as u32, row, column);
+                    assert_eq!(Some(region), universe.get_system(&id).unwrap().region);
+                }
+            }
+        }
+        let core = cluster_system(0, 0, 0);
+        let deep = cluster_system(2, 0, CLUSTER_COLUMNS - 1);
+        assert_eq!(
+            "Rim 6",
+            universe.get_system(&cluster_system(1, 1, 0)).unwrap().name
+        );
+        // 5 jumps to Core 10, 5 to Rim 10 and 6 to Deep 5
+        assert_eq!(Some(16), jump_distance(&universe, &core, &deep));
+    }
+
+    #[test]
+    fn test_connected_universe() {
+        let universe = connected_universe(7, 50, 10);
+        let ids = universe.systems().iter().map(|s| s.id).collect::<Vec<_>>();
+        assert_eq!(50, ids.len());
+        assert!(jump_distances(&universe, &ids[0], &ids)
+            .iter()
+            .all(Option::is_some));
+    }
+}
@@ -0,0 +1,281 @@
+/*
+ * Copyright (c) 2019. David "Tiran'Sol" Soria Parra
+ * All rights reserved.
+ */
+
+//! Integrity checks for universes.
+//!
+//! Data sources produce consistent universes, but hand-built ones, e.g. through
+//! `builder::UniverseBuilder`, easily end up with a connection to a system that
+//! was never added or a stargate without its way back. Such mistakes only show
+//! when a route is not found. `Universe::validate` reports them up front and
+//! `UniverseBuilder::try_build` refuses to build universes with them.
+//!
+//! # Example
+//! ```
+//! use neweden::builder::UniverseBuilder;
+//! use neweden::validate::IntegrityIssue;
+//! use neweden::{Connection, ConnectionSource, ConnectionType, Coordinate, StargateType, System};
+//!
+//! let result = UniverseBuilder::new()
+//!     .system(System::new(1.into(), "Lonely", Coordinate { x: 0.0, y: 0.0, z: 0.0 }, 0.5.into()))
+//!     .connection(Connection {
+//!         from: 1.into(),
+//!         to: 2.into(),
+//!         type_: ConnectionType::Stargate(StargateType::Local),
+//!         source: ConnectionSource::UserOverlay,
+//!     })
+//!     .try_build();
+//! assert_eq!(
+//!     Err(vec![IntegrityIssue::DanglingConnection { from: 1.into(), to: 2.into() }]),
+//!     result.map(|_| ())
+//! );
+//! ```
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::types::{Connection, ConnectionType, RegionId, SystemId, Universe};
+
+/// A problem found by `Universe::validate`.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum IntegrityIssue {
+    /// A connection from or to a system the universe does not have. Routes can not
+    /// use it.
+    #[error("connection from {from:?} to {to:?} refers to an unknown system")]
+    DanglingConnection { from: SystemId, to: SystemId },
+    /// A stargate without a stargate back, while gates always come in pairs.
+    #[error("stargate from {from:?} to {to:?} has no stargate back")]
+    AsymmetricGate { from: SystemId, to: SystemId },
+    /// Known space systems that can not be reached through stargates from the
+    /// largest group of known space systems, sorted by id.
+    #[error("{} known space systems are not connected to the rest", .0.len())]
+    DisconnectedSystems(Vec<SystemId>),
+    /// The same connection, including its source, is stored more than once.
+    #[error("connection from {from:?} to {to:?} is stored {count} times")]
+    DuplicateConnection {
+        from: SystemId,
+        to: SystemId,
+        count: usize,
+    },
+}
+
+impl IntegrityIssue {
+    /// Whether the issue breaks routing, rather than being implausible. Only these
+    /// make `UniverseBuilder::try_build` fail outside of strict mode.
+    pub fn is_fatal(&self) -> bool {
+        matches!(self, Self::DanglingConnection { .. })
+    }
+}
+
+impl Universe {
+    /// Checks the connections for dangling ends, stargates without a way back,
+    /// duplicates and known space cut off from the rest. The issues are ordered by
+    /// kind and system id.
+    ///
+    /// Real maps have known space without stargates, e.g. Jove space, so a universe
+    /// loaded from the static data export is not expected to pass.
+    pub fn validate(&self) -> Result<(), Vec<IntegrityIssue>> {
+        let mut from = self.connections.0.keys().copied().collect::<Vec<_>>();
+        from.sort();
+        let connections = || {
+            from.iter()
+                .flat_map(|id| self.connections.0[id].iter())
+                .collect::<Vec<_>>()
+        };
+
+        let mut issues = Vec::new();
+        for c in connections() {
+            if !self.systems.0.contains_key(&c.from) || !self.systems.0.contains_key(&c.to) {
+                issues.push(IntegrityIssue::DanglingConnection {
+                    from: c.from,
+                    to: c.to,
+                });
+            }
+        }
+        for c in connections().into_iter().filter(|c| is_gate(c)) {
+            let back = self.connections.0.get(&c.to).into_iter().flatten();
+            if !back.into_iter().any(|b| b.to == c.from && is_gate(b)) {
+                issues.push(IntegrityIssue::AsymmetricGate {
+                    from: c.from,
+                    to: c.to,
+                });
+            }
+        }
+        for id in &from {
+            let adjacent = &self.connections.0[id];
+            for (i, c) in adjacent.iter().enumerate() {
+                let same =
+                    |o: &&Connection| o.to == c.to && o.type_ == c.type_ && o.source == c.source;
+                // reported once, at the first copy
+                if adjacent[..i].iter().any(|o| same(&o)) {
+                    continue;
+                }
+                let count = adjacent.iter().filter(same).count();
+                if count > 1 {
+                    issues.push(IntegrityIssue::DuplicateConnection {
+                        from: c.from,
+                        to: c.to,
+                        count,
+                    });
+                }
+            }
+        }
+        issues.extend(
+            self.disconnected_kspace()
+                .into_iter()
+                .map(IntegrityIssue::DisconnectedSystems),
+        );
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(issues)
+        }
+    }
+
+    /// The groups of known space systems connected through stargates, except the
+    /// largest one.
+    fn disconnected_kspace(&self) -> Vec<Vec<SystemId>> {
+        let kspace = |id: &SystemId| {
+            id.0 < 31000000
+                && self
+                    .systems
+                    .0
+                    .get(id)
+                    .is_some_and(|s| s.region != Some(RegionId::POCHVEN))
+        };
+        let mut neighbors: HashMap<SystemId, Vec<SystemId>> = HashMap::new();
+        for c in self.connections.0.values().flatten() {
+            if is_gate(c) && kspace(&c.from) && kspace(&c.to) {
+                neighbors.entry(c.from).or_default().push(c.to);
+                neighbors.entry(c.to).or_default().push(c.from);
+            }
+        }
+
+        let mut ids = self
+            .systems
+            .0
+            .keys()
+            .filter(|id| kspace(id))
+            .collect::<Vec<_>>();
+        ids.sort();
+        let mut seen = HashSet::new();
+        let mut components = Vec::new();
+        for id in ids {
+            if !seen.insert(*id) {
+                continue;
+            }
+            let mut component = vec![*id];
+            let mut queue = VecDeque::from([*id]);
+            while let Some(next) = queue.pop_front() {
+                for n in neighbors.get(&next).into_iter().flatten() {
+                    if seen.insert(*n) {
+                        component.push(*n);
+                        queue.push_back(*n);
+                    }
+                }
+            }
+            component.sort();
+            components.push(component);
+        }
+        // the first of the largest groups is the main map
+        if let Some(main) = components
+            .iter()
+            .enumerate()
+            .max_by_key(|(i, c)| (c.len(), std::cmp::Reverse(*i)))
+            .map(|(i, _)| i)
+        {
+            components.remove(main);
+        }
+        components
+    }
+}
+
+fn is_gate(connection: &Connection) -> bool {
+    matches!(connection.type_, ConnectionType::Stargate(_))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::UniverseBuilder;
+    use crate::testing::*;
+    use crate::types::{ConnectionSource, Coordinate, StargateType, System};
+
+    fn gate(from: SystemId, to: SystemId) -> Connection {
+        Connection {
+            from,
+            to,
+            type_: ConnectionType::Stargate(StargateType::Local),
+            source: ConnectionSource::UserOverlay,
+        }
+    }
+
+    #[test]
+    fn test_fixtures_are_valid() {
+        assert_eq!(Ok(()), universe().validate());
+        assert_eq!(Ok(()), cluster().validate());
+    }
+
+    #[test]
+    fn test_issues() {
+        let system = |id: u32| {
+            System::new(
+                id.into(),
+                "",
+                Coordinate {
+                    x: 0.0,
+                    y: 0.0,
+                    z: 0.0,
+                },
+                0.5.into(),
+            )
+        };
+        let builder = || {
+            UniverseBuilder::new()
+                .system(system(1))
+                .system(system(2))
+                .system(system(3))
+                .connection_bidirectional(gate(1.into(), 2.into()))
+        };
+        // the third system is cut off
+        let issues = builder().build().validate().unwrap_err();
+        assert_eq!(
+            vec![IntegrityIssue::DisconnectedSystems(vec![3.into()])],
+            issues
+        );
+        assert!(!issues[0].is_fatal());
+        assert!(builder().try_build().is_ok());
+        assert_eq!(Some(issues), builder().strict().try_build().err());
+
+        let issues = builder()
+            .connection(gate(2.into(), 3.into()))
+            .connection(gate(1.into(), 2.into()))
+            .connection(gate(3.into(), 4.into()))
+            .build()
+            .validate()
+            .unwrap_err();
+        assert_eq!(
+            vec![
+                IntegrityIssue::DanglingConnection {
+                    from: 3.into(),
+                    to: 4.into()
+                },
+                IntegrityIssue::AsymmetricGate {
+                    from: 2.into(),
+                    to: 3.into()
+                },
+                IntegrityIssue::AsymmetricGate {
+                    from: 3.into(),
+                    to: 4.into()
+                },
+                IntegrityIssue::DuplicateConnection {
+                    from: 1.into(),
+                    to: 2.into(),
+                    count: 2
+                },
+            ],
+            issues
+        );
+        assert!(issues.iter().any(|i| i.is_fatal()));
+    }
+}